@@ -0,0 +1,40 @@
+/// Parses a size such as `2GB`, `500 MB` or `1048576`, where KB, MB and GB are powers of 1024 to
+/// match how sizes are printed.
+pub fn parse_byte_size(value: &str) -> Result<u64, String> {
+	let value = value.trim();
+	let split = value
+		.find(|c: char| !c.is_ascii_digit() && c != '.')
+		.unwrap_or(value.len());
+	let (number, unit) = value.split_at(split);
+	let number: f64 = number
+		.parse()
+		.map_err(|_| format!("Invalid size '{value}', expected a size like 2GB"))?;
+	let multiplier: u64 = match unit.trim().to_uppercase().as_str() {
+		"" | "B" => 1,
+		"K" | "KB" | "KIB" => 1 << 10,
+		"M" | "MB" | "MIB" => 1 << 20,
+		"G" | "GB" | "GIB" => 1 << 30,
+		"T" | "TB" | "TIB" => 1 << 40,
+		unit => return Err(format!("Unknown size unit '{unit}', expected B, KB, MB, GB or TB")),
+	};
+	Ok((number * multiplier as f64) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn sizes_parse_with_and_without_units() {
+		assert_eq!(parse_byte_size("1048576"), Ok(1_048_576));
+		assert_eq!(parse_byte_size("2GB"), Ok(2 * 1024 * 1024 * 1024));
+		assert_eq!(parse_byte_size("500 mb"), Ok(500 * 1024 * 1024));
+		assert_eq!(parse_byte_size("1.5K"), Ok(1536));
+	}
+
+	#[test]
+	fn invalid_sizes_are_rejected() {
+		assert!(parse_byte_size("GB").is_err());
+		assert!(parse_byte_size("2 parsecs").is_err());
+	}
+}
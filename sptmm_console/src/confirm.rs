@@ -0,0 +1,57 @@
+use std::io::{BufRead, Write};
+
+use anyhow::{anyhow, Result};
+
+/// Asks a yes/no question before a destructive action, answering no unless the user types yes.
+/// `assume_yes` skips the prompt and a non-interactive session fails rather than waiting for input.
+pub fn confirm(
+	question: &str,
+	assume_yes: bool,
+	interactive: bool,
+	mut input: impl BufRead,
+	mut output: impl Write,
+) -> Result<bool> {
+	if assume_yes {
+		return Ok(true);
+	}
+	if !interactive {
+		return Err(anyhow!(
+			"Refusing to continue without confirmation, pass --assume-yes to run non-interactively"
+		));
+	}
+
+	write!(output, "{question} [y/N] ")?;
+	output.flush()?;
+	let mut answer = String::new();
+	input.read_line(&mut answer)?;
+	Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn assume_yes_skips_the_prompt() {
+		let mut output = Vec::new();
+
+		let confirmed = confirm("Continue?", true, false, "".as_bytes(), &mut output).unwrap();
+
+		assert!(confirmed);
+		assert!(output.is_empty());
+	}
+
+	#[test]
+	fn non_interactive_without_assume_yes_errors() {
+		let result = confirm("Continue?", false, false, "y\n".as_bytes(), Vec::new());
+
+		assert!(result.is_err());
+	}
+
+	#[test]
+	fn only_yes_confirms() {
+		assert!(confirm("Continue?", false, true, "Y\n".as_bytes(), Vec::new()).unwrap());
+		assert!(!confirm("Continue?", false, true, "\n".as_bytes(), Vec::new()).unwrap());
+		assert!(!confirm("Continue?", false, true, "nope\n".as_bytes(), Vec::new()).unwrap());
+	}
+}
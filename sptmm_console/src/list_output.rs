@@ -0,0 +1,195 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use sptmm_lib::remote_mod_access::ModKind;
+use sptmm_lib::spt_access::InstalledMod;
+use versions::Versioning;
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq, Default)]
+pub enum ListFormat {
+	#[default]
+	Table,
+	Json,
+	Csv,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ListRow {
+	pub name: String,
+	pub version: String,
+	pub target: String,
+	/// The kind of host the mod was downloaded from.
+	pub source: String,
+}
+
+const HEADERS: [&str; 4] = ["name", "version", "target", "source"];
+
+impl ListRow {
+	fn values(&self) -> [&str; 4] {
+		[&self.name, &self.version, &self.target, &self.source]
+	}
+}
+
+impl From<&InstalledMod> for ListRow {
+	fn from(installed_mod: &InstalledMod) -> Self {
+		Self {
+			name: installed_mod.name.clone(),
+			version: installed_mod
+				.version
+				.clone()
+				.unwrap_or_else(|| "unknown".to_string()),
+			target: installed_mod
+				.install_target
+				.map(|target| target.to_string())
+				.unwrap_or_else(|| "unknown".to_string()),
+			source: "unknown".to_string(),
+		}
+	}
+}
+
+impl ListRow {
+	/// A mod extracted to its `install_path`, which isn't in the install index so its files and
+	/// target aren't tracked.
+	pub fn unmanaged(name: &str, version: Option<&Versioning>) -> Self {
+		Self {
+			name: name.to_string(),
+			version: version.map_or_else(|| "unknown".to_string(), |version| version.to_string()),
+			target: "unmanaged".to_string(),
+			source: "unknown".to_string(),
+		}
+	}
+
+	/// Names where the mod came from, leaving it unknown for mods that aren't cached or configured.
+	pub fn with_source(mut self, mod_kind: Option<&ModKind>) -> Self {
+		if let Some(mod_kind) = mod_kind {
+			self.source = mod_kind.get_source_name().to_string();
+		}
+		self
+	}
+}
+
+/// Sorts rows alphabetically by name, ignoring case.
+pub fn sort_rows(rows: &mut [ListRow]) {
+	rows.sort_by_key(|row| row.name.to_lowercase());
+}
+
+pub fn format_rows(rows: &[ListRow], format: ListFormat) -> Result<String> {
+	Ok(match format {
+		ListFormat::Table => to_table(rows),
+		ListFormat::Json => serde_json::to_string_pretty(rows)?,
+		ListFormat::Csv => to_csv(rows),
+	})
+}
+
+fn to_table(rows: &[ListRow]) -> String {
+	let mut widths = HEADERS.map(str::len);
+	for row in rows {
+		for (width, value) in widths.iter_mut().zip(row.values()) {
+			*width = (*width).max(value.len());
+		}
+	}
+
+	let format_line = |values: [&str; 4]| {
+		values
+			.iter()
+			.zip(widths)
+			.map(|(value, width)| format!("{value:<width$}"))
+			.collect::<Vec<_>>()
+			.join("  ")
+			.trim_end()
+			.to_string()
+	};
+
+	let mut lines = vec![format_line(HEADERS)];
+	lines.extend(rows.iter().map(|row| format_line(row.values())));
+	lines.join("\n")
+}
+
+fn to_csv(rows: &[ListRow]) -> String {
+	let mut lines = vec![HEADERS.join(",")];
+	lines.extend(rows.iter().map(|row| {
+		row.values()
+			.iter()
+			.map(|value| escape_csv(value))
+			.collect::<Vec<_>>()
+			.join(",")
+	}));
+	lines.join("\n")
+}
+
+fn escape_csv(value: &str) -> String {
+	if value.contains([',', '"', '\n', '\r']) {
+		format!("\"{}\"", value.replace('"', "\"\""))
+	} else {
+		value.to_string()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn fixture() -> Vec<ListRow> {
+		vec![
+			ListRow {
+				name: "Better Keys Updated".to_string(),
+				version: "1.2.3".to_string(),
+				target: "server".to_string(),
+				source: "SPT hub".to_string(),
+			},
+			ListRow {
+				name: "SAIN, Solarint's AI".to_string(),
+				version: "2.3.0".to_string(),
+				target: "client".to_string(),
+				source: "GitHub".to_string(),
+			},
+		]
+	}
+
+	#[test]
+	fn json_rows_match_table_data() {
+		let rows = fixture();
+		let table = format_rows(&rows, ListFormat::Table).unwrap();
+		let json = format_rows(&rows, ListFormat::Json).unwrap();
+
+		let parsed: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+		assert_eq!(parsed.len(), rows.len());
+		for (value, row) in parsed.iter().zip(&rows) {
+			assert_eq!(value["name"], row.name.as_str());
+			assert_eq!(value["version"], row.version.as_str());
+			assert_eq!(value["target"], row.target.as_str());
+			assert_eq!(value["source"], row.source.as_str());
+			assert!(table.contains(&row.name));
+		}
+	}
+
+	#[test]
+	fn unmanaged_rows_sort_between_indexed_rows() {
+		let mut rows = fixture();
+		rows.push(ListRow::unmanaged("extracted-tool", Versioning::new("0.4.0").as_ref()));
+		rows.push(ListRow::unmanaged("Config Pack", None));
+
+		sort_rows(&mut rows);
+
+		let names: Vec<_> = rows.iter().map(|row| row.name.as_str()).collect();
+		assert_eq!(
+			names,
+			["Better Keys Updated", "Config Pack", "extracted-tool", "SAIN, Solarint's AI"]
+		);
+		assert_eq!(rows[1].version, "unknown");
+		assert_eq!(rows[2].target, "unmanaged");
+	}
+
+	#[test]
+	fn csv_rows_match_table_data() {
+		let rows = fixture();
+		let table = format_rows(&rows, ListFormat::Table).unwrap();
+		let csv = format_rows(&rows, ListFormat::Csv).unwrap();
+
+		let lines: Vec<_> = csv.lines().collect();
+		assert_eq!(lines[0], "name,version,target,source");
+		assert_eq!(lines[1], "Better Keys Updated,1.2.3,server,SPT hub");
+		assert_eq!(lines[2], "\"SAIN, Solarint's AI\",2.3.0,client,GitHub");
+		assert_eq!(table.lines().count(), lines.len());
+	}
+}
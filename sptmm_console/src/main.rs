@@ -1,15 +1,45 @@
+mod byte_size;
+mod confirm;
+mod list_output;
+mod mod_resolver;
+mod status_output;
+mod update_report;
+
 use std::borrow::Cow;
-use std::time::Duration;
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use clap::{Parser, Subcommand, ValueEnum};
-use indicatif::{ProgressBar, ProgressStyle};
-use sptmm_lib::configuration_access::ConfigurationAccess;
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+use list_output::{ListFormat, ListRow};
+use mod_resolver::ModCandidate;
+use sptmm_lib::configuration_access::{
+	parse_mods_list, AppConfiguration, ConfigurationAccess, ModConfiguration,
+	ModVersionConfiguration, DEFAULT_INSTALL_TARGET,
+};
 use sptmm_lib::path_access::PathAccess;
-use sptmm_lib::remote_mod_access::{ModKind, RemoteModAccess};
-use sptmm_lib::shared_traits::ModVersion;
-use sptmm_lib::spt_access::{InstallTarget, SptAccess};
+use sptmm_lib::remote_mod_access::cache_mod_access::{
+	CacheReference, CachedMod, CachedModVersion, ModCacheStatus,
+};
+use sptmm_lib::remote_mod_access::{
+	total_download_size, ModKind, RemoteError, RemoteModAccess, RetryPolicy,
+	DEFAULT_HUB_CACHE_TTL,
+};
+use sptmm_lib::shared_traits::{ModName, ModVersion};
+use sptmm_lib::spt_access::{
+	BackupCancellation, BackupChange, BackupOptions, CompressionKind, FileProblem, InstallOptions,
+	InstallTarget, InstalledMod, Lockfile, ModDrift, OverwritePolicy, RestoreChange, RestoreScope,
+	SptAccess, SptLayout, SptSupport, DEFAULT_PROTECTED_PLUGIN_FOLDERS,
+};
 use sptmm_lib::time_access::Time;
+use sptmm_lib::version_constraint::VersionConstraint;
+use status_output::StatusRow;
+use tokio::sync::mpsc;
+use update_report::{download_progress, UpdateEvent, UpdateOutcome, UpdateReporter};
+use versions::Versioning;
 
 #[derive(Debug, Parser)]
 #[command(name = "spt mod manager")]
@@ -17,25 +47,289 @@ use sptmm_lib::time_access::Time;
 struct Cli {
 	#[command(subcommand)]
 	command: Commands,
+	/// Answer yes to confirmation prompts, required when not running in a terminal
+	#[arg(short = 'y', long, global = true)]
+	assume_yes: bool,
+	/// Limit the download speed in bytes per second
+	#[arg(long, global = true, value_parser = clap::value_parser!(u64).range(1..))]
+	max_bandwidth: Option<u64>,
+	/// Operate on a named SPT installation from the app configuration instead of the current folder
+	#[arg(long, global = true)]
+	install: Option<String>,
+	/// Retry a download or page fetch that failed on a dropped connection, a timeout or a 5xx this
+	/// many times, overriding the configuration's download_retries
+	#[arg(long, global = true)]
+	download_retries: Option<u32>,
+	/// Wait this many milliseconds before the first retry, doubling for each retry after it
+	#[arg(long, global = true)]
+	retry_backoff_ms: Option<u64>,
+	/// Wait up to this many seconds for GitHub's rate limit to reset instead of failing the mod
+	#[arg(long, global = true, default_value_t = 0)]
+	max_rate_limit_wait_secs: u64,
+	/// Reuse the hub's mod versions while it reports them unchanged for up to this many seconds,
+	/// then fetch them in full again
+	#[arg(long, global = true, default_value_t = DEFAULT_HUB_CACHE_TTL.as_secs())]
+	hub_cache_ttl_secs: u64,
 }
 
 #[derive(Debug, Subcommand)]
 enum Commands {
-	#[command(arg_required_else_help = true)]
 	Update {
-		#[arg(required = true)]
-		target: UpdateTarget,
+		/// Overrides the configuration's default install target, mods with their own target keep it
+		target: Option<UpdateTarget>,
+		/// Read the spt_mods.json configuration from stdin instead of the SPT folder
+		#[arg(long)]
+		config_stdin: bool,
+		/// Read the spt_mods.json configuration from a file path or an http(s) url instead of the SPT
+		/// folder, a fetched configuration is kept for when the url can't be reached
+		#[arg(long, conflicts_with = "config_stdin")]
+		config: Option<String>,
+		/// How many mods to check for an installed version at the same time
+		#[arg(long, default_value_t = 4)]
+		threads: usize,
+		/// Show one progress bar for the whole update and a summary at the end instead of a line per mod
+		#[arg(long)]
+		summary_only: bool,
+		/// Install the rest of a mod when some of its archive entries fail to read
+		#[arg(long)]
+		skip_bad_entries: bool,
+		/// Install without recording the mods in the install index, so they aren't tracked and are
+		/// installed again on every update
+		#[arg(long)]
+		no_index: bool,
+		/// Install mods over files that other mods installed instead of failing them
+		#[arg(long)]
+		force: bool,
+		/// Keep the folders that updating a mod leaves empty
+		#[arg(long)]
+		keep_empty_dirs: bool,
+		/// How many files of a mod to write at the same time, more is faster on SSDs but slower on
+		/// hard drives
+		#[arg(long, default_value_t = 1)]
+		write_threads: usize,
+		/// Install mods again when any of their installed files were deleted
+		#[arg(long)]
+		strict_hash: bool,
+		/// Let mods install files into the protected SPT plugin folders, such as BepInEx/plugins/spt
+		#[arg(long)]
+		allow_core_overwrite: bool,
+		/// Only update the mods of this group, such as optional
+		#[arg(long)]
+		group: Option<String>,
 	},
 	#[command(arg_required_else_help = true)]
 	Backup {
-		backup_to: String,
+		#[arg(required_unless_present = "list")]
+		backup_to: Option<String>,
+		/// Summarize the files of an existing backup by mod folder instead of making one
+		#[arg(long, value_name = "BACKUP", conflicts_with = "backup_to")]
+		list: Option<String>,
+		/// Store files that are identical across mods only once
+		#[arg(long)]
+		dedup: bool,
+		/// Also back up the BepInEx configuration
+		#[arg(long)]
+		include_config: bool,
+		/// Also back up the SPT user profiles
+		#[arg(long)]
+		include_profiles: bool,
+		/// Tag the backup file name, such as before-3.9-update
+		#[arg(long = "name")]
+		label: Option<String>,
+		/// Compress the backed up files, storing them uncompressed is fastest
+		#[arg(long, value_enum, default_value_t = BackupCompression::Stored)]
+		compress: BackupCompression,
+		/// The compression level, higher is smaller but slower
+		#[arg(long)]
+		compress_level: Option<i64>,
 	},
 	#[command(arg_required_else_help = true)]
 	Restore {
+		/// The backup to restore, or the folder to pick the newest backup from with --latest
 		restore_from: String,
+		/// Restore the newest backup in the restore_from folder
+		#[arg(long)]
+		latest: bool,
+		#[arg(long, value_enum, default_value_t = OnConflict::Overwrite)]
+		on_conflict: OnConflict,
+		/// Which files to restore from the backup
+		#[arg(long, value_enum, default_value_t = RestoreTypes::All)]
+		types: RestoreTypes,
+		/// List what restoring would change without extracting anything
+		#[arg(long)]
+		preview: bool,
+		/// Restore only the mod in this folder of user/mods or BepInEx/plugins, overwriting its files
+		#[arg(long = "mod", conflicts_with_all = ["preview", "types", "on_conflict"])]
+		mod_folder: Option<String>,
+	},
+	/// Lists the files added, removed and changed from one backup to another
+	#[command(arg_required_else_help = true)]
+	BackupDiff {
+		/// The older backup
+		from: String,
+		/// The newer backup
+		to: String,
+	},
+	/// Deletes every cached download, or with --keep-versions or --max-size only the oldest versions
+	CleanCache {
+		/// Delete the oldest cached versions until the cache fits in this size, such as 2GB, keeping
+		/// the newest version of every mod
+		#[arg(long, value_parser = byte_size::parse_byte_size)]
+		max_size: Option<u64>,
+		/// Keep only this many of the newest cached versions of every mod, before --max-size applies
+		#[arg(long, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+		keep_versions: Option<usize>,
+	},
+	/// Lists the cached mods with where they came from and reports cached files that are missing
+	/// their manifest or archive
+	CacheInfo {
+		/// Delete the unpaired files instead of only reporting them
+		#[arg(long)]
+		clean: bool,
+	},
+	/// Bundles every cached mod archive and manifest into one zip, for installing on a machine
+	/// without internet
+	#[command(arg_required_else_help = true)]
+	ExportCache {
+		out: PathBuf,
+	},
+	/// Unpacks a zip made by export-cache into the cache, keeping the files the cache already has
+	#[command(arg_required_else_help = true)]
+	ImportCache {
+		bundle: PathBuf,
+	},
+	/// Manage the downloaded mod cache
+	#[command(subcommand)]
+	Cache(CacheCommands),
+	RemoveMods {
+		/// Extra plugin folder names to keep, on top of the SPT core plugin
+		#[arg(long = "protect")]
+		protected_plugins: Vec<String>,
+	},
+	/// Removes the installed files of a single mod, keeping those other installed mods also installed
+	#[command(arg_required_else_help = true)]
+	Uninstall {
+		name: String,
+		/// Keep the folders the mod's files leave empty
+		#[arg(long)]
+		keep_empty_dirs: bool,
+	},
+	/// Lists the installed mods with their versions and install targets, including the unmanaged
+	/// mods extracted to an install_path
+	List {
+		#[arg(long, value_enum, default_value_t = ListFormat::Table)]
+		format: ListFormat,
+		/// Only list the mods of this group, such as optional
+		#[arg(long)]
+		group: Option<String>,
+	},
+	/// Installs a specific, usually older, version of a mod even when a newer one is installed
+	#[command(arg_required_else_help = true)]
+	Downgrade {
+		url: String,
+		version: String,
+		target: Option<UpdateTarget>,
+		/// Let mods install files into the protected SPT plugin folders, such as BepInEx/plugins/spt
+		#[arg(long)]
+		allow_core_overwrite: bool,
+	},
+	/// Removes the installed files of a configured mod and installs it again from the cache
+	#[command(arg_required_else_help = true)]
+	Reinstall {
+		url_or_name: String,
+		/// Look online for the newest version instead of reinstalling the newest cached one
+		#[arg(long)]
+		refresh: bool,
+		/// Let mods install files into the protected SPT plugin folders, such as BepInEx/plugins/spt
+		#[arg(long)]
+		allow_core_overwrite: bool,
+	},
+	/// Locks a configured mod to its installed version by writing it into the configuration
+	#[command(arg_required_else_help = true)]
+	Pin {
+		url_or_name: String,
+	},
+	/// Adds a mod url to the configuration, or every mod of a newline-delimited list of urls
+	#[command(arg_required_else_help = true)]
+	Add {
+		#[arg(required_unless_present = "from")]
+		url: Option<String>,
+		/// The asset name pattern, required for GitHub and GitLab urls
+		#[arg(long, conflicts_with = "from")]
+		github_pattern: Option<String>,
+		/// Read the urls from a file, one per line as `url|pattern|filter` where the pattern and
+		/// filter are optional, skipping blank lines and lines starting with #
+		#[arg(long, conflicts_with = "url")]
+		from: Option<PathBuf>,
+	},
+	/// Clears the version of a configured mod so it updates to the newest version again
+	#[command(arg_required_else_help = true)]
+	Unpin {
+		url_or_name: String,
+	},
+	/// Checks the installed files of every mod against the hashes recorded when installing them
+	Verify {
+		/// How many mods to verify at the same time
+		#[arg(long, default_value_t = 4)]
+		threads: usize,
+	},
+	/// Compares the installed mod versions and file hashes to a lockfile and fails on any difference
+	#[command(arg_required_else_help = true)]
+	Audit {
+		lockfile: PathBuf,
+	},
+	/// Resolves the version each mod would update to and reports the download size without downloading
+	Plan,
+	/// Shows which configured mods have a newer version than the installed one, without installing
+	Status,
+	/// Prints the configuration as update would act on it, with every default filled in and every
+	/// install path expanded, as JSON
+	ConfigDump {
+		/// Resolve as if updating with this target, mods with their own target keep it
+		target: Option<UpdateTarget>,
+		/// Read the spt_mods.json configuration from stdin instead of the SPT folder
+		#[arg(long)]
+		config_stdin: bool,
+		/// Read the spt_mods.json configuration from a file path or an http(s) url instead of the SPT
+		/// folder
+		#[arg(long, conflicts_with = "config_stdin")]
+		config: Option<String>,
+	},
+	/// Reports which installed server mods declare support for an SPT version before upgrading to it
+	#[command(arg_required_else_help = true)]
+	CheckSptUpgrade {
+		target_version: String,
+	},
+	/// Opens the hub page or GitHub releases of a configured mod in the browser
+	#[command(arg_required_else_help = true)]
+	Open {
+		url_or_name: String,
+	},
+	/// Extracts a mod archive, or the newest version of a mod url, to a folder for inspection
+	/// without installing it
+	#[command(arg_required_else_help = true)]
+	PreviewInstall {
+		url_or_archive: String,
+		out_dir: PathBuf,
+	},
+	/// Downloads the newest version of a mod and reports the files it shares with installed mods,
+	/// without installing it
+	#[command(arg_required_else_help = true)]
+	CheckConflicts {
+		url: String,
+		/// The asset name pattern, required for GitHub urls
+		#[arg(long)]
+		github_pattern: Option<String>,
+	},
+}
+
+#[derive(Debug, Subcommand)]
+enum CacheCommands {
+	/// Removes cached versions that none of the profiles use
+	Gc {
+		/// The profile configurations to keep versions for, defaults to the current spt_mods.json
+		profiles: Vec<PathBuf>,
 	},
-	CleanCache,
-	RemoveMods,
 }
 
 #[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
@@ -44,162 +338,1388 @@ enum UpdateTarget {
 	Server,
 }
 
+impl From<UpdateTarget> for InstallTarget {
+	fn from(target: UpdateTarget) -> Self {
+		match target {
+			UpdateTarget::Client => InstallTarget::Client,
+			UpdateTarget::Server => InstallTarget::Server,
+		}
+	}
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum BackupCompression {
+	Stored,
+	Deflated,
+	Zstd,
+}
+
+impl From<BackupCompression> for CompressionKind {
+	fn from(compression: BackupCompression) -> Self {
+		match compression {
+			BackupCompression::Stored => CompressionKind::Stored,
+			BackupCompression::Deflated => CompressionKind::Deflated,
+			BackupCompression::Zstd => CompressionKind::Zstd,
+		}
+	}
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum OnConflict {
+	Overwrite,
+	SkipExisting,
+	OverwriteOlderOnly,
+}
+
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum RestoreTypes {
+	Config,
+	Mods,
+	All,
+}
+
+impl From<RestoreTypes> for RestoreScope {
+	fn from(types: RestoreTypes) -> Self {
+		match types {
+			RestoreTypes::Config => RestoreScope::Config,
+			RestoreTypes::Mods => RestoreScope::Mods,
+			RestoreTypes::All => RestoreScope::All,
+		}
+	}
+}
+
+impl From<OnConflict> for OverwritePolicy {
+	fn from(on_conflict: OnConflict) -> Self {
+		match on_conflict {
+			OnConflict::Overwrite => OverwritePolicy::Overwrite,
+			OnConflict::SkipExisting => OverwritePolicy::SkipExisting,
+			OnConflict::OverwriteOlderOnly => OverwritePolicy::OverwriteOlderOnly,
+		}
+	}
+}
+
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> Result<()> {
 	let args = Cli::parse();
 
-	let path_access = PathAccess::new("./").map_err(|e| anyhow!(e))?;
-	let mut remote_access = RemoteModAccess::init(&path_access).await?;
+	let mut path_access = PathAccess::new("./").map_err(|e| anyhow!(e))?;
+	if let Some(install) = &args.install {
+		let app_configuration = AppConfiguration::read(path_access.config_root()).await?;
+		let spt_root = app_configuration.resolve_installation(install)?.to_path_buf();
+		path_access = path_access.with_spt_root(spt_root);
+	}
+	let mut remote_access = RemoteModAccess::init(&path_access)
+		.await?
+		.with_max_bandwidth(args.max_bandwidth);
+	remote_access.set_max_rate_limit_wait(Duration::from_secs(args.max_rate_limit_wait_secs));
+	remote_access.set_hub_cache_ttl(Duration::from_secs(args.hub_cache_ttl_secs));
+	// Every command fetching mods retries by the flags, an update also by its configuration
+	let default_retry_policy = RetryPolicy::default();
+	remote_access.set_retry_policy(RetryPolicy {
+		retries: args.download_retries.unwrap_or(default_retry_policy.retries),
+		backoff: args
+			.retry_backoff_ms
+			.map_or(default_retry_policy.backoff, Duration::from_millis),
+	});
 	let cfg_access = ConfigurationAccess::init(&path_access).await?;
 	let spt_access = SptAccess::init(&path_access, Time::new()).await?;
 
 	match args.command {
-		Commands::Update { target } => {
-			update(&mut remote_access, &cfg_access, &spt_access, target).await?
+		Commands::Update {
+			target,
+			config_stdin,
+			config,
+			threads,
+			summary_only,
+			skip_bad_entries,
+			no_index,
+			force,
+			keep_empty_dirs,
+			write_threads,
+			strict_hash,
+			allow_core_overwrite,
+			group,
+		} => {
+			let mut configuration =
+				read_configuration(&path_access, &cfg_access, config_stdin, config).await?;
+			if let Some(group) = &group {
+				configuration.retain_group(group);
+			}
+			remote_access.set_retry_policy(
+				configuration.resolve_retry_policy(args.download_retries, args.retry_backoff_ms),
+			);
+			remote_access.set_blocked_hashes(&configuration.blocked_hashes);
+			let install_options = InstallOptions::default()
+				.with_never_install(&configuration.never_install)?
+				.with_skip_bad_entries(skip_bad_entries)
+				.with_no_index(no_index)
+				.with_force(force)
+				.with_keep_empty_dirs(keep_empty_dirs)
+				.with_write_threads(write_threads)
+				.with_strict_hash(strict_hash)
+				.with_allow_core_overwrite(allow_core_overwrite);
+			let spt_access = spt_access.with_install_options(install_options);
+			update(
+				&mut remote_access,
+				configuration,
+				&spt_access,
+				target,
+				threads,
+				summary_only,
+			)
+			.await?
+		}
+		Commands::Backup {
+			list: Some(list),
+			..
+		} => list_backup(&spt_access, &list)?,
+		Commands::Backup {
+			backup_to,
+			dedup,
+			include_config,
+			include_profiles,
+			label,
+			compress,
+			compress_level,
+			list: None,
+		} => {
+			let backup_to = backup_to.context("Found no folder to back up to")?;
+			let options = BackupOptions {
+				deduplicate: dedup,
+				include_config,
+				include_profiles,
+				label,
+				compression: compress.into(),
+				level: compress_level,
+			};
+			backup(&spt_access, &backup_to, options).await?
+		}
+		Commands::Restore {
+			restore_from,
+			latest,
+			on_conflict,
+			types,
+			preview,
+			mod_folder,
+		} => {
+			let restore_from = if latest {
+				SptAccess::<Time>::find_latest_backup(&restore_from)?
+					.to_string_lossy()
+					.to_string()
+			} else {
+				restore_from
+			};
+			if preview {
+				preview_restore(&spt_access, &restore_from, types.into())?;
+				return Ok(());
+			}
+			if let Some(mod_folder) = mod_folder {
+				let question = format!("This will restore '{mod_folder}' from '{restore_from}' over its installed files. Continue?");
+				if confirm_or_cancel(&question, args.assume_yes)? {
+					spt_access.restore_mod_from(&restore_from, &mod_folder)?;
+					println!("Restored '{mod_folder}' from: {restore_from}");
+				}
+				return Ok(());
+			}
+			let question = format!("This will restore the files from '{restore_from}' over your SPT folder. Continue?");
+			if confirm_or_cancel(&question, args.assume_yes)? {
+				restore(&spt_access, &restore_from, on_conflict.into(), types.into())?
+			}
+		}
+		Commands::BackupDiff { from, to } => backup_diff(&from, &to)?,
+		Commands::CleanCache {
+			max_size: None,
+			keep_versions: None,
+		} => cleanup(&mut remote_access, &spt_access).await?,
+		Commands::CleanCache {
+			max_size,
+			keep_versions,
+		} => prune_cache(&mut remote_access, keep_versions, max_size).await?,
+		Commands::CacheInfo { clean } => cache_info(&mut remote_access, clean).await?,
+		Commands::ExportCache { out } => {
+			let files = remote_access.export_cache(&out).await?;
+			println!("Bundled {files} cached file(s) into {}", out.display());
+		}
+		Commands::ImportCache { bundle } => {
+			let files = remote_access.import_cache(&bundle).await?;
+			println!("Imported {files} file(s) into the cache from {}", bundle.display());
+		}
+		Commands::Cache(CacheCommands::Gc { profiles }) => {
+			collect_cache_garbage(&mut remote_access, &cfg_access, profiles).await?
+		}
+		Commands::RemoveMods { protected_plugins } => {
+			let question = "This will delete all mods and clear config. Continue?";
+			if confirm_or_cancel(question, args.assume_yes)? {
+				let protected_plugins = DEFAULT_PROTECTED_PLUGIN_FOLDERS
+					.iter()
+					.map(|name| name.to_string())
+					.chain(protected_plugins)
+					.collect();
+				remove_mods(&spt_access.with_protected_plugin_folders(protected_plugins)).await?
+			}
+		}
+		Commands::Uninstall {
+			name,
+			keep_empty_dirs,
+		} => {
+			let install_options = InstallOptions::default().with_keep_empty_dirs(keep_empty_dirs);
+			uninstall(&spt_access.with_install_options(install_options), &name)?
+		}
+		Commands::List { format, group } => {
+			list(&spt_access, &cfg_access, &remote_access, format, group.as_deref()).await?
+		}
+		Commands::Downgrade {
+			url,
+			version,
+			target,
+			allow_core_overwrite,
+		} => {
+			let install_options =
+				InstallOptions::default().with_allow_core_overwrite(allow_core_overwrite);
+			let spt_access = spt_access.with_install_options(install_options);
+			downgrade(&mut remote_access, &cfg_access, &spt_access, &url, &version, target).await?
+		}
+		Commands::Reinstall {
+			url_or_name,
+			refresh,
+			allow_core_overwrite,
+		} => {
+			let install_options =
+				InstallOptions::default().with_allow_core_overwrite(allow_core_overwrite);
+			let spt_access = spt_access.with_install_options(install_options);
+			reinstall(&mut remote_access, &cfg_access, &spt_access, &url_or_name, refresh).await?
+		}
+		Commands::Pin { url_or_name } => {
+			pin(&remote_access, &cfg_access, &spt_access, &url_or_name, true).await?
+		}
+		Commands::Add {
+			url,
+			github_pattern,
+			from,
+		} => add(&cfg_access, url, github_pattern, from).await?,
+		Commands::Unpin { url_or_name } => {
+			pin(&remote_access, &cfg_access, &spt_access, &url_or_name, false).await?
 		}
-		Commands::Backup { backup_to } => backup(&spt_access, &backup_to)?,
-		Commands::Restore { restore_from } => restore(&spt_access, &restore_from)?,
-		Commands::CleanCache => cleanup(&mut remote_access, &spt_access).await?,
-		Commands::RemoveMods => remove_mods(&spt_access).await?,
+		Commands::Verify { threads } => verify(&spt_access, threads)?,
+		Commands::Audit { lockfile } => audit(&spt_access, &lockfile)?,
+		Commands::Plan => plan(&mut remote_access, &cfg_access).await?,
+		Commands::Status => status(&mut remote_access, &cfg_access, &spt_access).await?,
+		Commands::ConfigDump {
+			target,
+			config_stdin,
+			config,
+		} => {
+			let configuration =
+				read_configuration(&path_access, &cfg_access, config_stdin, config).await?;
+			let resolved = configuration.resolve(
+				spt_access.layout(),
+				target.map(InstallTarget::from),
+				args.download_retries,
+				args.retry_backoff_ms,
+			);
+			println!("{}", ConfigurationAccess::to_json(&resolved)?);
+		}
+		Commands::CheckSptUpgrade { target_version } => {
+			check_spt_upgrade(&spt_access, &target_version)?
+		}
+		Commands::Open { url_or_name } => open_page(&cfg_access, &url_or_name).await?,
+		Commands::PreviewInstall {
+			url_or_archive,
+			out_dir,
+		} => {
+			preview_install(&mut remote_access, &cfg_access, &spt_access, &url_or_archive, &out_dir)
+				.await?
+		}
+		Commands::CheckConflicts {
+			url,
+			github_pattern,
+		} => check_conflicts(&mut remote_access, &spt_access, &url, github_pattern).await?,
 	}
 
 	Ok(())
 }
 
+/// Reads the configuration from stdin, the `--config` path or url, or else the SPT folder.
+async fn read_configuration(
+	path_access: &PathAccess,
+	cfg_access: &ConfigurationAccess,
+	config_stdin: bool,
+	config: Option<String>,
+) -> Result<ModConfiguration> {
+	if config_stdin {
+		return ConfigurationAccess::read_remote_mods_from(std::io::stdin().lock());
+	}
+	match config {
+		Some(config) if ConfigurationAccess::is_remote(&config) => {
+			ConfigurationAccess::fetch_remote_mods(&config, path_access.cache_root()).await
+		}
+		Some(config) => ConfigurationAccess::from_file(config).read_remote_mods().await,
+		None => cfg_access.read_remote_mods().await,
+	}
+}
+
 async fn cleanup(remote_access: &mut RemoteModAccess, spt_access: &SptAccess<Time>) -> Result<()> {
 	remote_access.clear_cache().await?;
 	spt_access.clear_mm_cache().await?;
 	Ok(())
 }
 
+/// A configured mod together with the cached archive it updates to.
+struct ResolvedMod<'a> {
+	mod_cfg: &'a ModVersionConfiguration,
+	raw_file_name: Option<String>,
+	cached_mod: CachedModVersion,
+}
+
 async fn update(
 	remote_mod_access: &mut RemoteModAccess,
-	cfg_man: &ConfigurationAccess,
+	configuration: ModConfiguration,
 	spt_access: &SptAccess<Time>,
-	target: UpdateTarget,
+	target: Option<UpdateTarget>,
+	threads: usize,
+	summary_only: bool,
 ) -> Result<()> {
-	let mod_cfg = cfg_man.read_remote_mods().await?;
+	let mut reporter = UpdateReporter::new(summary_only, configuration.mods.len());
+
+	let mut resolved_mods = Vec::with_capacity(configuration.mods.len());
+	for mod_cfg in &configuration.mods {
+		match resolve_mod(remote_mod_access, &reporter, mod_cfg).await {
+			Ok(resolved_mod) => resolved_mods.push(resolved_mod),
+			Err((bar, event)) => reporter.finish_mod(bar, event),
+		}
+	}
+	remote_mod_access.set_download_progress(None);
 
-	for mod_cfg in mod_cfg.mods {
-		let mod_url = mod_cfg.url;
+	// Checking the install index only reads the SPT folder, so every mod is checked at once before
+	// the installs run one at a time.
+	let install_target = |mod_cfg: &ModVersionConfiguration| {
+		configuration.resolve_install_target(mod_cfg, target.map(InstallTarget::from))
+	};
+	let index_mods: Vec<_> = resolved_mods
+		.iter()
+		.enumerate()
+		.filter(|(_, resolved_mod)| resolved_mod.mod_cfg.install_path.is_none())
+		.collect();
+	let index_checks: Vec<_> = index_mods
+		.iter()
+		.map(|(_, resolved_mod)| {
+			(
+				resolved_mod.cached_mod.path.as_path(),
+				&resolved_mod.cached_mod,
+				install_target(resolved_mod.mod_cfg),
+			)
+		})
+		.collect();
+	let bar = reporter.start_step("Checking the installed versions");
+	let installed_checks = spt_access.find_installed_versions(&index_checks, threads);
+	bar.finish_and_clear();
+	let mut is_installed = vec![false; resolved_mods.len()];
+	for ((index, _), installed) in index_mods.iter().zip(installed_checks?) {
+		is_installed[*index] = installed;
+	}
 
-		let mod_kind = match ModKind::parse(&mod_url, mod_cfg.github_pattern, mod_cfg.github_filter)
-		{
-			Ok(mod_kind) => mod_kind,
-			Err(err) => {
-				println!("Failed to parse '{mod_url}' with: {err}");
+	for (resolved_mod, is_installed) in resolved_mods.iter().zip(is_installed) {
+		let mod_url = &resolved_mod.mod_cfg.url;
+		let bar = reporter.start_step(format!("Updating: {mod_url}"));
+		let (outcome, message) = if resolved_mod.mod_cfg.install_path.is_some() {
+			install_to_path(spt_access, resolved_mod, &bar)?
+		} else {
+			install_to_spt(
+				spt_access,
+				&reporter,
+				resolved_mod,
+				is_installed,
+				install_target(resolved_mod.mod_cfg),
+				&configuration.spt_version,
+				&bar,
+			)?
+		};
+		reporter.finish_mod(bar, UpdateEvent::new(mod_url, outcome, message));
+	}
+	reporter.finish();
+	Ok(())
+}
+
+/// Copies or extracts a mod with an `install_path` there, outside of the SPT folders.
+fn install_to_path(
+	spt_access: &SptAccess<Time>,
+	resolved_mod: &ResolvedMod,
+	bar: &ProgressBar,
+) -> Result<(UpdateOutcome, String)> {
+	let ResolvedMod {
+		mod_cfg,
+		raw_file_name,
+		cached_mod,
+	} = resolved_mod;
+	let mod_url = &mod_cfg.url;
+	let install_path = spt_access
+		.layout()
+		.resolve_install_path(mod_cfg.install_path.as_deref().unwrap_or_default());
+	let version = cached_mod.get_version();
+
+	if let Some(file_name) = raw_file_name {
+		let target_path = install_path.join(file_name);
+		if spt_access.is_same_installed_file(&cached_mod.path, &target_path)? {
+			return Ok((
+				UpdateOutcome::Skipped,
+				format!(
+					"Version {version} has already been copied to '{}' for: {mod_url}",
+					target_path.display()
+				),
+			));
+		}
+		return Ok(match spt_access.install_file_to_path(&cached_mod.path, &target_path) {
+			Ok(()) => (
+				UpdateOutcome::Installed,
+				format!("Copied version {version} to '{}' for: {mod_url}", target_path.display()),
+			),
+			Err(err) => (
+				UpdateOutcome::Failed,
+				format!("Failed to copy '{mod_url}' with error: {err}"),
+			),
+		});
+	}
+
+	if spt_access.is_same_installed_path_version(&cached_mod.path, &install_path)? {
+		return Ok((
+			UpdateOutcome::Skipped,
+			format!(
+				"Version {version} has already been extracted to '{}' for: {mod_url}",
+				install_path.display()
+			),
+		));
+	}
+	bar.set_message(format!("Extracting the newest version for: {mod_url}"));
+	Ok(match spt_access.install_mod_to_path(&cached_mod.path, &install_path) {
+		Ok(_) => (
+			UpdateOutcome::Installed,
+			format!("Extracted version {version} to '{}' for: {mod_url}", install_path.display()),
+		),
+		Err(err) => (
+			UpdateOutcome::Failed,
+			format!("Failed to extract '{mod_url}' with error: {err}"),
+		),
+	})
+}
+
+fn install_to_spt(
+	spt_access: &SptAccess<Time>,
+	reporter: &UpdateReporter,
+	resolved_mod: &ResolvedMod,
+	is_installed: bool,
+	install_target: InstallTarget,
+	spt_version: &Versioning,
+	bar: &ProgressBar,
+) -> Result<(UpdateOutcome, String)> {
+	let cached_mod = &resolved_mod.cached_mod;
+	let mod_url = &resolved_mod.mod_cfg.url;
+	let version = cached_mod.get_version();
+	if is_installed {
+		return Ok((
+			UpdateOutcome::Skipped,
+			format!("Version {version} has already been installed for: {mod_url}"),
+		));
+	}
+	bar.set_message(format!("Installing the newest version for: {mod_url}"));
+	let report = match spt_access.install_mod(&cached_mod.path, cached_mod, install_target) {
+		Ok(report) => report,
+		Err(err) => {
+			return Ok((
+				UpdateOutcome::Failed,
+				format!("Failed to install '{mod_url}' with error: {err}"),
+			))
+		}
+	};
+	for skipped_file in &report.skipped_files {
+		reporter.note(&format!("Skipped '{skipped_file}' as it matches never_install"));
+	}
+	for (entry, err) in &report.bad_entries {
+		reporter.note(&format!("Skipped '{entry}' of '{mod_url}' as it failed to read: {err}"));
+	}
+	for conflict in &report.conflicts {
+		reporter.note(&format!(
+			"Warning: '{mod_url}' overwrote '{}' of {}",
+			conflict.path, conflict.installed_by
+		));
+	}
+	for entry in &report.invalid_names {
+		reporter.note(&format!("Warning: skipped '{entry}' of '{mod_url}' as its name isn't UTF-8"));
+	}
+	for warning in spt_access.check_dependencies(&report.packages, spt_version)? {
+		reporter.note(&format!("Warning: {warning}"));
+	}
+	Ok((
+		UpdateOutcome::Installed,
+		format!("Installed version {version} for: {mod_url}"),
+	))
+}
+
+/// Adds how long until mods can be fetched again to a spent rate limit.
+fn describe_remote_error(err: &anyhow::Error) -> String {
+	match err.downcast_ref::<RemoteError>() {
+		Some(RemoteError::RateLimited { reset_at }) => {
+			let now = SystemTime::now()
+				.duration_since(UNIX_EPOCH)
+				.map_or(0, |since_epoch| since_epoch.as_secs() as i64);
+			let minutes = ((reset_at.timestamp() - now).max(0) + 59) / 60;
+			format!("{err}, mods can be fetched again in {minutes} minute(s)")
+		}
+		None => err.to_string(),
+	}
+}
+
+/// Finds and caches the version a mod updates to, or the event reporting why it can't be found.
+async fn resolve_mod<'a>(
+	remote_mod_access: &mut RemoteModAccess,
+	reporter: &UpdateReporter,
+	mod_cfg: &'a ModVersionConfiguration,
+) -> Result<ResolvedMod<'a>, (ProgressBar, UpdateEvent)> {
+	let mod_url = &mod_cfg.url;
+	let failed = |bar, message| Err((bar, UpdateEvent::new(mod_url, UpdateOutcome::Failed, message)));
+
+	let bar = reporter.start_step(format!("Finding versions for: {mod_url}"));
+	let mod_kind = match ModKind::from_configuration(mod_cfg) {
+		Ok(mod_kind) => mod_kind,
+		Err(err) => return failed(bar, format!("Failed to parse '{mod_url}' with: {err}")),
+	};
+
+	let raw_file_name = match &mod_kind {
+		ModKind::RawFile(link) => Some(link.get_file_name().to_string()),
+		_ => None,
+	};
+
+	remote_mod_access.set_download_timeout(mod_cfg.timeout_secs.map(Duration::from_secs));
+	remote_mod_access.set_version_scheme(mod_cfg.version_scheme.clone());
+	remote_mod_access.set_allow_prerelease(mod_cfg.allow_prerelease);
+	remote_mod_access.set_min_release_date(mod_cfg.github_min_release_date);
+	remote_mod_access.set_download_progress(Some(download_progress(&bar)));
+	let cached_mod = match &mod_cfg.version {
+		None => {
+			bar.set_message(format!("Finding newest version online for: {mod_url}"));
+			match remote_mod_access.get_newest_release(mod_kind).await {
+				Ok(mod_version) => mod_version,
+				Err(err) => {
+					let err = describe_remote_error(&err);
+					return failed(bar, format!("Failed storing mod '{mod_url}' with error: {err}"));
+				}
+			}
+		}
+		Some(version) => {
+			bar.set_message(format!("Finding version '{version}' for: {mod_url}"));
+			let option = match remote_mod_access
+				.get_specific_version(mod_kind, version, mod_cfg.version_filter.as_deref())
+				.await
+			{
+				Ok(mod_version) => mod_version,
+				Err(err) => {
+					let err = describe_remote_error(&err);
+					return failed(
+						bar,
+						format!("Failed to find versions for '{mod_url}' with error: {err}"),
+					);
+				}
+			};
+
+			let Some(cached_mod) = option else {
+				return failed(bar, format!("Failed to find version '{version}' for: {mod_url}"));
+			};
+			cached_mod
+		}
+	};
+	bar.finish_and_clear();
+
+	Ok(ResolvedMod {
+		mod_cfg,
+		raw_file_name,
+		cached_mod,
+	})
+}
+
+async fn prune_cache(
+	remote_access: &mut RemoteModAccess,
+	keep_versions: Option<usize>,
+	max_size: Option<u64>,
+) -> Result<()> {
+	let mut removed = Vec::new();
+	if let Some(keep_versions) = keep_versions {
+		removed.extend(remote_access.prune_cache_versions(keep_versions).await?);
+	}
+	if let Some(max_size) = max_size {
+		removed.extend(remote_access.prune_cache_to_size(max_size).await?);
+	}
+	for path in &removed {
+		println!("Removed: {}", path.display());
+	}
+	let size = remote_access.cache_size().await?;
+	println!("Removed {} cached versions, the cache takes {}", removed.len(), HumanBytes(size));
+	if let Some(max_size) = max_size.filter(|max_size| size > *max_size) {
+		let max_size = HumanBytes(max_size);
+		println!("The newest version of every mod is kept, which takes more than {max_size}");
+	}
+	Ok(())
+}
+
+async fn cache_info(remote_access: &mut RemoteModAccess, clean: bool) -> Result<()> {
+	for cached_mod in remote_access.get_cached_mods() {
+		let versions: Vec<_> = cached_mod
+			.get_versions()
+			.iter()
+			.map(|version| version.get_version().to_string())
+			.collect();
+		println!(
+			"{} from {}: {}",
+			cached_mod.get_name(),
+			cached_mod.get_mod_kind().get_source_name(),
+			versions.join(", ")
+		);
+	}
+
+	let report = if clean {
+		remote_access.clean_cache().await?
+	} else {
+		remote_access.check_cache().await?
+	};
+	if report.is_healthy() {
+		println!("All cached archives are paired with a manifest");
+		return Ok(());
+	}
+
+	let action = if clean { "Deleted" } else { "Found" };
+	for path in &report.unpaired_manifests {
+		println!("{action} manifest without an archive: {}", path.display());
+	}
+	for path in &report.unpaired_archives {
+		println!("{action} archive without a manifest: {}", path.display());
+	}
+	if !clean {
+		println!("Run with --clean to delete the unpaired files");
+	}
+	Ok(())
+}
+
+async fn collect_cache_garbage(
+	remote_access: &mut RemoteModAccess,
+	cfg_access: &ConfigurationAccess,
+	profiles: Vec<PathBuf>,
+) -> Result<()> {
+	let profiles = if profiles.is_empty() {
+		vec![cfg_access.clone()]
+	} else {
+		profiles.into_iter().map(ConfigurationAccess::from_file).collect()
+	};
+
+	let mut references = Vec::new();
+	for profile in profiles {
+		for mod_cfg in profile.read_remote_mods().await?.mods {
+			references.push(CacheReference::from_configuration(&mod_cfg)?);
+		}
+	}
+
+	let removed = remote_access.collect_cache_garbage(&references).await?;
+	for path in &removed {
+		println!("Removed: {}", path.display());
+	}
+	println!("Removed {} unused cached versions", removed.len());
+	Ok(())
+}
+
+async fn remove_mods(spt_access: &SptAccess<Time>) -> Result<()> {
+	let deleted_files = spt_access.remove_all_mods().await?;
+	for file in deleted_files {
+		println!("Deleted: {}", file.to_string_lossy());
+	}
+	Ok(())
+}
+
+fn uninstall(spt_access: &SptAccess<Time>, name: &str) -> Result<()> {
+	let installed_mods = spt_access.list_installed()?;
+	let candidates: Vec<_> = installed_mods.iter().map(ModCandidate::from).collect();
+	let candidate = mod_resolver::pick_mod(
+		name,
+		&candidates,
+		std::io::stdin().is_terminal(),
+		std::io::stdin().lock(),
+		std::io::stdout(),
+	)?;
+	let installed_mod = installed_mods
+		.iter()
+		.find(|installed_mod| installed_mod.name == candidate.name)
+		.context("Found no installed mod for the picked name")?;
+	// Index entries written before targets were recorded can be removed for either target
+	let install_target = installed_mod.install_target.unwrap_or(DEFAULT_INSTALL_TARGET);
+	for file in spt_access.uninstall_mod(installed_mod, install_target)? {
+		println!("Deleted: {}", file.to_string_lossy());
+	}
+	println!("Uninstalled: {}", installed_mod.name);
+	Ok(())
+}
+
+async fn list(
+	spt_access: &SptAccess<Time>,
+	cfg_access: &ConfigurationAccess,
+	remote_access: &RemoteModAccess,
+	format: ListFormat,
+	group: Option<&str>,
+) -> Result<()> {
+	let cached_mods = remote_access.get_cached_mods();
+	// Groups are only known from the configuration, which is optional without one
+	let configuration = match group {
+		Some(_) => Some(cfg_access.read_remote_mods().await?),
+		None => cfg_access.read_remote_mods().await.ok(),
+	};
+	let mut installed_mods = spt_access.list_installed()?;
+	if let (Some(group), Some(configuration)) = (group, &configuration) {
+		let group_names: Vec<_> = configuration
+			.mods
+			.iter()
+			.filter(|mod_cfg| mod_cfg.is_in_group(group))
+			.filter_map(|mod_cfg| ModKind::from_configuration(mod_cfg).ok())
+			.filter_map(|mod_kind| find_installed_mod(remote_access, &mod_kind, &installed_mods))
+			.map(|installed_mod| installed_mod.name.clone())
+			.collect();
+		installed_mods.retain(|installed_mod| group_names.contains(&installed_mod.name));
+	}
+	let mut rows: Vec<ListRow> = installed_mods
+		.iter()
+		.map(|installed_mod| {
+			let mod_kind = cached_mods
+				.iter()
+				.find(|cached_mod| cached_mod.is_same_name(installed_mod))
+				.map(CachedMod::get_mod_kind);
+			ListRow::from(installed_mod).with_source(mod_kind)
+		})
+		.collect();
+	// Mods extracted to an install_path aren't indexed, so they're found through the configuration
+	if let Some(configuration) = &configuration {
+		for mod_cfg in &configuration.mods {
+			if group.is_some_and(|group| !mod_cfg.is_in_group(group)) {
+				continue;
+			}
+			let Some(install_path) = &mod_cfg.install_path else {
+				continue;
+			};
+			if !spt_access.is_installed_to_path(spt_access.layout().resolve_install_path(install_path)) {
 				continue;
 			}
+			let mod_kind = ModKind::from_configuration(mod_cfg).ok();
+			let name = match &mod_kind {
+				Some(mod_kind) => mod_kind.get_short_name().to_string(),
+				None => mod_cfg.url.clone(),
+			};
+			let version = mod_cfg.version.as_ref().and_then(VersionConstraint::as_exact);
+			let row = ListRow::unmanaged(&name, version);
+			rows.push(row.with_source(mod_kind.as_ref()));
+		}
+	}
+	list_output::sort_rows(&mut rows);
+	println!("{}", list_output::format_rows(&rows, format)?);
+	Ok(())
+}
+
+async fn downgrade(
+	remote_access: &mut RemoteModAccess,
+	cfg_access: &ConfigurationAccess,
+	spt_access: &SptAccess<Time>,
+	url: &str,
+	version: &str,
+	target: Option<UpdateTarget>,
+) -> Result<()> {
+	let version = Versioning::new(version).ok_or_else(|| anyhow!("Invalid version: {version}"))?;
+	let configuration = cfg_access.read_remote_mods().await?;
+	remote_access.set_blocked_hashes(&configuration.blocked_hashes);
+	let mod_cfg = configuration.mods.iter().find(|mod_cfg| mod_cfg.url == url);
+	let (mod_kind, version_filter) = match mod_cfg {
+		Some(mod_cfg) => (
+			ModKind::from_configuration(mod_cfg)?,
+			mod_cfg.version_filter.as_deref(),
+		),
+		None => (ModKind::parse(url, None, None)?, None),
+	};
+	let install_target = match mod_cfg {
+		Some(mod_cfg) => configuration.resolve_install_target(mod_cfg, target.map(InstallTarget::from)),
+		None => configuration.resolve_default_install_target(target.map(InstallTarget::from)),
+	};
+
+	let bar = ProgressBar::new_spinner();
+	bar.enable_steady_tick(Duration::from_millis(100));
+	bar.set_message(format!("Finding version '{version}' for: {url}"));
+	let Some(cached_mod) = remote_access
+		.get_specific_version(mod_kind, &VersionConstraint::from(version.clone()), version_filter)
+		.await?
+	else {
+		fail_with_error(bar, format!("Failed to find version '{version}' for: {url}"));
+		return Ok(());
+	};
+
+	let installed_version = spt_access.installed_version(&cached_mod)?;
+	bar.set_message(format!("Installing version {version} for: {url}"));
+	match spt_access.install_mod(&cached_mod.path, &cached_mod, install_target) {
+		Ok(_) => bar.finish_with_message(match installed_version {
+			Some(installed_version) => {
+				format!("Downgraded from version {installed_version} to {version} for: {url}")
+			}
+			None => format!("Installed version {version} for: {url}"),
+		}),
+		Err(err) => fail_with_error(bar, format!("Failed to install '{url}' with error: {err}")),
+	}
+	Ok(())
+}
+
+async fn reinstall(
+	remote_access: &mut RemoteModAccess,
+	cfg_access: &ConfigurationAccess,
+	spt_access: &SptAccess<Time>,
+	url_or_name: &str,
+	refresh: bool,
+) -> Result<()> {
+	let configuration = cfg_access.read_remote_mods().await?;
+	let mod_cfg = pick_configured_mod(&configuration, url_or_name)?;
+	let mod_url = &mod_cfg.url;
+	let mod_kind = ModKind::from_configuration(mod_cfg)?;
+	let install_target = configuration.resolve_install_target(mod_cfg, None);
+	remote_access.set_blocked_hashes(&configuration.blocked_hashes);
+
+	let bar = ProgressBar::new_spinner();
+	bar.enable_steady_tick(Duration::from_millis(100));
+	let cached_mod = if refresh {
+		bar.set_message(format!("Finding newest version online for: {mod_url}"));
+		match &mod_cfg.version {
+			Some(version) => remote_access
+				.get_specific_version(mod_kind, version, mod_cfg.version_filter.as_deref())
+				.await?
+				.with_context(|| format!("Failed to find version '{version}' for: {mod_url}"))?,
+			None => remote_access.get_newest_release(mod_kind).await?,
+		}
+	} else {
+		remote_access.get_newest_cached(&mod_kind).with_context(|| {
+			format!("Found no cached version of '{mod_url}', run Reinstall with --refresh to download it")
+		})?
+	};
+
+	// The archive is found before uninstalling, so a failed download leaves the mod as it was
+	bar.set_message(format!("Removing the installed files of: {mod_url}"));
+	let removed = spt_access.uninstall_mod(&cached_mod, install_target)?;
+	bar.set_message(format!("Installing version {} for: {mod_url}", cached_mod.get_version()));
+	match spt_access.install_mod(&cached_mod.path, &cached_mod, install_target) {
+		Ok(report) => bar.finish_with_message(format!(
+			"Reinstalled version {} for: {mod_url} ({} paths removed, {} files installed)",
+			cached_mod.get_version(),
+			removed.len(),
+			report.installed_files
+		)),
+		Err(err) => fail_with_error(bar, format!("Failed to reinstall '{mod_url}' with error: {err}")),
+	}
+	Ok(())
+}
+
+async fn preview_install(
+	remote_access: &mut RemoteModAccess,
+	cfg_access: &ConfigurationAccess,
+	spt_access: &SptAccess<Time>,
+	url_or_archive: &str,
+	out_dir: &Path,
+) -> Result<()> {
+	let bar = ProgressBar::new_spinner();
+	bar.enable_steady_tick(Duration::from_millis(100));
+	let archive_path = if Path::new(url_or_archive).is_file() {
+		PathBuf::from(url_or_archive)
+	} else {
+		let configuration = cfg_access.read_remote_mods().await?;
+		remote_access.set_blocked_hashes(&configuration.blocked_hashes);
+		let mod_kind = match configuration.mods.iter().find(|mod_cfg| mod_cfg.url == url_or_archive) {
+			Some(mod_cfg) => ModKind::from_configuration(mod_cfg)?,
+			None => ModKind::parse(url_or_archive, None, None)?,
 		};
+		bar.set_message(format!("Finding newest version online for: {url_or_archive}"));
+		remote_access.get_newest_release(mod_kind).await?.path
+	};
 
-		let bar = ProgressBar::new_spinner();
-		bar.enable_steady_tick(Duration::from_millis(100));
+	bar.set_message(format!("Extracting '{}'", archive_path.display()));
+	match spt_access.preview_install(&archive_path, out_dir) {
+		Ok(()) => bar.finish_with_message(format!(
+			"Extracted '{}' to '{}' without installing it",
+			archive_path.display(),
+			out_dir.display()
+		)),
+		Err(err) => fail_with_error(
+			bar,
+			format!("Failed to extract '{url_or_archive}' with error: {err}"),
+		),
+	}
+	Ok(())
+}
 
-		let cached_mod = match mod_cfg.version {
-			None => {
-				bar.set_message(format!("Finding newest version online for: {mod_url}"));
-				let result = remote_mod_access.get_newest_release(mod_kind).await;
-				match result {
-					Ok(mod_version) => mod_version,
-					Err(err) => {
-						fail_with_error(
-							bar,
-							format!("Failed storing mod '{mod_url}' with error: {err}"),
-						);
-						continue;
-					}
+async fn check_conflicts(
+	remote_access: &mut RemoteModAccess,
+	spt_access: &SptAccess<Time>,
+	url: &str,
+	github_pattern: Option<String>,
+) -> Result<()> {
+	let mod_kind = ModKind::parse(url, github_pattern, None)?;
+	let bar = ProgressBar::new_spinner();
+	bar.enable_steady_tick(Duration::from_millis(100));
+	bar.set_message(format!("Finding newest version online for: {url}"));
+	let cached_mod = remote_access.get_newest_release(mod_kind).await;
+	bar.finish_and_clear();
+	let cached_mod = cached_mod?;
+
+	let paths = spt_access.inspect_archive(&cached_mod.path, InstallTarget::Client)?;
+	let conflicts = spt_access.find_conflicts(&paths)?;
+	if conflicts.is_empty() {
+		println!(
+			"Version {} of '{url}' shares no files with the installed mods",
+			cached_mod.get_version()
+		);
+		return Ok(());
+	}
+	for conflict in &conflicts {
+		println!("'{}' is already installed by: {}", conflict.path, conflict.installed_by);
+	}
+	println!(
+		"Version {} of '{url}' would overwrite {} file(s) of installed mods",
+		cached_mod.get_version(),
+		conflicts.len()
+	);
+	Ok(())
+}
+
+fn verify(spt_access: &SptAccess<Time>, threads: usize) -> Result<()> {
+	let bar = ProgressBar::new_spinner();
+	bar.enable_steady_tick(Duration::from_millis(100));
+	bar.set_message("Verifying installed mods");
+	let verifications = spt_access.verify_installed(threads, |verified_mods| {
+		bar.set_message(format!("Verifying installed mods ({verified_mods} done)"));
+	})?;
+	bar.finish_and_clear();
+
+	for verification in &verifications {
+		if verification.is_intact() {
+			println!("{}: {} files intact", verification.name, verification.checked_files);
+			continue;
+		}
+		println!("{}: {} problems", verification.name, verification.problems.len());
+		for (file, problem) in &verification.problems {
+			match problem {
+				FileProblem::Missing => println!("  missing: {file}"),
+				FileProblem::Modified => println!("  modified: {file}"),
+				FileProblem::Unreadable(err) => println!("  unreadable: {file} ({err})"),
+			}
+		}
+	}
+	Ok(())
+}
+
+fn check_spt_upgrade(spt_access: &SptAccess<Time>, target_version: &str) -> Result<()> {
+	let target_version = Versioning::new(target_version)
+		.ok_or_else(|| anyhow!("Invalid version: {target_version}"))?;
+	let compatibilities = spt_access.check_spt_upgrade(&target_version)?;
+	let mut unsupported = 0;
+	for compatibility in &compatibilities {
+		let mod_name = &compatibility.mod_name;
+		match &compatibility.support {
+			SptSupport::Supported => println!("supported:     {mod_name}"),
+			SptSupport::Unsupported { requirement } => {
+				unsupported += 1;
+				println!("unsupported:   {mod_name} requires SPT {requirement}");
+			}
+			SptSupport::Undeclared => println!("undeclared:    {mod_name}"),
+		}
+	}
+	println!(
+		"{unsupported} of {} server mods don't support SPT {target_version}, client plugins declare no SPT version",
+		compatibilities.len()
+	);
+	Ok(())
+}
+
+fn audit(spt_access: &SptAccess<Time>, lockfile_path: &Path) -> Result<()> {
+	let lockfile = Lockfile::read(lockfile_path)?;
+	let drift = spt_access.audit(&lockfile)?;
+	if drift.is_empty() {
+		println!("All {} mods match the lockfile", lockfile.mods.len());
+		return Ok(());
+	}
+	let unknown = || "unknown".to_string();
+	for mod_drift in &drift {
+		match mod_drift {
+			ModDrift::Missing { name } => println!("missing: {name}"),
+			ModDrift::Extra { name } => println!("not in lockfile: {name}"),
+			ModDrift::WrongVersion {
+				name,
+				locked,
+				installed,
+			} => println!(
+				"wrong version: {name} is {} but the lockfile has {}",
+				installed.clone().unwrap_or_else(unknown),
+				locked.clone().unwrap_or_else(unknown)
+			),
+			ModDrift::HashMismatch { name, files } => {
+				println!("hash mismatch: {name}");
+				for file in files {
+					println!("  {file}");
 				}
 			}
-			Some(version) => {
-				bar.set_message(format!("Finding version '{version}' for: {mod_url}"));
-
-				let option = match remote_mod_access
-					.get_specific_version(mod_kind, &version, mod_cfg.version_filter.as_deref())
-					.await
-				{
-					Ok(mod_version) => mod_version,
-					Err(err) => {
-						fail_with_error(
-							bar,
-							format!("Failed to find versions for '{mod_url}' with error: {err}"),
-						);
-						continue;
-					}
-				};
+		}
+	}
+	Err(anyhow!("Found {} differences from the lockfile", drift.len()))
+}
 
-				let Some(cached_mod) = option else {
-					fail_with_error(
-						bar,
-						format!("Failed to find version '{version}' for: {mod_url}"),
-					);
-					continue;
-				};
-				cached_mod
+async fn plan(remote_access: &mut RemoteModAccess, cfg_access: &ConfigurationAccess) -> Result<()> {
+	let configuration = cfg_access.read_remote_mods().await?;
+	let mut plans = Vec::new();
+	for mod_cfg in &configuration.mods {
+		let mod_url = &mod_cfg.url;
+		let mod_kind = match ModKind::from_configuration(mod_cfg) {
+			Ok(mod_kind) => mod_kind,
+			Err(err) => {
+				println!("Failed to parse '{mod_url}' with: {err}");
+				continue;
 			}
 		};
+		remote_access.set_version_scheme(mod_cfg.version_scheme.clone());
+		remote_access.set_allow_prerelease(mod_cfg.allow_prerelease);
+		remote_access.set_min_release_date(mod_cfg.github_min_release_date);
+		let planned = remote_access
+			.plan_release(mod_kind, mod_cfg.version.as_ref(), mod_cfg.version_filter.as_deref())
+			.await;
+		match planned {
+			Ok(Some(planned)) => {
+				let size = match (planned.is_cached, planned.download_size) {
+					(true, _) => "cached".to_string(),
+					(false, Some(size)) => HumanBytes(size).to_string(),
+					(false, None) => "unknown size".to_string(),
+				};
+				println!("{} {} ({size})", planned.title, planned.version);
+				plans.push(planned);
+			}
+			Ok(None) => println!("Found no matching version for: {mod_url}"),
+			Err(err) => println!("Failed to resolve '{mod_url}' with: {err}"),
+		}
+	}
 
-		if let Some(install_path) = mod_cfg.install_path {
-			spt_access.install_mod_to_path(&cached_mod.path, install_path)?;
-		} else {
-			let install_target = match target {
-				UpdateTarget::Client => InstallTarget::Client,
-				UpdateTarget::Server => InstallTarget::Server,
-			};
-			if spt_access.is_same_installed_version(
-				&cached_mod.path,
-				&cached_mod,
-				install_target,
-			)? {
-				bar.finish_with_message(format!(
-					"Version {} has already been installed for: {mod_url}",
-					cached_mod.get_version()
-				));
+	let (total_size, unknown_sizes) = total_download_size(&plans);
+	println!("Total download size: {}", HumanBytes(total_size));
+	if unknown_sizes > 0 {
+		println!("{unknown_sizes} downloads didn't report their size");
+	}
+	Ok(())
+}
+
+/// Shows which configured mods have a newer version than the installed one, without installing.
+async fn status(
+	remote_access: &mut RemoteModAccess,
+	cfg_access: &ConfigurationAccess,
+	spt_access: &SptAccess<Time>,
+) -> Result<()> {
+	let configuration = cfg_access.read_remote_mods().await?;
+	let installed_mods = spt_access.list_installed()?;
+	let mut rows = Vec::new();
+	for mod_cfg in &configuration.mods {
+		let mod_url = &mod_cfg.url;
+		let bar = ProgressBar::new_spinner();
+		bar.enable_steady_tick(Duration::from_millis(100));
+		bar.set_message(format!("Finding newest version online for: {mod_url}"));
+		let mod_kind = match ModKind::from_configuration(mod_cfg) {
+			Ok(mod_kind) => mod_kind,
+			Err(err) => {
+				fail_with_error(bar, format!("Failed to parse '{mod_url}' with: {err}"));
+				continue;
+			}
+		};
+		remote_access.set_version_scheme(mod_cfg.version_scheme.clone());
+		remote_access.set_allow_prerelease(mod_cfg.allow_prerelease);
+		remote_access.set_min_release_date(mod_cfg.github_min_release_date);
+		let resolved = remote_access
+			.resolve_release(
+				mod_kind.clone(),
+				mod_cfg.version.as_ref(),
+				mod_cfg.version_filter.as_deref(),
+			)
+			.await;
+		let newest = match resolved {
+			Ok(Some(newest)) => newest,
+			Ok(None) => {
+				fail_with_error(bar, format!("Found no matching version for: {mod_url}"));
+				continue;
+			}
+			Err(err) => {
+				fail_with_error(bar, format!("Failed to resolve '{mod_url}' with: {err}"));
 				continue;
 			}
-			bar.set_message(format!("Installing the newest version for: {mod_url}"));
-			match spt_access.install_mod(&cached_mod.path, &cached_mod, install_target) {
-				Ok(_) => {
-					bar.finish_with_message(format!(
-						"Installed version {} for: {mod_url}",
-						cached_mod.get_version()
-					));
-				}
-				Err(err) => fail_with_error(
-					bar,
-					format!("Failed to install '{mod_url}' with error: {err}"),
-				),
-			};
 		};
+		bar.finish_and_clear();
+
+		// Index entries without a version fall back to the newest cached one
+		let installed = find_installed_mod(remote_access, &mod_kind, &installed_mods)
+			.and_then(|installed_mod| {
+				installed_mod.get_version().or_else(|| {
+					let cached_mod = remote_access.get_newest_cached(&mod_kind)?;
+					Some(cached_mod.get_version().clone())
+				})
+			});
+		let status =
+			ModCacheStatus::compare(&newest.version, installed.as_ref(), &mod_cfg.version_scheme);
+		rows.push(StatusRow {
+			name: newest.title,
+			installed,
+			newest: newest.version,
+			status,
+		});
 	}
+	status_output::print_rows(&rows);
 	Ok(())
 }
 
-async fn remove_mods(spt_access: &SptAccess<Time>) -> Result<()> {
-	let deleted_files = spt_access.remove_all_mods().await?;
-	for file in deleted_files {
-		println!("Deleted: {}", file.to_string_lossy());
+/// The installed mod of a configured one. The index names a mod after its download, which the
+/// cache remembers.
+fn find_installed_mod<'a>(
+	remote_access: &RemoteModAccess,
+	mod_kind: &ModKind,
+	installed_mods: &'a [InstalledMod],
+) -> Option<&'a InstalledMod> {
+	let cached_name = remote_access
+		.get_newest_cached(mod_kind)
+		.map(|cached_mod| cached_mod.get_name().to_string());
+	installed_mods.iter().find(|installed_mod| match &cached_name {
+		Some(name) => &installed_mod.name == name,
+		None => installed_mod.name.eq_ignore_ascii_case(mod_kind.get_short_name()),
+	})
+}
+
+/// Resolves the name or url to a configured mod, asking which one when several match.
+fn pick_configured_mod<'a>(
+	configuration: &'a ModConfiguration,
+	url_or_name: &str,
+) -> Result<&'a ModVersionConfiguration> {
+	let candidates: Vec<_> = configuration
+		.mods
+		.iter()
+		.filter_map(ModCandidate::from_configuration)
+		.collect();
+	let candidate = mod_resolver::pick_mod(
+		url_or_name,
+		&candidates,
+		std::io::stdin().is_terminal(),
+		std::io::stdin().lock(),
+		std::io::stdout(),
+	)?;
+	configuration
+		.mods
+		.iter()
+		.find(|mod_cfg| candidate.url.as_ref() == Some(&mod_cfg.url))
+		.context("Found no configuration for the picked mod")
+}
+
+/// Pins a configured mod to its installed version, or clears its version with `pin` false.
+async fn pin(
+	remote_access: &RemoteModAccess,
+	cfg_access: &ConfigurationAccess,
+	spt_access: &SptAccess<Time>,
+	url_or_name: &str,
+	pin: bool,
+) -> Result<()> {
+	let mut configuration = cfg_access.read_remote_mods().await?;
+	let mod_cfg = pick_configured_mod(&configuration, url_or_name)?;
+	let url = mod_cfg.url.clone();
+	let version = if pin {
+		let mod_kind = ModKind::from_configuration(mod_cfg)?;
+		let installed_mods = spt_access.list_installed()?;
+		let installed_mod = find_installed_mod(remote_access, &mod_kind, &installed_mods)
+			.with_context(|| format!("Found no installed version of: {url}"))?;
+		let version = installed_mod
+			.get_version()
+			.with_context(|| format!("The install index has no version for: {url}"))?;
+		println!("Pinned {url} to version {version}");
+		Some(version)
+	} else {
+		println!("Unpinned {url}");
+		None
+	};
+	configuration.set_mod_version(&url, version)?;
+	cfg_access.write_remote_mods(&configuration).await
+}
+
+async fn add(
+	cfg_access: &ConfigurationAccess,
+	url: Option<String>,
+	github_pattern: Option<String>,
+	from: Option<PathBuf>,
+) -> Result<()> {
+	let mut configuration = cfg_access.read_remote_mods().await?;
+	let mods = match (url, from) {
+		(_, Some(from)) => {
+			let text = tokio::fs::read_to_string(&from)
+				.await
+				.with_context(|| format!("Failed to read the mods list {}", from.display()))?;
+			let (mods, rejected) = parse_mods_list(&text);
+			for rejected_line in &rejected {
+				eprintln!(
+					"Skipped line {} ({}): {}",
+					rejected_line.line, rejected_line.text, rejected_line.error
+				);
+			}
+			mods
+		}
+		(Some(url), None) => {
+			ModKind::parse(&url, github_pattern.clone(), None)?;
+			vec![ModVersionConfiguration::from_url(url, github_pattern, None)]
+		}
+		(None, None) => {
+			return Err(anyhow!("Provide a mod url or a list of them with --from"));
+		}
+	};
+
+	let mut added = 0;
+	for mod_cfg in mods {
+		let url = mod_cfg.url.clone();
+		match configuration.add_mod(mod_cfg) {
+			Ok(()) => {
+				println!("Added {url}");
+				added += 1;
+			}
+			Err(err) => eprintln!("Skipped {url}: {err}"),
+		}
+	}
+	if added > 0 {
+		cfg_access.write_remote_mods(&configuration).await?;
 	}
+	println!("Added {added} mod(s) to the configuration");
 	Ok(())
 }
 
-fn restore(spt_access: &SptAccess<Time>, restore_from: &str) -> Result<()> {
+async fn open_page(cfg_access: &ConfigurationAccess, url_or_name: &str) -> Result<()> {
+	let configuration = cfg_access.read_remote_mods().await?;
+	let mod_cfg = pick_configured_mod(&configuration, url_or_name)?;
+
+	let page = ModKind::from_configuration(mod_cfg)?.get_source_page();
+	println!("Opening: {page}");
+	open::that(&page)?;
+	Ok(())
+}
+
+fn restore(
+	spt_access: &SptAccess<Time>,
+	restore_from: &str,
+	policy: OverwritePolicy,
+	scope: RestoreScope,
+) -> Result<()> {
 	let bar = ProgressBar::new_spinner();
 	bar.enable_steady_tick(Duration::from_millis(100));
 	bar.set_message("Restoring mods and configurations");
-	spt_access.restore_from(restore_from)?;
+	spt_access.restore_from(restore_from, policy, scope)?;
 	bar.finish_with_message(format!("Restored your files from: {restore_from}"));
 	Ok(())
 }
 
-fn backup(spt_access: &SptAccess<Time>, backup_to_path: &str) -> Result<()> {
+fn preview_restore(spt_access: &SptAccess<Time>, restore_from: &str, scope: RestoreScope) -> Result<()> {
+	let previews = spt_access.preview_restore(restore_from, scope)?;
+	for preview in &previews {
+		let change = match preview.change {
+			RestoreChange::New => "new",
+			RestoreChange::Identical => "identical",
+			RestoreChange::Overwrite => "overwrites",
+			RestoreChange::OverwriteNewer => "overwrites newer",
+		};
+		println!("{change:<16}  {}", preview.path);
+	}
+	let overwrites = previews
+		.iter()
+		.filter(|preview| {
+			matches!(
+				preview.change,
+				RestoreChange::Overwrite | RestoreChange::OverwriteNewer
+			)
+		})
+		.count();
+	println!(
+		"{} files in the backup, {overwrites} would overwrite a different file",
+		previews.len()
+	);
+	Ok(())
+}
+
+/// Groups the files of a backup by mod folder, such as `user/mods/<mod>`, or by the folder below
+/// the SPT folder for everything else, with the size of each group.
+fn list_backup(spt_access: &SptAccess<Time>, backup_path: &str) -> Result<()> {
+	let entries = spt_access.inspect_backup(backup_path)?;
+	let mut groups: BTreeMap<String, (Option<InstallTarget>, usize, u64)> = BTreeMap::new();
+	for entry in &entries {
+		let components: Vec<_> = entry.path.split('/').collect();
+		let is_mod = SptLayout::mod_folders().iter().any(|folder| {
+			entry.path.strip_prefix(folder).is_some_and(|rest| rest.starts_with('/'))
+		});
+		let depth = if is_mod { 3 } else { 2 };
+		let group = components[..depth.min(components.len())].join("/");
+		let (_, files, size) = groups.entry(group).or_insert((entry.target, 0, 0));
+		*files += 1;
+		*size += entry.size;
+	}
+	for (group, (target, files, size)) in &groups {
+		let target = match target {
+			Some(target) => target.to_string(),
+			None => "other".to_string(),
+		};
+		let size = HumanBytes(*size).to_string();
+		println!("{size:>12}  {files:>6} file(s)  {target:<6}  {group}");
+	}
+	let total = HumanBytes(entries.iter().map(|entry| entry.size).sum());
+	println!("{} file(s) in {} folder(s), {total} in total", entries.len(), groups.len());
+	Ok(())
+}
+
+fn backup_diff(from: &str, to: &str) -> Result<()> {
+	let differences = SptAccess::<Time>::diff_backups(from, to)?;
+	for difference in &differences {
+		let change = match difference.change {
+			BackupChange::Added => "added",
+			BackupChange::Removed => "removed",
+			BackupChange::Changed => "changed",
+		};
+		println!("{change:<8}  {}", difference.path);
+	}
+	if differences.is_empty() {
+		println!("The backups contain the same files");
+	}
+	Ok(())
+}
+
+async fn backup(
+	spt_access: &SptAccess<Time>,
+	backup_to_path: &str,
+	options: BackupOptions,
+) -> Result<()> {
 	let bar = ProgressBar::new_spinner();
 	bar.enable_steady_tick(Duration::from_millis(100));
 	bar.set_message("Backing up mods and configurations");
-	spt_access.backup_to(backup_to_path)?;
-	bar.finish_with_message(format!("Backed up mods to: {backup_to_path}"));
+	let (sender, mut receiver) = mpsc::unbounded_channel();
+	let backup = spt_access.backup_to_async(backup_to_path, options, BackupCancellation::new(), sender);
+	let progress = async {
+		while let Some(files_processed) = receiver.recv().await {
+			bar.set_message(format!(
+				"Backing up mods and configurations ({files_processed} files)"
+			));
+		}
+	};
+	let (backup_path, _) = tokio::join!(backup, progress);
+	let backup_path = backup_path?;
+	bar.finish_with_message(format!("Backed up mods to: {}", backup_path.display()));
 	Ok(())
 }
 
+fn confirm_or_cancel(question: &str, assume_yes: bool) -> Result<bool> {
+	let confirmed = confirm::confirm(
+		question,
+		assume_yes,
+		std::io::stdin().is_terminal(),
+		std::io::stdin().lock(),
+		std::io::stdout(),
+	)?;
+	if !confirmed {
+		println!("Cancelled");
+	}
+	Ok(confirmed)
+}
+
 fn fail_with_error(bar: ProgressBar, msg: impl Into<Cow<'static, str>>) {
 	bar.set_style(ProgressStyle::with_template("{spinner} {msg:.red}").unwrap());
 	bar.finish_with_message(msg);
@@ -0,0 +1,184 @@
+use std::io::{BufRead, Write};
+
+use anyhow::{anyhow, Result};
+use sptmm_lib::configuration_access::ModVersionConfiguration;
+use sptmm_lib::remote_mod_access::ModKind;
+use sptmm_lib::spt_access::InstalledMod;
+
+/// A configured or installed mod that a command can be pointed at by name or url.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModCandidate {
+	pub name: String,
+	pub url: Option<String>,
+}
+
+impl ModCandidate {
+	pub fn from_configuration(mod_cfg: &ModVersionConfiguration) -> Option<Self> {
+		let mod_kind = ModKind::from_configuration(mod_cfg).ok()?;
+		Some(Self {
+			name: mod_kind.get_short_name().to_string(),
+			url: Some(mod_cfg.url.clone()),
+		})
+	}
+
+	fn matches_exactly(&self, query: &str) -> bool {
+		self.name.to_lowercase() == query
+			|| self.url.as_ref().is_some_and(|url| url.to_lowercase() == query)
+	}
+
+	fn contains(&self, query: &str) -> bool {
+		self.name.to_lowercase().contains(query)
+			|| self.url.as_ref().is_some_and(|url| url.to_lowercase().contains(query))
+	}
+}
+
+impl From<&InstalledMod> for ModCandidate {
+	fn from(installed_mod: &InstalledMod) -> Self {
+		Self {
+			name: installed_mod.name.clone(),
+			url: None,
+		}
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ModMatch<'a> {
+	Unique(&'a ModCandidate),
+	Ambiguous(Vec<&'a ModCandidate>),
+	NoMatch,
+}
+
+/// Matches the query case-insensitively against names and urls, preferring exact matches over substrings.
+pub fn resolve_mod<'a>(query: &str, candidates: &'a [ModCandidate]) -> ModMatch<'a> {
+	let query = query.trim().to_lowercase();
+	let exact: Vec<_> = candidates
+		.iter()
+		.filter(|candidate| candidate.matches_exactly(&query))
+		.collect();
+	let mut matches = if exact.is_empty() {
+		candidates
+			.iter()
+			.filter(|candidate| candidate.contains(&query))
+			.collect()
+	} else {
+		exact
+	};
+
+	match matches.len() {
+		0 => ModMatch::NoMatch,
+		1 => ModMatch::Unique(matches.remove(0)),
+		_ => ModMatch::Ambiguous(matches),
+	}
+}
+
+/// Resolves the query to a single mod, asking the user to pick one when several match.
+/// Fails instead of asking when not running interactively.
+pub fn pick_mod<'a>(
+	query: &str,
+	candidates: &'a [ModCandidate],
+	interactive: bool,
+	mut input: impl BufRead,
+	mut output: impl Write,
+) -> Result<&'a ModCandidate> {
+	let matches = match resolve_mod(query, candidates) {
+		ModMatch::Unique(candidate) => return Ok(candidate),
+		ModMatch::NoMatch => return Err(anyhow!("Found no mod matching: {query}")),
+		ModMatch::Ambiguous(matches) => matches,
+	};
+
+	let names = matches
+		.iter()
+		.map(|candidate| candidate.name.as_str())
+		.collect::<Vec<_>>()
+		.join(", ");
+	if !interactive {
+		return Err(anyhow!("'{query}' matches several mods: {names}"));
+	}
+
+	writeln!(output, "'{query}' matches several mods:")?;
+	for (index, candidate) in matches.iter().enumerate() {
+		match &candidate.url {
+			Some(url) => writeln!(output, "  {}) {} ({url})", index + 1, candidate.name)?,
+			None => writeln!(output, "  {}) {}", index + 1, candidate.name)?,
+		}
+	}
+	write!(output, "Pick a mod [1-{}]: ", matches.len())?;
+	output.flush()?;
+
+	let mut answer = String::new();
+	input.read_line(&mut answer)?;
+	answer
+		.trim()
+		.parse::<usize>()
+		.ok()
+		.and_then(|choice| matches.get(choice.checked_sub(1)?))
+		.copied()
+		.ok_or_else(|| anyhow!("'{}' isn't one of the listed mods", answer.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn candidates() -> Vec<ModCandidate> {
+		vec![
+			ModCandidate {
+				name: "betterkeys-updated".to_string(),
+				url: Some("https://github.com/maxloo2/betterkeys-updated".to_string()),
+			},
+			ModCandidate {
+				name: "SAIN".to_string(),
+				url: None,
+			},
+			ModCandidate {
+				name: "SAIN Presets".to_string(),
+				url: None,
+			},
+		]
+	}
+
+	#[test]
+	fn unique_partial_match_is_resolved() {
+		let candidates = candidates();
+
+		assert_eq!(resolve_mod("betterkeys", &candidates), ModMatch::Unique(&candidates[0]));
+	}
+
+	#[test]
+	fn exact_match_wins_over_partial_matches() {
+		let candidates = candidates();
+
+		assert_eq!(resolve_mod("sain", &candidates), ModMatch::Unique(&candidates[1]));
+	}
+
+	#[test]
+	fn ambiguous_match_lists_candidates() {
+		let candidates = candidates();
+
+		assert_eq!(
+			resolve_mod("sai", &candidates),
+			ModMatch::Ambiguous(vec![&candidates[1], &candidates[2]])
+		);
+		let error = pick_mod("sai", &candidates, false, "".as_bytes(), Vec::new()).unwrap_err();
+		assert!(error.to_string().contains("SAIN, SAIN Presets"));
+	}
+
+	#[test]
+	fn ambiguous_match_is_picked_interactively() {
+		let candidates = candidates();
+		let mut output = Vec::new();
+
+		let picked = pick_mod("sai", &candidates, true, "2\n".as_bytes(), &mut output).unwrap();
+
+		assert_eq!(picked, &candidates[2]);
+		assert!(String::from_utf8(output).unwrap().contains("2) SAIN Presets"));
+	}
+
+	#[test]
+	fn no_match_is_reported() {
+		let candidates = candidates();
+
+		assert_eq!(resolve_mod("realism", &candidates), ModMatch::NoMatch);
+		assert!(pick_mod("realism", &candidates, true, "".as_bytes(), Vec::new()).is_err());
+	}
+}
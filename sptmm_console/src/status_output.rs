@@ -0,0 +1,90 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use sptmm_lib::remote_mod_access::cache_mod_access::ModCacheStatus;
+use versions::Versioning;
+
+/// How the installed version of a configured mod compares to the version an update would install.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StatusRow {
+	pub name: String,
+	pub installed: Option<Versioning>,
+	pub newest: Versioning,
+	/// The newest version compared to the installed one.
+	pub status: ModCacheStatus,
+}
+
+impl StatusRow {
+	pub fn describe(&self) -> String {
+		match (&self.installed, self.status) {
+			(None, _) | (_, ModCacheStatus::NotCached) => {
+				format!("not installed ({} available)", self.newest)
+			}
+			(Some(installed), ModCacheStatus::OlderVersion) => {
+				format!("update available ({installed} → {})", self.newest)
+			}
+			(Some(installed), ModCacheStatus::SameVersion | ModCacheStatus::NewerVersion) => {
+				format!("up to date ({installed})")
+			}
+		}
+	}
+
+	fn color(&self) -> &'static str {
+		match (&self.installed, self.status) {
+			(None, _) | (_, ModCacheStatus::NotCached) => "red",
+			(_, ModCacheStatus::OlderVersion) => "yellow",
+			(_, ModCacheStatus::SameVersion | ModCacheStatus::NewerVersion) => "green",
+		}
+	}
+}
+
+/// A line per row with the names padded to line up, and the color it's shown in.
+pub fn format_rows(rows: &[StatusRow]) -> Vec<(&'static str, String)> {
+	let width = rows.iter().map(|row| row.name.chars().count()).max().unwrap_or(0);
+	rows.iter()
+		.map(|row| (row.color(), format!("{:<width$}  {}", row.name, row.describe())))
+		.collect()
+}
+
+pub fn print_rows(rows: &[StatusRow]) {
+	for (color, line) in format_rows(rows) {
+		let bar = ProgressBar::new_spinner();
+		bar.set_style(ProgressStyle::with_template(&format!("{{msg:.{color}}}")).unwrap());
+		bar.finish_with_message(line);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use sptmm_lib::version_scheme::VersionScheme;
+
+	use super::*;
+
+	fn row(name: &str, installed: Option<&str>, newest: &str) -> StatusRow {
+		let installed = installed.map(|version| Versioning::new(version).unwrap());
+		let newest = Versioning::new(newest).unwrap();
+		let status = ModCacheStatus::compare(&newest, installed.as_ref(), &VersionScheme::Semver);
+		StatusRow {
+			name: name.to_string(),
+			installed,
+			newest,
+			status,
+		}
+	}
+
+	#[test]
+	fn rows_describe_each_state_with_aligned_names() {
+		let rows = [
+			row("SAIN", Some("3.1.0"), "3.1.0"),
+			row("Realism", Some("1.4.2"), "1.5.0"),
+			row("LootingBots", None, "1.3.5"),
+		];
+
+		assert_eq!(
+			format_rows(&rows),
+			vec![
+				("green", "SAIN         up to date (3.1.0)".to_string()),
+				("yellow", "Realism      update available (1.4.2 → 1.5.0)".to_string()),
+				("red", "LootingBots  not installed (1.3.5 available)".to_string()),
+			]
+		);
+	}
+}
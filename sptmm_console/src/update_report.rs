@@ -0,0 +1,184 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use indicatif::{ProgressBar, ProgressStyle};
+use sptmm_lib::remote_mod_access::DownloadProgress;
+
+use crate::fail_with_error;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum UpdateOutcome {
+	Installed,
+	Skipped,
+	Failed,
+}
+
+/// What happened to one mod during an update, with the line describing it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UpdateEvent {
+	pub mod_url: String,
+	pub outcome: UpdateOutcome,
+	pub message: String,
+}
+
+impl UpdateEvent {
+	pub fn new(mod_url: &str, outcome: UpdateOutcome, message: String) -> Self {
+		Self {
+			mod_url: mod_url.to_string(),
+			outcome,
+			message,
+		}
+	}
+}
+
+/// The mods of an update grouped by what happened to them, in the order they were updated.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UpdateSummary {
+	pub installed: Vec<String>,
+	pub skipped: Vec<String>,
+	pub failed: Vec<(String, String)>,
+}
+
+impl UpdateSummary {
+	pub fn add(&mut self, event: &UpdateEvent) {
+		match event.outcome {
+			UpdateOutcome::Installed => self.installed.push(event.mod_url.clone()),
+			UpdateOutcome::Skipped => self.skipped.push(event.mod_url.clone()),
+			UpdateOutcome::Failed => self
+				.failed
+				.push((event.mod_url.clone(), event.message.clone())),
+		}
+	}
+
+	pub fn format(&self) -> String {
+		let mut lines = vec![format!("Installed {} mod(s):", self.installed.len())];
+		lines.extend(self.installed.iter().map(|mod_url| format!("  {mod_url}")));
+		lines.push(format!("Skipped {} mod(s) already up to date:", self.skipped.len()));
+		lines.extend(self.skipped.iter().map(|mod_url| format!("  {mod_url}")));
+		lines.push(format!("Failed {} mod(s):", self.failed.len()));
+		lines.extend(self.failed.iter().map(|(_, message)| format!("  {message}")));
+		lines.join("\n")
+	}
+}
+
+impl FromIterator<UpdateEvent> for UpdateSummary {
+	fn from_iter<T: IntoIterator<Item = UpdateEvent>>(events: T) -> Self {
+		let mut summary = Self::default();
+		for event in events {
+			summary.add(&event);
+		}
+		summary
+	}
+}
+
+/// Shows an update either as a spinner per mod, or with `summary_only` as one bar over every mod
+/// followed by an [`UpdateSummary`].
+pub struct UpdateReporter {
+	overall: Option<ProgressBar>,
+	summary: UpdateSummary,
+}
+
+impl UpdateReporter {
+	pub fn new(summary_only: bool, mod_count: usize) -> Self {
+		let overall = summary_only.then(|| {
+			let bar = ProgressBar::new(mod_count as u64);
+			bar.set_style(ProgressStyle::with_template("{bar:40} {pos} of {len} mods {msg}").unwrap());
+			bar
+		});
+		Self {
+			overall,
+			summary: UpdateSummary::default(),
+		}
+	}
+
+	/// A spinner for one step of the update, hidden when only the summary is shown.
+	pub fn start_step(&self, message: impl Into<String>) -> ProgressBar {
+		let message = message.into();
+		if let Some(overall) = &self.overall {
+			overall.set_message(message);
+			return ProgressBar::hidden();
+		}
+		let bar = ProgressBar::new_spinner();
+		bar.enable_steady_tick(Duration::from_millis(100));
+		bar.set_message(message);
+		bar
+	}
+
+	pub fn finish_mod(&mut self, bar: ProgressBar, event: UpdateEvent) {
+		self.summary.add(&event);
+		match &self.overall {
+			Some(overall) => overall.inc(1),
+			None if event.outcome == UpdateOutcome::Failed => fail_with_error(bar, event.message),
+			None => bar.finish_with_message(event.message),
+		}
+	}
+
+	/// Prints a line, such as a dependency warning, without breaking the progress bar.
+	pub fn note(&self, line: &str) {
+		match &self.overall {
+			Some(overall) => overall.suspend(|| println!("{line}")),
+			None => println!("{line}"),
+		}
+	}
+
+	pub fn finish(self) {
+		if let Some(overall) = self.overall {
+			overall.finish_and_clear();
+			println!("{}", self.summary.format());
+		}
+	}
+}
+
+/// Shows a download on a step's bar with the download's size as its length, or as the bytes
+/// downloaded so far next to the spinner when the server doesn't tell the size.
+pub fn download_progress(bar: &ProgressBar) -> DownloadProgress {
+	let bar = bar.clone();
+	let is_styled = AtomicBool::new(false);
+	Arc::new(move |downloaded, total_size| {
+		if !is_styled.swap(true, Ordering::Relaxed) {
+			let template = match total_size {
+				Some(total_size) => {
+					bar.set_length(total_size);
+					"{spinner} {msg} {bar:30} {bytes}/{total_bytes}"
+				}
+				None => "{spinner} {msg} {bytes}",
+			};
+			bar.set_style(ProgressStyle::with_template(template).unwrap());
+		}
+		bar.set_position(downloaded);
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn events_are_grouped_by_outcome() {
+		let events = vec![
+			UpdateEvent::new("https://a", UpdateOutcome::Installed, "Installed version 1.0.0 for: https://a".to_string()),
+			UpdateEvent::new("https://b", UpdateOutcome::Failed, "Failed to install 'https://b'".to_string()),
+			UpdateEvent::new("https://c", UpdateOutcome::Skipped, "Already installed".to_string()),
+			UpdateEvent::new("https://d", UpdateOutcome::Installed, "Installed version 2.0.0 for: https://d".to_string()),
+		];
+
+		let summary: UpdateSummary = events.into_iter().collect();
+
+		assert_eq!(summary.installed, vec!["https://a", "https://d"]);
+		assert_eq!(summary.skipped, vec!["https://c"]);
+		assert_eq!(
+			summary.format(),
+			[
+				"Installed 2 mod(s):",
+				"  https://a",
+				"  https://d",
+				"Skipped 1 mod(s) already up to date:",
+				"  https://c",
+				"Failed 1 mod(s):",
+				"  Failed to install 'https://b'",
+			]
+			.join("\n")
+		);
+	}
+}
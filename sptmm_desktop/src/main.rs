@@ -7,6 +7,7 @@ use iced::{alignment, window, Command, Element};
 use sptmm_lib::configuration_access::{
 	ConfigurationAccess, ModConfiguration, ModVersionConfiguration,
 };
+use sptmm_lib::path_access::PathAccess;
 use sptmm_lib::spt_access::SptAccess;
 use sptmm_lib::time_access::Time;
 
@@ -95,7 +96,8 @@ enum SaveError {
 }
 impl SavedState {
 	async fn load() -> Result<Self, LoadError> {
-		let cfg_access = ConfigurationAccess::init("./").await.unwrap();
+		let path_access = PathAccess::new("./").unwrap();
+		let cfg_access = ConfigurationAccess::init(&path_access).await.unwrap();
 		let cfg = cfg_access.read_remote_mods().await.unwrap();
 		let state = Self { cfg, cfg_access };
 		Ok(state)
@@ -1,33 +1,260 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDate;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
 use tokio::fs::OpenOptions;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use versions::Versioning;
 
 use crate::path_access::PathAccess;
+use crate::remote_mod_access::{ModKind, RetryPolicy};
+use crate::spt_access::{InstallTarget, SptLayout};
+use crate::version_constraint::VersionConstraint;
+use crate::version_scheme::{VersionScheme, VersionSchemeName};
+
+/// Holds the last configuration fetched from each url, named by the hash of the url.
+pub const REMOTE_CONFIG_CACHE_FOLDER: &str = "remote_configs";
+
+/// The mod configuration in the SPT folder, used when there's no YAML one.
+pub const MOD_CONFIG_FILE_NAME: &str = "spt_mods.json";
+
+/// The YAML mod configurations in the SPT folder, picked over [`MOD_CONFIG_FILE_NAME`] in this order.
+pub const YAML_MOD_CONFIG_FILE_NAMES: [&str; 2] = ["spt_mods.yaml", "spt_mods.yml"];
+
+/// The mod manager's own configuration, kept in [`PathAccess::config_root`].
+pub const APP_CONFIG_FILE_NAME: &str = "sptmm.json";
+
+/// Used when neither the mod, the command line nor the configuration picks an install target.
+pub const DEFAULT_INSTALL_TARGET: InstallTarget = InstallTarget::Client;
 
 #[derive(PartialEq, Debug, Clone)]
 pub struct ModConfiguration {
 	pub spt_version: Versioning,
+	pub default_install_target: Option<InstallTarget>,
+	/// Globs of archive paths that are never installed, whichever mod they belong to.
+	pub never_install: Vec<String>,
+	/// The sha256 hashes of archives that are refused, such as builds known to be malicious.
+	pub blocked_hashes: Vec<String>,
+	/// How often to retry a download or page fetch that failed on a dropped connection, a timeout or
+	/// a 5xx, unless the command line says otherwise.
+	pub download_retries: Option<u32>,
+	/// How long to wait before the first retry, doubling for each retry after it.
+	pub retry_backoff_ms: Option<u64>,
 	pub mods: Vec<ModVersionConfiguration>,
 }
 
+impl ModConfiguration {
+	/// Picks the most specific install target for a mod: its own override, then the command line,
+	/// then the configuration's default and finally [`DEFAULT_INSTALL_TARGET`].
+	pub fn resolve_install_target(
+		&self,
+		mod_cfg: &ModVersionConfiguration,
+		cli_target: Option<InstallTarget>,
+	) -> InstallTarget {
+		mod_cfg
+			.install_target
+			.unwrap_or_else(|| self.resolve_default_install_target(cli_target))
+	}
+
+	/// Pins the mod configured with `url` to a version, or lets it update again with `None`.
+	pub fn set_mod_version(&mut self, url: &str, version: Option<Versioning>) -> Result<()> {
+		let mod_cfg = self
+			.mods
+			.iter_mut()
+			.find(|mod_cfg| mod_cfg.url == url)
+			.with_context(|| format!("Found no configured mod with the url: {url}"))?;
+		mod_cfg.version = version.map(VersionConstraint::from);
+		Ok(())
+	}
+
+	/// Keeps only the mods in the group, such as for `update --group optional`.
+	pub fn retain_group(&mut self, group: &str) {
+		self.mods.retain(|mod_cfg| mod_cfg.is_in_group(group));
+	}
+
+	/// Appends a mod to the configuration, failing when its url is already configured.
+	pub fn add_mod(&mut self, mod_cfg: ModVersionConfiguration) -> Result<()> {
+		if self.mods.iter().any(|configured| configured.url == mod_cfg.url) {
+			return Err(anyhow!("The mod is already configured: {}", mod_cfg.url));
+		}
+		self.mods.push(mod_cfg);
+		Ok(())
+	}
+
+	/// Picks the install target for a mod that isn't configured: the command line, then the
+	/// configuration's default and finally [`DEFAULT_INSTALL_TARGET`].
+	pub fn resolve_default_install_target(&self, cli_target: Option<InstallTarget>) -> InstallTarget {
+		cli_target
+			.or(self.default_install_target)
+			.unwrap_or(DEFAULT_INSTALL_TARGET)
+	}
+
+	/// Picks each retry setting from the command line, then the configuration and finally the
+	/// [`RetryPolicy`] default.
+	pub fn resolve_retry_policy(&self, cli_retries: Option<u32>, cli_backoff_ms: Option<u64>) -> RetryPolicy {
+		let default = RetryPolicy::default();
+		RetryPolicy {
+			retries: cli_retries.or(self.download_retries).unwrap_or(default.retries),
+			backoff: cli_backoff_ms
+				.or(self.retry_backoff_ms)
+				.map_or(default.backoff, Duration::from_millis),
+		}
+	}
+
+	/// The configuration as an update acts on it, with every setting picked the way the resolve
+	/// functions above pick it and every install path expanded under `layout`.
+	pub fn resolve(
+		&self,
+		layout: &SptLayout,
+		cli_target: Option<InstallTarget>,
+		cli_retries: Option<u32>,
+		cli_backoff_ms: Option<u64>,
+	) -> ModConfiguration {
+		let retry_policy = self.resolve_retry_policy(cli_retries, cli_backoff_ms);
+		let mods = self
+			.mods
+			.iter()
+			.map(|mod_cfg| ModVersionConfiguration {
+				install_target: Some(self.resolve_install_target(mod_cfg, cli_target)),
+				install_path: mod_cfg.install_path.as_deref().map(|install_path| {
+					layout.resolve_install_path(install_path).to_string_lossy().to_string()
+				}),
+				..mod_cfg.clone()
+			})
+			.collect();
+		ModConfiguration {
+			spt_version: self.spt_version.clone(),
+			default_install_target: Some(self.resolve_default_install_target(cli_target)),
+			never_install: self.never_install.clone(),
+			blocked_hashes: self.blocked_hashes.clone(),
+			download_retries: Some(retry_policy.retries),
+			retry_backoff_ms: Some(retry_policy.backoff.as_millis() as u64),
+			mods,
+		}
+	}
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub struct ModVersionConfiguration {
 	pub url: String,
-	pub version: Option<Versioning>,
+	/// An exact version such as `1.2.3` or a range such as `^1.2`, which installs the highest
+	/// version in it, see [`VersionConstraint::parse`].
+	pub version: Option<VersionConstraint>,
 	pub version_filter: Option<String>,
 	pub github_pattern: Option<String>,
+	/// Extracts or copies the mod here instead of installing it, relative to the SPT folder. It may
+	/// start with `${SPT_ROOT}`, `@plugins` (BepInEx/plugins), `@mods` (user/mods) or `@config`
+	/// (BepInEx/config), see [`crate::spt_access::SptLayout::resolve_install_path`].
 	pub install_path: Option<String>,
 	pub github_filter: Option<String>,
+	pub github_split_parts: bool,
+	/// Leaves out GitHub releases published before this date when picking the newest, for
+	/// repositories that re-release old builds.
+	pub github_min_release_date: Option<NaiveDate>,
+	pub install_target: Option<InstallTarget>,
+	/// Overrides how long the mod's download may take, for mods hosted on slow mirrors.
+	pub timeout_secs: Option<u64>,
+	/// Orders the mod's versions as dates when picking the newest, for mods versioned such as
+	/// `11-06-2024`.
+	pub version_scheme: VersionScheme,
+	/// Lets the newest version be a pre-release such as `1.3.0-rc1`, which are skipped otherwise.
+	pub allow_prerelease: bool,
+	/// The one group the mod belongs to, such as `core`, `optional` or `cosmetic`, for updating or
+	/// listing a tier of a modpack at a time.
+	pub group: Option<String>,
+}
+
+impl ModVersionConfiguration {
+	/// A mod that follows its newest version, with the asset pattern and filter GitHub and GitLab
+	/// mods need.
+	pub fn from_url(
+		url: impl Into<String>,
+		github_pattern: Option<String>,
+		github_filter: Option<String>,
+	) -> Self {
+		Self {
+			url: url.into(),
+			version: None,
+			version_filter: None,
+			github_pattern,
+			install_path: None,
+			github_filter,
+			github_split_parts: false,
+			github_min_release_date: None,
+			install_target: None,
+			timeout_secs: None,
+			version_scheme: VersionScheme::default(),
+			allow_prerelease: false,
+			group: None,
+		}
+	}
+
+	/// Reads a line of a mods list, a url optionally followed by `|pattern|filter` for the asset
+	/// of a GitHub or GitLab mod, checking that the url is one of a supported host.
+	pub fn parse_list_line(line: &str) -> Result<Self> {
+		let mut parts = line.split('|').map(str::trim);
+		let url = parts.next().unwrap_or_default();
+		let non_empty = |part: Option<&str>| part.filter(|part| !part.is_empty()).map(String::from);
+		let github_pattern = non_empty(parts.next());
+		let github_filter = non_empty(parts.next());
+		if parts.next().is_some() {
+			return Err(anyhow!("Expected url|pattern|filter but found more parts"));
+		}
+		ModKind::parse(url, github_pattern.clone(), github_filter.clone())?;
+		Ok(Self::from_url(url, github_pattern, github_filter))
+	}
+
+	pub fn is_in_group(&self, group: &str) -> bool {
+		self.group.as_deref() == Some(group)
+	}
 }
 
+/// A line of a mods list that couldn't be read, numbered from 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RejectedLine {
+	pub line: usize,
+	pub text: String,
+	pub error: String,
+}
+
+/// Reads a newline-delimited list of mods as [`ModVersionConfiguration::parse_list_line`] does,
+/// skipping blank lines and `#` comments and collecting the lines that fail instead of stopping.
+pub fn parse_mods_list(text: &str) -> (Vec<ModVersionConfiguration>, Vec<RejectedLine>) {
+	let mut mods = Vec::new();
+	let mut rejected = Vec::new();
+	for (index, line) in text.lines().enumerate() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+		match ModVersionConfiguration::parse_list_line(line) {
+			Ok(mod_cfg) => mods.push(mod_cfg),
+			Err(err) => rejected.push(RejectedLine {
+				line: index + 1,
+				text: line.to_string(),
+				error: err.to_string(),
+			}),
+		}
+	}
+	(mods, rejected)
+}
+
+const MIN_RELEASE_DATE_FORMAT: &str = "%Y-%m-%d";
+
 #[derive(Deserialize, Serialize)]
 struct ModConfigurationRaw {
-	#[serde(deserialize_with = "Versioning::deserialize_pretty")]
-	spt_version: Versioning,
+	spt_version: String,
+	default_install_target: Option<InstallTarget>,
+	never_install: Option<Vec<String>>,
+	blocked_hashes: Option<Vec<String>>,
+	download_retries: Option<u32>,
+	retry_backoff_ms: Option<u64>,
 	mods: Vec<ModVersionConfigurationRaw>,
 }
 #[derive(Deserialize, Serialize)]
@@ -38,17 +265,45 @@ struct ModVersionConfigurationRaw {
 	github_assert_pattern: Option<String>,
 	install_path: Option<String>,
 	github_assert_filter: Option<String>,
+	github_split_parts: Option<bool>,
+	/// A date such as `2024-06-11`.
+	github_min_release_date: Option<String>,
+	install_target: Option<InstallTarget>,
+	timeout_secs: Option<u64>,
+	version_scheme: Option<VersionSchemeName>,
+	/// The chrono format of date versions, such as `%d-%m-%Y`.
+	version_date_format: Option<String>,
+	allow_prerelease: Option<bool>,
+	group: Option<String>,
 }
 
 impl TryFrom<ModVersionConfigurationRaw> for ModVersionConfiguration {
 	type Error = anyhow::Error;
 
 	fn try_from(value: ModVersionConfigurationRaw) -> std::result::Result<Self, Self::Error> {
-		let version = if let Some(version) = value.version {
-			Some(Versioning::try_from(version.as_str())?)
-		} else {
-			None
+		let version = value.version.as_deref().map(VersionConstraint::parse).transpose()?;
+		let version_scheme = match (value.version_scheme, value.version_date_format) {
+			(Some(VersionSchemeName::Date), format) => VersionScheme::Date { format },
+			(_, Some(_)) => {
+				return Err(anyhow!(
+					"version_date_format needs version_scheme: date for the mod: {}",
+					value.url
+				));
+			}
+			(Some(VersionSchemeName::Semver) | None, None) => VersionScheme::Semver,
 		};
+		let github_min_release_date = value
+			.github_min_release_date
+			.as_deref()
+			.map(|date| {
+				NaiveDate::parse_from_str(date.trim(), MIN_RELEASE_DATE_FORMAT).with_context(|| {
+					format!(
+						"github_min_release_date '{date}' must be a date such as 2024-06-11 for the mod: {}",
+						value.url
+					)
+				})
+			})
+			.transpose()?;
 
 		Ok(Self {
 			url: value.url,
@@ -56,7 +311,14 @@ impl TryFrom<ModVersionConfigurationRaw> for ModVersionConfiguration {
 			install_path: value.install_path,
 			github_pattern: value.github_assert_pattern,
 			github_filter: value.github_assert_filter,
+			github_split_parts: value.github_split_parts.unwrap_or_default(),
+			github_min_release_date,
+			install_target: value.install_target,
+			timeout_secs: value.timeout_secs,
 			version,
+			version_scheme,
+			allow_prerelease: value.allow_prerelease.unwrap_or_default(),
+			group: value.group,
 		})
 	}
 }
@@ -69,20 +331,131 @@ impl From<ModVersionConfiguration> for ModVersionConfigurationRaw {
 			install_path: value.install_path,
 			github_assert_pattern: value.github_pattern,
 			github_assert_filter: value.github_filter,
+			github_split_parts: value.github_split_parts.then_some(true),
+			github_min_release_date: value
+				.github_min_release_date
+				.map(|date| date.format(MIN_RELEASE_DATE_FORMAT).to_string()),
+			install_target: value.install_target,
+			timeout_secs: value.timeout_secs,
 			version: value.version.map(|t| t.to_string()),
+			version_date_format: value.version_scheme.get_date_format().map(String::from),
+			version_scheme: (value.version_scheme != VersionScheme::Semver)
+				.then(|| value.version_scheme.name()),
+			allow_prerelease: value.allow_prerelease.then_some(true),
+			group: value.group,
 		}
 	}
 }
 
+impl TryFrom<ModConfigurationRaw> for ModConfiguration {
+	type Error = anyhow::Error;
+
+	fn try_from(value: ModConfigurationRaw) -> std::result::Result<Self, Self::Error> {
+		let spt_version = Versioning::new(value.spt_version.trim())
+			.filter(|version| !matches!(version, Versioning::Complex(_)))
+			.ok_or_else(|| {
+				anyhow!(
+					"Invalid spt_version '{}' in the mod configuration, expected a version like 3.9.8",
+					value.spt_version
+				)
+			})?;
+
+		let mut mods = Vec::new();
+		for x in value.mods {
+			mods.push(ModVersionConfiguration::try_from(x)?)
+		}
+
+		Ok(Self {
+			mods,
+			spt_version,
+			default_install_target: value.default_install_target,
+			never_install: value.never_install.unwrap_or_default(),
+			blocked_hashes: value.blocked_hashes.unwrap_or_default(),
+			download_retries: value.download_retries,
+			retry_backoff_ms: value.retry_backoff_ms,
+		})
+	}
+}
+
 impl From<ModConfiguration> for ModConfigurationRaw {
 	fn from(value: ModConfiguration) -> Self {
 		Self {
-			spt_version: value.spt_version,
+			spt_version: value.spt_version.to_string(),
+			default_install_target: value.default_install_target,
+			never_install: (!value.never_install.is_empty()).then_some(value.never_install),
+			blocked_hashes: (!value.blocked_hashes.is_empty()).then_some(value.blocked_hashes),
+			download_retries: value.download_retries,
+			retry_backoff_ms: value.retry_backoff_ms,
 			mods: value.mods.into_iter().map(|x| x.into()).collect(),
 		}
 	}
 }
 
+/// Settings of the mod manager itself rather than of an SPT folder, such as the SPT installations
+/// it can be pointed at by name.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct AppConfiguration {
+	#[serde(default)]
+	pub installations: HashMap<String, PathBuf>,
+}
+
+impl AppConfiguration {
+	/// Reads [`APP_CONFIG_FILE_NAME`] from `config_root`, or the defaults when there is none yet.
+	pub async fn read(config_root: &Path) -> Result<Self> {
+		let path = config_root.join(APP_CONFIG_FILE_NAME);
+		if !path.is_file() {
+			return Ok(Self::default());
+		}
+		let buffer = tokio::fs::read(&path).await?;
+		serde_json::from_slice(&buffer)
+			.with_context(|| format!("Failed to parse the app configuration: {}", path.display()))
+	}
+
+	pub fn resolve_installation(&self, name: &str) -> Result<&Path> {
+		if let Some(path) = self.installations.get(name) {
+			return Ok(path);
+		}
+		let mut names: Vec<_> = self.installations.keys().map(String::as_str).collect();
+		names.sort();
+		Err(anyhow!(
+			"Found no installation named '{name}' in the app configuration, known installations: [{}]",
+			names.join(", ")
+		))
+	}
+}
+
+/// The format of a mod configuration file, told apart by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigurationFormat {
+	Json,
+	Yaml,
+}
+
+impl ConfigurationFormat {
+	pub fn from_path(path: &Path) -> Self {
+		let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+		match extension.to_lowercase().as_str() {
+			"yaml" | "yml" => Self::Yaml,
+			_ => Self::Json,
+		}
+	}
+
+	fn parse(self, data: &[u8]) -> Result<ModConfigurationRaw> {
+		Ok(match self {
+			Self::Json => serde_json::from_slice(data)?,
+			Self::Yaml => serde_yaml::from_slice(data)?,
+		})
+	}
+
+	/// Writes JSON pretty printed. YAML comments aren't kept, as they aren't read in the first place.
+	fn serialize(self, cfg: &ModConfigurationRaw) -> Result<Vec<u8>> {
+		Ok(match self {
+			Self::Json => serde_json::to_vec_pretty(cfg)?,
+			Self::Yaml => serde_yaml::to_string(cfg)?.into_bytes(),
+		})
+	}
+}
+
 #[derive(Debug, Clone)]
 pub struct ConfigurationAccess {
 	mod_cfg_path: PathBuf,
@@ -94,10 +467,22 @@ impl ConfigurationAccess {
 		if !root_path.is_dir() {
 			return Err(anyhow!("Root folder must be a directory"));
 		}
-		let mod_cfg_path = root_path.join("spt_mods.json");
+		let mod_cfg_path = YAML_MOD_CONFIG_FILE_NAMES
+			.iter()
+			.map(|file_name| root_path.join(file_name))
+			.find(|path| path.is_file())
+			.unwrap_or_else(|| root_path.join(MOD_CONFIG_FILE_NAME));
 
 		Ok(Self { mod_cfg_path })
 	}
+
+	/// Reads and writes a configuration at another path, such as another profile's `spt_mods.json`,
+	/// in the format its extension names.
+	pub fn from_file(mod_cfg_path: impl Into<PathBuf>) -> Self {
+		Self {
+			mod_cfg_path: mod_cfg_path.into(),
+		}
+	}
 	pub async fn read_remote_mods(&self) -> Result<ModConfiguration> {
 		let mut buffer = Vec::new();
 		OpenOptions::new()
@@ -107,23 +492,81 @@ impl ConfigurationAccess {
 			.read_to_end(&mut buffer)
 			.await?;
 
-		let raw_cfgs: ModConfigurationRaw = serde_json::from_slice(&buffer)?;
+		let raw_cfgs = ConfigurationFormat::from_path(&self.mod_cfg_path).parse(&buffer)?;
+		ModConfiguration::try_from(raw_cfgs)
+	}
 
-		let mut mods = Vec::new();
-		for x in raw_cfgs.mods {
-			mods.push(ModVersionConfiguration::try_from(x)?)
+	/// Reads a whole `spt_mods.json` from `reader`, such as a configuration piped through stdin.
+	pub fn read_remote_mods_from(mut reader: impl Read) -> Result<ModConfiguration> {
+		let mut buffer = Vec::new();
+		reader.read_to_end(&mut buffer)?;
+		Self::parse_remote_mods(&buffer)
+	}
+
+	/// Whether a configuration location is a url to fetch rather than a file path.
+	pub fn is_remote(location: &str) -> bool {
+		location.starts_with("https://") || location.starts_with("http://")
+	}
+
+	/// Downloads the `spt_mods.json` a modpack hosts at `url`, keeping a copy in `cache_root` that is
+	/// read instead when the url can't be reached.
+	pub async fn fetch_remote_mods(url: &str, cache_root: &Path) -> Result<ModConfiguration> {
+		let cache_path = cache_root
+			.join(REMOTE_CONFIG_CACHE_FOLDER)
+			.join(format!("{}.json", sha256::digest(url)));
+		let response = match Client::new().get(url).send().await {
+			Ok(response) => response,
+			Err(err) if cache_path.is_file() => {
+				let cached = Self::from_file(&cache_path).read_remote_mods().await;
+				return cached.with_context(|| {
+					format!("Failed to fetch the configuration from '{url}' with: {err}")
+				});
+			}
+			Err(err) => return Err(err.into()),
+		};
+
+		match response.status() {
+			StatusCode::NOT_FOUND => {
+				return Err(anyhow!("Found no configuration at '{url}' (404 Not Found)"))
+			}
+			status if !status.is_success() => {
+				return Err(anyhow!("Failed to fetch the configuration from '{url}': {status}"))
+			}
+			_ => {}
+		}
+		if let Some(content_type) = response.headers().get(CONTENT_TYPE) {
+			let content_type = content_type.to_str().unwrap_or_default();
+			if !content_type.contains("json") && !content_type.starts_with("text/plain") {
+				return Err(anyhow!(
+					"Expected a JSON configuration from '{url}' but got '{content_type}'"
+				));
+			}
 		}
 
-		Ok(ModConfiguration {
-			mods,
-			spt_version: raw_cfgs.spt_version,
-		})
+		let data = response.bytes().await?;
+		let configuration = Self::parse_remote_mods(&data)
+			.with_context(|| format!("Failed to parse the configuration from '{url}'"))?;
+		tokio::fs::create_dir_all(cache_root.join(REMOTE_CONFIG_CACHE_FOLDER)).await?;
+		tokio::fs::write(&cache_path, &data).await?;
+		Ok(configuration)
+	}
+
+	/// Prints a configuration the way `spt_mods.json` holds it.
+	pub fn to_json(mod_configuration: &ModConfiguration) -> Result<String> {
+		let cfg: ModConfigurationRaw = mod_configuration.clone().into();
+		Ok(String::from_utf8(ConfigurationFormat::Json.serialize(&cfg)?)?)
+	}
+
+	pub fn parse_remote_mods(data: &[u8]) -> Result<ModConfiguration> {
+		let raw_cfgs: ModConfigurationRaw = serde_json::from_slice(data)?;
+		ModConfiguration::try_from(raw_cfgs)
 	}
 
 	pub async fn write_remote_mods(&self, mod_configuration: &ModConfiguration) -> Result<()> {
 		let cfg: ModConfigurationRaw = mod_configuration.clone().into();
-		let buffer = serde_json::to_vec(&cfg)?;
+		let buffer = ConfigurationFormat::from_path(&self.mod_cfg_path).serialize(&cfg)?;
 		let mut file = OpenOptions::new()
+			.write(true)
 			.create(true)
 			.truncate(true)
 			.open(&self.mod_cfg_path)
@@ -136,6 +579,9 @@ impl ConfigurationAccess {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::shared_traits::MockTimeProvider;
+	use crate::spt_access::SptAccess;
+	use crate::test_server::{TestResponse, TestServer};
 
 	//TODO: More tests please :)
 
@@ -157,9 +603,414 @@ mod tests {
 				install_path: None,
 				version_filter: None,
 				github_filter: None,
+				github_split_parts: false,
+				github_min_release_date: None,
+				install_target: None,
+				timeout_secs: None,
+				version_scheme: VersionScheme::default(),
+				allow_prerelease: false,
+				group: None,
 			}],
 			spt_version: Versioning::Ideal("3.8.3".parse().unwrap()),
+			default_install_target: None,
+			never_install: Vec::new(),
+			blocked_hashes: Vec::new(),
+			download_retries: None,
+			retry_backoff_ms: None,
 		};
 		assert_eq!(option, cfg);
 	}
+
+	#[tokio::test]
+	async fn invalid_spt_version_is_reported_clearly() {
+		let path = "./test_output/invalid_spt_version_test";
+		tokio::fs::create_dir_all(path).await.unwrap();
+		let cfg_path = format!("{path}/spt_mods.json");
+		tokio::fs::write(&cfg_path, r#"{ "spt_version": "three point nine", "mods": [] }"#)
+			.await
+			.unwrap();
+
+		let error = ConfigurationAccess::from_file(&cfg_path)
+			.read_remote_mods()
+			.await
+			.unwrap_err();
+
+		assert!(error.to_string().contains("Invalid spt_version 'three point nine'"));
+		tokio::fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[test]
+	fn install_target_precedence_prefers_most_specific() {
+		let mod_cfg = ModVersionConfiguration {
+			url: "https://github.com/test/mactest/".to_string(),
+			version: None,
+			github_pattern: None,
+			install_path: None,
+			version_filter: None,
+			github_filter: None,
+			github_split_parts: false,
+			github_min_release_date: None,
+			install_target: None,
+			timeout_secs: None,
+			version_scheme: VersionScheme::default(),
+			allow_prerelease: false,
+			group: None,
+		};
+		let mut cfg = ModConfiguration {
+			mods: vec![mod_cfg.clone()],
+			spt_version: Versioning::Ideal("3.8.3".parse().unwrap()),
+			default_install_target: None,
+			never_install: Vec::new(),
+			blocked_hashes: Vec::new(),
+			download_retries: None,
+			retry_backoff_ms: None,
+		};
+		assert_eq!(cfg.resolve_install_target(&mod_cfg, None), DEFAULT_INSTALL_TARGET);
+
+		cfg.default_install_target = Some(InstallTarget::Server);
+		assert_eq!(cfg.resolve_install_target(&mod_cfg, None), InstallTarget::Server);
+		assert_eq!(
+			cfg.resolve_install_target(&mod_cfg, Some(InstallTarget::Client)),
+			InstallTarget::Client
+		);
+
+		let mod_override = ModVersionConfiguration {
+			install_target: Some(InstallTarget::Server),
+			..mod_cfg
+		};
+		cfg.default_install_target = Some(InstallTarget::Client);
+		assert_eq!(
+			cfg.resolve_install_target(&mod_override, Some(InstallTarget::Client)),
+			InstallTarget::Server
+		);
+	}
+
+	#[test]
+	fn resolved_configuration_fills_in_every_default() {
+		let data = br#"{
+			"spt_version": "3.9.8",
+			"default_install_target": "server",
+			"retry_backoff_ms": 250,
+			"mods": [
+				{ "url": "https://github.com/test/mactest/", "install_path": "@plugins/MacTest" },
+				{ "url": "https://github.com/test/client/", "install_target": "client" }
+			]
+		}"#;
+		let cfg = ConfigurationAccess::read_remote_mods_from(&data[..]).unwrap();
+		let layout = SptLayout::new("spt");
+
+		let resolved = cfg.resolve(&layout, None, Some(7), None);
+
+		let plugin_path = layout.resolve_install_path("@plugins/MacTest");
+		let expected = ModConfiguration {
+			mods: vec![
+				ModVersionConfiguration {
+					install_path: Some(plugin_path.to_string_lossy().to_string()),
+					install_target: Some(InstallTarget::Server),
+					..cfg.mods[0].clone()
+				},
+				ModVersionConfiguration {
+					install_target: Some(InstallTarget::Client),
+					..cfg.mods[1].clone()
+				},
+			],
+			default_install_target: Some(InstallTarget::Server),
+			download_retries: Some(7),
+			retry_backoff_ms: Some(250),
+			..cfg.clone()
+		};
+		assert_eq!(resolved, expected);
+		let dumped = ConfigurationAccess::to_json(&resolved).unwrap();
+		assert_eq!(ConfigurationAccess::parse_remote_mods(dumped.as_bytes()).unwrap(), expected);
+	}
+
+	#[tokio::test]
+	async fn mods_list_adds_valid_lines_and_reports_the_rest() {
+		let path = "./test_output/mods_list_test";
+		tokio::fs::create_dir_all(path).await.unwrap();
+		let list_path = format!("{path}/mods.txt");
+		let list = "# Server mods
+https://hub.sp-tarkov.com/files/file/1963-better-keys-updated
+
+https://github.com/test/mactest|mactest.*\\.zip|beta
+https://github.com/test/no-pattern
+not a url
+https://hub.sp-tarkov.com/files/file/1963-better-keys-updated
+";
+		tokio::fs::write(&list_path, list).await.unwrap();
+		let mut cfg = ConfigurationAccess::read_remote_mods_from(
+			&br#"{ "spt_version": "3.9.8", "mods": [] }"#[..],
+		)
+		.unwrap();
+
+		let text = tokio::fs::read_to_string(&list_path).await.unwrap();
+		let (mods, rejected) = parse_mods_list(&text);
+		let duplicates: Vec<_> = mods
+			.into_iter()
+			.filter_map(|mod_cfg| cfg.add_mod(mod_cfg).err())
+			.collect();
+
+		let urls: Vec<_> = cfg.mods.iter().map(|mod_cfg| mod_cfg.url.as_str()).collect();
+		assert_eq!(
+			urls,
+			[
+				"https://hub.sp-tarkov.com/files/file/1963-better-keys-updated",
+				"https://github.com/test/mactest"
+			]
+		);
+		assert_eq!(cfg.mods[1].github_pattern.as_deref(), Some("mactest.*\\.zip"));
+		assert_eq!(cfg.mods[1].github_filter.as_deref(), Some("beta"));
+		let rejected_lines: Vec<_> = rejected.iter().map(|rejected| rejected.line).collect();
+		assert_eq!(rejected_lines, [5, 6]);
+		assert!(rejected[0].error.contains("No asset pattern"), "{}", rejected[0].error);
+		assert_eq!(duplicates.len(), 1);
+		tokio::fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[test]
+	fn configuration_reads_from_reader() {
+		let data = br#"{
+			"spt_version": "3.9.8",
+			"never_install": ["**/readme.txt"],
+			"mods": [{ "url": "https://github.com/test/mactest/", "version": "1.2.3" }]
+		}"#;
+
+		let cfg = ConfigurationAccess::read_remote_mods_from(&data[..]).unwrap();
+
+		assert_eq!(cfg.spt_version, Versioning::new("3.9.8").unwrap());
+		assert_eq!(cfg.never_install, vec!["**/readme.txt".to_string()]);
+		assert_eq!(cfg.mods.len(), 1);
+		assert_eq!(cfg.mods[0].version, Versioning::new("1.2.3").map(VersionConstraint::from));
+	}
+
+	#[test]
+	fn date_version_scheme_is_read_with_its_format() {
+		let data = br#"{ "spt_version": "3.9.8", "mods": [
+			{ "url": "https://hub.sp-tarkov.com/files/file/1963-better-keys-updated",
+			  "version_scheme": "date", "version_date_format": "%d-%m-%Y" }
+		] }"#;
+		let format_only = br#"{ "spt_version": "3.9.8", "mods": [
+			{ "url": "https://hub.sp-tarkov.com/files/file/1963-better-keys-updated",
+			  "version_date_format": "%d-%m-%Y" }
+		] }"#;
+
+		let cfg = ConfigurationAccess::read_remote_mods_from(&data[..]).unwrap();
+
+		let expected = VersionScheme::Date {
+			format: Some("%d-%m-%Y".to_string()),
+		};
+		assert_eq!(cfg.mods[0].version_scheme, expected);
+		let written = ModVersionConfigurationRaw::from(cfg.mods[0].clone());
+		assert_eq!(written.version_scheme, Some(VersionSchemeName::Date));
+		assert_eq!(written.version_date_format.as_deref(), Some("%d-%m-%Y"));
+		assert!(ConfigurationAccess::read_remote_mods_from(&format_only[..]).is_err());
+	}
+
+	#[test]
+	fn github_min_release_date_is_read_and_written_back() {
+		let data = br#"{ "spt_version": "3.9.8", "mods": [
+			{ "url": "https://github.com/test/sain/", "github_min_release_date": "2024-06-11" }
+		] }"#;
+		let invalid = br#"{ "spt_version": "3.9.8", "mods": [
+			{ "url": "https://github.com/test/sain/", "github_min_release_date": "11-06-2024" }
+		] }"#;
+
+		let cfg = ConfigurationAccess::read_remote_mods_from(&data[..]).unwrap();
+
+		assert_eq!(cfg.mods[0].github_min_release_date, NaiveDate::from_ymd_opt(2024, 6, 11));
+		let written = ModVersionConfigurationRaw::from(cfg.mods[0].clone());
+		assert_eq!(written.github_min_release_date.as_deref(), Some("2024-06-11"));
+		assert!(ConfigurationAccess::read_remote_mods_from(&invalid[..]).is_err());
+	}
+
+	#[test]
+	fn group_keeps_only_its_mods() {
+		let data = br#"{ "spt_version": "3.9.8", "mods": [
+			{ "url": "https://github.com/test/sain/", "group": "core" },
+			{ "url": "https://github.com/test/realism/", "group": "optional" },
+			{ "url": "https://github.com/test/ungrouped/" },
+			{ "url": "https://github.com/test/lootingbots/", "group": "optional" }
+		] }"#;
+		let mut cfg = ConfigurationAccess::read_remote_mods_from(&data[..]).unwrap();
+
+		cfg.retain_group("optional");
+
+		let urls: Vec<_> = cfg.mods.iter().map(|mod_cfg| mod_cfg.url.as_str()).collect();
+		assert_eq!(
+			urls,
+			["https://github.com/test/realism/", "https://github.com/test/lootingbots/"]
+		);
+	}
+
+	#[test]
+	fn version_ranges_are_read_and_written_back_as_written() {
+		let data = br#"{ "spt_version": "3.9.8", "mods": [
+			{ "url": "https://hub.sp-tarkov.com/files/file/1963-better-keys-updated", "version": "^1.2" }
+		] }"#;
+
+		let cfg = ConfigurationAccess::read_remote_mods_from(&data[..]).unwrap();
+		let version = cfg.mods[0].version.as_ref().unwrap();
+
+		assert_eq!(version.as_exact(), None);
+		assert!(version.matches(&Versioning::new("1.4.0").unwrap()));
+		assert!(!version.matches(&Versioning::new("2.0.0").unwrap()));
+		let written = ModVersionConfigurationRaw::from(cfg.mods[0].clone());
+		assert_eq!(written.version.as_deref(), Some("^1.2"));
+	}
+
+	#[tokio::test]
+	async fn remote_configuration_is_fetched_and_cached() {
+		let cache_root = Path::new("./test_output/remote_config_test");
+		let _discard = tokio::fs::remove_dir_all(cache_root).await;
+		let server = TestServer::start(vec![
+			(
+				"/spt_mods.json",
+				TestResponse::ok(
+					r#"{ "spt_version": "3.9.8", "mods": [{ "url": "https://github.com/test/mactest/" }] }"#,
+				)
+				.with_header("Content-Type", "application/json"),
+			),
+			(
+				"/page.html",
+				TestResponse::ok("<html></html>").with_header("Content-Type", "text/html"),
+			),
+		])
+		.await;
+		let url = server.url("/spt_mods.json");
+
+		let cfg = ConfigurationAccess::fetch_remote_mods(url.as_str(), cache_root)
+			.await
+			.unwrap();
+		assert_eq!(cfg.spt_version, Versioning::new("3.9.8").unwrap());
+		assert_eq!(cfg.mods.len(), 1);
+
+		let missing_url = server.url("/missing.json");
+		let error = ConfigurationAccess::fetch_remote_mods(missing_url.as_str(), cache_root)
+			.await
+			.unwrap_err();
+		assert!(error.to_string().contains("404 Not Found"));
+
+		let page_url = server.url("/page.html");
+		let error = ConfigurationAccess::fetch_remote_mods(page_url.as_str(), cache_root)
+			.await
+			.unwrap_err();
+		assert!(error.to_string().contains("text/html"));
+
+		let cache_path = cache_root
+			.join(REMOTE_CONFIG_CACHE_FOLDER)
+			.join(format!("{}.json", sha256::digest(url.as_str())));
+		assert_eq!(
+			ConfigurationAccess::from_file(cache_path).read_remote_mods().await.unwrap(),
+			cfg
+		);
+		tokio::fs::remove_dir_all(cache_root).await.unwrap()
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn configured_retries_apply_unless_the_command_line_overrides_them() {
+		let data = br#"{
+			"spt_version": "3.9.8",
+			"download_retries": 3,
+			"retry_backoff_ms": 250,
+			"mods": []
+		}"#;
+		let cfg = ConfigurationAccess::parse_remote_mods(data).unwrap();
+
+		assert_eq!(
+			cfg.resolve_retry_policy(None, None),
+			RetryPolicy {
+				retries: 3,
+				backoff: Duration::from_millis(250),
+			}
+		);
+		assert_eq!(
+			cfg.resolve_retry_policy(Some(1), None),
+			RetryPolicy {
+				retries: 1,
+				backoff: Duration::from_millis(250),
+			}
+		);
+		let defaults =
+			ConfigurationAccess::parse_remote_mods(br#"{ "spt_version": "3.9.8", "mods": [] }"#)
+				.unwrap();
+		assert_eq!(defaults.resolve_retry_policy(None, None), RetryPolicy::default());
+
+		let attempts = std::sync::atomic::AtomicU32::new(0);
+		let result: Result<()> = cfg
+			.resolve_retry_policy(None, None)
+			.retry(|| async {
+				attempts.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+				Err(anyhow!("mirror unavailable"))
+			})
+			.await;
+		assert!(result.is_err());
+		assert_eq!(attempts.into_inner(), 4);
+	}
+
+	#[tokio::test]
+	async fn named_installation_resolves_to_its_spt_folder() {
+		let path = "./test_output/installations_test";
+		let _discard = tokio::fs::remove_dir_all(path).await;
+		let beta_root = format!("{path}/spt_beta");
+		tokio::fs::create_dir_all(&beta_root).await.unwrap();
+		tokio::fs::write(format!("{beta_root}/SPT.Server.exe"), "").await.unwrap();
+		let path_access = PathAccess::from(path, "./").unwrap();
+		let config_root = path_access.config_root().to_path_buf();
+		let _discard = tokio::fs::remove_dir_all(&config_root).await;
+		tokio::fs::create_dir_all(&config_root).await.unwrap();
+		tokio::fs::write(
+			config_root.join(APP_CONFIG_FILE_NAME),
+			format!(
+				r#"{{ "installations": {{ "stable": "{path}/spt_stable", "beta": "{beta_root}" }} }}"#
+			),
+		)
+		.await
+		.unwrap();
+
+		let app_configuration = AppConfiguration::read(&config_root).await.unwrap();
+		let beta_path = app_configuration.resolve_installation("beta").unwrap();
+		assert_eq!(beta_path, Path::new(&beta_root));
+		assert!(app_configuration.resolve_installation("nightly").is_err());
+
+		let path_access = path_access.with_spt_root(beta_path);
+		let spt_access = SptAccess::init(&path_access, MockTimeProvider::new()).await.unwrap();
+		assert_eq!(spt_access.layout().root(), Path::new(&beta_root));
+		tokio::fs::remove_dir_all(&config_root).await.unwrap();
+		tokio::fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn yaml_configuration_is_preferred_and_written_back_as_yaml() {
+		let path = "./test_output/yaml_configuration_test";
+		let _discard = tokio::fs::remove_dir_all(path).await;
+		tokio::fs::create_dir_all(path).await.unwrap();
+		tokio::fs::write(
+			format!("{path}/{MOD_CONFIG_FILE_NAME}"),
+			r#"{ "spt_version": "3.8.3", "mods": [] }"#,
+		)
+		.await
+		.unwrap();
+		let yaml_path = format!("{path}/spt_mods.yml");
+		let yaml = concat!(
+			"# Kept by hand\nspt_version: 3.9.8\nmods:\n",
+			"  - url: https://github.com/test/mactest/ # Keys\n",
+		);
+		tokio::fs::write(&yaml_path, yaml).await.unwrap();
+		let path_access = PathAccess::from(path, path).unwrap();
+		let cfg_access = ConfigurationAccess::init(&path_access).await.unwrap();
+
+		let mut configuration = cfg_access.read_remote_mods().await.unwrap();
+		assert_eq!(configuration.spt_version, Versioning::new("3.9.8").unwrap());
+		assert_eq!(configuration.mods[0].url, "https://github.com/test/mactest/");
+
+		configuration
+			.set_mod_version("https://github.com/test/mactest/", Versioning::new("1.2.3"))
+			.unwrap();
+		cfg_access.write_remote_mods(&configuration).await.unwrap();
+
+		let written = tokio::fs::read_to_string(&yaml_path).await.unwrap();
+		assert!(written.starts_with("spt_version:"), "{written}");
+		assert_eq!(cfg_access.read_remote_mods().await.unwrap(), configuration);
+		tokio::fs::remove_dir_all(path).await.unwrap()
+	}
 }
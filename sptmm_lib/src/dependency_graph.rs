@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+	InProgress,
+	Done,
+}
+
+/// Finds a cycle in a graph mapping each node to the nodes it points at, returned as the path
+/// around it with the first node repeated at the end, such as `A, B, C, A`.
+pub fn find_cycle<Node: Ord + Clone>(graph: &BTreeMap<Node, Vec<Node>>) -> Option<Vec<Node>> {
+	let mut visits = BTreeMap::new();
+	let mut path = Vec::new();
+	graph
+		.keys()
+		.find_map(|node| visit(node, graph, &mut visits, &mut path))
+}
+
+fn visit<Node: Ord + Clone>(
+	node: &Node,
+	graph: &BTreeMap<Node, Vec<Node>>,
+	visits: &mut BTreeMap<Node, Visit>,
+	path: &mut Vec<Node>,
+) -> Option<Vec<Node>> {
+	match visits.get(node) {
+		Some(Visit::Done) => return None,
+		Some(Visit::InProgress) => {
+			let start = path.iter().position(|visited| visited == node)?;
+			let mut cycle = path[start..].to_vec();
+			cycle.push(node.clone());
+			return Some(cycle);
+		}
+		None => {}
+	}
+
+	visits.insert(node.clone(), Visit::InProgress);
+	path.push(node.clone());
+	for next in graph.get(node).into_iter().flatten() {
+		if let Some(cycle) = visit(next, graph, visits, path) {
+			return Some(cycle);
+		}
+	}
+	path.pop();
+	visits.insert(node.clone(), Visit::Done);
+	None
+}
+
+/// Formats a cycle from [`find_cycle`] as `A -> B -> C -> A`.
+pub fn format_cycle<Node: Display>(cycle: &[Node]) -> String {
+	cycle
+		.iter()
+		.map(|node| node.to_string())
+		.collect::<Vec<_>>()
+		.join(" -> ")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn graph(edges: &[(&'static str, &[&'static str])]) -> BTreeMap<&'static str, Vec<&'static str>> {
+		edges
+			.iter()
+			.map(|(node, next)| (*node, next.to_vec()))
+			.collect()
+	}
+
+	#[test]
+	fn three_node_cycle_reports_full_path() {
+		let graph = graph(&[("A", &["B"]), ("B", &["C"]), ("C", &["A"]), ("D", &["A"])]);
+
+		let cycle = find_cycle(&graph).unwrap();
+
+		assert_eq!(cycle, vec!["A", "B", "C", "A"]);
+		assert_eq!(format_cycle(&cycle), "A -> B -> C -> A");
+	}
+
+	#[test]
+	fn cycle_path_starts_where_it_loops() {
+		let graph = graph(&[("A", &["B"]), ("B", &["C"]), ("C", &["D"]), ("D", &["B"])]);
+
+		assert_eq!(find_cycle(&graph).unwrap(), vec!["B", "C", "D", "B"]);
+	}
+
+	#[test]
+	fn shared_dependencies_are_not_cycles() {
+		let graph = graph(&[("A", &["B", "C"]), ("B", &["C"]), ("C", &[])]);
+
+		assert_eq!(find_cycle(&graph), None);
+	}
+}
@@ -0,0 +1,189 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::shared_traits::FileSystem;
+
+/// Reads and writes the files on disk.
+#[derive(Debug, Clone, Default)]
+pub struct DiskFileSystem {}
+
+impl FileSystem for DiskFileSystem {
+	fn create_dir_all(&self, path: &Path) -> Result<()> {
+		std::fs::create_dir_all(path)
+	}
+
+	fn write(&self, path: &Path, data: &[u8]) -> Result<()> {
+		std::fs::write(path, data)
+	}
+
+	fn read(&self, path: &Path) -> Result<Vec<u8>> {
+		std::fs::read(path)
+	}
+
+	fn remove_file(&self, path: &Path) -> Result<()> {
+		std::fs::remove_file(path)
+	}
+
+	fn remove_dir(&self, path: &Path) -> Result<()> {
+		std::fs::remove_dir(path)
+	}
+
+	fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+		std::fs::rename(from, to)
+	}
+
+	fn list_files(&self, path: &Path) -> Result<Vec<PathBuf>> {
+		let mut files = Vec::new();
+		for entry in std::fs::read_dir(path)? {
+			let path = entry?.path();
+			if path.is_file() {
+				files.push(path);
+			}
+		}
+		Ok(files)
+	}
+
+	fn is_file(&self, path: &Path) -> bool {
+		path.is_file()
+	}
+}
+
+/// Keeps the files in memory, so installs can be tested without touching the disk.
+#[derive(Debug, Default)]
+pub struct MemoryFileSystem {
+	files: Mutex<HashMap<PathBuf, Vec<u8>>>,
+	dirs: Mutex<HashSet<PathBuf>>,
+}
+
+impl MemoryFileSystem {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The paths of every file written so far, sorted.
+	pub fn file_paths(&self) -> Vec<PathBuf> {
+		let mut paths: Vec<_> = self.files.lock().unwrap().keys().cloned().collect();
+		paths.sort();
+		paths
+	}
+
+	fn ensure_parent(&self, path: &Path) -> Result<()> {
+		let has_parent = match path.parent() {
+			Some(parent) if !parent.as_os_str().is_empty() => self.dirs.lock().unwrap().contains(parent),
+			_ => true,
+		};
+		if !has_parent {
+			return Err(Error::new(ErrorKind::NotFound, format!("Missing folder for {}", path.display())));
+		}
+		Ok(())
+	}
+}
+
+impl FileSystem for MemoryFileSystem {
+	fn create_dir_all(&self, path: &Path) -> Result<()> {
+		let path = normalize(path);
+		let mut dirs = self.dirs.lock().unwrap();
+		dirs.extend(path.ancestors().map(Path::to_path_buf));
+		Ok(())
+	}
+
+	fn write(&self, path: &Path, data: &[u8]) -> Result<()> {
+		let path = normalize(path);
+		self.ensure_parent(&path)?;
+		self.files.lock().unwrap().insert(path, data.to_vec());
+		Ok(())
+	}
+
+	fn read(&self, path: &Path) -> Result<Vec<u8>> {
+		let path = normalize(path);
+		self.files
+			.lock()
+			.unwrap()
+			.get(&path)
+			.cloned()
+			.ok_or_else(|| Error::new(ErrorKind::NotFound, format!("No file at {}", path.display())))
+	}
+
+	fn remove_file(&self, path: &Path) -> Result<()> {
+		let path = normalize(path);
+		self.files
+			.lock()
+			.unwrap()
+			.remove(&path)
+			.map(|_| ())
+			.ok_or_else(|| Error::new(ErrorKind::NotFound, format!("No file at {}", path.display())))
+	}
+
+	fn remove_dir(&self, path: &Path) -> Result<()> {
+		let path = normalize(path);
+		let mut dirs = self.dirs.lock().unwrap();
+		if !dirs.contains(&path) {
+			return Err(Error::new(ErrorKind::NotFound, format!("No folder at {}", path.display())));
+		}
+		let is_child = |child: &PathBuf| child.parent() == Some(path.as_path());
+		if dirs.iter().any(is_child) || self.files.lock().unwrap().keys().any(is_child) {
+			return Err(Error::other(format!("Folder isn't empty: {}", path.display())));
+		}
+		dirs.remove(&path);
+		Ok(())
+	}
+
+	fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+		let (from, to) = (normalize(from), normalize(to));
+		self.ensure_parent(&to)?;
+		let mut files = self.files.lock().unwrap();
+		let data = files
+			.remove(&from)
+			.ok_or_else(|| Error::new(ErrorKind::NotFound, format!("No file at {}", from.display())))?;
+		files.insert(to, data);
+		Ok(())
+	}
+
+	fn list_files(&self, path: &Path) -> Result<Vec<PathBuf>> {
+		let path = normalize(path);
+		if !self.dirs.lock().unwrap().contains(&path) {
+			return Err(Error::new(ErrorKind::NotFound, format!("No folder at {}", path.display())));
+		}
+		Ok(self
+			.files
+			.lock()
+			.unwrap()
+			.keys()
+			.filter(|file| file.parent() == Some(path.as_path()))
+			.cloned()
+			.collect())
+	}
+
+	fn is_file(&self, path: &Path) -> bool {
+		self.files.lock().unwrap().contains_key(&normalize(path))
+	}
+}
+
+/// Drops `.` components so `./spt/file` and `spt/file` name the same file.
+fn normalize(path: &Path) -> PathBuf {
+	path.components()
+		.filter(|component| !matches!(component, Component::CurDir))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn memory_files_need_their_folder() {
+		let files = MemoryFileSystem::new();
+		let path = Path::new("./spt/user/mods/file.txt");
+
+		assert_eq!(files.write(path, b"data").unwrap_err().kind(), ErrorKind::NotFound);
+		files.create_dir_all(Path::new("spt/user/mods")).unwrap();
+		files.write(path, b"data").unwrap();
+
+		assert!(files.is_file(Path::new("spt/user/mods/file.txt")));
+		assert_eq!(files.read(path).unwrap(), b"data");
+		files.remove_file(path).unwrap();
+		assert!(!files.is_file(path));
+	}
+}
@@ -1,6 +1,13 @@
 pub mod configuration_access;
+pub mod dependency_graph;
+pub mod file_access;
 pub mod remote_mod_access;
 pub mod shared_traits;
 pub mod spt_access;
 pub mod time_access;
-pub mod path_access;
\ No newline at end of file
+pub mod path_access;
+pub mod version_constraint;
+pub mod version_scheme;
+
+#[cfg(test)]
+mod test_server;
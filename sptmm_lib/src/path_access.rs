@@ -1,3 +1,4 @@
+use anyhow::anyhow;
 use directories_next::ProjectDirs;
 use std::path::{Path, PathBuf};
 
@@ -31,6 +32,13 @@ impl PathAccess {
 		})
 	}
 
+	/// Points at another SPT folder while keeping the cache and configuration folders, such as a
+	/// named installation from the app configuration.
+	pub fn with_spt_root(mut self, spt_path: impl AsRef<Path>) -> Self {
+		self.spt_root = spt_path.as_ref().into();
+		self
+	}
+
 	pub fn cache_root(&self) -> &Path {
 		self.project_dirs.cache_dir()
 	}
@@ -42,4 +50,31 @@ impl PathAccess {
 	pub fn spt_root(&self) -> &Path {
 		&self.spt_root
 	}
+
+	/// Errors when the SPT folder and the cache folder contain one another, as backing up or
+	/// removing one of them would then reach into the other.
+	pub fn ensure_separate_roots(&self) -> anyhow::Result<()> {
+		let spt_root = resolve(self.spt_root());
+		let cache_root = resolve(self.cache_root());
+		if spt_root.starts_with(&cache_root) || cache_root.starts_with(&spt_root) {
+			return Err(anyhow!(
+				"The SPT folder '{}' and the cache folder '{}' overlap, move one of them outside the other",
+				spt_root.display(),
+				cache_root.display()
+			));
+		}
+		Ok(())
+	}
+}
+
+/// Resolves symlinks of the deepest existing ancestor, as the cache folder might not exist yet.
+pub(crate) fn resolve(path: &Path) -> PathBuf {
+	let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+	for ancestor in absolute.ancestors() {
+		if let Ok(canonical) = ancestor.canonicalize() {
+			let rest = absolute.strip_prefix(ancestor).unwrap_or(Path::new(""));
+			return canonical.join(rest);
+		}
+	}
+	absolute
 }
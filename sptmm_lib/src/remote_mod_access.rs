@@ -1,30 +1,72 @@
 use crate::remote_mod_access::cache_mod_access::{
-	CacheModAccess, CachedModVersion, ModCacheStatus,
+	ArchiveIntegrity, CacheModAccess, CachePairingReport, CacheReference, CachedMod,
+	CachedModVersion, ModCacheStatus,
 };
 use anyhow::{anyhow, Context, Result};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use reqwest::header::HeaderMap;
 use reqwest::{Client, ClientBuilder, Url};
 use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 use serde::{Deserialize, Serialize};
 use versions::Versioning;
+use crate::configuration_access::ModVersionConfiguration;
 use crate::path_access::PathAccess;
+use crate::remote_mod_access::direct_repository::{DirectLink, DirectRepository};
 use crate::remote_mod_access::github_mod_repository::{GITHUB_DOMAIN, GitHubLink, GithubModRepository};
+use crate::remote_mod_access::gitlab_mod_repository::{GitLabLink, GitLabModRepository, GITLAB_DOMAIN};
 use crate::remote_mod_access::mod_version_downloader::ModVersionDownloader;
+use crate::remote_mod_access::raw_file_repository::{
+	RawFileLink, GIST_DOMAIN, RAW_GIST_DOMAIN, RAW_GITHUB_DOMAIN,
+};
 use crate::remote_mod_access::spt_mod_repository::{SptModRepository, SptLink, SPT_DOMAIN};
 use crate::shared_traits::{ModName, ModVersion};
+use crate::version_constraint::VersionConstraint;
+use crate::version_scheme::VersionScheme;
+pub use crate::remote_mod_access::remote_error::RemoteError;
+pub use crate::remote_mod_access::retry::RetryPolicy;
+pub use crate::remote_mod_access::spt_mod_repository::DEFAULT_HUB_CACHE_TTL;
+
+/// Told the bytes downloaded so far and the size of the whole download, when the server tells it,
+/// after every chunk of a download.
+pub type DownloadProgress = Arc<dyn Fn(u64, Option<u64>) + Send + Sync>;
 
+mod bandwidth_limiter;
 pub mod cache_mod_access;
+mod direct_repository;
 mod github_mod_repository;
+mod gitlab_mod_repository;
 mod html_parsers;
 mod mod_version_downloader;
+mod raw_file_repository;
+mod remote_error;
+mod retry;
 mod spt_mod_repository;
 
-const SUPPORTED_DOMAINS: &[&str] = &[GITHUB_DOMAIN, SPT_DOMAIN];
+/// Remembers the `ETag` and `Last-Modified` of each hub mod's versions between runs.
+const HUB_VALIDATORS_FILE_NAME: &str = "hub_validators.json";
+
+const SUPPORTED_DOMAINS: &[&str] = &[
+	GITHUB_DOMAIN,
+	GITLAB_DOMAIN,
+	SPT_DOMAIN,
+	RAW_GITHUB_DOMAIN,
+	GIST_DOMAIN,
+	RAW_GIST_DOMAIN,
+];
+
+/// Words that mark a version the `versions` crate can't split into parts as a pre-release.
+const PRERELEASE_MARKERS: &[&str] = &["alpha", "beta", "rc", "pre", "preview", "dev"];
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum ModKind {
 	GitHub(GitHubLink),
 	SpTarkov(SptLink),
+	RawFile(RawFileLink),
+	GitLab(GitLabLink),
+	Direct(DirectLink),
 }
 
 impl ModKind {
@@ -33,6 +75,10 @@ impl ModKind {
 			return Ok(Self::SpTarkov(SptLink::parse(url)?));
 		}
 
+		if RawFileLink::starts_with_host(&url) {
+			return Ok(Self::RawFile(RawFileLink::parse(url)?));
+		}
+
 		if GitHubLink::starts_with_host(&url) {
 			let Some(pattern) = gh_pattern else {
 				return Err(anyhow!("No asset pattern was provided for Github"));
@@ -40,9 +86,75 @@ impl ModKind {
 
 			return Ok(Self::GitHub(GitHubLink::parse(url, pattern, gh_filter)?));
 		}
-		Err(anyhow!("Unsupported mod host: {}", url.as_ref()))
+
+		if GitLabLink::starts_with_host(&url) {
+			let Some(pattern) = gh_pattern else {
+				return Err(anyhow!("No asset pattern was provided for GitLab"));
+			};
+
+			return Ok(Self::GitLab(GitLabLink::parse(url, pattern, gh_filter)?));
+		}
+
+		// Any other host works when the url points straight at an archive
+		Ok(Self::Direct(DirectLink::parse(url)?))
 	}
 	
+	pub fn from_configuration(mod_cfg: &ModVersionConfiguration) -> Result<Self> {
+		let mod_kind = Self::parse(
+			&mod_cfg.url,
+			mod_cfg.github_pattern.clone(),
+			mod_cfg.github_filter.clone(),
+		)?;
+		Ok(match mod_kind {
+			ModKind::GitHub(link) => ModKind::GitHub(
+				link.with_split_parts(mod_cfg.github_split_parts),
+			),
+			ModKind::RawFile(_) if mod_cfg.install_path.is_none() => {
+				return Err(anyhow!("Raw file mods need an install_path: {}", mod_cfg.url));
+			}
+			ModKind::RawFile(_) if !has_exact_version(mod_cfg) => {
+				return Err(anyhow!("Raw file mods need an exact version: {}", mod_cfg.url));
+			}
+			ModKind::Direct(_) if !has_exact_version(mod_cfg) => {
+				return Err(anyhow!("Direct download mods need an exact version: {}", mod_cfg.url));
+			}
+			mod_kind => mod_kind,
+		})
+	}
+
+	/// The page users visit to read about the mod, the releases page for GitHub mods.
+	pub fn get_source_page(&self) -> String {
+		match self {
+			ModKind::GitHub(link) => link.get_releases_page(),
+			ModKind::SpTarkov(link) => link.get_page().to_string(),
+			ModKind::RawFile(link) => link.get_url().to_string(),
+			ModKind::GitLab(link) => link.get_releases_page(),
+			ModKind::Direct(link) => link.get_url().to_string(),
+		}
+	}
+
+	/// A short name derived from the link, the repository for GitHub and the file slug for the hub.
+	pub fn get_short_name(&self) -> &str {
+		match self {
+			ModKind::GitHub(link) => link.get_repo(),
+			ModKind::SpTarkov(link) => link.get_slug(),
+			ModKind::RawFile(link) => link.get_file_name(),
+			ModKind::GitLab(link) => link.get_repo(),
+			ModKind::Direct(link) => link.get_name(),
+		}
+	}
+
+	/// The kind of host the mod is downloaded from, for showing where a mod came from.
+	pub fn get_source_name(&self) -> &'static str {
+		match self {
+			ModKind::GitHub(_) => "GitHub",
+			ModKind::SpTarkov(_) => "SPT hub",
+			ModKind::RawFile(_) => "raw file",
+			ModKind::GitLab(_) => "GitLab",
+			ModKind::Direct(_) => "direct download",
+		}
+	}
+
 	pub fn get_supported_domains() -> &'static [&'static str]{
 		SUPPORTED_DOMAINS
 	}
@@ -53,6 +165,10 @@ pub struct ModDownloadVersion {
 	pub title: String,
 	pub file_name: String,
 	pub download_url: Url,
+	/// The remaining parts of an archive split across several downloads, in order.
+	pub additional_parts: Vec<Url>,
+	/// Sent with every part, such as the token private GitHub assets are downloaded with.
+	pub download_headers: HeaderMap,
 	pub uploaded_at: DateTime<Utc>,
 	pub version: Versioning,
 }
@@ -76,70 +192,341 @@ impl ModVersion for ModDownloadVersion {
 	}
 }
 
+/// What updating a mod would download, found without downloading it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedDownload {
+	pub title: String,
+	pub version: Versioning,
+	pub is_cached: bool,
+	/// `None` when the host doesn't report the size.
+	pub download_size: Option<u64>,
+}
+
+/// The total size of the downloads that aren't cached yet, and how many of them have an unknown size.
+pub fn total_download_size(plans: &[PlannedDownload]) -> (u64, usize) {
+	plans
+		.iter()
+		.filter(|plan| !plan.is_cached)
+		.fold((0, 0), |(total, unknown), plan| match plan.download_size {
+			Some(size) => (total + size, unknown),
+			None => (total, unknown + 1),
+		})
+}
+
+/// Whether the version names a pre-release, such as `1.3.0-rc1`, `2.0-beta` or `1.0.0-alpha.2`.
+pub(crate) fn is_prerelease(version: &Versioning) -> bool {
+	match version {
+		Versioning::Ideal(semver) => semver.pre_rel.is_some(),
+		Versioning::General(general) => general.release.is_some(),
+		Versioning::Complex(_) => {
+			// Only whole segments count, so `predator` isn't read as `pre`, but `rc2` is `rc`
+			let version = version.to_string().to_lowercase();
+			version.split(['-', '.', '+']).any(|segment| {
+				let segment = segment.trim_end_matches(|c: char| c.is_ascii_digit());
+				PRERELEASE_MARKERS.contains(&segment)
+			})
+		}
+	}
+}
+
+/// Leaves out pre-releases, by their version or the host marking them as one, unless allowed.
+pub(crate) fn is_selectable(
+	version: &Versioning,
+	marked_prerelease: bool,
+	allow_prerelease: bool,
+) -> bool {
+	allow_prerelease || (!marked_prerelease && !is_prerelease(version))
+}
+
+/// Raw files and direct downloads list no versions to pick from, so they can't use a range.
+fn has_exact_version(mod_cfg: &ModVersionConfiguration) -> bool {
+	mod_cfg.version.as_ref().and_then(VersionConstraint::as_exact).is_some()
+}
+
+fn exact_version<'a>(version: &'a VersionConstraint, url: &Url) -> Result<&'a Versioning> {
+	version
+		.as_exact()
+		.ok_or_else(|| anyhow!("Mods without a list of versions need an exact version: {url}"))
+}
+
+fn raw_file_without_version(link: &RawFileLink) -> anyhow::Error {
+	anyhow!("Raw file mods need a version in the configuration: {}", link.get_url())
+}
+
+fn direct_without_version(link: &DirectLink) -> anyhow::Error {
+	anyhow!("Direct download mods need a version in the configuration: {}", link.get_url())
+}
+
 pub struct RemoteModAccess {
 	spt_client: SptModRepository,
 	reqwest: Client,
 	github: GithubModRepository,
+	gitlab: GitLabModRepository,
+	direct: DirectRepository,
 	cache_mod_access: CacheModAccess,
+	max_bandwidth: Option<u64>,
+	download_timeout: Option<Duration>,
+	retry_policy: RetryPolicy,
+	/// Lowercase sha256 hashes of archives that are refused and removed from the cache.
+	blocked_hashes: Vec<String>,
+	version_scheme: VersionScheme,
+	allow_prerelease: bool,
+	min_release_date: Option<NaiveDate>,
+	download_progress: Option<DownloadProgress>,
 }
 
 impl RemoteModAccess {
 	pub async fn init(project: &PathAccess) -> Result<Self> {
+		Self::init_with_retry_policy(project, RetryPolicy::default()).await
+	}
+
+	/// [`RemoteModAccess::init`] retrying downloads and page fetches as the policy says, such as
+	/// not at all in tests.
+	pub async fn init_with_retry_policy(
+		project: &PathAccess,
+		retry_policy: RetryPolicy,
+	) -> Result<Self> {
 		let client = ClientBuilder::new()
 			.user_agent("spt_mod_manager_rs")
 			.build()
 			.unwrap();
-		Ok(Self {
+		let mut remote_access = Self {
 			reqwest: client.clone(),
-			spt_client: SptModRepository::new(client),
+			spt_client: SptModRepository::new(client.clone())
+				.with_validators_file(project.cache_root().join(HUB_VALIDATORS_FILE_NAME)),
 			github: GithubModRepository::new(),
+			gitlab: GitLabModRepository::new(client.clone()),
+			direct: DirectRepository::new(client.clone()),
 			cache_mod_access: CacheModAccess::init(project).await?,
-		})
+			max_bandwidth: None,
+			download_timeout: None,
+			retry_policy,
+			blocked_hashes: Vec::new(),
+			version_scheme: VersionScheme::default(),
+			allow_prerelease: false,
+			min_release_date: None,
+			download_progress: None,
+		};
+		remote_access.set_retry_policy(retry_policy);
+		Ok(remote_access)
+	}
+
+	/// Caps the speed of every download in bytes per second, `None` downloads at full speed.
+	pub fn with_max_bandwidth(mut self, max_bandwidth: Option<u64>) -> Self {
+		self.max_bandwidth = max_bandwidth;
+		self
+	}
+
+	/// Limits how long the following downloads may take until changed again, such as to a mod's own
+	/// `timeout_secs`. `None` lets downloads take as long as they need.
+	pub fn set_download_timeout(&mut self, download_timeout: Option<Duration>) {
+		self.download_timeout = download_timeout;
+	}
+
+	/// Orders the versions of the following mods by the scheme when picking the newest until
+	/// changed again, such as to a mod's own `version_scheme`.
+	pub fn set_version_scheme(&mut self, version_scheme: VersionScheme) {
+		self.version_scheme = version_scheme;
+	}
+
+	/// Lets the newest version of the following mods be a pre-release until changed again, such as
+	/// to a mod's own `allow_prerelease`.
+	pub fn set_allow_prerelease(&mut self, allow_prerelease: bool) {
+		self.allow_prerelease = allow_prerelease;
+	}
+
+	/// Leaves out GitHub releases published before the date when picking the newest version of the
+	/// following mods until changed again, such as to a mod's own `github_min_release_date`.
+	pub fn set_min_release_date(&mut self, min_release_date: Option<NaiveDate>) {
+		self.min_release_date = min_release_date;
+	}
+
+	/// Retries the following downloads and page fetches that fail on a dropped connection, a timeout
+	/// or a 5xx as the policy says, such as one resolved from the configuration and the command
+	/// line.
+	pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+		self.retry_policy = retry_policy;
+		self.spt_client.set_retry_policy(retry_policy);
+		self.github.set_retry_policy(retry_policy);
+	}
+
+	/// Tells `download_progress` how far the following downloads got until changed again, such as
+	/// to drive a progress bar per mod.
+	pub fn set_download_progress(&mut self, download_progress: Option<DownloadProgress>) {
+		self.download_progress = download_progress;
+	}
+
+	/// Waits for GitHub's rate limit to reset when it does so within `max_rate_limit_wait`, instead
+	/// of failing with [`RemoteError::RateLimited`].
+	pub fn set_max_rate_limit_wait(&mut self, max_rate_limit_wait: Duration) {
+		self.github.set_max_rate_limit_wait(max_rate_limit_wait);
+	}
+
+	/// Fetches hub versions again in full once they're older than `hub_cache_ttl`, even when the
+	/// hub answers that they're unchanged.
+	pub fn set_hub_cache_ttl(&mut self, hub_cache_ttl: Duration) {
+		self.spt_client.set_cache_ttl(hub_cache_ttl);
+	}
+
+	/// Refuses the archives with these sha256 hashes, such as builds a modpack knows to be broken.
+	pub fn set_blocked_hashes(&mut self, blocked_hashes: &[String]) {
+		self.blocked_hashes =
+			blocked_hashes.iter().map(|hash| hash.trim().to_lowercase()).collect();
+	}
+
+	/// Removes a cached version whose archive is blocked and fails with why.
+	async fn refuse_blocked(&mut self, cached_mod: CachedModVersion) -> Result<CachedModVersion> {
+		if self.blocked_hashes.is_empty() {
+			return Ok(cached_mod);
+		}
+		let hash = match cached_mod.manifest.get_archive_sha256() {
+			Some(hash) => hash.to_string(),
+			None => sha256::try_digest(&cached_mod.path)?,
+		};
+		if !self.blocked_hashes.contains(&hash) {
+			return Ok(cached_mod);
+		}
+		self.cache_mod_access.remove_cached_version(&cached_mod).await?;
+		Err(anyhow!(
+			"{} {} is blocked by policy, its archive's sha256 {hash} is in blocked_hashes",
+			cached_mod.get_name(),
+			cached_mod.get_version()
+		))
+	}
+
+	fn downloader(&self, mod_version: ModDownloadVersion) -> ModVersionDownloader {
+		ModVersionDownloader::new(mod_version, &self.reqwest)
+			.with_max_bandwidth(self.max_bandwidth)
+			.with_timeout(self.download_timeout)
+			.with_retry_policy(self.retry_policy)
 	}
 
 	pub async fn get_newest_release(&mut self, mod_entry: ModKind) -> Result<CachedModVersion> {
-		// TODO: Handle rate limits
+		let cached_mod = self.find_newest_release(mod_entry).await?;
+		self.refuse_blocked(cached_mod).await
+	}
+
+	async fn find_newest_release(&mut self, mod_entry: ModKind) -> Result<CachedModVersion> {
+		let (version_scheme, allow_prerelease) = (&self.version_scheme, self.allow_prerelease);
+		let spt_link = match &mod_entry {
+			ModKind::SpTarkov(link) => Some(link.clone()),
+			_ => None,
+		};
+		if let Some(link) = &spt_link {
+			let client = &mut self.spt_client;
+			let unchanged = client.get_unchanged_newest(link, version_scheme, allow_prerelease).await;
+			if let Some(version) = unchanged {
+				let cached = &self.cache_mod_access;
+				if let Some(cached_mod) = cached.get_cached_mod_from_kind(&mod_entry, &version) {
+					if cached_mod.verify_integrity()? != ArchiveIntegrity::Mismatch {
+						return Ok(cached_mod.clone());
+					}
+				}
+			}
+		}
+
 		let mod_version = match mod_entry.clone() {
-			ModKind::GitHub(gh_mod) => self.github.get_latest_version(gh_mod).await?,
-			ModKind::SpTarkov(link) => self.spt_client.get_latest_version(link).await?,
+			ModKind::GitHub(gh_mod) => {
+				let min_release_date = self.min_release_date;
+				self.github.get_latest_version(gh_mod, allow_prerelease, min_release_date).await?
+			}
+			ModKind::SpTarkov(link) => {
+				self.spt_client.get_latest_version(link, version_scheme, allow_prerelease).await?
+			}
+			ModKind::RawFile(link) => return Err(raw_file_without_version(&link)),
+			ModKind::GitLab(gl_mod) => self.gitlab.get_latest_version(gl_mod).await?,
+			ModKind::Direct(link) => return Err(direct_without_version(&link)),
 		};
 
-		let cached_mod = match self.cache_mod_access.get_status(&mod_version) {
-			ModCacheStatus::SameVersion | ModCacheStatus::NewerVersion => self
-				.cache_mod_access
-				.get_cached_mod(&mod_version)
-				.context("Failed to find cached version")?,
+		let status = self.cache_mod_access.get_status(&mod_version, &self.version_scheme);
+		let cached_mod = match status {
+			ModCacheStatus::SameVersion | ModCacheStatus::NewerVersion => {
+				let cached_mod = self
+					.cache_mod_access
+					.get_cached_mod(&mod_version)
+					.context("Failed to find cached version")?
+					.clone();
+				if cached_mod.verify_integrity()? != ArchiveIntegrity::Mismatch {
+					cached_mod
+				} else {
+					// A download cut short is downloaded again rather than installed
+					self.cache_mod_access.remove_cached_version(&cached_mod).await?;
+					let downloader = self.downloader(mod_version);
+					let progress = self.download_progress.as_ref();
+					self.cache_mod_access.cache_mod(downloader, mod_entry, progress).await?.clone()
+				}
+			}
 			ModCacheStatus::NotCached | ModCacheStatus::OlderVersion => {
-				self.cache_mod_access
-					.cache_mod(ModVersionDownloader::new(mod_version, &self.reqwest), mod_entry)
-					.await?
+				let downloader = self.downloader(mod_version);
+				let progress = self.download_progress.as_ref();
+				self.cache_mod_access.cache_mod(downloader, mod_entry, progress).await?.clone()
 			}
 		};
 
-		Ok(cached_mod.clone())
+		if let Some(link) = &spt_link {
+			self.spt_client.confirm_cached(link)?;
+		}
+		Ok(cached_mod)
+	}
+
+	/// The newest version of the mod already in the cache, without looking online.
+	pub fn get_newest_cached(&self, mod_kind: &ModKind) -> Option<CachedModVersion> {
+		self.cache_mod_access.get_newest_cached_from_kind(mod_kind).cloned()
+	}
+
+	pub fn get_cached_mods(&self) -> &[CachedMod] {
+		self.cache_mod_access.get_cached_mods()
 	}
 
+	/// The highest version the constraint allows, which an exact version only looks up online when
+	/// it isn't cached.
 	pub async fn get_specific_version(
 		&mut self,
 		mod_kind: ModKind,
-		version: &Versioning,
+		version: &VersionConstraint,
+		version_filter: Option<&str>,
+	) -> Result<Option<CachedModVersion>> {
+		match self.find_specific_version(mod_kind, version, version_filter).await? {
+			Some(cached_mod) => Ok(Some(self.refuse_blocked(cached_mod).await?)),
+			None => Ok(None),
+		}
+	}
+
+	async fn find_specific_version(
+		&mut self,
+		mod_kind: ModKind,
+		version: &VersionConstraint,
 		version_filter: Option<&str>,
 	) -> Result<Option<CachedModVersion>> {
-		// TODO: Handle rate limits
-		if let Some(cached_mod) = self.cache_mod_access.get_cached_mod_from_kind(&mod_kind, version) {
+		// A range may have gained a higher version since, so only an exact version is taken from
+		// the cache without looking online
+		let cached_mod = version
+			.as_exact()
+			.and_then(|version| self.cache_mod_access.get_cached_mod_from_kind(&mod_kind, version));
+		if let Some(cached_mod) = cached_mod {
 			return Ok(Some(cached_mod.clone()))
 		};
 		
 		let mod_version = match mod_kind.clone() {
 			ModKind::GitHub(gh_mod) => self.github.get_version(gh_mod, version, version_filter).await?,
 			ModKind::SpTarkov(spt_mod) => self.spt_client.get_version(spt_mod, version).await?,
+			ModKind::RawFile(link) => {
+				let version = exact_version(version, link.get_url())?;
+				Some(link.to_download_version(version))
+			}
+			ModKind::GitLab(gl_mod) => self.gitlab.get_version(gl_mod, version, version_filter).await?,
+			ModKind::Direct(link) => {
+				let version = exact_version(version, link.get_url())?;
+				Some(self.direct.get_version(link, version).await?)
+			}
 		};
 
 		let Some(mod_version) = mod_version else {
 			return Ok(None);
 		};
 
-		let cached_mod = match self.cache_mod_access.get_status(&mod_version) {
+		let status = self.cache_mod_access.get_status(&mod_version, &self.version_scheme);
+		let cached_mod = match status {
 			ModCacheStatus::SameVersion => self
 				.cache_mod_access
 				.get_cached_mod(&mod_version)
@@ -147,16 +534,309 @@ impl RemoteModAccess {
 			ModCacheStatus::NewerVersion
 			| ModCacheStatus::NotCached
 			| ModCacheStatus::OlderVersion => {
-				self.cache_mod_access
-					.cache_mod(ModVersionDownloader::new(mod_version, &self.reqwest), mod_kind)
-					.await?
+				let downloader = self.downloader(mod_version);
+				let progress = self.download_progress.as_ref();
+				self.cache_mod_access.cache_mod(downloader, mod_kind, progress).await?
 			}
 		};
 
 		Ok(Some(cached_mod.clone()))
 	}
 
+	/// Resolves the version an update would install and its download size, without downloading it.
+	pub async fn plan_release(
+		&mut self,
+		mod_kind: ModKind,
+		version: Option<&VersionConstraint>,
+		version_filter: Option<&str>,
+	) -> Result<Option<PlannedDownload>> {
+		let Some(mod_version) = self.resolve_release(mod_kind, version, version_filter).await? else {
+			return Ok(None);
+		};
+
+		let is_cached = matches!(
+			self.cache_mod_access.get_status(&mod_version, &self.version_scheme),
+			ModCacheStatus::SameVersion | ModCacheStatus::NewerVersion
+		);
+		let title = mod_version.title.clone();
+		let version = mod_version.version.clone();
+		let download_size = if is_cached {
+			Some(0)
+		} else {
+			self.downloader(mod_version).get_download_size().await?
+		};
+		Ok(Some(PlannedDownload {
+			title,
+			version,
+			is_cached,
+			download_size,
+		}))
+	}
+
+	/// Finds the version an update would install without downloading it, `None` when no version
+	/// matches the configured one.
+	pub async fn resolve_release(
+		&mut self,
+		mod_kind: ModKind,
+		version: Option<&VersionConstraint>,
+		version_filter: Option<&str>,
+	) -> Result<Option<ModDownloadVersion>> {
+		let (version_scheme, allow_prerelease) = (&self.version_scheme, self.allow_prerelease);
+		let mod_version = match (mod_kind, version) {
+			(ModKind::GitHub(gh_mod), None) => {
+				let min_release_date = self.min_release_date;
+				let github = &mut self.github;
+				Some(github.get_latest_version(gh_mod, allow_prerelease, min_release_date).await?)
+			}
+			(ModKind::SpTarkov(link), None) => {
+				Some(self.spt_client.get_latest_version(link, version_scheme, allow_prerelease).await?)
+			}
+			(ModKind::GitHub(gh_mod), Some(version)) => {
+				self.github.get_version(gh_mod, version, version_filter).await?
+			}
+			(ModKind::SpTarkov(link), Some(version)) => {
+				self.spt_client.get_version(link, version).await?
+			}
+			(ModKind::RawFile(link), None) => return Err(raw_file_without_version(&link)),
+			(ModKind::RawFile(link), Some(version)) => {
+				let version = exact_version(version, link.get_url())?;
+				Some(link.to_download_version(version))
+			}
+			(ModKind::GitLab(gl_mod), None) => Some(self.gitlab.get_latest_version(gl_mod).await?),
+			(ModKind::GitLab(gl_mod), Some(version)) => {
+				self.gitlab.get_version(gl_mod, version, version_filter).await?
+			}
+			(ModKind::Direct(link), None) => return Err(direct_without_version(&link)),
+			(ModKind::Direct(link), Some(version)) => {
+				let version = exact_version(version, link.get_url())?;
+				Some(self.direct.get_version(link, version).await?)
+			}
+		};
+		Ok(mod_version)
+	}
+
 	pub async fn clear_cache(&mut self) -> Result<()> {
 		self.cache_mod_access.remove_cache().await
 	}
+
+	pub async fn check_cache(&self) -> Result<CachePairingReport> {
+		self.cache_mod_access.check_pairing().await
+	}
+
+	pub async fn clean_cache(&mut self) -> Result<CachePairingReport> {
+		self.cache_mod_access.clean_unpaired().await
+	}
+
+	pub async fn collect_cache_garbage(&mut self, references: &[CacheReference]) -> Result<Vec<PathBuf>> {
+		self.cache_mod_access.collect_garbage(references).await
+	}
+
+	pub async fn cache_size(&self) -> Result<u64> {
+		self.cache_mod_access.cache_size().await
+	}
+
+	pub async fn prune_cache_to_size(&mut self, max_bytes: u64) -> Result<Vec<PathBuf>> {
+		self.cache_mod_access.prune_to_size(max_bytes).await
+	}
+
+	pub async fn prune_cache_versions(&mut self, keep: usize) -> Result<Vec<PathBuf>> {
+		self.cache_mod_access.prune_versions(keep).await
+	}
+
+	pub async fn export_cache(&self, bundle_path: &Path) -> Result<usize> {
+		self.cache_mod_access.export_bundle(bundle_path).await
+	}
+
+	pub async fn import_cache(&mut self, bundle_path: &Path) -> Result<usize> {
+		self.cache_mod_access.import_bundle(bundle_path).await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_server::{TestResponse, TestServer};
+
+	fn planned(download_size: Option<u64>, is_cached: bool) -> PlannedDownload {
+		PlannedDownload {
+			title: "Mod".to_string(),
+			version: Versioning::new("1.0.0").unwrap(),
+			is_cached,
+			download_size,
+		}
+	}
+
+	#[test]
+	fn total_download_size_skips_cached_and_counts_unknown() {
+		let plans = [
+			planned(Some(1500), false),
+			planned(Some(700), false),
+			planned(None, false),
+			planned(Some(9000), true),
+		];
+
+		assert_eq!(total_download_size(&plans), (2200, 1));
+	}
+
+	#[test]
+	fn pre_releases_are_recognised_by_their_version() {
+		let is_prerelease = |version| is_prerelease(&Versioning::new(version).unwrap());
+
+		assert!(is_prerelease("1.3.0-rc1"));
+		assert!(is_prerelease("2.0-beta"));
+		assert!(!is_prerelease("1.3.0"));
+		// Versions that are neither semver nor general only match whole segments
+		assert!(is_prerelease("1.02.3-rc2"));
+		assert!(is_prerelease("1.02.3-beta.1"));
+		assert!(is_prerelease("1.02.3+dev"));
+		assert!(!is_prerelease("1.02.3-predator"));
+		assert!(!is_prerelease("1.02.3-developer"));
+	}
+
+	#[test]
+	fn github_source_page_is_releases_page() {
+		let mod_kind = ModKind::parse(
+			"https://github.com/maxloo2/betterkeys-updated",
+			Some("betterkeys".to_string()),
+			None,
+		)
+		.unwrap();
+
+		assert_eq!(
+			mod_kind.get_source_page(),
+			"https://github.com/maxloo2/betterkeys-updated/releases"
+		);
+		assert_eq!(mod_kind.get_short_name(), "betterkeys-updated");
+	}
+
+	#[test]
+	fn gitlab_source_page_is_releases_page() {
+		let mod_kind = ModKind::parse(
+			"https://gitlab.com/group/subgroup/some-mod/-/releases",
+			Some("some-mod".to_string()),
+			None,
+		)
+		.unwrap();
+
+		assert_eq!(
+			mod_kind.get_source_page(),
+			"https://gitlab.com/group/subgroup/some-mod/-/releases"
+		);
+		assert_eq!(mod_kind.get_short_name(), "some-mod");
+		assert!(ModKind::parse("https://gitlab.com/owner/some-mod", None, None).is_err());
+	}
+
+	#[test]
+	fn hub_source_page_is_file_page() {
+		let mod_kind =
+			ModKind::parse("https://hub.sp-tarkov.com/files/file/1963-better-keys-updated", None, None)
+				.unwrap();
+
+		assert_eq!(
+			mod_kind.get_source_page(),
+			"https://hub.sp-tarkov.com/files/file/1963-better-keys-updated/"
+		);
+		assert_eq!(mod_kind.get_short_name(), "1963-better-keys-updated");
+	}
+
+	#[test]
+	fn raw_and_gist_urls_parse_as_raw_files() {
+		for url in [
+			"https://raw.githubusercontent.com/owner/tweaks/main/sain.json",
+			"https://gist.github.com/owner/0123abcd",
+			"https://gist.githubusercontent.com/owner/0123abcd/raw/4567/sain.json",
+		] {
+			let mod_kind = ModKind::parse(url, None, None).unwrap();
+			assert!(matches!(mod_kind, ModKind::RawFile(_)), "{url}");
+		}
+	}
+
+	#[test]
+	fn raw_file_configuration_needs_install_path_and_version() {
+		let mut mod_cfg = ModVersionConfiguration {
+			url: "https://raw.githubusercontent.com/owner/tweaks/main/sain.json".to_string(),
+			version: Some(VersionConstraint::parse("1.0.0").unwrap()),
+			version_filter: None,
+			github_pattern: None,
+			install_path: None,
+			github_filter: None,
+			github_split_parts: false,
+			github_min_release_date: None,
+			install_target: None,
+			timeout_secs: None,
+			version_scheme: VersionScheme::default(),
+			allow_prerelease: false,
+			group: None,
+		};
+		assert!(ModKind::from_configuration(&mod_cfg).is_err());
+
+		mod_cfg.install_path = Some("BepInEx/config".to_string());
+		assert!(ModKind::from_configuration(&mod_cfg).is_ok());
+
+		mod_cfg.version = None;
+		assert!(ModKind::from_configuration(&mod_cfg).is_err());
+	}
+
+	#[test]
+	fn archive_urls_on_other_hosts_parse_as_direct_downloads() {
+		let mod_kind = ModKind::parse("https://example.com/files/some-mod.zip", None, None).unwrap();
+
+		assert!(matches!(mod_kind, ModKind::Direct(_)));
+		assert_eq!(mod_kind.get_short_name(), "some-mod");
+		assert!(ModKind::parse("https://example.com/files/some-mod", None, None).is_err());
+	}
+
+	#[tokio::test]
+	async fn blocked_archives_are_refused_and_removed_from_the_cache() {
+		let path = "./test_output/blocked_hash_test";
+		let archive = b"a known bad build".to_vec();
+		let server =
+			TestServer::start(vec![("/bad-mod.zip", TestResponse::ok(archive.clone()))]).await;
+		let project = PathAccess::from(path, path).unwrap();
+		let _discard = tokio::fs::remove_dir_all(project.cache_root()).await;
+		let no_retries = RetryPolicy {
+			retries: 0,
+			..RetryPolicy::default()
+		};
+		let mut remote_access =
+			RemoteModAccess::init_with_retry_policy(&project, no_retries).await.unwrap();
+		let blocked_hash = sha256::digest(archive.as_slice()).to_uppercase();
+		remote_access.set_blocked_hashes(&[blocked_hash]);
+		let mod_kind = ModKind::parse(server.url("/bad-mod.zip"), None, None).unwrap();
+
+		let error = remote_access
+			.get_specific_version(mod_kind.clone(), &VersionConstraint::parse("1.0.0").unwrap(), None)
+			.await
+			.unwrap_err();
+
+		assert!(error.to_string().contains("blocked by policy"));
+		assert!(remote_access.get_newest_cached(&mod_kind).is_none());
+		tokio::fs::remove_dir_all(project.cache_root()).await.unwrap()
+	}
+
+	#[test]
+	fn direct_download_configuration_needs_version() {
+		let mut mod_cfg = ModVersionConfiguration {
+			url: "https://example.com/files/some-mod.zip".to_string(),
+			version: Some(VersionConstraint::parse("1.0.0").unwrap()),
+			version_filter: None,
+			github_pattern: None,
+			install_path: None,
+			github_filter: None,
+			github_split_parts: false,
+			github_min_release_date: None,
+			install_target: None,
+			timeout_secs: None,
+			version_scheme: VersionScheme::default(),
+			allow_prerelease: false,
+			group: None,
+		};
+		assert!(ModKind::from_configuration(&mod_cfg).is_ok());
+
+		mod_cfg.version = Some(VersionConstraint::parse("^1.0").unwrap());
+		assert!(ModKind::from_configuration(&mod_cfg).is_err());
+
+		mod_cfg.version = None;
+		assert!(ModKind::from_configuration(&mod_cfg).is_err());
+	}
 }
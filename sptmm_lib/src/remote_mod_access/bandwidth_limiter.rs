@@ -0,0 +1,28 @@
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// Paces downloads so the average speed since the first read stays at or below the limit.
+#[derive(Debug)]
+pub(super) struct BandwidthLimiter {
+	bytes_per_second: u64,
+	started: Instant,
+	consumed: u64,
+}
+
+impl BandwidthLimiter {
+	pub(super) fn new(bytes_per_second: u64) -> Self {
+		Self {
+			bytes_per_second: bytes_per_second.max(1),
+			started: Instant::now(),
+			consumed: 0,
+		}
+	}
+
+	/// Waits until reading `bytes` more keeps the download within the limit.
+	pub(super) async fn consume(&mut self, bytes: usize) {
+		self.consumed += bytes as u64;
+		let allowed_after = Duration::from_secs_f64(self.consumed as f64 / self.bytes_per_second as f64);
+		tokio::time::sleep_until(self.started + allowed_after).await;
+	}
+}
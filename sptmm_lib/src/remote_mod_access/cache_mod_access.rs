@@ -1,4 +1,5 @@
 use std::cmp::Ordering;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Context, Result};
@@ -11,13 +12,19 @@ use winnow::prelude::*;
 use winnow::token::take_until;
 use winnow::PResult;
 
+use crate::configuration_access::ModVersionConfiguration;
 use crate::path_access::PathAccess;
-use crate::remote_mod_access::cache_mod_access::cached_mod::CachedMod;
-pub use crate::remote_mod_access::cache_mod_access::cached_mod_version::CachedModVersion;
+pub use crate::remote_mod_access::cache_mod_access::cached_mod::CachedMod;
+pub use crate::remote_mod_access::cache_mod_access::cached_mod_version::{
+	ArchiveIntegrity, CachedModVersion,
+};
 use crate::remote_mod_access::cache_mod_access::mod_manifest::ModManifest;
-use crate::remote_mod_access::ModKind;
-use crate::shared_traits::{ModName, ModVersion, ModVersionDownload};
+use crate::remote_mod_access::{DownloadProgress, ModKind};
+use crate::shared_traits::{DownloadStream, ModName, ModVersion, ModVersionDownload};
+use crate::version_constraint::VersionConstraint;
+use crate::version_scheme::VersionScheme;
 
+mod cache_bundle;
 mod cached_mod;
 mod cached_mod_version;
 mod mod_manifest;
@@ -37,12 +44,60 @@ impl CacheFile {
 	fn is_manifest(&self) -> bool {
 		self.file_ext.as_ref().is_some_and(|s| s == ".manifest")
 	}
-	async fn delete(self) -> Result<()> {
-		fs::remove_file(self.path).await?;
-		Ok(())
+	fn is_paired_with(&self, other: &CacheFile) -> bool {
+		self.file_name == other.file_name && self.is_manifest() != other.is_manifest()
+	}
+}
+
+/// Cached files that are missing their manifest or archive counterpart.
+#[derive(Debug, Default, PartialEq)]
+pub struct CachePairingReport {
+	pub unpaired_manifests: Vec<PathBuf>,
+	pub unpaired_archives: Vec<PathBuf>,
+}
+
+impl CachePairingReport {
+	pub fn is_healthy(&self) -> bool {
+		self.unpaired_manifests.is_empty() && self.unpaired_archives.is_empty()
+	}
+}
+
+/// A cached version that a configuration uses, kept when collecting garbage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheReference {
+	pub mod_kind: ModKind,
+	/// `None` references the newest cached version, a range the newest cached version in it.
+	pub version: Option<VersionConstraint>,
+}
+
+impl CacheReference {
+	pub fn from_configuration(mod_cfg: &ModVersionConfiguration) -> Result<Self> {
+		Ok(Self {
+			mod_kind: ModKind::from_configuration(mod_cfg)?,
+			version: mod_cfg.version.clone(),
+		})
+	}
+
+	fn references(&self, cached_mod: &CachedMod, version: &CachedModVersion) -> bool {
+		if &self.mod_kind != cached_mod.get_mod_kind() {
+			return false;
+		}
+		match &self.version {
+			Some(referenced) => {
+				let versions = cached_mod.get_versions().iter();
+				let candidates = versions.map(|cached| (cached, cached.get_version().clone()));
+				referenced
+					.select_highest(candidates)
+					.is_some_and(|(highest, _)| highest == version)
+			}
+			None => cached_mod.get_newest() == Some(version),
+		}
 	}
 }
 
+/// How a version compares to the one it's checked against, such as the newest cached version or
+/// the installed one. `NewerVersion` means the version checked against is the newer one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ModCacheStatus {
 	NotCached,
 	NewerVersion,
@@ -50,8 +105,27 @@ pub enum ModCacheStatus {
 	OlderVersion,
 }
 
+impl ModCacheStatus {
+	/// Compares the version to a known one ordered by the scheme, `NotCached` without one.
+	pub fn compare(
+		version: &Versioning,
+		known_version: Option<&Versioning>,
+		version_scheme: &VersionScheme,
+	) -> Self {
+		let Some(known_version) = known_version else {
+			return ModCacheStatus::NotCached;
+		};
+		match version_scheme.compare(version, known_version) {
+			Ordering::Less => ModCacheStatus::NewerVersion,
+			Ordering::Equal => ModCacheStatus::SameVersion,
+			Ordering::Greater => ModCacheStatus::OlderVersion,
+		}
+	}
+}
+
 impl CacheModAccess {
 	pub async fn init(project: &PathAccess) -> Result<Self> {
+		project.ensure_separate_roots()?;
 		let cache_dir = project.cache_root().join("remote");
 		fs::create_dir_all(&cache_dir).await?;
 		let cached_mods = calculate_cache(&cache_dir).await?;
@@ -61,7 +135,12 @@ impl CacheModAccess {
 		})
 	}
 
-	pub fn get_status<Version: ModVersion>(&self, mod_version: &Version) -> ModCacheStatus {
+	/// Compares the version to the newest cached one, both ordered by the scheme.
+	pub fn get_status<Version: ModVersion>(
+		&self,
+		mod_version: &Version,
+		version_scheme: &VersionScheme,
+	) -> ModCacheStatus {
 		let Some(cached_mod) = self
 			.cached_mods
 			.iter()
@@ -69,18 +148,10 @@ impl CacheModAccess {
 		else {
 			return ModCacheStatus::NotCached;
 		};
-		let Some(cached_mod_version) = cached_mod.get_newest() else {
-			return ModCacheStatus::NotCached;
-		};
-
-		return match mod_version
-			.get_version()
-			.cmp(cached_mod_version.get_version())
-		{
-			Ordering::Less => ModCacheStatus::NewerVersion,
-			Ordering::Equal => ModCacheStatus::SameVersion,
-			Ordering::Greater => ModCacheStatus::OlderVersion,
-		};
+		let versions = cached_mod.get_versions();
+		let newest = version_scheme.newest(versions, |version| version.get_version());
+		let cached_version = newest.map(|cached_mod_version| cached_mod_version.get_version());
+		ModCacheStatus::compare(mod_version.get_version(), cached_version, version_scheme)
 	}
 
 	pub fn get_cached_mod<Version: ModVersion>(
@@ -104,10 +175,24 @@ impl CacheModAccess {
 			.and_then(|m| m.get_version(version))
 	}
 
+	pub fn get_cached_mods(&self) -> &[CachedMod] {
+		&self.cached_mods
+	}
+
+	pub fn get_newest_cached_from_kind(&self, mod_kind: &ModKind) -> Option<&CachedModVersion> {
+		self.cached_mods
+			.iter()
+			.find(|x| x.get_mod_kind() == mod_kind)
+			.and_then(|m| m.get_newest())
+	}
+
+	/// Downloads the mod into the cache unless another mod already cached the same download,
+	/// writing it a chunk at a time and telling `progress` after each one.
 	pub async fn cache_mod<Download: ModVersionDownload>(
 		&mut self,
 		downloader: Download,
 		mod_kind: ModKind,
+		progress: Option<&DownloadProgress>,
 	) -> Result<&CachedModVersion> {
 		let mod_path = self.ensure_mod_folder(&downloader).await?;
 
@@ -115,17 +200,38 @@ impl CacheModAccess {
 		let mod_file_path = mod_path.join(Path::new(&mod_file_name));
 		let manifest_path = ModManifest::create_manifest_path(mod_path, &mod_file_name)?;
 
-		let mut archive_file = File::create(&mod_file_path).await?;
-		let stream = downloader.download().await?;
-		archive_file.write_all(stream.as_ref()).await?;
+		// A download another mod already cached is linked instead of downloaded again
+		let download_url = downloader.get_download_url();
+		match self.find_shared_download(download_url, downloader.get_version()) {
+			Some(shared_path) if shared_path == mod_file_path => {}
+			Some(shared_path) => {
+				if mod_file_path.is_file() {
+					fs::remove_file(&mod_file_path).await?;
+				}
+				if fs::hard_link(&shared_path, &mod_file_path).await.is_err() {
+					fs::copy(&shared_path, &mod_file_path).await?;
+				}
+			}
+			None => {
+				let written = write_download(&downloader, &mod_file_path, progress).await;
+				if written.is_err() {
+					// A partial archive would look like a cached version missing its manifest
+					let _discard = fs::remove_file(&mod_file_path).await;
+				}
+				written?;
+			}
+		}
 
+		let archive_sha256 = sha256::try_digest(&mod_file_path)?;
 		let mut manifest_file = File::create(manifest_path).await?;
 		let manifest = ModManifest::new(
 			downloader.get_upload_date(),
 			downloader.get_name().to_string(),
 			downloader.get_version().clone(),
 			mod_kind,
-		);
+		)
+		.with_download_url(downloader.get_download_url())
+		.with_archive_sha256(archive_sha256);
 		let buffer = serde_json::to_vec(&manifest)?;
 		manifest_file.write_all(&buffer).await?;
 
@@ -134,6 +240,7 @@ impl CacheModAccess {
 		let version = self
 			.cached_mods
 			.iter()
+			.filter(|x| x.is_same_name(&downloader))
 			.find_map(|x| x.get_version(downloader.get_version()))
 			.context("Failed to find cached version")?;
 
@@ -155,6 +262,180 @@ impl CacheModAccess {
 		Ok(())
 	}
 
+	/// Reports cached files that aren't paired, without deleting anything.
+	pub async fn check_pairing(&self) -> Result<CachePairingReport> {
+		let mut report = CachePairingReport::default();
+		let mut read = fs::read_dir(&self.cache_dir).await?;
+		while let Some(entry) = read.next_entry().await? {
+			let path = entry.path();
+			if !path.is_dir() {
+				continue;
+			}
+
+			let cached_files = get_all_files(&path).await?;
+			for cached_file in cached_files.iter() {
+				if cached_files.iter().any(|f| f.is_paired_with(cached_file)) {
+					continue;
+				}
+				if cached_file.is_manifest() {
+					report.unpaired_manifests.push(cached_file.path.clone());
+				} else {
+					report.unpaired_archives.push(cached_file.path.clone());
+				}
+			}
+		}
+		report.unpaired_manifests.sort();
+		report.unpaired_archives.sort();
+		Ok(report)
+	}
+
+	/// Deletes the cached files that aren't paired and returns what was deleted.
+	pub async fn clean_unpaired(&mut self) -> Result<CachePairingReport> {
+		let report = self.check_pairing().await?;
+		for path in report.unpaired_manifests.iter().chain(&report.unpaired_archives) {
+			fs::remove_file(path).await?;
+		}
+
+		self.cached_mods = calculate_cache(&self.cache_dir).await?;
+		Ok(report)
+	}
+
+	/// Deletes the cached versions none of the references use and returns their archive paths.
+	pub async fn collect_garbage(&mut self, references: &[CacheReference]) -> Result<Vec<PathBuf>> {
+		let mut removed = Vec::new();
+		for cached_mod in &self.cached_mods {
+			for version in cached_mod.get_versions() {
+				if references.iter().any(|r| r.references(cached_mod, version)) {
+					continue;
+				}
+				fs::remove_file(version.get_manifest_path()?).await?;
+				fs::remove_file(&version.path).await?;
+				removed.push(version.path.clone());
+			}
+		}
+
+		self.cached_mods = calculate_cache(&self.cache_dir).await?;
+		Ok(removed)
+	}
+
+	/// The bytes the cached archives and manifests take on disk. An archive shared between mods is
+	/// hard linked, so it's only counted once.
+	pub async fn cache_size(&self) -> Result<u64> {
+		let mut size = 0;
+		let mut counted = HashSet::new();
+		let mut read = fs::read_dir(&self.cache_dir).await?;
+		while let Some(entry) = read.next_entry().await? {
+			let path = entry.path();
+			if !path.is_dir() {
+				continue;
+			}
+			let mut mod_read = fs::read_dir(&path).await?;
+			while let Some(mod_entry) = mod_read.next_entry().await? {
+				let metadata = mod_entry.metadata().await?;
+				if metadata.is_file() && counted.insert(file_id(&mod_entry.path(), &metadata)?) {
+					size += metadata.len();
+				}
+			}
+		}
+		Ok(size)
+	}
+
+	/// Deletes the oldest cached versions by upload date until the cache takes at most `max_bytes`
+	/// and returns their archive paths. The newest version of every mod is kept, so the cache may
+	/// stay above the limit.
+	pub async fn prune_to_size(&mut self, max_bytes: u64) -> Result<Vec<PathBuf>> {
+		let mut size = self.cache_size().await?;
+		let mut candidates: Vec<_> = self
+			.cached_mods
+			.iter()
+			.flat_map(|cached_mod| {
+				let newest = cached_mod.get_newest();
+				cached_mod
+					.get_versions()
+					.iter()
+					.filter(move |version| Some(*version) != newest)
+			})
+			.collect();
+		candidates.sort_by_key(|version| version.manifest.get_uploaded_at());
+
+		let mut removed = Vec::new();
+		for version in candidates {
+			if size <= max_bytes {
+				break;
+			}
+			let manifest_path = version.get_manifest_path()?;
+			fs::remove_file(&manifest_path).await?;
+			fs::remove_file(&version.path).await?;
+			// Removing one link to a shared archive frees nothing, so the size is counted again
+			size = self.cache_size().await?;
+			removed.push(version.path.clone());
+		}
+
+		self.cached_mods = calculate_cache(&self.cache_dir).await?;
+		Ok(removed)
+	}
+
+	/// Deletes all but the newest `keep` versions of every mod and returns their archive paths.
+	pub async fn prune_versions(&mut self, keep: usize) -> Result<Vec<PathBuf>> {
+		let mut removed = Vec::new();
+		for cached_mod in &self.cached_mods {
+			let mut versions: Vec<_> = cached_mod.get_versions().iter().collect();
+			versions.sort_by(|a, b| b.cmp(a));
+			for version in versions.into_iter().skip(keep) {
+				fs::remove_file(version.get_manifest_path()?).await?;
+				fs::remove_file(&version.path).await?;
+				removed.push(version.path.clone());
+			}
+		}
+
+		self.cached_mods = calculate_cache(&self.cache_dir).await?;
+		Ok(removed)
+	}
+
+	/// Bundles every cached archive and manifest into one zip, for moving the cache to a machine
+	/// without internet with [`Self::import_bundle`]. Returns how many files were bundled.
+	pub async fn export_bundle(&self, bundle_path: &Path) -> Result<usize> {
+		let cache_dir = self.cache_dir.clone();
+		let bundle_path = bundle_path.to_path_buf();
+		tokio::task::spawn_blocking(move || cache_bundle::write_bundle(&cache_dir, &bundle_path))
+			.await?
+	}
+
+	/// Unpacks a bundle from [`Self::export_bundle`] into the cache and reads the cache again, so
+	/// the imported versions install without downloading. Returns how many files were added.
+	pub async fn import_bundle(&mut self, bundle_path: &Path) -> Result<usize> {
+		let cache_dir = self.cache_dir.clone();
+		let bundle_path = bundle_path.to_path_buf();
+		let added =
+			tokio::task::spawn_blocking(move || cache_bundle::read_bundle(&bundle_path, &cache_dir))
+				.await??;
+		self.cached_mods = calculate_cache(&self.cache_dir).await?;
+		Ok(added)
+	}
+
+	/// Deletes a cached version, such as one whose archive no longer matches its hash, so caching it
+	/// again downloads it instead of linking the same archive.
+	pub async fn remove_cached_version(&mut self, version: &CachedModVersion) -> Result<()> {
+		fs::remove_file(version.get_manifest_path()?).await?;
+		fs::remove_file(&version.path).await?;
+		self.cached_mods = calculate_cache(&self.cache_dir).await?;
+		Ok(())
+	}
+
+	/// The archive cached from the same url for the same version. Raw files and direct downloads
+	/// keep their url across versions, so the url alone could be an older version's archive.
+	fn find_shared_download(&self, download_url: &str, version: &Versioning) -> Option<PathBuf> {
+		self.cached_mods
+			.iter()
+			.flat_map(CachedMod::get_versions)
+			.find(|cached| {
+				cached.manifest.get_download_url() == Some(download_url)
+					&& cached.get_version() == version
+					&& cached.path.is_file()
+			})
+			.map(|cached| cached.path.clone())
+	}
+
 	async fn ensure_mod_folder<MN: ModName>(&self, mod_name: &MN) -> Result<PathBuf> {
 		let mod_folder_name = mod_name.to_file_name();
 		let mod_path = self.cache_dir.join(mod_folder_name);
@@ -165,6 +446,8 @@ impl CacheModAccess {
 	}
 }
 
+/// Reads every mod folder of the cache. A folder that can't be read is skipped with a warning, so
+/// the other cached mods stay usable.
 async fn calculate_cache<P: AsRef<Path>>(cache_path: P) -> Result<Vec<CachedMod>> {
 	let mut cached_mods = Vec::new();
 	let mut read = fs::read_dir(&cache_path).await?;
@@ -174,39 +457,45 @@ async fn calculate_cache<P: AsRef<Path>>(cache_path: P) -> Result<Vec<CachedMod>
 			continue;
 		}
 
-		let cached_files = get_all_files(&path).await?;
-		let versions = clean_unmanaged_files_and_build_cache(cached_files).await?;
-		if versions.is_empty() {
-			continue;
+		match read_cached_mod(&path).await {
+			Ok(Some(cached_mod)) => cached_mods.push(cached_mod),
+			Ok(None) => {}
+			Err(err) => {
+				eprintln!("Skipped unreadable cache folder {}: {err}", path.to_string_lossy())
+			}
 		}
-		let (name, mod_kind) = versions
-			.first()
-			.map(|cmv| {
-				(
-					cmv.manifest.get_name().to_string(),
-					cmv.manifest.get_mod_kind().clone(),
-				)
-			})
-			.context("Found no mod name")?;
-		cached_mods.push(CachedMod::new(name, versions, mod_kind));
 	}
 	Ok(cached_mods)
 }
 
-async fn clean_unmanaged_files_and_build_cache(
-	mut vec: Vec<CacheFile>,
-) -> Result<Vec<CachedModVersion>> {
-	let mut to_keep = Vec::new();
+async fn read_cached_mod(path: &Path) -> Result<Option<CachedMod>> {
+	let cached_files = get_all_files(path).await?;
+	let versions = build_cache(cached_files).await?;
+	if versions.is_empty() {
+		return Ok(None);
+	}
+	let (name, mod_kind) = versions
+		.first()
+		.map(|cmv| {
+			(
+				cmv.manifest.get_name().to_string(),
+				cmv.manifest.get_mod_kind().clone(),
+			)
+		})
+		.context("Found no mod name")?;
+	Ok(Some(CachedMod::new(name, versions, mod_kind)))
+}
+
+/// Builds the cached versions from the paired files. Unpaired files are left alone,
+/// see [`CacheModAccess::check_pairing`].
+async fn build_cache(vec: Vec<CacheFile>) -> Result<Vec<CachedModVersion>> {
 	let mut cached_mods = Vec::new();
 	for cached_file in vec.iter() {
 		if !cached_file.is_manifest() {
 			continue;
 		}
 
-		let Some(paired) = vec
-			.iter()
-			.find(|f| f.file_name == cached_file.file_name && f.file_ext != cached_file.file_ext)
-		else {
+		let Some(paired) = vec.iter().find(|f| f.is_paired_with(cached_file)) else {
 			continue;
 		};
 
@@ -222,19 +511,12 @@ async fn clean_unmanaged_files_and_build_cache(
 			manifest,
 			path: paired.path.clone(),
 		});
-
-		to_keep.push(cached_file.path.clone());
-		to_keep.push(paired.path.clone());
-	}
-	while let Some(remove_index) = vec.iter().position(|cf| !to_keep.contains(&cf.path)) {
-		let file = vec.swap_remove(remove_index);
-		file.delete().await?
 	}
 
 	Ok(cached_mods)
 }
 
-async fn get_all_files(folder_path: &PathBuf) -> Result<Vec<CacheFile>> {
+async fn get_all_files(folder_path: &Path) -> Result<Vec<CacheFile>> {
 	let mut vec = Vec::new();
 	let mut read = fs::read_dir(&folder_path).await?;
 	while let Some(entry) = read.next_entry().await? {
@@ -251,6 +533,26 @@ async fn get_all_files(folder_path: &PathBuf) -> Result<Vec<CacheFile>> {
 	Ok(vec)
 }
 
+async fn write_download<Download: ModVersionDownload>(
+	downloader: &Download,
+	path: &Path,
+	progress: Option<&DownloadProgress>,
+) -> Result<()> {
+	let mut stream = downloader.download().await?;
+	let total_size = stream.get_total_size();
+	let mut archive_file = File::create(path).await?;
+	let mut downloaded = 0;
+	while let Some(chunk) = stream.chunk().await? {
+		archive_file.write_all(&chunk).await?;
+		downloaded += chunk.len() as u64;
+		if let Some(progress) = progress {
+			progress(downloaded, total_size);
+		}
+	}
+	archive_file.flush().await?;
+	Ok(())
+}
+
 fn to_file_name<Download: ModVersionDownload>(mod_version: &Download) -> String {
 	format!(
 		"{}_{}",
@@ -269,10 +571,54 @@ fn separate_file_and_ext(file_name: &str) -> PResult<(String, Option<String>)> {
 	Ok((separate.join("."), Some(remainder.to_string())))
 }
 
+/// Identifies the file on its drive, which hard links to the same file share.
+#[cfg(unix)]
+fn file_id(_path: &Path, metadata: &std::fs::Metadata) -> Result<(u64, u64)> {
+	use std::os::unix::fs::MetadataExt;
+
+	Ok((metadata.dev(), metadata.ino()))
+}
+
+/// Identifies the file on its drive, which hard links to the same file share.
+#[cfg(windows)]
+fn file_id(path: &Path, _metadata: &std::fs::Metadata) -> Result<(u64, u64)> {
+	let file = std::fs::File::open(path)?;
+	let information = winapi_util::file::information(&file)?;
+	Ok((information.volume_serial_number(), information.file_index()))
+}
+
 #[cfg(test)]
 mod tests {
+	use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+	use std::sync::Arc;
+
+	use bytes::Bytes;
+	use chrono::{DateTime, Utc};
+
 	use super::*;
 
+	#[test]
+	fn status_compares_to_the_known_version() {
+		let version = |version: &str| Versioning::new(version).unwrap();
+		let semver = VersionScheme::Semver;
+		let newest = version("1.3.0");
+
+		let installed =
+			[None, Some(version("1.2.0")), Some(version("1.3.0")), Some(version("2.0.0"))];
+		let statuses = installed
+			.map(|installed| ModCacheStatus::compare(&newest, installed.as_ref(), &semver));
+
+		assert_eq!(
+			statuses,
+			[
+				ModCacheStatus::NotCached,
+				ModCacheStatus::OlderVersion,
+				ModCacheStatus::SameVersion,
+				ModCacheStatus::NewerVersion,
+			]
+		);
+	}
+
 	#[test]
 	fn test_file_parser_proper() {
 		let result = separate_file_and_ext("1.0.0_maxloo2-betterkeys-updated.zip").unwrap();
@@ -285,6 +631,450 @@ mod tests {
 		);
 	}
 
+	#[tokio::test]
+	async fn unpaired_archive_is_reported_and_kept_in_check_mode() {
+		let path = "./test_output/cache_pairing_test";
+		let project = PathAccess::from(path, path).unwrap();
+		let _discard = fs::remove_dir_all(project.cache_root()).await;
+		let mod_folder = project.cache_root().join("remote").join("manual-mod");
+		fs::create_dir_all(&mod_folder).await.unwrap();
+		let archive_path = mod_folder.join("1.0.0_manual-mod.zip");
+		fs::write(&archive_path, "").await.unwrap();
+
+		let mut cache = CacheModAccess::init(&project).await.unwrap();
+		let report = cache.check_pairing().await.unwrap();
+
+		assert_eq!(report.unpaired_archives, vec![archive_path.clone()]);
+		assert!(report.unpaired_manifests.is_empty());
+		assert!(archive_path.is_file());
+
+		cache.clean_unpaired().await.unwrap();
+		assert!(!archive_path.exists());
+		fs::remove_dir_all(project.cache_root()).await.unwrap()
+	}
+
+	async fn write_cached_version(mod_folder: &Path, mod_kind: &ModKind, version: &str) -> PathBuf {
+		let archive_path = mod_folder.join(format!("{version}_gc-mod.zip"));
+		fs::write(&archive_path, "").await.unwrap();
+		let manifest = ModManifest::new(
+			Default::default(),
+			"gc-mod".to_string(),
+			Versioning::new(version).unwrap(),
+			mod_kind.clone(),
+		);
+		let manifest_path = mod_folder.join(format!("{version}_gc-mod.manifest"));
+		fs::write(manifest_path, serde_json::to_vec(&manifest).unwrap()).await.unwrap();
+		archive_path
+	}
+
+	#[tokio::test]
+	async fn garbage_collection_removes_versions_no_profile_references() {
+		let path = "./test_output/cache_gc_test";
+		let project = PathAccess::from(path, path).unwrap();
+		let _discard = fs::remove_dir_all(project.cache_root()).await;
+		let mod_folder = project.cache_root().join("remote").join("gc-mod");
+		fs::create_dir_all(&mod_folder).await.unwrap();
+		let mod_kind =
+			ModKind::parse("https://github.com/test/gc-mod", Some("gc-mod".to_string()), None)
+				.unwrap();
+		let first = write_cached_version(&mod_folder, &mod_kind, "1.0.0").await;
+		let second = write_cached_version(&mod_folder, &mod_kind, "2.0.0").await;
+		let unreferenced = write_cached_version(&mod_folder, &mod_kind, "3.0.0").await;
+		let profile_references = ["1.0.0", "2.0.0"].map(|version| CacheReference {
+			mod_kind: mod_kind.clone(),
+			version: Versioning::new(version).map(VersionConstraint::from),
+		});
+
+		let mut cache = CacheModAccess::init(&project).await.unwrap();
+		let removed = cache.collect_garbage(&profile_references).await.unwrap();
+
+		assert_eq!(removed, vec![unreferenced.clone()]);
+		assert!(first.is_file());
+		assert!(second.is_file());
+		assert!(!unreferenced.exists());
+		assert!(!mod_folder.join("3.0.0_gc-mod.manifest").exists());
+		fs::remove_dir_all(project.cache_root()).await.unwrap()
+	}
+
+	struct CountingDownload {
+		name: String,
+		version: Versioning,
+		url: String,
+		downloads: Arc<AtomicUsize>,
+	}
+
+	impl ModName for CountingDownload {
+		fn get_name(&self) -> &str {
+			&self.name
+		}
+
+		fn is_same_name<Name: ModName>(&self, mod_name: &Name) -> bool {
+			self.name == mod_name.get_name()
+		}
+	}
+
+	impl ModVersion for CountingDownload {
+		fn get_version(&self) -> &Versioning {
+			&self.version
+		}
+
+		fn get_order<Version: ModVersion>(&self, mod_version: &Version) -> Ordering {
+			self.version.cmp(mod_version.get_version())
+		}
+	}
+
+	/// Hands out its chunks in order.
+	struct ChunkStream {
+		chunks: Vec<Bytes>,
+	}
+
+	impl DownloadStream for ChunkStream {
+		fn get_total_size(&self) -> Option<u64> {
+			Some(self.chunks.iter().map(|chunk| chunk.len() as u64).sum())
+		}
+
+		async fn chunk(&mut self) -> Result<Option<Bytes>> {
+			Ok((!self.chunks.is_empty()).then(|| self.chunks.remove(0)))
+		}
+	}
+
+	impl ModVersionDownload for CountingDownload {
+		type Stream<'a> = ChunkStream;
+
+		async fn download(&self) -> Result<ChunkStream> {
+			self.downloads.fetch_add(1, AtomicOrdering::Relaxed);
+			let chunks = vec![Bytes::from_static(b"shared "), Bytes::from_static(b"framework")];
+			Ok(ChunkStream { chunks })
+		}
+
+		fn get_file_name(&self) -> &str {
+			"framework.zip"
+		}
+
+		fn get_upload_date(&self) -> DateTime<Utc> {
+			DateTime::default()
+		}
+
+		fn get_download_url(&self) -> &str {
+			&self.url
+		}
+	}
+
+	#[tokio::test]
+	async fn identical_download_urls_are_downloaded_once() {
+		let path = "./test_output/cache_shared_download_test";
+		let project = PathAccess::from(path, path).unwrap();
+		let _discard = fs::remove_dir_all(project.cache_root()).await;
+		let mut cache = CacheModAccess::init(&project).await.unwrap();
+		let downloads = Arc::new(AtomicUsize::new(0));
+		let url = "https://example.com/framework-1.0.0.zip";
+		let mut cached = Vec::new();
+		for name in ["framework", "framework-alias"] {
+			let download = CountingDownload {
+				name: name.to_string(),
+				version: Versioning::new("1.0.0").unwrap(),
+				url: url.to_string(),
+				downloads: downloads.clone(),
+			};
+			let repository_url = format!("https://github.com/test/{name}");
+			let mod_kind = ModKind::parse(repository_url, Some(name.to_string()), None).unwrap();
+			cached.push(cache.cache_mod(download, mod_kind, None).await.unwrap().clone());
+		}
+
+		assert_eq!(downloads.load(AtomicOrdering::Relaxed), 1);
+		assert_eq!(cached[0].get_name(), "framework");
+		assert_eq!(cached[1].get_name(), "framework-alias");
+		assert_ne!(cached[0].path, cached[1].path);
+		for cached_version in &cached {
+			assert_eq!(fs::read(&cached_version.path).await.unwrap(), b"shared framework");
+		}
+		// The archive is linked to from both mods, so it only takes its space once
+		let mut expected_size = b"shared framework".len() as u64;
+		for cached_version in &cached {
+			let manifest_path = cached_version.get_manifest_path().unwrap();
+			expected_size += fs::metadata(manifest_path).await.unwrap().len();
+		}
+		assert_eq!(cache.cache_size().await.unwrap(), expected_size);
+		fs::remove_dir_all(project.cache_root()).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn stable_download_url_is_downloaded_again_for_a_new_version() {
+		let path = "./test_output/cache_stable_url_test";
+		let project = PathAccess::from(path, path).unwrap();
+		let _discard = fs::remove_dir_all(project.cache_root()).await;
+		let mut cache = CacheModAccess::init(&project).await.unwrap();
+		let downloads = Arc::new(AtomicUsize::new(0));
+		let url = "https://example.com/files/framework.zip";
+		let mod_kind = ModKind::parse(url, Some("framework".to_string()), None).unwrap();
+		let mut cached = Vec::new();
+		for version in ["1.0.0", "1.1.0"] {
+			let download = CountingDownload {
+				name: "framework".to_string(),
+				version: Versioning::new(version).unwrap(),
+				url: url.to_string(),
+				downloads: downloads.clone(),
+			};
+			cached.push(cache.cache_mod(download, mod_kind.clone(), None).await.unwrap().clone());
+		}
+
+		assert_eq!(downloads.load(AtomicOrdering::Relaxed), 2);
+		assert_ne!(cached[0].path, cached[1].path);
+		assert_eq!(cached[1].get_version(), &Versioning::new("1.1.0").unwrap());
+		fs::remove_dir_all(project.cache_root()).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn download_progress_is_told_after_every_chunk() {
+		let path = "./test_output/cache_download_progress_test";
+		let project = PathAccess::from(path, path).unwrap();
+		let _discard = fs::remove_dir_all(project.cache_root()).await;
+		let mut cache = CacheModAccess::init(&project).await.unwrap();
+		let download = CountingDownload {
+			name: "framework".to_string(),
+			version: Versioning::new("1.0.0").unwrap(),
+			url: "https://example.com/framework-1.0.0.zip".to_string(),
+			downloads: Arc::new(AtomicUsize::new(0)),
+		};
+		let mod_kind =
+			ModKind::parse("https://github.com/test/framework", Some("framework".to_string()), None)
+				.unwrap();
+		let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+		let reported = reports.clone();
+		let progress: DownloadProgress = Arc::new(move |downloaded, total_size| {
+			reported.lock().unwrap().push((downloaded, total_size));
+		});
+
+		let cached = cache.cache_mod(download, mod_kind, Some(&progress)).await.unwrap().clone();
+
+		assert_eq!(*reports.lock().unwrap(), vec![(7, Some(16)), (16, Some(16))]);
+		assert_eq!(fs::read(&cached.path).await.unwrap(), b"shared framework");
+		fs::remove_dir_all(project.cache_root()).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn truncated_archive_fails_verification_and_is_downloaded_again() {
+		let path = "./test_output/cache_integrity_test";
+		let project = PathAccess::from(path, path).unwrap();
+		let _discard = fs::remove_dir_all(project.cache_root()).await;
+		let mut cache = CacheModAccess::init(&project).await.unwrap();
+		let downloads = Arc::new(AtomicUsize::new(0));
+		let mod_kind =
+			ModKind::parse("https://github.com/test/framework", Some("framework".to_string()), None)
+				.unwrap();
+		let download = || CountingDownload {
+			name: "framework".to_string(),
+			version: Versioning::new("1.0.0").unwrap(),
+			url: "https://example.com/framework-1.0.0.zip".to_string(),
+			downloads: downloads.clone(),
+		};
+		let cached = cache.cache_mod(download(), mod_kind.clone(), None).await.unwrap().clone();
+		let expected_sha256 = sha256::digest("shared framework");
+		assert_eq!(cached.manifest.get_archive_sha256(), Some(expected_sha256.as_str()));
+		assert_eq!(cached.verify_integrity().unwrap(), ArchiveIntegrity::Matches);
+
+		fs::write(&cached.path, "shared").await.unwrap();
+		assert_eq!(cached.verify_integrity().unwrap(), ArchiveIntegrity::Mismatch);
+		cache.remove_cached_version(&cached).await.unwrap();
+		let cached = cache.cache_mod(download(), mod_kind, None).await.unwrap().clone();
+
+		assert_eq!(downloads.load(AtomicOrdering::Relaxed), 2);
+		assert_eq!(cached.verify_integrity().unwrap(), ArchiveIntegrity::Matches);
+		fs::remove_dir_all(project.cache_root()).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn manifests_without_a_hash_are_unverified() {
+		let path = "./test_output/cache_unverified_test";
+		let project = PathAccess::from(path, path).unwrap();
+		let _discard = fs::remove_dir_all(project.cache_root()).await;
+		let mod_folder = project.cache_root().join("remote").join("gc-mod");
+		fs::create_dir_all(&mod_folder).await.unwrap();
+		let mod_kind =
+			ModKind::parse("https://github.com/test/gc-mod", Some("gc-mod".to_string()), None)
+				.unwrap();
+		write_cached_version(&mod_folder, &mod_kind, "1.0.0").await;
+
+		let cache = CacheModAccess::init(&project).await.unwrap();
+		let cached = cache.get_newest_cached_from_kind(&mod_kind).unwrap();
+
+		assert_eq!(cached.verify_integrity().unwrap(), ArchiveIntegrity::Unverified);
+		fs::remove_dir_all(project.cache_root()).await.unwrap()
+	}
+
+	async fn write_dated_version(
+		mod_folder: &Path,
+		name: &str,
+		version: &str,
+		uploaded_at: i64,
+	) -> PathBuf {
+		let url = format!("https://github.com/test/{name}");
+		let mod_kind = ModKind::parse(url, Some(name.to_string()), None).unwrap();
+		let archive_path = mod_folder.join(format!("{version}_{name}.zip"));
+		fs::write(&archive_path, vec![0; 100]).await.unwrap();
+		let manifest = ModManifest::new(
+			DateTime::from_timestamp(uploaded_at, 0).unwrap(),
+			name.to_string(),
+			Versioning::new(version).unwrap(),
+			mod_kind,
+		);
+		let manifest_path = mod_folder.join(format!("{version}_{name}.manifest"));
+		fs::write(manifest_path, serde_json::to_vec(&manifest).unwrap()).await.unwrap();
+		archive_path
+	}
+
+	#[tokio::test]
+	async fn pruning_removes_the_oldest_versions_but_keeps_the_newest_of_each_mod() {
+		let path = "./test_output/cache_prune_test";
+		let project = PathAccess::from(path, path).unwrap();
+		let _discard = fs::remove_dir_all(project.cache_root()).await;
+		let first_folder = project.cache_root().join("remote").join("first-mod");
+		let second_folder = project.cache_root().join("remote").join("second-mod");
+		fs::create_dir_all(&first_folder).await.unwrap();
+		fs::create_dir_all(&second_folder).await.unwrap();
+		let oldest = write_dated_version(&first_folder, "first-mod", "1.0.0", 1_600_000_000).await;
+		let older = write_dated_version(&first_folder, "first-mod", "2.0.0", 1_650_000_000).await;
+		let newest = write_dated_version(&first_folder, "first-mod", "3.0.0", 1_700_000_000).await;
+		let only = write_dated_version(&second_folder, "second-mod", "1.0.0", 1_500_000_000).await;
+		let mut cache = CacheModAccess::init(&project).await.unwrap();
+		let size = cache.cache_size().await.unwrap();
+		assert!(size > 400);
+
+		let removed = cache.prune_to_size(size - 1).await.unwrap();
+
+		assert_eq!(removed, vec![oldest.clone()]);
+		assert!(!first_folder.join("1.0.0_first-mod.manifest").exists());
+		assert!(cache.cache_size().await.unwrap() < size - 100);
+
+		let removed = cache.prune_to_size(0).await.unwrap();
+
+		assert_eq!(removed, vec![older]);
+		assert!(newest.is_file());
+		assert!(only.is_file());
+		assert_eq!(cache.cached_mods.iter().map(|m| m.get_versions().len()).sum::<usize>(), 2);
+		fs::remove_dir_all(project.cache_root()).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn pruning_versions_keeps_the_newest_of_each_mod() {
+		let path = "./test_output/cache_keep_versions_test";
+		let project = PathAccess::from(path, path).unwrap();
+		let _discard = fs::remove_dir_all(project.cache_root()).await;
+		let first_folder = project.cache_root().join("remote").join("first-mod");
+		let second_folder = project.cache_root().join("remote").join("second-mod");
+		fs::create_dir_all(&first_folder).await.unwrap();
+		fs::create_dir_all(&second_folder).await.unwrap();
+		let oldest = write_dated_version(&first_folder, "first-mod", "1.0.0", 1_600_000_000).await;
+		let older = write_dated_version(&first_folder, "first-mod", "2.0.0", 1_650_000_000).await;
+		let newest = write_dated_version(&first_folder, "first-mod", "3.0.0", 1_700_000_000).await;
+		let only = write_dated_version(&second_folder, "second-mod", "1.0.0", 1_500_000_000).await;
+		let mut cache = CacheModAccess::init(&project).await.unwrap();
+
+		let removed = cache.prune_versions(2).await.unwrap();
+
+		assert_eq!(removed, vec![oldest]);
+		assert!(!first_folder.join("1.0.0_first-mod.manifest").exists());
+		assert!(older.is_file());
+		assert!(newest.is_file());
+		assert!(only.is_file());
+		fs::remove_dir_all(project.cache_root()).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn exported_bundle_imports_into_an_empty_cache() {
+		let path = "./test_output/cache_bundle_test";
+		let _discard = fs::remove_dir_all(path).await;
+		fs::create_dir_all(path).await.unwrap();
+		let project = PathAccess::from(format!("{path}/source"), format!("{path}/source")).unwrap();
+		let _discard = fs::remove_dir_all(project.cache_root()).await;
+		let first_folder = project.cache_root().join("remote").join("first-mod");
+		let second_folder = project.cache_root().join("remote").join("second-mod");
+		fs::create_dir_all(&first_folder).await.unwrap();
+		fs::create_dir_all(&second_folder).await.unwrap();
+		write_dated_version(&first_folder, "first-mod", "1.0.0", 1_600_000_000).await;
+		write_dated_version(&first_folder, "first-mod", "2.0.0", 1_700_000_000).await;
+		write_dated_version(&second_folder, "second-mod", "1.0.0", 1_500_000_000).await;
+		let bundle_path = PathBuf::from(format!("{path}/bundle.zip"));
+		let offline_project =
+			PathAccess::from(format!("{path}/offline"), format!("{path}/offline")).unwrap();
+		let _discard = fs::remove_dir_all(offline_project.cache_root()).await;
+
+		let exported = CacheModAccess::init(&project)
+			.await
+			.unwrap()
+			.export_bundle(&bundle_path)
+			.await
+			.unwrap();
+		let mut offline_cache = CacheModAccess::init(&offline_project).await.unwrap();
+		let imported = offline_cache.import_bundle(&bundle_path).await.unwrap();
+
+		assert_eq!(exported, 6);
+		assert_eq!(imported, 6);
+		let mut versions: Vec<_> = offline_cache
+			.get_cached_mods()
+			.iter()
+			.flat_map(|cached_mod| {
+				let versions = cached_mod.get_versions().iter();
+				versions
+					.map(|version| format!("{} {}", cached_mod.get_name(), version.get_version()))
+			})
+			.collect();
+		versions.sort();
+		assert_eq!(versions, ["first-mod 1.0.0", "first-mod 2.0.0", "second-mod 1.0.0"]);
+		let imported_archive = offline_project
+			.cache_root()
+			.join("remote")
+			.join("first-mod")
+			.join("2.0.0_first-mod.zip");
+		assert_eq!(fs::read(imported_archive).await.unwrap(), vec![0; 100]);
+		fs::remove_dir_all(project.cache_root()).await.unwrap();
+		fs::remove_dir_all(offline_project.cache_root()).await.unwrap();
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn mod_kind_is_read_back_from_the_manifest() {
+		let path = "./test_output/cache_mod_kind_test";
+		let project = PathAccess::from(path, path).unwrap();
+		let _discard = fs::remove_dir_all(project.cache_root()).await;
+		let mod_folder = project.cache_root().join("remote").join("gc-mod");
+		fs::create_dir_all(&mod_folder).await.unwrap();
+		let mod_kind =
+			ModKind::parse("https://github.com/test/gc-mod", Some("gc-mod".to_string()), None)
+				.unwrap();
+		write_cached_version(&mod_folder, &mod_kind, "1.0.0").await;
+
+		let cache = CacheModAccess::init(&project).await.unwrap();
+
+		let cached_mod = &cache.get_cached_mods()[0];
+		assert_eq!(cached_mod.get_mod_kind(), &mod_kind);
+		assert_eq!(cached_mod.get_versions()[0].get_mod_kind(), &mod_kind);
+		assert_eq!(cached_mod.get_mod_kind().get_source_name(), "GitHub");
+		fs::remove_dir_all(project.cache_root()).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn unreadable_mod_folder_leaves_the_other_cached_mods_usable() {
+		let path = "./test_output/cache_unreadable_folder_test";
+		let project = PathAccess::from(path, path).unwrap();
+		let _discard = fs::remove_dir_all(project.cache_root()).await;
+		let mod_folder = project.cache_root().join("remote").join("gc-mod");
+		fs::create_dir_all(&mod_folder).await.unwrap();
+		let mod_kind =
+			ModKind::parse("https://github.com/test/gc-mod", Some("gc-mod".to_string()), None)
+				.unwrap();
+		write_cached_version(&mod_folder, &mod_kind, "1.0.0").await;
+		// A manifest that is a folder fails to read, as a folder without permissions would
+		let broken_folder = project.cache_root().join("remote").join("broken-mod");
+		fs::create_dir_all(broken_folder.join("1.0.0_broken-mod.manifest")).await.unwrap();
+		fs::write(broken_folder.join("1.0.0_broken-mod.zip"), "").await.unwrap();
+
+		let cache = CacheModAccess::init(&project).await.unwrap();
+
+		assert!(cache.get_newest_cached_from_kind(&mod_kind).is_some());
+		fs::remove_dir_all(project.cache_root()).await.unwrap()
+	}
+
 	#[test]
 	fn test_file_parser_simple() {
 		let result = separate_file_and_ext("foo").unwrap();
@@ -0,0 +1,78 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Component, Path};
+
+use anyhow::{anyhow, Context, Result};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Writes every file of every mod folder in the cache to `bundle_path` as `<mod folder>/<file>`
+/// and returns how many were bundled. The archives are already compressed, so they're stored.
+pub(super) fn write_bundle(cache_dir: &Path, bundle_path: &Path) -> Result<usize> {
+	let mut zip_writer = ZipWriter::new(BufWriter::new(File::create(bundle_path)?));
+	let options = SimpleFileOptions::default()
+		.compression_method(CompressionMethod::Stored)
+		.large_file(true);
+	let mut files = 0;
+	for mod_entry in std::fs::read_dir(cache_dir)? {
+		let mod_path = mod_entry?.path();
+		if !mod_path.is_dir() {
+			continue;
+		}
+		let mod_folder = file_name(&mod_path)?;
+		for file_entry in std::fs::read_dir(&mod_path)? {
+			let file_path = file_entry?.path();
+			if !file_path.is_file() {
+				continue;
+			}
+			zip_writer.start_file(format!("{mod_folder}/{}", file_name(&file_path)?), options)?;
+			std::io::copy(&mut File::open(&file_path)?, &mut zip_writer)?;
+			files += 1;
+		}
+	}
+	zip_writer.finish()?.flush()?;
+	Ok(files)
+}
+
+/// Extracts a bundle from [`write_bundle`] into the cache and returns how many files were added.
+/// Files the cache already has are kept as they are.
+pub(super) fn read_bundle(bundle_path: &Path, cache_dir: &Path) -> Result<usize> {
+	let mut zip_archive = ZipArchive::new(File::open(bundle_path)?)
+		.with_context(|| format!("Failed to read the cache bundle: {}", bundle_path.display()))?;
+	let mut added = 0;
+	for index in 0..zip_archive.len() {
+		let mut entry = zip_archive.by_index(index)?;
+		if entry.is_dir() {
+			continue;
+		}
+		let entry_path = entry
+			.enclosed_name()
+			.filter(|entry_path| is_cache_file(entry_path))
+			.ok_or_else(|| anyhow!("The cache bundle has an unexpected entry: {}", entry.name()))?;
+		let path = cache_dir.join(entry_path);
+		if path.exists() {
+			continue;
+		}
+		if let Some(parent) = path.parent() {
+			std::fs::create_dir_all(parent)?;
+		}
+		std::io::copy(&mut entry, &mut File::create(&path)?)?;
+		added += 1;
+	}
+	Ok(added)
+}
+
+/// Whether the path is a file in a mod folder, the only thing a bundle holds.
+fn is_cache_file(path: &Path) -> bool {
+	let components: Vec<_> = path.components().collect();
+	components.len() == 2
+		&& components
+			.iter()
+			.all(|component| matches!(component, Component::Normal(_)))
+}
+
+fn file_name(path: &Path) -> Result<&str> {
+	path.file_name()
+		.and_then(|name| name.to_str())
+		.with_context(|| format!("Found a cache file name that isn't UTF-8: {}", path.display()))
+}
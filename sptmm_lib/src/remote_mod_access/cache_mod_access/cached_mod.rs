@@ -17,6 +17,10 @@ impl CachedMod {
 		self.versions.iter().max()
 	}
 
+	pub fn get_versions(&self) -> &[CachedModVersion] {
+		&self.versions
+	}
+
 	pub fn get_version(&self, version: &Versioning) -> Option<&CachedModVersion> {
 		self.versions.iter().find(|x| x.get_version() == version)
 	}
@@ -1,16 +1,59 @@
 use std::cmp::Ordering;
 use std::path::PathBuf;
+use anyhow::Context;
 use versions::Versioning;
 use crate::remote_mod_access::cache_mod_access::mod_manifest::ModManifest;
+use crate::remote_mod_access::ModKind;
 use crate::shared_traits::{ModName, ModVersion};
 
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct CachedModVersion {
 	pub path: PathBuf,
 	pub manifest: ModManifest,
 }
 
+/// Whether a cached archive still is what was downloaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveIntegrity {
+	Matches,
+	/// The manifest was written before archives were hashed.
+	Unverified,
+	/// The archive changed since it was downloaded, such as by a download cut short.
+	Mismatch,
+}
+
+impl CachedModVersion {
+	/// Hashes the archive again and compares it to the hash recorded when it was downloaded.
+	pub fn verify_integrity(&self) -> anyhow::Result<ArchiveIntegrity> {
+		let Some(expected) = self.manifest.get_archive_sha256() else {
+			return Ok(ArchiveIntegrity::Unverified);
+		};
+		let actual = sha256::try_digest(&self.path)
+			.with_context(|| format!("Failed to hash cached archive {}", self.path.display()))?;
+		if actual == expected {
+			Ok(ArchiveIntegrity::Matches)
+		} else {
+			Ok(ArchiveIntegrity::Mismatch)
+		}
+	}
+
+	pub fn get_manifest_path(&self) -> anyhow::Result<PathBuf> {
+		let mod_path = self.path.parent().context("Found no cached mod folder")?;
+		let file_name = self
+			.path
+			.file_name()
+			.and_then(|name| name.to_str())
+			.context("Found no cached file name")?;
+		ModManifest::create_manifest_path(mod_path.to_path_buf(), file_name)
+	}
+
+	/// Where the version was downloaded from, as recorded in its manifest.
+	pub fn get_mod_kind(&self) -> &ModKind {
+		self.manifest.get_mod_kind()
+	}
+}
+
 impl PartialEq<Self> for CachedModVersion {
 	fn eq(&self, other: &Self) -> bool {
 		self.path == other.path
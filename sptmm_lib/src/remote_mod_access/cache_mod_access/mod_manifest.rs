@@ -14,6 +14,12 @@ pub struct ModManifest {
 	version: Versioning,
 	uploaded_at: DateTime<Utc>,
 	mod_kind: ModKind,
+	/// Missing from manifests written before downloads were shared between mods.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	download_url: Option<String>,
+	/// Missing from manifests written before archives were hashed, which leaves them unverified.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	archive_sha256: Option<String>,
 }
 
 impl ModManifest {
@@ -28,8 +34,21 @@ impl ModManifest {
 			name,
 			version,
 			mod_kind,
+			download_url: None,
+			archive_sha256: None,
 		}
 	}
+
+	pub fn with_download_url(mut self, download_url: impl Into<String>) -> Self {
+		self.download_url = Some(download_url.into());
+		self
+	}
+
+	pub fn with_archive_sha256(mut self, archive_sha256: impl Into<String>) -> Self {
+		self.archive_sha256 = Some(archive_sha256.into());
+		self
+	}
+
 	pub fn create_manifest_path(mod_path: PathBuf, mod_file_name: &str) -> anyhow::Result<PathBuf> {
 		let (manifest_file_name, _) =
 			separate_file_and_ext(mod_file_name).map_err(|_| anyhow!("Failed to get file"))?;
@@ -41,6 +60,18 @@ impl ModManifest {
 	pub fn get_mod_kind(&self) -> &ModKind{
 		&self.mod_kind
 	}
+
+	pub fn get_download_url(&self) -> Option<&str> {
+		self.download_url.as_deref()
+	}
+
+	pub fn get_archive_sha256(&self) -> Option<&str> {
+		self.archive_sha256.as_deref()
+	}
+
+	pub fn get_uploaded_at(&self) -> DateTime<Utc> {
+		self.uploaded_at
+	}
 }
 
 impl ModName for ModManifest {
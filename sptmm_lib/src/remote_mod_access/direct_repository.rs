@@ -0,0 +1,130 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, LAST_MODIFIED};
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use versions::Versioning;
+
+use crate::remote_mod_access::ModDownloadVersion;
+
+/// The archive types a direct link may point to, matched against the end of the url's path.
+pub const ARCHIVE_EXTENSIONS: &[&str] = &[".zip", ".7z", ".rar", ".tar.gz", ".tgz", ".tar"];
+
+/// An archive downloaded straight from its url, for mods without a hub or GitHub page. Nothing
+/// lists their releases, so the version always comes from the configuration.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DirectLink {
+	url: Url,
+	file_name: String,
+	name: String,
+}
+
+impl DirectLink {
+	pub fn parse<S: AsRef<str>>(url: S) -> Result<Self> {
+		let url = Url::parse(url.as_ref())?;
+		let file_name = url
+			.path_segments()
+			.and_then(|mut segments| segments.rfind(|s| !s.is_empty()))
+			.context("Found no file name in the url")?
+			.to_string();
+		let lowercase_name = file_name.to_lowercase();
+		let extension = ARCHIVE_EXTENSIONS
+			.iter()
+			.find(|extension| lowercase_name.ends_with(*extension))
+			.ok_or_else(|| anyhow!("Unsupported mod host: {url}"))?;
+		let name = file_name[..file_name.len() - extension.len()].to_string();
+		Ok(Self {
+			url,
+			file_name,
+			name,
+		})
+	}
+
+	pub fn get_url(&self) -> &Url {
+		&self.url
+	}
+
+	/// The archive's file name without its extension.
+	pub fn get_name(&self) -> &str {
+		&self.name
+	}
+}
+
+pub struct DirectRepository {
+	reqwest: Client,
+}
+
+impl DirectRepository {
+	pub fn new(reqwest: Client) -> Self {
+		Self { reqwest }
+	}
+
+	/// Uses the archive's `Last-Modified` as its upload date, or the epoch when the server
+	/// doesn't report it.
+	pub async fn get_version(
+		&self,
+		link: DirectLink,
+		version: &Versioning,
+	) -> Result<ModDownloadVersion> {
+		let response = self.reqwest.head(link.url.clone()).send().await?;
+		let uploaded_at = response
+			.status()
+			.is_success()
+			.then(|| response.headers().get(LAST_MODIFIED))
+			.flatten()
+			.and_then(|value| value.to_str().ok())
+			.and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+			.map_or(DateTime::<Utc>::UNIX_EPOCH, |date| date.with_timezone(&Utc));
+		Ok(ModDownloadVersion {
+			title: link.name,
+			file_name: link.file_name,
+			download_url: link.url,
+			additional_parts: Vec::new(),
+			download_headers: HeaderMap::new(),
+			uploaded_at,
+			version: version.clone(),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_server::{TestResponse, TestServer};
+
+	#[test]
+	fn archive_url_keeps_file_name() {
+		let link =
+			DirectLink::parse("https://example.com/downloads/Some-Mod-1.2.0.7z?token=1").unwrap();
+
+		assert_eq!(link.file_name, "Some-Mod-1.2.0.7z");
+		assert_eq!(link.get_name(), "Some-Mod-1.2.0");
+	}
+
+	#[test]
+	fn url_without_archive_extension_is_rejected() {
+		assert!(DirectLink::parse("https://example.com/downloads/page.html").is_err());
+		assert!(DirectLink::parse("https://example.com/").is_err());
+	}
+
+	#[tokio::test]
+	async fn last_modified_is_the_upload_date() {
+		let server = TestServer::start(vec![(
+			"/mod.zip",
+			TestResponse::ok(vec![0; 10])
+				.with_header("Last-Modified", "Wed, 21 Oct 2015 07:28:00 GMT"),
+		)])
+		.await;
+		let link = DirectLink::parse(server.url("/mod.zip")).unwrap();
+
+		let mod_version = DirectRepository::new(Client::new())
+			.get_version(link, &Versioning::new("1.0.0").unwrap())
+			.await
+			.unwrap();
+
+		assert_eq!(mod_version.title, "mod");
+		assert_eq!(mod_version.file_name, "mod.zip");
+		assert_eq!(mod_version.uploaded_at, DateTime::from_timestamp(1445412480, 0).unwrap());
+		assert_eq!(server.requests(), vec!["HEAD /mod.zip"]);
+	}
+}
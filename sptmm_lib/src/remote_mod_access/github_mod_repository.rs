@@ -1,18 +1,26 @@
 use anyhow::Result;
 use anyhow::{anyhow, Context, Error};
+use chrono::{DateTime, NaiveDate, Utc};
 use octocrab::models::repos::{Asset, Release};
+use octocrab::service::middleware::retry::RetryConfig;
 use octocrab::Octocrab;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION};
+use reqwest::{StatusCode, Url};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
 use std::ops::Sub;
 use std::time::Duration;
-use tokio::time::{sleep_until, Instant};
+use tokio::time::{sleep, sleep_until, Instant};
 use versions::Versioning;
 use winnow::combinator::opt;
 use winnow::stream::AsChar;
 use winnow::token::{take, take_till, take_until};
 use winnow::{PResult, Parser};
 
-use crate::remote_mod_access::ModDownloadVersion;
+use crate::remote_mod_access::retry::RetryPolicy;
+use crate::remote_mod_access::{is_prerelease, is_selectable, ModDownloadVersion, RemoteError};
+use crate::version_constraint::VersionConstraint;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct GitHubLink {
@@ -20,9 +28,17 @@ pub struct GitHubLink {
 	repo: String,
 	asset_pattern: String,
 	asset_filter: Option<String>,
+	#[serde(default)]
+	split_parts: bool,
 }
 
 pub const GITHUB_DOMAIN: &str = "https://github.com";
+/// Authenticates the GitHub requests when set, which raises the rate limit from 60 requests an hour
+/// and is needed for private repositories.
+pub const GITHUB_TOKEN_VARIABLE: &str = "SPTMM_GITHUB_TOKEN";
+/// Read when [`GITHUB_TOKEN_VARIABLE`] isn't set, as CI and the GitHub CLI already set it.
+pub const FALLBACK_GITHUB_TOKEN_VARIABLE: &str = "GITHUB_TOKEN";
+
 impl GitHubLink {
 	pub fn parse<S: AsRef<str>>(
 		url: S,
@@ -35,68 +51,218 @@ impl GitHubLink {
 			repo,
 			asset_pattern,
 			asset_filter,
+			split_parts: false,
 		})
 	}
 
+	/// Treats assets with a numeric suffix such as `mod.zip.001`, `mod.zip.002` as parts of one archive.
+	pub fn with_split_parts(mut self, split_parts: bool) -> Self {
+		self.split_parts = split_parts;
+		self
+	}
+
 	pub fn starts_with_host<S: AsRef<str>>(url: &S) -> bool {
 		url.as_ref().starts_with(GITHUB_DOMAIN)
 	}
+
+	pub fn get_repo(&self) -> &str {
+		&self.repo
+	}
+
+	pub fn get_releases_page(&self) -> String {
+		format!("{GITHUB_DOMAIN}/{}/{}/releases", self.owner, self.repo)
+	}
 }
 
 pub struct GithubModRepository {
 	octo: Octocrab,
 	last_request: Instant,
 	request_interval: Duration,
+	max_rate_limit_wait: Duration,
+	retry_policy: RetryPolicy,
+	token: Option<String>,
+	/// Whether each `owner/repo` looked up so far is private.
+	private_repositories: HashMap<String, bool>,
 }
 
 impl GithubModRepository {
+	/// Authenticates with the token in [`GITHUB_TOKEN_VARIABLE`] or [`FALLBACK_GITHUB_TOKEN_VARIABLE`].
 	pub fn new() -> Self {
-		let request_interval = Duration::from_secs(1);
+		let token = std::env::var(GITHUB_TOKEN_VARIABLE)
+			.or_else(|_| std::env::var(FALLBACK_GITHUB_TOKEN_VARIABLE))
+			.ok()
+			.filter(|token| !token.is_empty());
+		Self::with_token(token)
+	}
+
+	/// Authenticated requests have a far higher rate limit, so they're spaced out less. Octocrab's
+	/// own retries are turned off, failed requests are only retried as the [`RetryPolicy`] says.
+	pub fn with_token(token: Option<String>) -> Self {
+		let (octo, request_interval) = match &token {
+			Some(token) => (
+				Octocrab::builder()
+					.add_retry_config(RetryConfig::None)
+					.personal_token(token.clone())
+					.build()
+					.unwrap_or_default(),
+				Duration::from_millis(100),
+			),
+			None => (
+				Octocrab::builder()
+					.add_retry_config(RetryConfig::None)
+					.build()
+					.unwrap_or_default(),
+				Duration::from_secs(1),
+			),
+		};
 		Self {
-			octo: Octocrab::default(),
+			octo,
 			last_request: Instant::now().sub(request_interval),
 			request_interval,
+			max_rate_limit_wait: Duration::ZERO,
+			retry_policy: RetryPolicy::default(),
+			token,
+			private_repositories: HashMap::new(),
 		}
 	}
-	pub async fn get_latest_version(&mut self, gh_mod: GitHubLink) -> Result<ModDownloadVersion> {
+
+	/// Waits for a spent rate limit to reset when it does so within `max_rate_limit_wait`, instead
+	/// of failing with [`RemoteError::RateLimited`].
+	pub fn set_max_rate_limit_wait(&mut self, max_rate_limit_wait: Duration) {
+		self.max_rate_limit_wait = max_rate_limit_wait;
+	}
+
+	/// Retries requests that failed to connect or got a 5xx as the policy says.
+	pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+		self.retry_policy = retry_policy;
+	}
+
+	/// The newest release, skipping pre-releases unless they're allowed and releases published
+	/// before `min_release_date`. GitHub's latest release leaves out releases marked as
+	/// pre-releases, but not ones only named like one such as `1.3.0-rc1`.
+	pub async fn get_latest_version(
+		&mut self,
+		gh_mod: GitHubLink,
+		allow_prerelease: bool,
+		min_release_date: Option<NaiveDate>,
+	) -> Result<ModDownloadVersion> {
+		if allow_prerelease || min_release_date.is_some() {
+			// GitHub's latest release can't be narrowed down, so pick from the listed ones
+			match self.find_newest_listed(&gh_mod, allow_prerelease, min_release_date).await? {
+				Some((release, version)) => {
+					let private_token = self.get_private_token(&gh_mod).await?;
+					let private_token = private_token.as_deref();
+					return Self::to_download_version(gh_mod, release, version, private_token);
+				}
+				None => {
+					if let Some(min_release_date) = min_release_date {
+						return Err(anyhow!(
+							"Found no releases of {}/{} published since {min_release_date}",
+							gh_mod.owner,
+							gh_mod.repo
+						));
+					}
+				}
+			}
+		}
+
+		let (owner, repo) = (&gh_mod.owner, &gh_mod.repo);
 		let release = self
-			.get_client()
-			.await
-			.repos(&gh_mod.owner, &gh_mod.repo)
-			.releases()
-			.get_latest()
+			.send(move |octo| async move { octo.repos(owner, repo).releases().get_latest().await })
 			.await?;
+		let release = match release {
+			Ok(release) => release,
+			Err(err) if is_missing(&err) => {
+				// GitHub answers 404 both for a missing repository and one without releases
+				let repository = self
+					.get_client()
+					.await
+					.repos(&gh_mod.owner, &gh_mod.repo)
+					.get()
+					.await;
+				return Err(match repository {
+					Ok(_) => anyhow!("No releases found for {}/{}", gh_mod.owner, gh_mod.repo),
+					Err(err) => map_repository_error(err, &gh_mod),
+				});
+			}
+			Err(err) => return Err(map_repository_error(err, &gh_mod)),
+		};
 
 		let version = release.name.clone().context("Found no name")?;
-		let asset = Self::filter_asset(&gh_mod, release)?;
-
 		let version = parse_version(&version)
 			.ok()
 			.flatten()
 			.context("Failed to parse version")?;
-		Ok(ModDownloadVersion {
-			title: gh_mod.repo,
-			file_name: asset.name.clone(),
-			download_url: asset.browser_download_url.clone(),
-			version,
-			uploaded_at: asset.created_at,
-		})
+		let (release, version) = if allow_prerelease || !is_prerelease(&version) {
+			(release, version)
+		} else {
+			self.find_newest_listed(&gh_mod, false, None).await?.with_context(|| {
+				format!(
+					"Found only pre-releases for {}/{}, set allow_prerelease to install them",
+					gh_mod.owner, gh_mod.repo
+				)
+			})?
+		};
+		let private_token = self.get_private_token(&gh_mod).await?;
+		Self::to_download_version(gh_mod, release, version, private_token.as_deref())
+	}
+
+	/// The release with the highest version on the first page of releases, leaving out drafts,
+	/// releases published before `min_release_date` and pre-releases unless they're allowed.
+	async fn find_newest_listed(
+		&mut self,
+		gh_mod: &GitHubLink,
+		allow_prerelease: bool,
+		min_release_date: Option<NaiveDate>,
+	) -> Result<Option<(Release, Versioning)>> {
+		let (owner, repo) = (&gh_mod.owner, &gh_mod.repo);
+		let releases = self
+			.send(move |octo| async move { octo.repos(owner, repo).releases().list().send().await })
+			.await?
+			.map_err(|err| map_repository_error(err, gh_mod))?;
+		let newest = releases
+			.into_iter()
+			.filter(|release| !release.draft)
+			.filter(|release| is_published_since(release.published_at, min_release_date))
+			.filter_map(|release| {
+				let version = parse_version(release.name.as_deref()?).ok().flatten()?;
+				is_selectable(&version, release.prerelease, allow_prerelease)
+					.then_some((release, version))
+			})
+			.max_by(|(_, a), (_, b)| a.cmp(b));
+		Ok(newest)
 	}
 
 	pub async fn get_version(
 		&mut self,
 		gh_mod: GitHubLink,
-		version: &Versioning,
+		version: &VersionConstraint,
 		version_filter: Option<&str>,
 	) -> Result<Option<ModDownloadVersion>> {
+		let (owner, repo) = (&gh_mod.owner, &gh_mod.repo);
 		let releases = self
-			.get_client()
-			.await
-			.repos(&gh_mod.owner, &gh_mod.repo)
-			.releases()
-			.list()
-			.send()
-			.await?;
+			.send(move |octo| async move { octo.repos(owner, repo).releases().list().send().await })
+			.await?
+			.map_err(|err| map_repository_error(err, &gh_mod))?;
+
+		let Some(version) = version.as_exact() else {
+			let candidates = releases.into_iter().filter_map(|release| {
+				let name = release.name.as_deref()?;
+				if version_filter.is_some_and(|filter| name.contains(filter)) {
+					return None;
+				}
+				let release_version = parse_version(name).ok().flatten()?;
+				Some((release, release_version))
+			});
+			let Some((release, release_version)) = version.select_highest(candidates) else {
+				return Ok(None);
+			};
+			let private_token = self.get_private_token(&gh_mod).await?;
+			let private_token = private_token.as_deref();
+			let mod_version =
+				Self::to_download_version(gh_mod, release, release_version, private_token)?;
+			return Ok(Some(mod_version));
+		};
 
 		let mut versions: Vec<_> = if let Some(version_filter) = version_filter {
 			releases
@@ -133,21 +299,138 @@ impl GithubModRepository {
 			}
 		};
 
-		let asset = Self::filter_asset(&gh_mod, release)?;
-
-		Ok(Some(ModDownloadVersion {
-			title: gh_mod.repo,
-			file_name: asset.name,
-			download_url: asset.browser_download_url,
-			version: version.clone(),
-			uploaded_at: asset.created_at,
-		}))
+		let private_token = self.get_private_token(&gh_mod).await?;
+		let private_token = private_token.as_deref();
+		Ok(Some(Self::to_download_version(gh_mod, release, version.clone(), private_token)?))
 	}
 	async fn get_client(&mut self) -> &Octocrab {
 		sleep_until(self.last_request + self.request_interval).await;
 		self.last_request = Instant::now();
 		&self.octo
 	}
+
+	/// Sends the request, waiting once for a spent rate limit to reset when it's soon enough and
+	/// retrying transient failures. The outer error is [`RemoteError::RateLimited`], the inner one
+	/// is left to the caller to map.
+	async fn send<T, Fut: Future<Output = octocrab::Result<T>>>(
+		&mut self,
+		request: impl Fn(Octocrab) -> Fut,
+	) -> Result<octocrab::Result<T>> {
+		let mut waited = false;
+		let mut backoffs = self.retry_policy.backoffs();
+		loop {
+			let octo = self.get_client().await.clone();
+			match request(octo).await {
+				Err(err) if is_rate_limited(&err) => {
+					// Octocrab doesn't hand out the X-RateLimit-Reset header, so ask GitHub again,
+					// which doesn't count against the limit
+					let Some(reset_at) = self.get_rate_limit_reset().await else {
+						return Ok(Err(err));
+					};
+					let wait = (reset_at - Utc::now()).to_std().unwrap_or_default();
+					if waited || wait > self.max_rate_limit_wait {
+						return Err(RemoteError::RateLimited { reset_at }.into());
+					}
+					waited = true;
+					sleep(wait).await;
+				}
+				Err(err) if is_transient(&err) => match backoffs.next() {
+					Some(backoff) => sleep(backoff).await,
+					None => return Ok(Err(err)),
+				},
+				result => return Ok(result),
+			}
+		}
+	}
+
+	async fn get_rate_limit_reset(&mut self) -> Option<DateTime<Utc>> {
+		let rate_limit = self.get_client().await.ratelimit().get().await.ok()?;
+		DateTime::from_timestamp(i64::try_from(rate_limit.resources.core.reset).ok()?, 0)
+	}
+
+	/// The token to download the repository's assets with, when it's private. Their browser
+	/// download urls need a signed in browser, so they're fetched through the API instead. Whether
+	/// the repository is private is only looked up once.
+	async fn get_private_token(&mut self, gh_mod: &GitHubLink) -> Result<Option<String>> {
+		let Some(token) = self.token.clone() else {
+			return Ok(None);
+		};
+		let full_name = format!("{}/{}", gh_mod.owner, gh_mod.repo);
+		let is_private = match self.private_repositories.get(&full_name) {
+			Some(is_private) => *is_private,
+			None => {
+				let (owner, repo) = (&gh_mod.owner, &gh_mod.repo);
+				let repository = self
+					.send(move |octo| async move { octo.repos(owner, repo).get().await })
+					.await?
+					.map_err(|err| map_repository_error(err, gh_mod))?;
+				let is_private = repository.private.unwrap_or(false);
+				self.private_repositories.insert(full_name, is_private);
+				is_private
+			}
+		};
+		Ok(is_private.then_some(token))
+	}
+
+	fn to_download_version(
+		gh_mod: GitHubLink,
+		release: Release,
+		version: Versioning,
+		private_token: Option<&str>,
+	) -> Result<ModDownloadVersion> {
+		let download_headers = match private_token {
+			Some(token) => api_download_headers(token)?,
+			None => HeaderMap::new(),
+		};
+		let asset_url = |asset: Asset| asset_download_url(asset, private_token.is_some());
+		if gh_mod.split_parts {
+			let mut parts = Self::filter_split_assets(&gh_mod, release)?.into_iter();
+			let first_part = parts.next().context("Found no split asset parts")?;
+			let file_name = first_part
+				.name
+				.rsplit_once('.')
+				.map_or(first_part.name.clone(), |(name, _)| name.to_string());
+			let uploaded_at = first_part.created_at;
+			return Ok(ModDownloadVersion {
+				title: gh_mod.repo,
+				file_name,
+				download_url: asset_url(first_part),
+				additional_parts: parts.map(asset_url).collect(),
+				download_headers,
+				version,
+				uploaded_at,
+			});
+		}
+
+		let asset = Self::filter_asset(&gh_mod, release)?;
+		Ok(ModDownloadVersion {
+			title: gh_mod.repo,
+			file_name: asset.name.clone(),
+			uploaded_at: asset.created_at,
+			download_url: asset_url(asset),
+			additional_parts: Vec::new(),
+			download_headers,
+			version,
+		})
+	}
+
+	fn filter_split_assets(gh_mod: &GitHubLink, release: Release) -> Result<Vec<Asset>> {
+		let parts = select_split_parts(
+			release.assets,
+			|asset: &Asset| asset.name.as_str(),
+			&gh_mod.asset_pattern,
+			gh_mod.asset_filter.as_deref(),
+		);
+		if parts.is_empty() {
+			return Err(anyhow!(
+				"Failed to find split asset parts from pattern: {}, and filter: {:?}",
+				&gh_mod.asset_pattern,
+				&gh_mod.asset_filter
+			));
+		}
+		Ok(parts)
+	}
+
 	fn filter_asset(gh_mod: &GitHubLink, release: Release) -> Result<Asset, Error> {
 		if let Some(filter) = &gh_mod.asset_filter {
 			return release
@@ -175,6 +458,122 @@ impl GithubModRepository {
 	}
 }
 
+/// Whether a release published at `published_at` may be the latest one, which a release without a
+/// publishing date, such as a draft, never is once a minimum date is set.
+fn is_published_since(
+	published_at: Option<DateTime<Utc>>,
+	min_release_date: Option<NaiveDate>,
+) -> bool {
+	match min_release_date {
+		Some(min_release_date) => {
+			published_at.is_some_and(|published_at| published_at.date_naive() >= min_release_date)
+		}
+		None => true,
+	}
+}
+
+fn github_status(err: &octocrab::Error) -> Option<StatusCode> {
+	match err {
+		octocrab::Error::GitHub { source, .. } => Some(source.status_code),
+		_ => None,
+	}
+}
+
+fn is_missing(err: &octocrab::Error) -> bool {
+	github_status(err) == Some(StatusCode::NOT_FOUND)
+}
+
+/// Whether the request couldn't reach GitHub or GitHub failed to answer it, which trying again
+/// can fix. Client errors such as a 404 never are.
+fn is_transient(err: &octocrab::Error) -> bool {
+	match err {
+		octocrab::Error::Hyper { .. } | octocrab::Error::Service { .. } => true,
+		_ => github_status(err).is_some_and(|status| status.is_server_error()),
+	}
+}
+
+/// GitHub answers a spent rate limit with a 403 or 429 that says so, unlike a private repository.
+fn is_rate_limited(err: &octocrab::Error) -> bool {
+	match err {
+		octocrab::Error::GitHub { source, .. } => {
+			matches!(source.status_code, StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS)
+				&& source.message.to_lowercase().contains("rate limit")
+		}
+		_ => false,
+	}
+}
+
+/// GitHub hides private repositories behind a 404 or 403, which only a token can get past. Other
+/// errors, such as network failures, are kept as they are.
+fn map_repository_error(err: octocrab::Error, gh_mod: &GitHubLink) -> Error {
+	if is_rate_limited(&err) {
+		return anyhow!(
+			"GitHub's rate limit was reached checking {}/{}, set {GITHUB_TOKEN_VARIABLE} to a personal access token to raise it",
+			gh_mod.owner,
+			gh_mod.repo
+		);
+	}
+	match github_status(&err) {
+		Some(StatusCode::NOT_FOUND | StatusCode::FORBIDDEN) => anyhow!(
+			"Repository {}/{} not found or private (set {GITHUB_TOKEN_VARIABLE}?)",
+			gh_mod.owner,
+			gh_mod.repo
+		),
+		_ => Error::new(err).context(format!(
+			"Failed to reach GitHub for {}/{}",
+			gh_mod.owner, gh_mod.repo
+		)),
+	}
+}
+
+/// The API serves the asset itself instead of its description when asked for a binary.
+fn api_download_headers(token: &str) -> Result<HeaderMap> {
+	let mut authorization = HeaderValue::from_str(&format!("Bearer {token}"))?;
+	authorization.set_sensitive(true);
+	let mut headers = HeaderMap::new();
+	headers.insert(ACCEPT, HeaderValue::from_static("application/octet-stream"));
+	headers.insert(AUTHORIZATION, authorization);
+	Ok(headers)
+}
+
+fn asset_download_url(asset: Asset, through_api: bool) -> Url {
+	if through_api {
+		asset.url
+	} else {
+		asset.browser_download_url
+	}
+}
+
+/// Finds the items matching the pattern that end in a numeric part suffix, ordered by that suffix.
+fn select_split_parts<T>(
+	items: Vec<T>,
+	name: impl Fn(&T) -> &str,
+	pattern: &str,
+	filter: Option<&str>,
+) -> Vec<T> {
+	let mut parts: Vec<_> = items
+		.into_iter()
+		.filter_map(|item| {
+			let item_name = name(&item);
+			if !item_name.contains(pattern) || filter.is_some_and(|f| item_name.contains(f)) {
+				return None;
+			}
+			let part_number = split_part_number(item_name)?;
+			Some((part_number, item))
+		})
+		.collect();
+	parts.sort_by_key(|(part_number, _)| *part_number);
+	parts.into_iter().map(|(_, item)| item).collect()
+}
+
+fn split_part_number(name: &str) -> Option<u32> {
+	let (_, suffix) = name.rsplit_once('.')?;
+	if suffix.is_empty() || !suffix.chars().all(|c| c.is_ascii_digit()) {
+		return None;
+	}
+	suffix.parse().ok()
+}
+
 fn validate_url(input: &str) -> PResult<(String, String)> {
 	let (remainder, _) = "https://github.com/".parse_peek(input)?;
 	let (remainder, owner) = take_until(0.., "/").parse_peek(remainder)?;
@@ -197,6 +596,7 @@ pub fn parse_version(version: &str) -> PResult<Option<Versioning>> {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::test_server::{TestResponse, TestServer};
 
 	#[test]
 	fn longer_github_url_should_parse() {
@@ -213,6 +613,21 @@ mod tests {
 		assert!(result.is_err())
 	}
 
+	#[test]
+	fn split_parts_are_selected_in_order() {
+		let assets = vec![
+			"mod.zip.002",
+			"readme.txt",
+			"mod.zip.001",
+			"mod-sources.zip.001",
+			"mod.zip",
+		];
+
+		let parts = select_split_parts(assets, |name: &&str| *name, "mod", Some("sources"));
+
+		assert_eq!(parts, vec!["mod.zip.001", "mod.zip.002"]);
+	}
+
 	#[test]
 	fn short_github_url_should_parse() {
 		let result = validate_url("https://github.com/maxloo2/betterkeys-updated").unwrap();
@@ -221,4 +636,321 @@ mod tests {
 			("maxloo2".to_string(), "betterkeys-updated".to_string())
 		);
 	}
+
+	fn github_error(status: u16, message: &str) -> TestResponse {
+		let mut response = TestResponse::status(status);
+		response.body = format!(r#"{{"message":"{message}","documentation_url":"https://docs.github.com/rest"}}"#).into_bytes();
+		response
+	}
+
+	fn repository(base_uri: &str) -> GithubModRepository {
+		GithubModRepository {
+			octo: Octocrab::builder()
+				.add_retry_config(RetryConfig::None)
+				.base_uri(base_uri)
+				.unwrap()
+				.build()
+				.unwrap(),
+			last_request: Instant::now(),
+			request_interval: Duration::ZERO,
+			max_rate_limit_wait: Duration::ZERO,
+			retry_policy: RetryPolicy::default(),
+			token: None,
+			private_repositories: HashMap::new(),
+		}
+	}
+
+	fn test_link() -> GitHubLink {
+		GitHubLink::parse("https://github.com/owner/repo", "mod".to_string(), None).unwrap()
+	}
+
+	#[tokio::test]
+	async fn missing_repository_asks_for_token() {
+		let server = TestServer::start(vec![
+			("/repos/owner/repo/releases/latest", github_error(404, "Not Found")),
+			("/repos/owner/repo", github_error(404, "Not Found")),
+		])
+		.await;
+		let mut repository = repository(server.url("/").as_str());
+
+		let err = repository.get_latest_version(test_link(), false, None).await.unwrap_err();
+
+		assert_eq!(
+			err.to_string(),
+			"Repository owner/repo not found or private (set SPTMM_GITHUB_TOKEN?)"
+		);
+	}
+
+	#[tokio::test]
+	async fn forbidden_repository_asks_for_token() {
+		let server = TestServer::start(vec![(
+			"/repos/owner/repo/releases/latest",
+			github_error(403, "Forbidden"),
+		)])
+		.await;
+		let mut repository = repository(server.url("/").as_str());
+
+		let err = repository.get_latest_version(test_link(), false, None).await.unwrap_err();
+
+		assert_eq!(
+			err.to_string(),
+			"Repository owner/repo not found or private (set SPTMM_GITHUB_TOKEN?)"
+		);
+	}
+
+	#[tokio::test]
+	async fn rate_limit_is_told_apart_from_a_private_repository() {
+		let server = TestServer::start(vec![(
+			"/repos/owner/repo/releases/latest",
+			github_error(403, "API rate limit exceeded for 127.0.0.1."),
+		)])
+		.await;
+		let mut repository = repository(server.url("/").as_str());
+
+		let err = repository.get_latest_version(test_link(), false, None).await.unwrap_err();
+
+		assert_eq!(
+			err.to_string(),
+			"GitHub's rate limit was reached checking owner/repo, set SPTMM_GITHUB_TOKEN to a personal access token to raise it"
+		);
+	}
+
+	/// A release named `name` with a `mod.zip` asset, published at `published_at` unless a draft.
+	fn release_json(id: u64, name: &str, published_at: Option<&str>, prerelease: bool) -> String {
+		let api = "https://api.github.com/repos/owner/repo";
+		let download = format!("https://github.com/owner/repo/releases/download/{name}/mod.zip");
+		let published_at = published_at.map_or("null".to_string(), |date| format!(r#""{date}""#));
+		format!(
+			r#"{{"url":"{api}/releases/{id}","html_url":"{api}/releases/{name}",
+			"assets_url":"{api}/releases/{id}/assets","upload_url":"{api}/releases/{id}/assets",
+			"id":{id},"node_id":"R{id}","tag_name":"{name}","target_commitish":"main",
+			"name":"{name}","draft":{draft},"prerelease":{prerelease},
+			"created_at":"2023-01-01T00:00:00Z","published_at":{published_at},
+			"assets":[{{"url":"{api}/releases/assets/{id}","browser_download_url":"{download}",
+			"id":{id},"node_id":"A{id}","name":"mod.zip","label":null,"state":"uploaded",
+			"content_type":"application/zip","size":1,"download_count":0,
+			"created_at":"2023-01-01T00:00:00Z","updated_at":"2023-01-01T00:00:00Z"}}]}}"#,
+			draft = published_at == "null",
+		)
+	}
+
+	#[tokio::test]
+	async fn releases_before_the_minimum_date_are_left_out() {
+		// 1.0.1 is an old build tagged again, which would be the newest without the date
+		let releases = [
+			release_json(1, "0.9.0", Some("2023-11-02T10:00:00Z"), false),
+			release_json(2, "1.0.1", Some("2023-12-20T10:00:00Z"), false),
+			release_json(3, "0.9.5", Some("2024-06-01T00:00:00Z"), false),
+			release_json(4, "0.9.8", Some("2024-07-15T18:30:00Z"), false),
+			release_json(5, "0.9.9", None, false),
+		];
+		let server = TestServer::start(vec![
+			("/repos/owner/repo/releases?", TestResponse::ok(format!("[{}]", releases.join(",")))),
+			("/repos/owner/repo/releases/latest", TestResponse::ok(releases[1].clone())),
+		])
+		.await;
+		let mut repository = repository(server.url("/").as_str());
+		let min_release_date = NaiveDate::from_ymd_opt(2024, 6, 1);
+
+		let since = repository.get_latest_version(test_link(), false, min_release_date).await;
+		let any_date = repository.get_latest_version(test_link(), false, None).await;
+
+		assert_eq!(since.unwrap().version, Versioning::new("0.9.8").unwrap());
+		assert_eq!(any_date.unwrap().version, Versioning::new("1.0.1").unwrap());
+		assert_eq!(
+			server.requests(),
+			vec!["GET /repos/owner/repo/releases?", "GET /repos/owner/repo/releases/latest"]
+		);
+	}
+
+	#[tokio::test]
+	async fn pre_releases_are_skipped_unless_allowed() {
+		// 1.3.0 is only marked as a pre-release by GitHub, the others by their version
+		let published_at = Some("2024-06-01T00:00:00Z");
+		let releases = [
+			release_json(1, "1.2.0", published_at, false),
+			release_json(2, "1.2.4", published_at, false),
+			release_json(3, "1.2.5-beta.2", published_at, false),
+			release_json(4, "1.3.0-rc1", published_at, false),
+			release_json(5, "1.3.0", published_at, true),
+		];
+		let server = TestServer::start(vec![
+			("/repos/owner/repo/releases?", TestResponse::ok(format!("[{}]", releases.join(",")))),
+			("/repos/owner/repo/releases/latest", TestResponse::ok(releases[3].clone())),
+		])
+		.await;
+		let mut repository = repository(server.url("/").as_str());
+
+		let stable = repository.get_latest_version(test_link(), false, None).await.unwrap();
+		let allowed = repository.get_latest_version(test_link(), true, None).await.unwrap();
+		let listed = repository.find_newest_listed(&test_link(), false, None).await.unwrap();
+
+		assert_eq!(stable.version, Versioning::new("1.2.4").unwrap());
+		assert_eq!(allowed.version, Versioning::new("1.3.0").unwrap());
+		assert_eq!(listed.map(|(_, version)| version), Some(Versioning::new("1.2.4").unwrap()));
+		// The latest release is named like a pre-release, so the listed ones are picked from
+		assert_eq!(
+			server.requests()[..2],
+			["GET /repos/owner/repo/releases/latest", "GET /repos/owner/repo/releases?"]
+		);
+	}
+
+	#[tokio::test]
+	async fn minimum_date_without_releases_since_is_reported() {
+		let releases = [release_json(1, "1.0.1", Some("2023-12-20T10:00:00Z"), false)];
+		let server = TestServer::start(vec![(
+			"/repos/owner/repo/releases?",
+			TestResponse::ok(format!("[{}]", releases.join(","))),
+		)])
+		.await;
+		let mut repository = repository(server.url("/").as_str());
+		let min_release_date = NaiveDate::from_ymd_opt(2024, 6, 1);
+
+		let err = repository.get_latest_version(test_link(), false, min_release_date).await;
+
+		assert_eq!(
+			err.unwrap_err().to_string(),
+			"Found no releases of owner/repo published since 2024-06-01"
+		);
+	}
+
+	#[tokio::test]
+	async fn server_errors_are_retried() {
+		let server = TestServer::start(vec![(
+			"/repos/owner/repo/releases/latest",
+			github_error(502, "Server Error"),
+		)])
+		.await;
+		let mut repository = repository(server.url("/").as_str());
+		repository.set_retry_policy(RetryPolicy {
+			retries: 1,
+			backoff: Duration::from_millis(1),
+		});
+
+		assert!(repository.get_latest_version(test_link(), false, None).await.is_err());
+
+		assert_eq!(
+			server.requests(),
+			vec!["GET /repos/owner/repo/releases/latest", "GET /repos/owner/repo/releases/latest"]
+		);
+	}
+
+	#[tokio::test]
+	async fn rate_limit_reports_when_it_resets() {
+		let rate = r#"{"limit":60,"used":60,"remaining":0,"reset":4102444800}"#;
+		let server = TestServer::start(vec![
+			(
+				"/repos/owner/repo/releases/latest",
+				github_error(403, "API rate limit exceeded for 127.0.0.1."),
+			),
+			(
+				"/rate_limit",
+				TestResponse::ok(format!(
+					r#"{{"resources":{{"core":{rate},"search":{rate}}},"rate":{rate}}}"#
+				)),
+			),
+		])
+		.await;
+		let mut repository = repository(server.url("/").as_str());
+		repository.set_max_rate_limit_wait(Duration::from_secs(60));
+
+		let err = repository.get_latest_version(test_link(), false, None).await.unwrap_err();
+
+		assert_eq!(
+			err.downcast_ref::<RemoteError>(),
+			Some(&RemoteError::RateLimited {
+				reset_at: DateTime::from_timestamp(4102444800, 0).unwrap()
+			})
+		);
+	}
+
+	#[tokio::test]
+	async fn private_repository_assets_are_downloaded_through_the_api() {
+		let server = TestServer::start(vec![
+			(
+				"/repos/owner/repo/releases/latest",
+				TestResponse::ok(release_json(7, "1.2.0", Some("2024-06-01T00:00:00Z"), false)),
+			),
+			(
+				"/repos/owner/repo",
+				TestResponse::ok(
+					r#"{"id":1,"name":"repo","url":"https://api.github.com/repos/owner/repo","private":true}"#,
+				),
+			),
+		])
+		.await;
+		let mut repository = repository(server.url("/").as_str());
+		repository.token = Some("secret".to_string());
+
+		let mod_version = repository.get_latest_version(test_link(), false, None).await.unwrap();
+		repository.get_latest_version(test_link(), false, None).await.unwrap();
+
+		let asset_api_url = "https://api.github.com/repos/owner/repo/releases/assets/7";
+		assert_eq!(mod_version.download_url.as_str(), asset_api_url);
+		let headers = &mod_version.download_headers;
+		assert_eq!(headers[ACCEPT], "application/octet-stream");
+		assert_eq!(headers[AUTHORIZATION], "Bearer secret");
+		assert!(headers[AUTHORIZATION].is_sensitive());
+		// Whether the repository is private is only asked once
+		let lookups = server.requests().into_iter().filter(|r| r == "GET /repos/owner/repo");
+		assert_eq!(lookups.count(), 1);
+	}
+
+	#[tokio::test]
+	async fn public_repository_assets_keep_the_browser_url() {
+		let server = TestServer::start(vec![
+			(
+				"/repos/owner/repo/releases/latest",
+				TestResponse::ok(release_json(7, "1.2.0", Some("2024-06-01T00:00:00Z"), false)),
+			),
+			(
+				"/repos/owner/repo",
+				TestResponse::ok(
+					r#"{"id":1,"name":"repo","url":"https://api.github.com/repos/owner/repo","private":false}"#,
+				),
+			),
+		])
+		.await;
+		let mut repository = repository(server.url("/").as_str());
+		repository.token = Some("secret".to_string());
+
+		let mod_version = repository.get_latest_version(test_link(), false, None).await.unwrap();
+
+		assert_eq!(
+			mod_version.download_url.as_str(),
+			"https://github.com/owner/repo/releases/download/1.2.0/mod.zip"
+		);
+		assert!(mod_version.download_headers.is_empty());
+	}
+
+	#[tokio::test]
+	async fn repository_without_releases_is_reported() {
+		let server = TestServer::start(vec![
+			("/repos/owner/repo/releases/latest", github_error(404, "Not Found")),
+			(
+				"/repos/owner/repo",
+				TestResponse::ok(
+					r#"{"id":1,"name":"repo","url":"https://api.github.com/repos/owner/repo"}"#,
+				),
+			),
+		])
+		.await;
+		let mut repository = repository(server.url("/").as_str());
+
+		let err = repository.get_latest_version(test_link(), false, None).await.unwrap_err();
+
+		assert_eq!(err.to_string(), "No releases found for owner/repo");
+	}
+
+	#[tokio::test]
+	async fn network_errors_are_not_reported_as_missing() {
+		let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+		let base_uri = format!("http://{}/", listener.local_addr().unwrap());
+		drop(listener);
+		let mut repository = repository(&base_uri);
+
+		let err = repository.get_latest_version(test_link(), false, None).await.unwrap_err();
+
+		assert_eq!(err.to_string(), "Failed to reach GitHub for owner/repo");
+	}
 }
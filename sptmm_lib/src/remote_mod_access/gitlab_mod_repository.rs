@@ -0,0 +1,355 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use reqwest::{Client, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use versions::Versioning;
+use winnow::combinator::opt;
+use winnow::error::{ContextError, ErrMode};
+use winnow::token::take_until;
+use winnow::{PResult, Parser};
+
+use crate::remote_mod_access::github_mod_repository::parse_version;
+use crate::remote_mod_access::ModDownloadVersion;
+use crate::version_constraint::VersionConstraint;
+
+pub const GITLAB_DOMAIN: &str = "https://gitlab.com";
+const GITLAB_API: &str = "https://gitlab.com/api/v4";
+/// The most releases GitLab lists per page.
+const RELEASES_PER_PAGE: u32 = 100;
+
+/// A GitLab project, whose release assets are picked with the same pattern and filter as GitHub's.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GitLabLink {
+	/// The full project path, which may include subgroups such as `group/subgroup/project`.
+	project: String,
+	asset_pattern: String,
+	asset_filter: Option<String>,
+}
+
+impl GitLabLink {
+	pub fn parse<S: AsRef<str>>(
+		url: S,
+		asset_pattern: String,
+		asset_filter: Option<String>,
+	) -> Result<Self> {
+		let project = validate_url(url.as_ref()).map_err(|_| anyhow!("Failed to parse"))?;
+		Ok(Self {
+			project,
+			asset_pattern,
+			asset_filter,
+		})
+	}
+
+	pub fn starts_with_host<S: AsRef<str>>(url: &S) -> bool {
+		url.as_ref().starts_with(GITLAB_DOMAIN)
+	}
+
+	/// The project name, the last segment of its path.
+	pub fn get_repo(&self) -> &str {
+		self.project.rsplit('/').next().unwrap_or(&self.project)
+	}
+
+	pub fn get_releases_page(&self) -> String {
+		format!("{GITLAB_DOMAIN}/{}/-/releases", self.project)
+	}
+}
+
+#[derive(Deserialize, Debug)]
+struct GitLabRelease {
+	name: Option<String>,
+	tag_name: String,
+	released_at: DateTime<Utc>,
+	assets: GitLabAssets,
+}
+
+#[derive(Deserialize, Debug)]
+struct GitLabAssets {
+	#[serde(default)]
+	links: Vec<GitLabAssetLink>,
+}
+
+#[derive(Deserialize, Debug)]
+struct GitLabAssetLink {
+	name: String,
+	url: Url,
+	direct_asset_url: Option<Url>,
+}
+
+impl GitLabRelease {
+	/// GitLab releases don't need a name, in which case the tag names the release.
+	fn get_name(&self) -> &str {
+		self.name.as_deref().unwrap_or(&self.tag_name)
+	}
+}
+
+pub struct GitLabModRepository {
+	reqwest: Client,
+	api_url: String,
+}
+
+impl GitLabModRepository {
+	pub fn new(reqwest: Client) -> Self {
+		Self {
+			reqwest,
+			api_url: GITLAB_API.to_string(),
+		}
+	}
+
+	pub async fn get_latest_version(&self, gl_mod: GitLabLink) -> Result<ModDownloadVersion> {
+		let url = format!("{}/releases/permalink/latest", self.project_url(&gl_mod));
+		let response = self.reqwest.get(url).send().await.with_context(|| {
+			format!("Failed to reach GitLab for {}", gl_mod.project)
+		})?;
+		if response.status() == StatusCode::NOT_FOUND {
+			// GitLab answers 404 both for a missing project and one without releases
+			return Err(anyhow!(
+				"No releases found for {}, or the project is missing or private",
+				gl_mod.project
+			));
+		}
+		let release: GitLabRelease = serde_json::from_slice(&response.error_for_status()?.bytes().await?)?;
+
+		let version = parse_version(release.get_name())
+			.ok()
+			.flatten()
+			.context("Failed to parse version")?;
+		Self::to_download_version(gl_mod, release, version)
+	}
+
+	pub async fn get_version(
+		&self,
+		gl_mod: GitLabLink,
+		version: &VersionConstraint,
+		version_filter: Option<&str>,
+	) -> Result<Option<ModDownloadVersion>> {
+		let releases = self.get_all_releases(&gl_mod).await?;
+
+		let Some(version) = version.as_exact() else {
+			let candidates = releases.into_iter().filter_map(|release| {
+				if version_filter.is_some_and(|filter| release.get_name().contains(filter)) {
+					return None;
+				}
+				let release_version = parse_version(release.get_name()).ok().flatten()?;
+				Some((release, release_version))
+			});
+			return match version.select_highest(candidates) {
+				Some((release, release_version)) => {
+					Ok(Some(Self::to_download_version(gl_mod, release, release_version)?))
+				}
+				None => Ok(None),
+			};
+		};
+
+		let version_name = version.to_string();
+		let mut versions: Vec<_> = releases
+			.into_iter()
+			.filter(|release| {
+				let name = release.get_name();
+				name.contains(&version_name) && !version_filter.is_some_and(|filter| name.contains(filter))
+			})
+			.collect();
+
+		let release = match versions.len() {
+			0 => return Ok(None),
+			1 => versions.pop().context("Failed to get version")?,
+			_ => {
+				return Err(anyhow!(
+					"Found too many versions: {}",
+					versions
+						.iter()
+						.map(GitLabRelease::get_name)
+						.collect::<Vec<_>>()
+						.join(", ")
+				))
+			}
+		};
+
+		Ok(Some(Self::to_download_version(gl_mod, release, version.clone())?))
+	}
+
+	/// Lists every release of the project, following GitLab's pages until `X-Next-Page` is empty.
+	async fn get_all_releases(&self, gl_mod: &GitLabLink) -> Result<Vec<GitLabRelease>> {
+		let url = format!("{}/releases", self.project_url(gl_mod));
+		let mut releases = Vec::new();
+		let mut page_number = "1".to_string();
+		loop {
+			let per_page = RELEASES_PER_PAGE.to_string();
+			let response = self
+				.reqwest
+				.get(&url)
+				.query(&[("per_page", per_page.as_str()), ("page", page_number.as_str())])
+				.send()
+				.await
+				.with_context(|| format!("Failed to reach GitLab for {}", gl_mod.project))?
+				.error_for_status()?;
+			let next_page = response
+				.headers()
+				.get("X-Next-Page")
+				.and_then(|next_page| next_page.to_str().ok())
+				.filter(|next_page| !next_page.is_empty())
+				.map(str::to_string);
+			let page: Vec<GitLabRelease> = serde_json::from_slice(&response.bytes().await?)?;
+			releases.extend(page);
+			match next_page {
+				Some(next_page) => page_number = next_page,
+				None => return Ok(releases),
+			}
+		}
+	}
+
+	fn project_url(&self, gl_mod: &GitLabLink) -> String {
+		format!("{}/projects/{}", self.api_url, gl_mod.project.replace('/', "%2F"))
+	}
+
+	fn to_download_version(
+		gl_mod: GitLabLink,
+		release: GitLabRelease,
+		version: Versioning,
+	) -> Result<ModDownloadVersion> {
+		let asset = release
+			.assets
+			.links
+			.into_iter()
+			.find(|asset| {
+				asset.name.contains(&gl_mod.asset_pattern)
+					&& !gl_mod
+						.asset_filter
+						.as_ref()
+						.is_some_and(|filter| asset.name.contains(filter))
+			})
+			.with_context(|| {
+				format!(
+					"Failed to find asset from pattern: {}, and filter: {:?}",
+					&gl_mod.asset_pattern, &gl_mod.asset_filter
+				)
+			})?;
+		Ok(ModDownloadVersion {
+			title: gl_mod.get_repo().to_string(),
+			file_name: asset.name,
+			download_url: asset.direct_asset_url.unwrap_or(asset.url),
+			additional_parts: Vec::new(),
+			download_headers: HeaderMap::new(),
+			version,
+			uploaded_at: release.released_at,
+		})
+	}
+}
+
+/// Reads the project path from a project url, ignoring GitLab's `/-/` pages such as its releases.
+fn validate_url(input: &str) -> PResult<String> {
+	let (remainder, _) = "https://gitlab.com/".parse_peek(input)?;
+	let (_, project) = opt(take_until(0.., "/-/")).parse_peek(remainder)?;
+	let project = project.unwrap_or(remainder).trim_end_matches('/');
+	// A project lives in at least one namespace
+	if project.split('/').filter(|segment| !segment.is_empty()).count() < 2 {
+		return Err(ErrMode::Backtrack(ContextError::new()));
+	}
+	Ok(project.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::test_server::{TestResponse, TestServer};
+
+	#[test]
+	fn short_gitlab_url_should_parse() {
+		let result = validate_url("https://gitlab.com/owner/some-mod").unwrap();
+		assert_eq!(result, "owner/some-mod");
+	}
+
+	#[test]
+	fn longer_gitlab_url_should_parse() {
+		let result = validate_url("https://gitlab.com/group/subgroup/some-mod/-/releases/v1.2.3").unwrap();
+		assert_eq!(result, "group/subgroup/some-mod");
+	}
+
+	#[test]
+	fn incorrect_gitlab_url_should_not_parse() {
+		assert!(validate_url("https://gitlab.com/owner").is_err());
+		assert!(validate_url("https://github.com/owner/some-mod").is_err());
+	}
+
+	#[tokio::test]
+	async fn latest_release_picks_asset_by_pattern_and_filter() {
+		let release = r#"{
+			"name": "Some Mod v1.2.3",
+			"tag_name": "v1.2.3",
+			"released_at": "2024-06-11T19:06:55Z",
+			"assets": { "links": [
+				{ "name": "some-mod-sources.zip", "url": "https://gitlab.com/a/sources.zip", "direct_asset_url": null },
+				{ "name": "some-mod.zip", "url": "https://gitlab.com/a/mod.zip", "direct_asset_url": "https://gitlab.com/a/direct/mod.zip" }
+			] }
+		}"#;
+		let server = TestServer::start(vec![(
+			"/projects/owner%2Fsome-mod/releases/permalink/latest",
+			TestResponse::ok(release).with_header("Content-Type", "application/json"),
+		)])
+		.await;
+		let repository = GitLabModRepository {
+			reqwest: Client::new(),
+			api_url: server.url("").to_string().trim_end_matches('/').to_string(),
+		};
+		let link = GitLabLink::parse(
+			"https://gitlab.com/owner/some-mod",
+			"some-mod".to_string(),
+			Some("sources".to_string()),
+		)
+		.unwrap();
+
+		let mod_version = repository.get_latest_version(link).await.unwrap();
+
+		assert_eq!(mod_version.title, "some-mod");
+		assert_eq!(mod_version.file_name, "some-mod.zip");
+		assert_eq!(mod_version.download_url.as_str(), "https://gitlab.com/a/direct/mod.zip");
+		assert_eq!(mod_version.version, Versioning::new("1.2.3").unwrap());
+	}
+
+	#[tokio::test]
+	async fn version_range_picks_the_highest_release_in_it() {
+		let release = |version: &str| {
+			format!(
+				r#"{{
+					"name": "Some Mod v{version}",
+					"tag_name": "v{version}",
+					"released_at": "2024-06-11T19:06:55Z",
+					"assets": {{ "links": [
+						{{ "name": "some-mod-{version}.zip", "url": "https://gitlab.com/a/{version}.zip", "direct_asset_url": null }}
+					] }}
+				}}"#
+			)
+		};
+		// The release in the range is only listed on the second page
+		let first_page = format!("[{}, {}]", release("2.1.0"), release("2.0.0"));
+		let second_page = format!("[{}, {}]", release("1.4.2"), release("1.1.0"));
+		let server = TestServer::start(vec![
+			(
+				"/projects/owner%2Fsome-mod/releases?per_page=100&page=1",
+				TestResponse::ok(first_page).with_header("X-Next-Page", "2"),
+			),
+			(
+				"/projects/owner%2Fsome-mod/releases?per_page=100&page=2",
+				TestResponse::ok(second_page).with_header("X-Next-Page", ""),
+			),
+		])
+		.await;
+		let repository = GitLabModRepository {
+			reqwest: Client::new(),
+			api_url: server.url("").to_string().trim_end_matches('/').to_string(),
+		};
+		let link =
+			GitLabLink::parse("https://gitlab.com/owner/some-mod", "some-mod".to_string(), None)
+				.unwrap();
+
+		let constraint = VersionConstraint::parse(">=1.2.0, <2.0.0").unwrap();
+		let mod_version = repository.get_version(link.clone(), &constraint, None).await.unwrap();
+		let outside = VersionConstraint::parse("^3.0").unwrap();
+		let missing = repository.get_version(link, &outside, None).await.unwrap();
+
+		let mod_version = mod_version.unwrap();
+		assert_eq!(mod_version.version, Versioning::new("1.4.2").unwrap());
+		assert_eq!(mod_version.file_name, "some-mod-1.4.2.zip");
+		assert!(missing.is_none());
+	}
+}
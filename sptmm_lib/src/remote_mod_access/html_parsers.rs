@@ -4,19 +4,20 @@ use once_cell::sync::Lazy;
 use reqwest::Url;
 use scraper::{CaseSensitivity, Element, Html, Selector};
 use scraper::selector::CssLocalName;
+use serde::{Deserialize, Serialize};
 use versions::Versioning;
 use winnow::prelude::*;
 use winnow::PResult;
 use winnow::stream::AsChar;
 use winnow::token::take_till;
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub(super) struct SptMod {
 	pub title: String,
 	pub versions: Vec<SptModVersion>,
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub(super) struct SptModVersion {
 	pub version: Versioning,
 	pub download_url: Url,
@@ -34,7 +35,9 @@ static VERSIONS_CSS: Lazy<CssLocalName> = Lazy::new(|| CssLocalName::from("versi
 static CONTENT_TITLE_CSS: Lazy<CssLocalName> = Lazy::new(|| CssLocalName::from("contentTitle"));
 static URL_CSS: Lazy<CssLocalName> = Lazy::new(|| CssLocalName::from("externalURL"));
 
-pub fn spt_parse_mod_page(document: &str) -> Result<SptMod> {
+/// Reads the title and versions of a hub page, using `fallback_title` when the title markup
+/// isn't found so a changed title alone doesn't stop the versions from being read.
+pub fn spt_parse_mod_page(document: &str, fallback_title: &str) -> Result<SptMod> {
 	let html = Html::parse_document(document);
 	let title = html
 		.select(&H1_SELECTOR)
@@ -44,7 +47,7 @@ pub fn spt_parse_mod_page(document: &str) -> Result<SptMod> {
 				.find(|n| n.attr("itemprop").is_some_and(|x| x == "name"))
 		})
 		.and_then(|e| e.text().next())
-		.context("Found no title")?;
+		.unwrap_or(fallback_title);
 
 	let versions = html
 		.select(&DIV_SELECTOR)
@@ -171,7 +174,7 @@ mod tests {
 			.unwrap()
 			.read_to_string(&mut buffer)
 			.unwrap();
-		let vec = spt_parse_mod_page(&buffer).unwrap();
+		let vec = spt_parse_mod_page(&buffer, "Fallback").unwrap();
 		assert_eq!(vec.title, "Better Keys Updated".to_string());
 		println!("{:?}", vec.versions);
 		for element in &vec.versions {
@@ -179,4 +182,20 @@ mod tests {
 		}
 		assert_eq!(vec.versions.len(), 7);
 	}
+
+	#[test]
+	fn renamed_title_falls_back_and_keeps_the_versions() {
+		let mut buffer = String::new();
+		File::open("test_data/spt_versions.html")
+			.unwrap()
+			.read_to_string(&mut buffer)
+			.unwrap();
+		let renamed = buffer.replace(r#"<h1 class="contentTitle">"#, r#"<h1 class="pageTitle">"#);
+		assert_ne!(renamed, buffer);
+
+		let spt_mod = spt_parse_mod_page(&renamed, "Fallback Title").unwrap();
+
+		assert_eq!(spt_mod.title, "Fallback Title");
+		assert_eq!(spt_mod.versions.len(), 7);
+	}
 }
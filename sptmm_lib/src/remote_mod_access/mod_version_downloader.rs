@@ -1,27 +1,48 @@
-use crate::remote_mod_access::ModDownloadVersion;
-use crate::shared_traits::{ModName, ModVersion, ModVersionDownload};
-use anyhow::Result;
+use crate::remote_mod_access::bandwidth_limiter::BandwidthLimiter;
+use crate::remote_mod_access::retry::is_transient_reqwest;
+use crate::remote_mod_access::{ModDownloadVersion, RetryPolicy};
+use crate::shared_traits::{DownloadStream, ModName, ModVersion, ModVersionDownload};
+use anyhow::{anyhow, Result};
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
-use reqwest::Client;
+use reqwest::header::{CONTENT_LENGTH, RANGE};
+use reqwest::{Client, Response, StatusCode, Url};
 use std::cmp::Ordering;
+use std::time::Duration;
 use versions::Versioning;
 
 #[derive(Debug)]
 pub struct ModVersionDownloader {
 	mod_version: ModDownloadVersion,
 	reqwest: Client,
+	max_bandwidth: Option<u64>,
+	timeout: Option<Duration>,
+	retry_policy: RetryPolicy,
 }
 
 impl ModVersionDownload for ModVersionDownloader {
-	async fn download(&self) -> Result<Bytes> {
-		Ok(self
-			.reqwest
-			.get(self.mod_version.download_url.clone())
-			.send()
-			.await?
-			.bytes()
-			.await?)
+	type Stream<'a> = PartsStream<'a>;
+
+	/// Opens the first part, the others are opened once the part before them has been read.
+	async fn download(&self) -> Result<PartsStream<'_>> {
+		let mut urls: Vec<_> = self.get_urls().collect();
+		urls.reverse();
+		let first_url = urls.pop().expect("A download always has its first part");
+		let response = self.retry_policy.retry_transient(|| self.open_part(first_url, 0)).await?;
+		let total_size = if urls.is_empty() {
+			response.content_length()
+		} else {
+			// The parts after the first are only asked for their size, not downloaded yet
+			self.get_download_size().await.ok().flatten()
+		};
+		Ok(PartsStream {
+			downloader: self,
+			remaining_urls: urls,
+			current: Some((first_url, response, 0)),
+			limiter: self.max_bandwidth.map(BandwidthLimiter::new),
+			retries: 0,
+			total_size,
+		})
 	}
 
 	fn get_file_name(&self) -> &str {
@@ -31,6 +52,10 @@ impl ModVersionDownload for ModVersionDownloader {
 	fn get_upload_date(&self) -> DateTime<Utc> {
 		self.mod_version.uploaded_at
 	}
+
+	fn get_download_url(&self) -> &str {
+		self.mod_version.download_url.as_str()
+	}
 }
 
 impl ModVersionDownloader {
@@ -38,10 +63,84 @@ impl ModVersionDownloader {
 		Self {
 			mod_version,
 			reqwest: reqwest.clone(),
+			max_bandwidth: None,
+			timeout: None,
+			retry_policy: RetryPolicy::default(),
+		}
+	}
+
+	/// Caps the download speed in bytes per second across all parts, `None` downloads at full speed.
+	pub(super) fn with_max_bandwidth(mut self, max_bandwidth: Option<u64>) -> Self {
+		self.max_bandwidth = max_bandwidth;
+		self
+	}
+
+	/// Limits how long each part may take to download, `None` keeps the client's timeout.
+	pub(super) fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+		self.timeout = timeout;
+		self
+	}
+
+	pub(super) fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+		self.retry_policy = retry_policy;
+		self
+	}
+
+	/// Requests a part from `offset` on, which a server has to support ranges for past 0.
+	async fn open_part(&self, url: &Url, offset: u64) -> Result<Response> {
+		let mut request = self
+			.reqwest
+			.get(url.clone())
+			.headers(self.mod_version.download_headers.clone());
+		if let Some(timeout) = self.timeout {
+			request = request.timeout(timeout);
 		}
+		if offset > 0 {
+			request = request.header(RANGE, format!("bytes={offset}-"));
+		}
+		let response = request.send().await?.error_for_status()?;
+		if offset > 0 && response.status() != StatusCode::PARTIAL_CONTENT {
+			return Err(anyhow!("The connection to {url} dropped and the server can't resume it"));
+		}
+		Ok(response)
+	}
+
+	/// Sums the `Content-Length` of every part using HEAD requests, without downloading anything.
+	/// Returns `None` when a part doesn't report its size.
+	pub(super) async fn get_download_size(&self) -> Result<Option<u64>> {
+		let mut total_size = 0;
+		for url in self.get_urls() {
+			let response = self
+				.reqwest
+				.head(url.clone())
+				.headers(self.mod_version.download_headers.clone())
+				.send()
+				.await?
+				.error_for_status()?;
+			let Some(size) = content_length(&response) else {
+				return Ok(None);
+			};
+			total_size += size;
+		}
+		Ok(Some(total_size))
+	}
+
+	fn get_urls(&self) -> impl Iterator<Item = &Url> {
+		std::iter::once(&self.mod_version.download_url).chain(&self.mod_version.additional_parts)
 	}
 }
 
+/// Reads the header directly, as the body of a HEAD response is always empty.
+fn content_length(response: &Response) -> Option<u64> {
+	response
+		.headers()
+		.get(CONTENT_LENGTH)?
+		.to_str()
+		.ok()?
+		.parse()
+		.ok()
+}
+
 impl ModName for ModVersionDownloader {
 	fn get_name(&self) -> &str {
 		self.mod_version.get_name()
@@ -61,3 +160,197 @@ impl ModVersion for ModVersionDownloader {
 		self.mod_version.get_order(mod_version)
 	}
 }
+
+/// The parts of a download read one after the other, as one archive. A connection that drops or
+/// times out partway through a part is resumed where it stopped, as the retry policy allows.
+#[derive(Debug)]
+pub struct PartsStream<'a> {
+	downloader: &'a ModVersionDownloader,
+	/// The parts still to open, the next one last.
+	remaining_urls: Vec<&'a Url>,
+	/// The part being read with how many of its bytes were read so far.
+	current: Option<(&'a Url, Response, u64)>,
+	/// Shared by every part and retry, so resuming counts toward the same limit.
+	limiter: Option<BandwidthLimiter>,
+	/// How many times reading was resumed, across every part.
+	retries: u32,
+	total_size: Option<u64>,
+}
+
+impl DownloadStream for PartsStream<'_> {
+	fn get_total_size(&self) -> Option<u64> {
+		self.total_size
+	}
+
+	async fn chunk(&mut self) -> Result<Option<Bytes>> {
+		let downloader = self.downloader;
+		let retry_policy = downloader.retry_policy;
+		loop {
+			let Some((url, response, read)) = &mut self.current else {
+				let Some(url) = self.remaining_urls.pop() else {
+					return Ok(None);
+				};
+				let response = retry_policy.retry_transient(|| downloader.open_part(url, 0)).await?;
+				self.current = Some((url, response, 0));
+				continue;
+			};
+			match response.chunk().await {
+				Ok(Some(chunk)) => {
+					*read += chunk.len() as u64;
+					if let Some(limiter) = self.limiter.as_mut() {
+						limiter.consume(chunk.len()).await;
+					}
+					return Ok(Some(chunk));
+				}
+				Ok(None) => self.current = None,
+				Err(err) if is_transient_reqwest(&err) && self.retries < retry_policy.retries => {
+					tokio::time::sleep(retry_policy.get_backoff(self.retries)).await;
+					self.retries += 1;
+					let (url, offset) = (*url, *read);
+					*response =
+						retry_policy.retry_transient(|| downloader.open_part(url, offset)).await?;
+				}
+				Err(err) => return Err(err.into()),
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use reqwest::header::HeaderMap;
+
+	use super::*;
+	use crate::test_server::{TestResponse, TestServer};
+
+	#[tokio::test]
+	async fn download_size_sums_head_responses() {
+		let server = TestServer::start(vec![
+			("/mod.zip.001", TestResponse::ok(vec![0; 1500])),
+			("/mod.zip.002", TestResponse::ok(vec![0; 700])),
+		])
+		.await;
+		let mod_version = ModDownloadVersion {
+			title: "Split Mod".to_string(),
+			file_name: "mod.zip".to_string(),
+			download_url: server.url("/mod.zip.001"),
+			additional_parts: vec![server.url("/mod.zip.002")],
+			download_headers: HeaderMap::new(),
+			uploaded_at: Utc::now(),
+			version: Versioning::new("1.0.0").unwrap(),
+		};
+
+		let downloader = ModVersionDownloader::new(mod_version, &Client::new());
+		let size = downloader.get_download_size().await.unwrap();
+
+		assert_eq!(size, Some(2200));
+		assert_eq!(server.requests(), vec!["HEAD /mod.zip.001", "HEAD /mod.zip.002"]);
+	}
+
+	#[tokio::test]
+	async fn capped_download_takes_at_least_size_over_bandwidth() {
+		let server = TestServer::start(vec![("/mod.zip", TestResponse::ok(vec![7; 2000]))]).await;
+		let mod_version = ModDownloadVersion {
+			title: "Slow Mod".to_string(),
+			file_name: "mod.zip".to_string(),
+			download_url: server.url("/mod.zip"),
+			additional_parts: Vec::new(),
+			download_headers: HeaderMap::new(),
+			uploaded_at: Utc::now(),
+			version: Versioning::new("1.0.0").unwrap(),
+		};
+
+		let downloader =
+			ModVersionDownloader::new(mod_version, &Client::new()).with_max_bandwidth(Some(4000));
+		let started = std::time::Instant::now();
+		let data = downloader.download().await.unwrap().read_all().await.unwrap();
+
+		assert_eq!(data.len(), 2000);
+		assert!(started.elapsed() >= std::time::Duration::from_millis(500));
+	}
+
+	#[tokio::test]
+	async fn mod_timeout_fails_slow_download_the_default_allows() {
+		let server = TestServer::start(vec![(
+			"/mod.zip",
+			TestResponse::ok(vec![7; 100]).with_delay(Duration::from_millis(500)),
+		)])
+		.await;
+		let mod_version = || ModDownloadVersion {
+			title: "Mirrored Mod".to_string(),
+			file_name: "mod.zip".to_string(),
+			download_url: server.url("/mod.zip"),
+			additional_parts: Vec::new(),
+			download_headers: HeaderMap::new(),
+			uploaded_at: Utc::now(),
+			version: Versioning::new("1.0.0").unwrap(),
+		};
+
+		let data = ModVersionDownloader::new(mod_version(), &Client::new())
+			.download()
+			.await
+			.unwrap()
+			.read_all()
+			.await
+			.unwrap();
+		let err = ModVersionDownloader::new(mod_version(), &Client::new())
+			.with_timeout(Some(Duration::from_millis(100)))
+			.download()
+			.await
+			.unwrap_err();
+
+		assert_eq!(data.len(), 100);
+		assert!(err.downcast_ref::<reqwest::Error>().is_some_and(reqwest::Error::is_timeout), "{err}");
+	}
+
+	#[tokio::test]
+	async fn split_parts_are_joined_in_order() {
+		let server = TestServer::start(vec![
+			("/mod.zip.001", TestResponse::ok(&b"PK\x03\x04first"[..])),
+			("/mod.zip.002", TestResponse::ok(&b"second"[..])),
+		])
+		.await;
+		let mod_version = ModDownloadVersion {
+			title: "Split Mod".to_string(),
+			file_name: "mod.zip".to_string(),
+			download_url: server.url("/mod.zip.001"),
+			additional_parts: vec![server.url("/mod.zip.002")],
+			download_headers: HeaderMap::new(),
+			uploaded_at: Utc::now(),
+			version: Versioning::new("1.0.0").unwrap(),
+		};
+
+		let downloader = ModVersionDownloader::new(mod_version, &Client::new());
+		let mut stream = downloader.download().await.unwrap();
+		let joined = stream.read_all().await.unwrap();
+
+		assert_eq!(joined.as_ref(), b"PK\x03\x04firstsecond");
+		assert_eq!(stream.get_total_size(), Some(15));
+	}
+
+	#[tokio::test]
+	async fn download_headers_are_sent_with_every_part() {
+		let server = TestServer::start(vec![(
+			"/repos/owner/repo/releases/assets/1",
+			TestResponse::ok(vec![3; 10]).with_required_header("Accept", "application/octet-stream"),
+		)])
+		.await;
+		let mut download_headers = HeaderMap::new();
+		download_headers.insert("accept", "application/octet-stream".parse().unwrap());
+		let mod_version = ModDownloadVersion {
+			title: "Private Mod".to_string(),
+			file_name: "mod.zip".to_string(),
+			download_url: server.url("/repos/owner/repo/releases/assets/1"),
+			additional_parts: Vec::new(),
+			download_headers,
+			uploaded_at: Utc::now(),
+			version: Versioning::new("1.0.0").unwrap(),
+		};
+
+		let downloader = ModVersionDownloader::new(mod_version, &Client::new());
+		let mut stream = downloader.download().await.unwrap();
+
+		assert_eq!(stream.get_total_size(), Some(10));
+		assert_eq!(stream.read_all().await.unwrap().len(), 10);
+	}
+}
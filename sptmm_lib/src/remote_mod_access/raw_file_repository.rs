@@ -0,0 +1,130 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use versions::Versioning;
+
+use crate::remote_mod_access::ModDownloadVersion;
+
+pub const RAW_GITHUB_DOMAIN: &str = "https://raw.githubusercontent.com";
+pub const GIST_DOMAIN: &str = "https://gist.github.com";
+pub const RAW_GIST_DOMAIN: &str = "https://gist.githubusercontent.com";
+
+/// A single file downloaded as is, such as a config published as a raw GitHub file or a gist.
+/// Raw files have no releases, so the version always comes from the configuration.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RawFileLink {
+	url: Url,
+	file_name: String,
+}
+
+impl RawFileLink {
+	pub fn parse<S: AsRef<str>>(url: S) -> Result<Self> {
+		let mut url = Url::parse(url.as_ref())?;
+		let segments: Vec<String> = url
+			.path_segments()
+			.map(|segments| segments.filter(|s| !s.is_empty()).map(str::to_string).collect())
+			.unwrap_or_default();
+		let is_gist_page =
+			url.as_str().starts_with(GIST_DOMAIN) && !segments.iter().any(|s| s == "raw");
+		let file_name = if is_gist_page {
+			// A gist page such as gist.github.com/<user>/<id> serves its file below /raw
+			let id = segments.get(1).context("Found no gist id in the url")?.clone();
+			url.path_segments_mut()
+				.map_err(|_| anyhow!("Failed to parse the gist url"))?
+				.pop_if_empty()
+				.push("raw");
+			id
+		} else {
+			segments.last().context("Found no file name in the url")?.clone()
+		};
+		Ok(Self { url, file_name })
+	}
+
+	pub fn starts_with_host<S: AsRef<str>>(url: &S) -> bool {
+		[RAW_GITHUB_DOMAIN, GIST_DOMAIN, RAW_GIST_DOMAIN]
+			.iter()
+			.any(|domain| url.as_ref().starts_with(domain))
+	}
+
+	pub fn get_url(&self) -> &Url {
+		&self.url
+	}
+
+	pub fn get_file_name(&self) -> &str {
+		&self.file_name
+	}
+
+	pub fn to_download_version(&self, version: &Versioning) -> ModDownloadVersion {
+		ModDownloadVersion {
+			title: self.file_name.clone(),
+			file_name: self.file_name.clone(),
+			download_url: self.url.clone(),
+			additional_parts: Vec::new(),
+			download_headers: HeaderMap::new(),
+			// Raw files don't report when they were published
+			uploaded_at: DateTime::<Utc>::UNIX_EPOCH,
+			version: version.clone(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::remote_mod_access::mod_version_downloader::ModVersionDownloader;
+	use crate::shared_traits::{DownloadStream, ModVersionDownload};
+	use crate::test_server::{TestResponse, TestServer};
+	use reqwest::Client;
+
+	#[test]
+	fn raw_github_url_keeps_file_name() {
+		let link = RawFileLink::parse(
+			"https://raw.githubusercontent.com/owner/tweaks/main/config/sain.json",
+		)
+		.unwrap();
+
+		assert_eq!(link.get_file_name(), "sain.json");
+		assert_eq!(
+			link.get_url().as_str(),
+			"https://raw.githubusercontent.com/owner/tweaks/main/config/sain.json"
+		);
+	}
+
+	#[test]
+	fn gist_page_downloads_raw_content() {
+		let link = RawFileLink::parse("https://gist.github.com/owner/0123abcd").unwrap();
+
+		assert_eq!(link.get_file_name(), "0123abcd");
+		assert_eq!(link.get_url().as_str(), "https://gist.github.com/owner/0123abcd/raw");
+	}
+
+	#[test]
+	fn raw_gist_url_keeps_file_name() {
+		let link = RawFileLink::parse(
+			"https://gist.githubusercontent.com/owner/0123abcd/raw/4567/settings.cfg",
+		)
+		.unwrap();
+
+		assert_eq!(link.get_file_name(), "settings.cfg");
+	}
+
+	#[tokio::test]
+	async fn raw_file_downloads_single_file() {
+		let server = TestServer::start(vec![("/sain.json", TestResponse::ok("{\"enabled\":true}"))]).await;
+		let link = RawFileLink::parse(
+			"https://raw.githubusercontent.com/owner/tweaks/main/config/sain.json",
+		)
+		.unwrap();
+		let mut mod_version = link.to_download_version(&Versioning::new("1.0.0").unwrap());
+		mod_version.download_url = server.url("/sain.json");
+
+		let downloader = ModVersionDownloader::new(mod_version, &Client::new());
+		let data = downloader.download().await.unwrap().read_all().await.unwrap();
+
+		assert_eq!(data.as_ref(), b"{\"enabled\":true}");
+		assert_eq!(downloader.get_file_name(), "sain.json");
+		assert_eq!(server.requests(), vec!["GET /sain.json"]);
+	}
+}
@@ -0,0 +1,26 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+
+use crate::remote_mod_access::github_mod_repository::GITHUB_TOKEN_VARIABLE;
+
+/// Failures of a mod host that callers may want to act on, rather than only report.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteError {
+	/// GitHub's rate limit was spent and won't allow requests again until `reset_at`.
+	RateLimited { reset_at: DateTime<Utc> },
+}
+
+impl Display for RemoteError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			RemoteError::RateLimited { reset_at } => write!(
+				f,
+				"GitHub's rate limit was reached until {}, set {GITHUB_TOKEN_VARIABLE} to a personal access token to raise it",
+				reset_at.format("%H:%M:%S UTC")
+			),
+		}
+	}
+}
+
+impl std::error::Error for RemoteError {}
@@ -0,0 +1,144 @@
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::Result;
+
+pub const DEFAULT_DOWNLOAD_RETRIES: u32 = 3;
+pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// How often a failed request is tried again, waiting twice as long before each new attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+	pub retries: u32,
+	pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+	fn default() -> Self {
+		Self {
+			retries: DEFAULT_DOWNLOAD_RETRIES,
+			backoff: DEFAULT_RETRY_BACKOFF,
+		}
+	}
+}
+
+impl RetryPolicy {
+	/// Runs `operation` until it succeeds or every retry failed, returning the last error.
+	pub async fn retry<T, Fut: Future<Output = Result<T>>>(
+		&self,
+		operation: impl FnMut() -> Fut,
+	) -> Result<T> {
+		self.retry_if(operation, |_| true).await
+	}
+
+	/// [`RetryPolicy::retry`] for network requests, giving up right away on errors that trying
+	/// again won't fix, such as a 404.
+	pub async fn retry_transient<T, Fut: Future<Output = Result<T>>>(
+		&self,
+		operation: impl FnMut() -> Fut,
+	) -> Result<T> {
+		self.retry_if(operation, is_transient).await
+	}
+
+	async fn retry_if<T, Fut: Future<Output = Result<T>>>(
+		&self,
+		mut operation: impl FnMut() -> Fut,
+		should_retry: impl Fn(&anyhow::Error) -> bool,
+	) -> Result<T> {
+		let mut backoffs = self.backoffs();
+		loop {
+			match operation().await {
+				Err(err) if should_retry(&err) => match backoffs.next() {
+					Some(backoff) => tokio::time::sleep(backoff).await,
+					None => return Err(err),
+				},
+				result => return result,
+			}
+		}
+	}
+
+	/// How long to wait before each retry, for requests that need to retry by hand.
+	pub(crate) fn backoffs(&self) -> impl Iterator<Item = Duration> {
+		let policy = *self;
+		(0..self.retries).map(move |retry| policy.get_backoff(retry))
+	}
+
+	/// How long to wait before the retry numbered from 0.
+	pub(crate) fn get_backoff(&self, retry: u32) -> Duration {
+		self.backoff.saturating_mul(2u32.checked_pow(retry).unwrap_or(u32::MAX))
+	}
+}
+
+/// Whether a request failed in a way that can pass on its own: the connection failed, dropped
+/// or timed out, or the server answered with a 5xx. Client errors are never retried.
+pub(crate) fn is_transient(err: &anyhow::Error) -> bool {
+	err.chain()
+		.filter_map(|cause| cause.downcast_ref::<reqwest::Error>())
+		.any(is_transient_reqwest)
+}
+
+pub(crate) fn is_transient_reqwest(err: &reqwest::Error) -> bool {
+	match err.status() {
+		Some(status) => status.is_server_error(),
+		None => err.is_connect() || err.is_timeout() || err.is_body(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::atomic::{AtomicU32, Ordering};
+
+	use anyhow::anyhow;
+
+	use super::*;
+	use crate::test_server::{TestResponse, TestServer};
+
+	#[tokio::test(start_paused = true)]
+	async fn retries_until_the_attempts_run_out() {
+		let policy = RetryPolicy {
+			retries: 2,
+			backoff: Duration::from_millis(100),
+		};
+		let attempts = AtomicU32::new(0);
+		let started = tokio::time::Instant::now();
+
+		let result: Result<()> = policy
+			.retry(|| async {
+				attempts.fetch_add(1, Ordering::Relaxed);
+				Err(anyhow!("mirror unavailable"))
+			})
+			.await;
+
+		assert!(result.is_err());
+		assert_eq!(attempts.into_inner(), 3);
+		assert_eq!(started.elapsed(), Duration::from_millis(300));
+	}
+
+	#[tokio::test]
+	async fn transient_retries_server_errors_but_not_client_errors() {
+		let server = TestServer::start(vec![
+			("/unavailable", TestResponse::status(503)),
+			("/missing", TestResponse::status(404)),
+		])
+		.await;
+		let policy = RetryPolicy {
+			retries: 2,
+			backoff: Duration::from_millis(1),
+		};
+		let get = |path: &str| {
+			let url = server.url(path);
+			move || {
+				let url = url.clone();
+				async move { Ok(reqwest::get(url).await?.error_for_status()?) }
+			}
+		};
+
+		assert!(policy.retry_transient(get("/unavailable")).await.is_err());
+		assert!(policy.retry_transient(get("/missing")).await.is_err());
+
+		assert_eq!(
+			server.requests(),
+			vec!["GET /unavailable", "GET /unavailable", "GET /unavailable", "GET /missing"]
+		);
+	}
+}
@@ -1,9 +1,14 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::time::Duration;
 
-use anyhow::{anyhow, Context, Result};
-use reqwest::Client;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Client, Response, StatusCode};
 use serde::{Deserialize, Serialize};
-use tokio::time::{Instant, sleep_until};
+use once_cell::sync::Lazy;
+use tokio::time::{Instant, sleep, sleep_until};
 use url::Url;
 use versions::Versioning;
 use winnow::ascii::digit1;
@@ -11,13 +16,69 @@ use winnow::combinator::{eof, opt, repeat};
 use winnow::prelude::*;
 use winnow::token::{take, take_until};
 
-use crate::remote_mod_access::{html_parsers, ModDownloadVersion};
-use crate::remote_mod_access::html_parsers::SptMod;
+use crate::remote_mod_access::{html_parsers, is_selectable, ModDownloadVersion};
+use crate::remote_mod_access::html_parsers::{SptMod, SptModVersion};
+use crate::remote_mod_access::retry::{is_transient_reqwest, RetryPolicy};
+use crate::version_constraint::VersionConstraint;
+use crate::version_scheme::VersionScheme;
 
 pub struct SptModRepository {
 	client: Client,
 	last_request: Instant,
 	request_delay: Duration,
+	validators: HashMap<String, PageValidators>,
+	/// Validators of versions fetched since, kept until the newest of them is cached.
+	unconfirmed_validators: HashMap<String, PageValidators>,
+	validators_path: Option<PathBuf>,
+	cache_ttl: Duration,
+	retry_policy: RetryPolicy,
+}
+
+/// How long the versions parsed from the hub are reused while it answers that they're unchanged,
+/// before they're fetched and parsed again in full.
+pub const DEFAULT_HUB_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The `ETag` and `Last-Modified` the hub last sent for a mod's versions, replayed in a conditional
+/// request to learn whether anything changed without parsing the versions again.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct PageValidators {
+	url: Url,
+	etag: Option<String>,
+	last_modified: Option<String>,
+	/// The versions parsed from the response, reused when the hub answers 304 Not Modified.
+	#[serde(default)]
+	spt_mod: Option<SptMod>,
+	/// When the versions were last fetched in full.
+	#[serde(default)]
+	fetched_at: Option<DateTime<Utc>>,
+}
+
+impl PageValidators {
+	fn from_response(response: &Response) -> Option<Self> {
+		let header = |name| {
+			response
+				.headers()
+				.get(name)
+				.and_then(|value| value.to_str().ok())
+				.map(str::to_string)
+		};
+		let validators = Self {
+			url: response.url().clone(),
+			etag: header(ETAG),
+			last_modified: header(LAST_MODIFIED),
+			spt_mod: None,
+			fetched_at: None,
+		};
+		(validators.etag.is_some() || validators.last_modified.is_some()).then_some(validators)
+	}
+
+	/// Whether the versions were fetched in full within the TTL, older ones are fetched again even
+	/// when the hub would answer that they're unchanged.
+	fn is_fresh(&self, now: DateTime<Utc>, cache_ttl: Duration) -> bool {
+		self.fetched_at.is_some_and(|fetched_at| {
+			now.signed_duration_since(fetched_at).to_std().unwrap_or_default() < cache_ttl
+		})
+	}
 }
 
 #[derive(Clone)]
@@ -31,22 +92,86 @@ enum DownloadLink{
 
 impl SptModRepository {
 	pub fn new(client: Client) -> Self {
-		Self { client, last_request: Instant::now(), request_delay: Duration::from_millis(1000) }
+		Self {
+			client,
+			last_request: Instant::now(),
+			request_delay: Duration::from_millis(1000),
+			validators: HashMap::new(),
+			unconfirmed_validators: HashMap::new(),
+			validators_path: None,
+			cache_ttl: DEFAULT_HUB_CACHE_TTL,
+			retry_policy: RetryPolicy::default(),
+		}
+	}
+
+	/// Keeps the validators of each mod's versions in `path` between runs.
+	pub fn with_validators_file(mut self, path: PathBuf) -> Self {
+		self.validators = std::fs::read(&path)
+			.ok()
+			.and_then(|data| serde_json::from_slice(&data).ok())
+			.unwrap_or_default();
+		self.validators_path = Some(path);
+		self
+	}
+
+	/// Fetches and parses the versions again in full once they're older than `cache_ttl`, even when
+	/// the hub answers that they're unchanged.
+	pub fn set_cache_ttl(&mut self, cache_ttl: Duration) {
+		self.cache_ttl = cache_ttl;
+	}
+
+	/// Retries page fetches that failed to connect, timed out or got a 5xx as the policy says.
+	pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+		self.retry_policy = retry_policy;
+	}
+
+	/// Asks the hub whether the mod's versions changed since their newest was cached and picks the
+	/// newest of them again when they didn't. `None` when they were never cached, the hub doesn't
+	/// support conditional requests or asking failed, for the versions to be fetched in full.
+	pub async fn get_unchanged_newest(
+		&mut self,
+		spt_link: &SptLink,
+		version_scheme: &VersionScheme,
+		allow_prerelease: bool,
+	) -> Option<Versioning> {
+		let now = Utc::now();
+		let validators = self.validators.get(spt_link.get_slug());
+		let validators = validators.filter(|v| v.is_fresh(now, self.cache_ttl)).cloned()?;
+		let response = self.send_spt_request(&validators.url, Some(&validators)).await.ok()?;
+		if response.status() != StatusCode::NOT_MODIFIED {
+			return None;
+		}
+		let versions = validators.spt_mod?.versions;
+		let mod_version = select_newest(versions, version_scheme, allow_prerelease).ok()?;
+		Some(mod_version.version)
 	}
 
-	pub async fn get_latest_version(&mut self, spt_link: SptLink) -> Result<ModDownloadVersion> {
+	/// Keeps the validators of the mod's versions once their newest is cached, so the hub answering
+	/// that they're unchanged can't stand for a version that never got cached.
+	pub fn confirm_cached(&mut self, spt_link: &SptLink) -> Result<()> {
+		let Some(validators) = self.unconfirmed_validators.remove(spt_link.get_slug()) else {
+			return Ok(());
+		};
+		self.validators.insert(spt_link.get_slug().to_string(), validators);
+		self.save_validators()
+	}
+
+	pub async fn get_latest_version(
+		&mut self,
+		spt_link: SptLink,
+		version_scheme: &VersionScheme,
+		allow_prerelease: bool,
+	) -> Result<ModDownloadVersion> {
 		let spt_mod = self.get_all_versions(spt_link).await?;
-		let mod_version = spt_mod
-			.versions
-			.into_iter()
-			.max_by(|x, x1| x.version.cmp(&x1.version))
-			.context("Found no mods")?;
+		let mod_version = select_newest(spt_mod.versions, version_scheme, allow_prerelease)?;
 
 		let (download_url, file_name) = self.parse_download(mod_version.download_url).await?;
 		
 		Ok(ModDownloadVersion {
 			title: spt_mod.title,
 			download_url,
+			additional_parts: Vec::new(),
+			download_headers: HeaderMap::new(),
 			version: mod_version.version,
 			uploaded_at: mod_version.uploaded_at,
 			file_name,
@@ -56,15 +181,15 @@ impl SptModRepository {
 	pub async fn get_version(
 		&mut self,
 		spt_link: SptLink,
-		version: &Versioning,
+		version: &VersionConstraint,
 	) -> Result<Option<ModDownloadVersion>> {
 		let spt_mod = self.get_all_versions(spt_link).await?;
-		let mod_version = spt_mod
-			.versions
-			.into_iter()
-			.find(|mv| &mv.version == version);
+		let candidates = spt_mod.versions.into_iter().map(|mv| {
+			let version = mv.version.clone();
+			(mv, version)
+		});
 
-		let Some(mod_version) = mod_version else {
+		let Some((mod_version, _)) = version.select_highest(candidates) else {
 			return Ok(None);
 		};
 
@@ -75,6 +200,8 @@ impl SptModRepository {
 			version: mod_version.version,
 			uploaded_at: mod_version.uploaded_at,
 			download_url,
+			additional_parts: Vec::new(),
+			download_headers: HeaderMap::new(),
 			file_name,
 		}))
 	}
@@ -99,9 +226,11 @@ impl SptModRepository {
 
 	async fn get_all_versions(&mut self, spt_link: SptLink) -> Result<SptMod> {
 		let url = spt_link.get_versions_page()?;
-		let html = self.get_spt_html(&url).await?;
-		let mod_versions = html_parsers::spt_parse_mod_page(&html).map_err(|err| anyhow!(err))?;
-		Ok(mod_versions)
+		let fallback_title = spt_link.get_slug_title();
+		self.get_versions_listing(&spt_link, &url, |html| {
+			html_parsers::spt_parse_mod_page(html, &fallback_title).map_err(|err| anyhow!(err))
+		})
+		.await
 	}
 
 	async fn get_mod_dl_link(&mut self, external_url: Url) -> Result<Url> {
@@ -109,44 +238,181 @@ impl SptModRepository {
 		html_parsers::spt_parse_download(&html)
 	}
 
+	/// Fetches and parses a mod's versions, remembering them with the validators the hub sent until
+	/// [`SptModRepository::confirm_cached`]. While the confirmed ones are fresh the hub is asked
+	/// whether they changed, reusing them without parsing on a 304.
+	async fn get_versions_listing(
+		&mut self,
+		spt_link: &SptLink,
+		url: &Url,
+		parse: impl FnOnce(&str) -> Result<SptMod>,
+	) -> Result<SptMod> {
+		let now = Utc::now();
+		let cached = self
+			.validators
+			.get(spt_link.get_slug())
+			.filter(|validators| &validators.url == url && validators.spt_mod.is_some())
+			.filter(|validators| validators.is_fresh(now, self.cache_ttl))
+			.cloned();
+		let response = self.send_spt_request(url, cached.as_ref()).await?;
+		if response.status() == StatusCode::NOT_MODIFIED {
+			if let Some(spt_mod) = cached.and_then(|validators| validators.spt_mod) {
+				return Ok(spt_mod);
+			}
+		}
+
+		let response = response.error_for_status()?;
+		let validators = PageValidators::from_response(&response);
+		let spt_mod = parse(&response.text().await?)?;
+		if let Some(mut validators) = validators {
+			validators.spt_mod = Some(spt_mod.clone());
+			validators.fetched_at = Some(now);
+			self.unconfirmed_validators.insert(spt_link.get_slug().to_string(), validators);
+		}
+		Ok(spt_mod)
+	}
+
+	fn save_validators(&self) -> Result<()> {
+		let Some(path) = &self.validators_path else {
+			return Ok(());
+		};
+		if let Some(parent) = path.parent() {
+			std::fs::create_dir_all(parent)?;
+		}
+		std::fs::write(path, serde_json::to_vec(&self.validators)?)?;
+		Ok(())
+	}
+
 	async fn get_spt_html(&mut self, url: &Url) -> Result<String>{
-		sleep_until( self.last_request + self.request_delay).await;
-		self.last_request = Instant::now();
 		let html = self
-			.client
-			.get(url.clone())
-			.send()
+			.send_spt_request(url, None)
 			.await?
 			.error_for_status()?
 			.text()
 			.await?;
 		Ok(html)
 	}
+
+	/// Sends the request once the hub's request delay passed, retrying transient failures. Each
+	/// retry waits for its backoff and the request delay both, and a 5xx that's still there after
+	/// the last retry is returned for the caller to turn into an error.
+	async fn send_spt_request(
+		&mut self,
+		url: &Url,
+		validators: Option<&PageValidators>,
+	) -> Result<Response> {
+		let mut backoffs = self.retry_policy.backoffs();
+		loop {
+			sleep_until( self.last_request + self.request_delay).await;
+			self.last_request = Instant::now();
+			let mut request = self.client.get(url.clone());
+			if let Some(validators) = validators {
+				if let Some(etag) = &validators.etag {
+					request = request.header(IF_NONE_MATCH, etag);
+				}
+				if let Some(last_modified) = &validators.last_modified {
+					request = request.header(IF_MODIFIED_SINCE, last_modified);
+				}
+			}
+			let result = request.send().await;
+			let is_transient = match &result {
+				Ok(response) => response.status().is_server_error(),
+				Err(err) => is_transient_reqwest(err),
+			};
+			match backoffs.next().filter(|_| is_transient) {
+				Some(backoff) => sleep(backoff).await,
+				None => return Ok(result?),
+			}
+		}
+	}
 	async fn get_html(&self, url: &Url) -> Result<String>{
-		let html = self
-			.client
-			.get(url.clone())
-			.send()
-			.await?
-			.error_for_status()?
-			.text()
-			.await?;
-		Ok(html)
+		self.retry_policy
+			.retry_transient(|| async {
+				let html = self
+					.client
+					.get(url.clone())
+					.send()
+					.await?
+					.error_for_status()?
+					.text()
+					.await?;
+				Ok(html)
+			})
+			.await
 	}
 }
 
+/// The newest version by the scheme, leaving out pre-releases unless allowed.
+fn select_newest(
+	versions: Vec<SptModVersion>,
+	version_scheme: &VersionScheme,
+	allow_prerelease: bool,
+) -> Result<SptModVersion> {
+	let has_versions = !versions.is_empty();
+	let versions = versions
+		.into_iter()
+		.filter(|mod_version| is_selectable(&mod_version.version, false, allow_prerelease));
+	let Some(mod_version) = version_scheme.newest(versions, |mod_version| &mod_version.version)
+	else {
+		if has_versions {
+			return Err(anyhow!("Found only pre-releases, set allow_prerelease to install them"));
+		}
+		return Err(anyhow!("Found no mods"));
+	};
+	Ok(mod_version)
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct SptLink {
 	link: Url,
 }
 
 pub const SPT_DOMAIN: &str = "https://hub.sp-tarkov.com";
+/// Lists more hub hosts to accept besides [`SPT_DOMAIN`], separated by commas, such as a mirror.
+pub const SPT_HUB_HOSTS_VARIABLE: &str = "SPTMM_HUB_HOSTS";
+const SPT_FILE_PATH: &str = "/files/file/";
+
+static HUB_HOSTS: Lazy<Vec<String>> =
+	Lazy::new(|| parse_hub_hosts(std::env::var(SPT_HUB_HOSTS_VARIABLE).ok().as_deref()));
+
+/// The accepted hub hosts, [`SPT_DOMAIN`] first, then those configured in [`SPT_HUB_HOSTS_VARIABLE`].
+pub fn hub_hosts() -> &'static [String] {
+	&HUB_HOSTS
+}
+
+/// Reads hosts such as `hub-mirror.example.com, https://other.example.com/`, defaulting to https.
+fn parse_hub_hosts(configured: Option<&str>) -> Vec<String> {
+	let mut hosts = vec![SPT_DOMAIN.to_string()];
+	let configured = configured
+		.unwrap_or_default()
+		.split(',')
+		.map(|host| host.trim().trim_end_matches('/'))
+		.filter(|host| !host.is_empty())
+		.map(|host| {
+			if host.starts_with("https://") || host.starts_with("http://") {
+				host.to_string()
+			} else {
+				format!("https://{host}")
+			}
+		});
+	for host in configured {
+		if !hosts.contains(&host) {
+			hosts.push(host);
+		}
+	}
+	hosts
+}
 
 impl SptLink {
 	pub fn parse<S: AsRef<str>>(url: S) -> Result<Self> {
+		Self::parse_with_hosts(url, hub_hosts())
+	}
+
+	/// Parses a link to any of the hub `hosts`, keeping its host so the hub is reached through it.
+	pub fn parse_with_hosts<S: AsRef<str>>(url: S, hosts: &[String]) -> Result<Self> {
 		let url = url.as_ref();
 		// TODO: Improve validation to return file name
-		validate_url(url).map_err(|_| anyhow!("Failed to parse SP Tarkov url"))?;
+		validate_url(url, hosts).map_err(|_| anyhow!("Failed to parse SP Tarkov url"))?;
 		let link = if !url.ends_with('/') {
 			Url::parse(&format!("{}/", url))?
 		}
@@ -160,14 +426,61 @@ impl SptLink {
 		let url = self.link.join("#versions")?;
 		Ok(url)
 	}
+
 	
 	pub fn starts_with_host<S: AsRef<str>>(url: S) -> bool{
-		url.as_ref().starts_with(SPT_DOMAIN)
+		Self::starts_with_any_host(url, hub_hosts())
+	}
+
+	pub fn starts_with_any_host<S: AsRef<str>>(url: S, hosts: &[String]) -> bool {
+		hosts.iter().any(|host| {
+			url.as_ref()
+				.strip_prefix(host.as_str())
+				.is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+		})
+	}
+
+	pub fn get_page(&self) -> &Url {
+		&self.link
+	}
+
+	/// The last segment of the hub link, such as `1963-better-keys-updated`.
+	pub fn get_slug(&self) -> &str {
+		self.link
+			.path_segments()
+			.and_then(|mut segments| segments.rfind(|s| !s.is_empty()))
+			.unwrap_or_default()
+	}
+
+	/// A title made from the slug without its file id, `1963-better-keys-updated` becoming
+	/// `Better Keys Updated`, for when the page's own title can't be read.
+	pub fn get_slug_title(&self) -> String {
+		let slug = self.get_slug();
+		let name = match slug.split_once('-') {
+			Some((file_id, name)) if file_id.chars().all(|c| c.is_ascii_digit()) => name,
+			_ => slug,
+		};
+		name.split('-')
+			.filter(|word| !word.is_empty())
+			.map(|word| {
+				let mut chars = word.chars();
+				match chars.next() {
+					Some(first) => first.to_uppercase().chain(chars).collect(),
+					None => String::new(),
+				}
+			})
+			.collect::<Vec<_>>()
+			.join(" ")
 	}
 }
 
-fn validate_url(input: &str) -> PResult<()> {
-	let (remainder, _) = "https://hub.sp-tarkov.com/files/file/".parse_peek(input)?;
+fn validate_url(input: &str, hosts: &[String]) -> PResult<()> {
+	let mut host = hosts
+		.iter()
+		.find(|host| input.starts_with(host.as_str()))
+		.map_or(SPT_DOMAIN, String::as_str);
+	let (remainder, _) = host.parse_peek(input)?;
+	let (remainder, _) = { SPT_FILE_PATH }.parse_peek(remainder)?;
 	let (remainder, numbers) = take_until(1.., "-").parse_peek(remainder)?;
 	digit1.and_then(take(numbers.len())).parse_peek(numbers)?;
 	let (remainder, taken) = opt((take_until(1.., "/"), take(1usize))).parse_peek(remainder)?;
@@ -211,6 +524,7 @@ fn filename_parser<'a>(input: &mut &'a str) -> PResult<&'a str> {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::test_server::{TestResponse, TestServer};
 
 	#[tokio::test]
 	#[ignore]
@@ -223,21 +537,262 @@ mod tests {
 		assert!(!result.versions.is_empty());
 	}
 
+	fn repository() -> SptModRepository {
+		let mut repository = SptModRepository::new(Client::new());
+		repository.request_delay = Duration::ZERO;
+		repository
+	}
+
+	fn versions_page() -> String {
+		std::fs::read_to_string("test_data/spt_versions.html").unwrap()
+	}
+
+	fn parse_versions_page(html: &str) -> Result<SptMod> {
+		html_parsers::spt_parse_mod_page(html, "Fallback").map_err(|err| anyhow!(err))
+	}
+
+	#[tokio::test]
+	async fn not_modified_versions_are_unchanged_once_their_newest_is_cached() {
+		let page = "/files/file/1963-better-keys-updated/";
+		let server = TestServer::start(vec![(
+			page,
+			TestResponse::ok(versions_page()).with_header("ETag", "\"v1\""),
+		)])
+		.await;
+		let spt_link =
+			SptLink::parse("https://hub.sp-tarkov.com/files/file/1963-better-keys-updated/")
+				.unwrap();
+		let mut repository = repository();
+		let url = server.url(page);
+		let semver = VersionScheme::Semver;
+
+		repository
+			.get_versions_listing(&spt_link, &url, parse_versions_page)
+			.await
+			.unwrap();
+		// Until the newest version is cached, a 304 can't stand for it
+		assert!(repository.validators.is_empty());
+		assert_eq!(repository.get_unchanged_newest(&spt_link, &semver, false).await, None);
+		repository.confirm_cached(&spt_link).unwrap();
+		let validators = repository.validators.get(spt_link.get_slug()).unwrap();
+		assert_eq!(validators.etag.as_deref(), Some("\"v1\""));
+
+		let server = TestServer::start(vec![(page, TestResponse::status(304))]).await;
+		repository.validators.get_mut(spt_link.get_slug()).unwrap().url = server.url(page);
+		let newest = repository.get_unchanged_newest(&spt_link, &semver, false).await;
+		assert_eq!(newest, Some(Versioning::new("1.2.3").unwrap()));
+		assert_eq!(server.requests(), vec![format!("GET {page}")]);
+	}
+
+	fn confirmed_validators(url: Url, versions: &[&str]) -> PageValidators {
+		let download_url = url.clone();
+		let versions = versions.iter().map(|version| SptModVersion {
+			version: Versioning::new(version).unwrap(),
+			download_url: download_url.clone(),
+			uploaded_at: DateTime::default(),
+		});
+		PageValidators {
+			url,
+			etag: Some("\"v1\"".to_string()),
+			last_modified: None,
+			spt_mod: Some(SptMod {
+				title: "Better Keys Updated".to_string(),
+				versions: versions.collect(),
+			}),
+			fetched_at: Some(Utc::now()),
+		}
+	}
+
+	#[tokio::test]
+	async fn not_modified_versions_are_picked_from_again_by_the_current_settings() {
+		let server = TestServer::start(vec![("/unchanged", TestResponse::status(304))]).await;
+		let spt_link =
+			SptLink::parse("https://hub.sp-tarkov.com/files/file/1963-better-keys-updated/")
+				.unwrap();
+		let mut repository = repository();
+		let validators = confirmed_validators(server.url("/unchanged"), &["1.2.3", "1.3.0-beta"]);
+		repository.validators.insert(spt_link.get_slug().to_string(), validators);
+		let semver = VersionScheme::Semver;
+
+		let stable = repository.get_unchanged_newest(&spt_link, &semver, false).await;
+		let prerelease = repository.get_unchanged_newest(&spt_link, &semver, true).await;
+
+		assert_eq!(stable, Some(Versioning::new("1.2.3").unwrap()));
+		assert_eq!(prerelease, Some(Versioning::new("1.3.0-beta").unwrap()));
+	}
+
+	#[tokio::test]
+	async fn pre_releases_are_skipped_unless_allowed() {
+		let download_page =
+			std::fs::read_to_string("test_data/spt_external_download.html").unwrap();
+		let page = "/files/file/1963-better-keys-updated/";
+		let server = TestServer::start(vec![
+			(page, TestResponse::status(304)),
+			("/download", TestResponse::ok(download_page)),
+		])
+		.await;
+		let host = server.url("/").as_str().trim_end_matches('/').to_string();
+		let spt_link = SptLink::parse_with_hosts(server.url(page), &[host]).unwrap();
+		let mut validators = confirmed_validators(
+			spt_link.get_versions_page().unwrap(),
+			&["1.2.0", "1.2.4", "1.2.5-beta.2", "1.3.0-rc1"],
+		);
+		for mod_version in &mut validators.spt_mod.as_mut().unwrap().versions {
+			mod_version.download_url = server.url("/download");
+		}
+		let mut repository = repository();
+		repository.validators.insert(spt_link.get_slug().to_string(), validators);
+		let semver = VersionScheme::Semver;
+
+		let stable = repository.get_latest_version(spt_link.clone(), &semver, false).await.unwrap();
+		let allowed = repository.get_latest_version(spt_link, &semver, true).await.unwrap();
+
+		assert_eq!(stable.version, Versioning::new("1.2.4").unwrap());
+		assert_eq!(allowed.version, Versioning::new("1.3.0-rc1").unwrap());
+	}
+
+	#[tokio::test]
+	async fn failing_to_ask_whether_versions_changed_fetches_them_in_full() {
+		let server = TestServer::start(vec![("/unavailable", TestResponse::status(503))]).await;
+		let spt_link =
+			SptLink::parse("https://hub.sp-tarkov.com/files/file/1963-better-keys-updated/")
+				.unwrap();
+		let mut repository = repository();
+		repository.set_retry_policy(RetryPolicy {
+			retries: 0,
+			..RetryPolicy::default()
+		});
+		let validators = confirmed_validators(server.url("/unavailable"), &["1.2.3"]);
+		repository.validators.insert(spt_link.get_slug().to_string(), validators.clone());
+		let semver = VersionScheme::Semver;
+
+		assert_eq!(repository.get_unchanged_newest(&spt_link, &semver, false).await, None);
+
+		// A hub that can't be reached at all is asked in full too
+		let unreachable = Url::parse("http://127.0.0.1:1/unreachable").unwrap();
+		let validators = confirmed_validators(unreachable, &["1.2.3"]);
+		repository.validators.insert(spt_link.get_slug().to_string(), validators);
+		assert_eq!(repository.get_unchanged_newest(&spt_link, &semver, false).await, None);
+	}
+
+	#[tokio::test]
+	async fn not_modified_versions_reuse_the_parsed_versions_until_they_expire() {
+		let spt_link =
+			SptLink::parse("https://hub.sp-tarkov.com/files/file/1963-better-keys-updated/")
+				.unwrap();
+		let server = TestServer::start(vec![
+			(
+				"/fetched",
+				TestResponse::ok(versions_page()).with_header("ETag", "\"v1\""),
+			),
+			(
+				"/unchanged",
+				TestResponse::status(304).with_required_header("If-None-Match", "\"v1\""),
+			),
+		])
+		.await;
+		let (fetched, unchanged) = (server.url("/fetched"), server.url("/unchanged"));
+		let mut repository = repository();
+		let parsed = repository
+			.get_versions_listing(&spt_link, &fetched, parse_versions_page)
+			.await
+			.unwrap();
+		repository.confirm_cached(&spt_link).unwrap();
+
+		repository.validators.get_mut(spt_link.get_slug()).unwrap().url = unchanged.clone();
+		let parse_again = |_: &str| Err(anyhow!("Parsed the versions again"));
+		let reused = repository
+			.get_versions_listing(&spt_link, &unchanged, parse_again)
+			.await
+			.unwrap();
+		assert_eq!(reused, parsed);
+
+		// Expired versions are fetched without validators, which this route refuses
+		repository.set_cache_ttl(Duration::ZERO);
+		let expired = repository
+			.get_versions_listing(&spt_link, &unchanged, parse_versions_page)
+			.await;
+		assert!(expired.is_err());
+		assert_eq!(server.requests(), vec!["GET /fetched", "GET /unchanged", "GET /unchanged"]);
+	}
+
+	#[tokio::test]
+	async fn versions_without_validators_are_refetched() {
+		let spt_link =
+			SptLink::parse("https://hub.sp-tarkov.com/files/file/1963-better-keys-updated/")
+				.unwrap();
+		let semver = VersionScheme::Semver;
+
+		assert_eq!(repository().get_unchanged_newest(&spt_link, &semver, false).await, None);
+	}
+
+	#[tokio::test]
+	async fn server_errors_are_retried_after_the_request_delay() {
+		let server = TestServer::start(vec![("/unavailable", TestResponse::status(503))]).await;
+		let mut repository = repository();
+		repository.request_delay = Duration::from_millis(50);
+		repository.set_retry_policy(RetryPolicy {
+			retries: 2,
+			backoff: Duration::from_millis(1),
+		});
+		let started = Instant::now();
+
+		let result = repository.get_spt_html(&server.url("/unavailable")).await;
+
+		assert!(result.is_err());
+		assert_eq!(server.requests().len(), 3);
+		// Both retries waited out the request delay after the attempt before them
+		assert!(started.elapsed() >= Duration::from_millis(100));
+	}
+
+	#[tokio::test]
+	async fn client_errors_are_not_retried() {
+		let server = TestServer::start(vec![]).await;
+		let mut repository = repository();
+		repository.set_retry_policy(RetryPolicy {
+			retries: 2,
+			backoff: Duration::from_millis(1),
+		});
+
+		let result = repository.get_spt_html(&server.url("/missing")).await;
+
+		assert!(result.is_err());
+		assert_eq!(server.requests(), vec!["GET /missing"]);
+	}
+
+	#[test]
+	fn slug_title_drops_the_file_id() {
+		let spt_mod =
+			SptLink::parse("https://hub.sp-tarkov.com/files/file/1963-better-keys-updated/")
+				.unwrap();
+
+		assert_eq!(spt_mod.get_slug_title(), "Better Keys Updated");
+	}
+
 	#[test]
 	fn url_parses_correctly_with_slash() {
-		let result = validate_url("https://hub.sp-tarkov.com/files/file/1963-better-keys-updated/");
+		let result = validate_url(
+			"https://hub.sp-tarkov.com/files/file/1963-better-keys-updated/",
+			hub_hosts(),
+		);
 		assert!(result.is_ok());
 	}
 
 	#[test]
 	fn url_parses_correctly_without_slash() {
-		let result = validate_url("https://hub.sp-tarkov.com/files/file/1963-better-keys-updated");
+		let result = validate_url(
+			"https://hub.sp-tarkov.com/files/file/1963-better-keys-updated",
+			hub_hosts(),
+		);
 		assert!(result.is_ok());
 	}
 
 	#[test]
 	fn url_parses_incorrectly_with_ext() {
-		let result = validate_url("https://hub.sp-tarkov.com/files/file/1963-better-keys-updated/#versions");
+		let result = validate_url(
+			"https://hub.sp-tarkov.com/files/file/1963-better-keys-updated/#versions",
+			hub_hosts(),
+		);
 		assert!(result.is_err());
 	}
 
@@ -246,4 +801,44 @@ mod tests {
 		let result = get_mod_filename("https://github.com/maxloo2/betterkeys-updated/releases/download/v1.2.3/maxloo2-betterkeys-updated-v1.2.3.zip").unwrap();
 		assert_eq!(result, "maxloo2-betterkeys-updated-v1.2.3.zip".to_string());
 	}
+
+	#[test]
+	fn configured_mirror_host_is_accepted_and_resolved() {
+		let hosts = parse_hub_hosts(Some("hub-mirror.example.com/, http://localhost:8080"));
+		assert_eq!(
+			hosts,
+			vec![
+				SPT_DOMAIN.to_string(),
+				"https://hub-mirror.example.com".to_string(),
+				"http://localhost:8080".to_string(),
+			]
+		);
+
+		let mirror_url = "https://hub-mirror.example.com/files/file/1963-better-keys-updated";
+		assert!(SptLink::starts_with_any_host(mirror_url, &hosts));
+		let spt_link = SptLink::parse_with_hosts(mirror_url, &hosts).unwrap();
+
+		assert_eq!(
+			spt_link.get_page().as_str(),
+			"https://hub-mirror.example.com/files/file/1963-better-keys-updated/"
+		);
+		assert_eq!(
+			spt_link.get_versions_page().unwrap().as_str(),
+			"https://hub-mirror.example.com/files/file/1963-better-keys-updated/#versions"
+		);
+	}
+
+	#[test]
+	fn unconfigured_hosts_are_rejected() {
+		let hosts = parse_hub_hosts(None);
+		let mirror_url = "https://hub-mirror.example.com/files/file/1963-better-keys-updated";
+
+		assert_eq!(hosts, vec![SPT_DOMAIN.to_string()]);
+		assert!(!SptLink::starts_with_any_host(mirror_url, &hosts));
+		assert!(SptLink::parse_with_hosts(mirror_url, &hosts).is_err());
+		assert!(!SptLink::starts_with_any_host(
+			"https://hub.sp-tarkov.com.example.com/files",
+			&hosts
+		));
+	}
 }
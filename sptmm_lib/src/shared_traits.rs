@@ -1,4 +1,5 @@
 use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
 use anyhow::Result;
@@ -32,13 +33,55 @@ fn space_mapper(c: char) -> char {
 }
 
 pub trait ModVersionDownload: ModVersion + Unpin {
+	type Stream<'a>: DownloadStream
+	where
+		Self: 'a;
+
+	/// Starts the download, whose body is read from the stream a chunk at a time.
 	#[allow(async_fn_in_trait)]
-	async fn download(&self) -> Result<Bytes>;
+	async fn download(&self) -> Result<Self::Stream<'_>>;
 	fn get_file_name(&self) -> &str;
 	fn get_upload_date(&self) -> DateTime<Utc>;
+	/// The url the archive is downloaded from, which identifies the same download across mods.
+	fn get_download_url(&self) -> &str;
+}
+
+/// The body of a download, read a chunk at a time so a large mod is never held in memory whole.
+pub trait DownloadStream {
+	/// The size of the whole download in bytes, when the server tells it.
+	fn get_total_size(&self) -> Option<u64>;
+
+	/// The next chunk, `None` once the download is complete.
+	#[allow(async_fn_in_trait)]
+	async fn chunk(&mut self) -> Result<Option<Bytes>>;
+
+	/// Reads the rest of the download into memory.
+	#[allow(async_fn_in_trait)]
+	async fn read_all(&mut self) -> Result<Bytes> {
+		let mut data = Vec::new();
+		while let Some(chunk) = self.chunk().await? {
+			data.extend_from_slice(&chunk);
+		}
+		Ok(Bytes::from(data))
+	}
 }
 
 #[automock]
 pub trait TimeProvider{
 	fn get_current_time(&self) -> DateTime<Utc>;
-}
\ No newline at end of file
+}
+
+/// The file operations used to install mods, so installs can run against files kept in memory.
+pub trait FileSystem {
+	fn create_dir_all(&self, path: &Path) -> std::io::Result<()>;
+	fn write(&self, path: &Path, data: &[u8]) -> std::io::Result<()>;
+	fn read(&self, path: &Path) -> std::io::Result<Vec<u8>>;
+	fn remove_file(&self, path: &Path) -> std::io::Result<()>;
+	/// Removes a folder only when it's empty.
+	fn remove_dir(&self, path: &Path) -> std::io::Result<()>;
+	/// Moves a file, replacing the file at `to` if there is one.
+	fn rename(&self, from: &Path, to: &Path) -> std::io::Result<()>;
+	/// Lists the files directly inside a folder, leaving out its subfolders.
+	fn list_files(&self, path: &Path) -> std::io::Result<Vec<PathBuf>>;
+	fn is_file(&self, path: &Path) -> bool;
+}
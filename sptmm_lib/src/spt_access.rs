@@ -1,163 +1,830 @@
+mod audit;
+mod backup;
+mod backup_contents;
+mod backup_diff;
+mod conflicts;
+mod install_descriptor;
+mod install_index;
+mod integrity;
+mod mod_package;
+mod operation_lock;
+mod restore;
+mod spt_layout;
+mod verify;
+mod worker_pool;
 mod zip_data;
 
-use std::collections::HashMap;
-use std::ffi::{OsStr, OsString};
-use std::io::{BufReader, BufWriter, Read, Write};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsString;
+use std::fmt::{Display, Formatter};
+use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
-use crate::shared_traits::{ModName, TimeProvider};
+use crate::file_access::DiskFileSystem;
+use crate::shared_traits::{FileSystem, ModName, ModVersion, TimeProvider};
+use crate::spt_access::install_descriptor::InstallDescriptor;
 use crate::spt_access::zip_data::ZipData;
+use crate::spt_access::spt_layout::CLIENT_MODS_PATH;
+pub use crate::spt_access::audit::{Lockfile, ModDrift};
+pub use crate::spt_access::backup::{BackupCancellation, BackupOptions, CompressionKind};
+pub use crate::spt_access::backup_contents::BackupEntry;
+pub use crate::spt_access::backup_diff::{BackupChange, BackupDifference};
+pub use crate::spt_access::conflicts::{InstallConflict, ModConflict};
+pub use crate::spt_access::install_index::InstalledMod;
+pub use crate::spt_access::mod_package::{
+	DependencyWarning, ModPackage, SptCompatibility, SptSupport,
+};
+pub use crate::spt_access::restore::{OverwritePolicy, RestoreChange, RestorePreview, RestoreScope};
+pub use crate::spt_access::spt_layout::{SptLayout, INSTALL_PATH_TOKENS, SPT_ROOT_TOKEN};
+pub use crate::spt_access::verify::{FileProblem, ModVerification};
 use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
 use compress_tools::{ArchiveContents, ArchiveIterator, ArchiveIteratorBuilder, Ownership};
 use tokio::fs;
 use std::fs::File;
-use walkdir::WalkDir;
+use tokio::sync::mpsc::UnboundedSender;
 use winnow::combinator::{empty, opt, separated};
 use winnow::prelude::*;
 use winnow::token::take_until;
 use winnow::{dispatch, PResult};
-use zip::write::SimpleFileOptions;
-use zip::{ZipArchive, ZipWriter};
-use crate::path_access::PathAccess;
+use versions::Versioning;
+use glob::Pattern;
+use crate::path_access::{self, PathAccess};
 
 const OLD_SERVER_FILE_NAME: &str = "Aki.Server.exe";
 const SERVER_FILE_NAME: &str = "SPT.Server.exe";
-const BEPINEX_CONFIG_PATH: &str = "BepInEx/config";
-const BEPINEX_CACHE_PATH: &str = "BepInEx/cache";
-const USER_CACHE_PATH: &str = "user/cache";
+const INSTALLED_MARKER_FILE_NAME: &str = ".sptmm-installed";
+/// The SPT core client plugin, which removing all mods must keep.
+pub const DEFAULT_PROTECTED_PLUGIN_FOLDERS: &[&str] = &["spt"];
+/// A comma separated list of server executable names that replaces the defaults.
+pub const SERVER_FILE_NAMES_VAR: &str = "SPTMM_SERVER_FILE_NAMES";
 
-#[derive(Clone)]
+/// The server executable names used to detect the SPT folder, read from [`SERVER_FILE_NAMES_VAR`]
+/// when set and otherwise the current and old server names.
+pub fn server_file_names() -> Vec<String> {
+	let configured = std::env::var(SERVER_FILE_NAMES_VAR).unwrap_or_default();
+	let names: Vec<_> = configured
+		.split(',')
+		.map(str::trim)
+		.filter(|name| !name.is_empty())
+		.map(str::to_string)
+		.collect();
+	if names.is_empty() {
+		return vec![SERVER_FILE_NAME.to_string(), OLD_SERVER_FILE_NAME.to_string()];
+	}
+	names
+}
+
+#[derive(Clone, Debug, PartialEq)]
 enum FileType {
 	Unknown,
 	Client,
 	Server,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
 pub enum InstallTarget {
 	Server,
 	Client,
 }
 
+impl Display for InstallTarget {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			InstallTarget::Server => write!(f, "server"),
+			InstallTarget::Client => write!(f, "client"),
+		}
+	}
+}
+
+impl InstallTarget {
+	pub fn other(self) -> Self {
+		match self {
+			InstallTarget::Server => InstallTarget::Client,
+			InstallTarget::Client => InstallTarget::Server,
+		}
+	}
+}
+
+/// Settings applied to every mod installed with `install_mod`.
+#[derive(Debug, Clone, Default)]
+pub struct InstallOptions {
+	never_install: Vec<Pattern>,
+	skip_bad_entries: bool,
+	no_index: bool,
+	force: bool,
+	keep_empty_dirs: bool,
+	write_threads: usize,
+	strict_hash: bool,
+	allow_core_overwrite: bool,
+}
+
+impl InstallOptions {
+	/// Archive entries matching any of the globs, such as `BepInEx/core/*`, are skipped for every mod.
+	pub fn with_never_install(mut self, globs: &[String]) -> Result<Self> {
+		self.never_install = globs
+			.iter()
+			.map(|glob| Pattern::new(glob).with_context(|| format!("Invalid never_install glob: {glob}")))
+			.collect::<Result<_>>()?;
+		Ok(self)
+	}
+
+	/// Leaves out archive entries that fail to read instead of failing the whole mod, which then only
+	/// fails when no file could be installed.
+	pub fn with_skip_bad_entries(mut self, skip_bad_entries: bool) -> Self {
+		self.skip_bad_entries = skip_bad_entries;
+		self
+	}
+
+	/// Installs without recording the mod in the install index, so it isn't tracked afterwards and
+	/// [`SptAccess::is_same_installed_version`] can't tell that it's already installed.
+	pub fn with_no_index(mut self, no_index: bool) -> Self {
+		self.no_index = no_index;
+		self
+	}
+
+	/// Installs over files that other mods installed instead of failing with an [`InstallConflict`],
+	/// listing them in [`InstallReport::conflicts`].
+	pub fn with_force(mut self, force: bool) -> Self {
+		self.force = force;
+		self
+	}
+
+	/// Leaves the folders that uninstalling or updating a mod empties in place, instead of removing
+	/// them up to the SPT folders such as `BepInEx/plugins`.
+	pub fn with_keep_empty_dirs(mut self, keep_empty_dirs: bool) -> Self {
+		self.keep_empty_dirs = keep_empty_dirs;
+		self
+	}
+
+	/// Writes the files of a mod on up to `write_threads` threads, which speeds up installs on SSDs
+	/// but slows down hard drives. One thread, the default, writes them in archive order.
+	pub fn with_write_threads(mut self, write_threads: usize) -> Self {
+		self.write_threads = write_threads;
+		self
+	}
+
+	/// Also requires every file recorded for a mod to still be in the SPT folder before it counts as
+	/// installed, so mods with deleted files are installed again.
+	pub fn with_strict_hash(mut self, strict_hash: bool) -> Self {
+		self.strict_hash = strict_hash;
+		self
+	}
+
+	/// Lets mods install files into the protected SPT plugin folders, such as `BepInEx/plugins/spt`,
+	/// which otherwise fails the install.
+	pub fn with_allow_core_overwrite(mut self, allow_core_overwrite: bool) -> Self {
+		self.allow_core_overwrite = allow_core_overwrite;
+		self
+	}
+
+	fn is_never_installed(&self, zip_path: &str) -> bool {
+		self.never_install.iter().any(|pattern| pattern.matches(zip_path))
+	}
+}
+
+#[derive(Debug, Default)]
+pub struct InstallReport {
+	pub installed_files: usize,
+	/// The `package.json` manifests of the server mods found in the archive.
+	pub packages: Vec<ModPackage>,
+	/// Archive entries left out because they matched a `never_install` glob.
+	pub skipped_files: Vec<String>,
+	/// Archive entries that failed to read with `skip_bad_entries`, with the error of each.
+	pub bad_entries: Vec<(String, String)>,
+	/// Archive entries left out as their names aren't UTF-8, shown with replacement characters.
+	pub invalid_names: Vec<String>,
+	/// Files installed over those of other mods with `force`.
+	pub conflicts: Vec<ModConflict>,
+}
+
+/// Installs, uninstalls, backups and restores of the same SPT folder run one at a time, the
+/// later one waiting for the running one to finish.
 #[derive(Debug, Clone)]
-pub struct SptAccess<Time: TimeProvider> {
-	server_mods_path: PathBuf,
-	client_mods_path: PathBuf,
-	root_path: PathBuf,
+pub struct SptAccess<Time: TimeProvider, Files: FileSystem = DiskFileSystem> {
+	layout: SptLayout,
 	time: Time,
-	install_index: PathBuf,
+	files: Files,
+	protected_plugin_folders: Vec<String>,
+	install_options: InstallOptions,
 }
 
 impl<Time: TimeProvider> SptAccess<Time> {
+	/// Detects the SPT folder by the server executables in [`server_file_names`].
 	pub async fn init(paths: &PathAccess, time: Time) -> Result<Self> {
+		Self::init_with_server_file_names(paths, time, &server_file_names()).await
+	}
+
+	/// Detects the SPT folder by any of the given server executable names.
+	pub async fn init_with_server_file_names(
+		paths: &PathAccess,
+		time: Time,
+		server_file_names: &[String],
+	) -> Result<Self> {
+		paths.ensure_separate_roots()?;
 		let root_path = paths.spt_root();
-		if !Path::new(&root_path.join(SERVER_FILE_NAME)).exists() && !Path::new(&root_path.join(OLD_SERVER_FILE_NAME)).exists() {
-			return Err(anyhow!("Could not find {SERVER_FILE_NAME} or {OLD_SERVER_FILE_NAME} in the current folder"));
+		if !server_file_names.iter().any(|name| root_path.join(name).exists()) {
+			return Err(anyhow!(
+				"Could not find any of {} in the current folder",
+				server_file_names.join(", ")
+			));
 		}
-		let install_index = root_path.join("install_hash");
+		let layout = SptLayout::new(root_path);
+		let install_index = layout.install_index();
 		if !install_index.is_dir() {
 			fs::create_dir(&install_index).await?;
 		}
-		Ok(Self {
-			server_mods_path: root_path.join("user/mods/"),
-			client_mods_path: root_path.join("BepInEx/plugins/"),
-			root_path: PathBuf::from(root_path),
+		Ok(Self::new(layout, time, DiskFileSystem::default()))
+	}
+}
+
+impl<Time: TimeProvider, Files: FileSystem> SptAccess<Time, Files> {
+	/// Uses the SPT folder as is, without checking for a server executable or an install index.
+	pub fn new(layout: SptLayout, time: Time, files: Files) -> Self {
+		Self {
+			layout,
 			time,
-			install_index,
-		})
+			files,
+			protected_plugin_folders: DEFAULT_PROTECTED_PLUGIN_FOLDERS
+				.iter()
+				.map(|name| name.to_string())
+				.collect(),
+			install_options: InstallOptions::default(),
+		}
+	}
+
+	pub fn layout(&self) -> &SptLayout {
+		&self.layout
+	}
+
+	pub fn with_install_options(mut self, install_options: InstallOptions) -> Self {
+		self.install_options = install_options;
+		self
+	}
+
+	/// Replaces the names of the plugin folders `remove_all_mods` never deletes and mods may only
+	/// install into with [`InstallOptions::with_allow_core_overwrite`], compared case-insensitively.
+	pub fn with_protected_plugin_folders(mut self, protected_plugin_folders: Vec<String>) -> Self {
+		self.protected_plugin_folders = protected_plugin_folders;
+		self
+	}
+
+	fn is_protected_plugin(&self, path: &Path) -> bool {
+		let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+			return false;
+		};
+		self.protected_plugin_folders
+			.iter()
+			.any(|protected| protected.eq_ignore_ascii_case(name))
+	}
+
+	/// Whether an installed path is in one of the protected plugin folders.
+	fn is_core_path(&self, zip_path: &str) -> bool {
+		let mut components = Path::new(zip_path).components();
+		let is_plugins_folder = Path::new(CLIENT_MODS_PATH).components().all(|folder| {
+			components.next().is_some_and(|component| {
+				component.as_os_str().eq_ignore_ascii_case(folder.as_os_str())
+			})
+		});
+		is_plugins_folder
+			&& components
+				.next()
+				.is_some_and(|folder| self.is_protected_plugin(Path::new(folder.as_os_str())))
 	}
-	pub fn install_mod<P: AsRef<Path>, Mod: ModName>(
+
+	pub fn install_mod<P: AsRef<Path>, Mod: ModVersion>(
 		&self,
 		mod_archive_path: P,
 		spt_mod: &Mod,
 		install_target: InstallTarget,
-	) -> Result<()> {
-		let mut map = HashMap::new();
-		let archive_iter = new_file_archive_iter(BufReader::new(File::open(mod_archive_path)?))?;
-
-		let mut buffer = Vec::default();
-		let mut zip_path = String::default();
-		let mut installed_file_counter = 0;
-		for content in archive_iter {
-			match content {
-				ArchiveContents::StartOfEntry(name, _) => {
-					zip_path = name;
+	) -> Result<InstallReport>
+	where
+		Files: Sync,
+	{
+		let _operation = operation_lock::lock_root(self.layout.root());
+		let mod_archive_path = mod_archive_path.as_ref();
+		let descriptor = InstallDescriptor::read(mod_archive_path)?;
+		let mut report = InstallReport::default();
+		// Updating a mod overwrites its own files, so only other mods can conflict
+		let other_mods: Vec<_> = self
+			.list_installed()?
+			.into_iter()
+			.filter(|installed_mod| !installed_mod.is_same_name(spt_mod))
+			.collect();
+		if !other_mods.is_empty() {
+			let paths = self.inspect_archive(mod_archive_path, install_target)?;
+			let conflicts = conflicts::find_conflicts(&paths, &other_mods);
+			if !conflicts.is_empty() && !self.install_options.force {
+				return Err(InstallConflict {
+					mod_name: spt_mod.get_name().to_string(),
+					conflicts,
 				}
-				ArchiveContents::DataChunk(mut data) => buffer.append(&mut data),
-				ArchiveContents::EndOfEntry => {
-					let zip_data = ZipData::new(&buffer, &zip_path);
-					if !zip_data.should_install(&install_target) {
+				.into());
+			}
+			report.conflicts = conflicts;
+		}
+		// Nothing in the SPT folder changes until every file is extracted to the staging folder
+		let staging = self.layout.install_staging().join(spt_mod.to_file_name());
+		let mut map = HashMap::new();
+		let staged = self.stage_mod(
+			mod_archive_path,
+			&descriptor,
+			install_target,
+			&staging,
+			&mut map,
+			&mut report,
+		);
+		if let Err(err) = staged {
+			self.discard_staged(&staging, &map);
+			return Err(err);
+		}
+		self.swap_staged(spt_mod, &staging, &map)?;
+
+		if self.install_options.no_index {
+			return Ok(report);
+		}
+		let installed_mod = InstalledMod {
+			name: spt_mod.get_name().to_string(),
+			version: Some(spt_mod.get_version().to_string()),
+			install_target: Some(install_target),
+			files: map,
+		};
+		let index_path = self.layout.install_index().join(spt_mod.to_file_name());
+		installed_mod.write_to(&self.files, &index_path)?;
+
+		Ok(report)
+	}
+
+	/// Extracts the files of the mod to `staging`, recording the path and hash of each in `map`.
+	fn stage_mod(
+		&self,
+		mod_archive_path: &Path,
+		descriptor: &InstallDescriptor,
+		install_target: InstallTarget,
+		staging: &Path,
+		map: &mut HashMap<String, String>,
+		report: &mut InstallReport,
+	) -> Result<()>
+	where
+		Files: Sync,
+	{
+		let files = &self.files;
+		let threads = self.install_options.write_threads;
+		if threads <= 1 {
+			let write = |zip_path: &str, data: &[u8]| write_file(files, &staging.join(zip_path), data);
+			return self.extract_mod(mod_archive_path, descriptor, install_target, map, report, write);
+		}
+
+		// The archive is read on this thread while the files are written on the others, the channel
+		// holding back the reading when the writes fall behind.
+		let (sender, receiver) = std::sync::mpsc::sync_channel::<(String, Vec<u8>)>(threads);
+		let receiver = Mutex::new(receiver);
+		let failure = Mutex::new(None);
+		let extracted = std::thread::scope(|scope| {
+			for _ in 0..threads {
+				scope.spawn(|| loop {
+					let Ok((zip_path, data)) = receiver.lock().unwrap().recv() else {
+						break;
+					};
+					// The rest is drained without writing, so the reading never waits on a full channel
+					if failure.lock().unwrap().is_some() {
 						continue;
 					}
-					map.insert(
-						zip_data.get_path().to_string(),
-						zip_data.get_hash().to_string(),
-					);
-					self.write_file_to_tarkov(zip_data)?;
-					installed_file_counter += 1;
-					buffer = Vec::default();
-					zip_path = String::default();
+					if let Err(err) = write_file(files, &staging.join(&zip_path), &data) {
+						failure.lock().unwrap().get_or_insert(err);
+					}
+				});
+			}
+			let write = |zip_path: &str, data: &[u8]| {
+				if failure.lock().unwrap().is_some() {
+					return Err(anyhow!("Failed to write the staged files"));
 				}
-				ArchiveContents::Err(err) => {
-					return Err(err.into());
+				sender.send((zip_path.to_string(), data.to_vec()))?;
+				Ok(())
+			};
+			let extracted =
+				self.extract_mod(mod_archive_path, descriptor, install_target, map, report, write);
+			drop(sender);
+			extracted
+		});
+		match failure.into_inner().unwrap() {
+			Some(err) => Err(err),
+			None => extracted,
+		}
+	}
+
+	/// Reads the files of the mod that should be installed, recording the path and hash of each in
+	/// `map` before passing it to `write`.
+	fn extract_mod(
+		&self,
+		mod_archive_path: &Path,
+		descriptor: &InstallDescriptor,
+		install_target: InstallTarget,
+		map: &mut HashMap<String, String>,
+		report: &mut InstallReport,
+		mut write: impl FnMut(&str, &[u8]) -> Result<()>,
+	) -> Result<()> {
+		// An archive can't be read past a failing entry, so skipping one reopens the archive
+		// without the entries already read.
+		let mut read_entries = HashSet::new();
+		let mut entries = 0;
+		let mut core_paths = Vec::new();
+		'archive: loop {
+			let archive_iter = open_cached_archive(mod_archive_path, &read_entries)?;
+			let mut buffer = Vec::default();
+			let mut zip_path = String::default();
+			for content in archive_iter {
+				match content {
+					ArchiveContents::StartOfEntry(name, _) => {
+						zip_path = name;
+						buffer.clear();
+						entries += 1;
+					}
+					ArchiveContents::DataChunk(mut data) => buffer.append(&mut data),
+					ArchiveContents::EndOfEntry => {
+						read_entries.insert(zip_path.clone());
+						if is_invalid_entry_name(&zip_path) {
+							report.invalid_names.push(zip_path.clone());
+							continue;
+						}
+						let mapped_path = descriptor.map_path(&zip_path);
+						let zip_data = ZipData::new(&buffer, &mapped_path);
+						if !zip_data.should_install(&install_target) {
+							continue;
+						}
+						if self.install_options.is_never_installed(zip_data.get_path()) {
+							report.skipped_files.push(zip_data.get_path().to_string());
+							continue;
+						}
+						if !self.install_options.allow_core_overwrite
+							&& self.is_core_path(zip_data.get_path())
+						{
+							core_paths.push(zip_data.get_path().to_string());
+							continue;
+						}
+						map.insert(
+							zip_data.get_path().to_string(),
+							zip_data.get_hash().to_string(),
+						);
+						if is_server_package(zip_data.get_path()) {
+							report.packages.extend(ModPackage::parse(zip_data.get_data()));
+						}
+						write(zip_data.get_path(), zip_data.get_data())?;
+						report.installed_files += 1;
+					}
+					ArchiveContents::Err(err) => {
+						// A failing header leaves the last entry name, which can't be skipped again
+						if !self.install_options.skip_bad_entries || !read_entries.insert(zip_path.clone()) {
+							return Err(err.into());
+						}
+						report.bad_entries.push((zip_path, err.to_string()));
+						continue 'archive;
+					}
 				}
 			}
+			break;
 		}
 
-		if installed_file_counter == 0 {
+		if entries == 0 {
+			return Err(corrupt_archive_error(mod_archive_path));
+		}
+		if !core_paths.is_empty() {
+			return Err(anyhow!(
+				"The mod would overwrite SPT core files, install it with --allow-core-overwrite if \
+				 that's intended:\n  {}",
+				core_paths.join("\n  ")
+			));
+		}
+		if report.installed_files == 0 {
+			// An unknown format reads as a single raw entry, which has no installation path
+			if detect_archive_type(mod_archive_path).is_none() {
+				return Err(unsupported_archive_error(mod_archive_path));
+			}
+			if let Some(other_target) = self.detect_other_target(mod_archive_path, install_target)? {
+				return Err(wrong_target_error(install_target, other_target));
+			}
 			return Err(anyhow!("No files with a structured installation path was found"));
 		}
+		Ok(())
+	}
+
+	/// Removes whatever was extracted to `staging` before the install failed.
+	fn discard_staged(&self, staging: &Path, map: &HashMap<String, String>) {
+		let mut folders = HashSet::new();
+		for file in map.keys() {
+			let path = staging.join(file);
+			if self.files.remove_file(&path).is_ok() {
+				folders.extend(path.parent().map(Path::to_path_buf));
+			}
+		}
+		self.remove_empty_folders(folders);
+	}
 
-		let mod_name = self.install_index.join(spt_mod.to_file_name());
-		let writer = BufWriter::new(File::create(mod_name)?);
-		serde_json::to_writer(writer, &map)?;
+	/// Removes the files the installed version of the mod has that the staged one doesn't, then
+	/// moves the staged files into place.
+	fn swap_staged<Mod: ModName>(
+		&self,
+		spt_mod: &Mod,
+		staging: &Path,
+		map: &HashMap<String, String>,
+	) -> Result<()> {
+		let index_path = self.layout.install_index().join(spt_mod.to_file_name());
+		if self.files.is_file(&index_path) {
+			let installed_mod = InstalledMod::read_from(&self.files, &index_path)?;
+			self.remove_installed_files(&installed_mod, |file| map.contains_key(file))?;
+		}
 
+		let mut staged_folders = HashSet::new();
+		for file in map.keys() {
+			let staged_path = staging.join(file);
+			let path = self.layout.root().join(file);
+			if let Some(parent) = path.parent() {
+				self.files.create_dir_all(parent)?;
+			}
+			self.files
+				.rename(&staged_path, &path)
+				.with_context(|| format!("Failed to move the staged file into place: {file}"))?;
+			staged_folders.extend(staged_path.parent().map(Path::to_path_buf));
+		}
+		self.remove_empty_folders(staged_folders);
 		Ok(())
 	}
 
-	pub fn is_same_installed_version<P: AsRef<Path>, Mod: ModName>(
+	/// The paths a mod archive would install for the target, read without extracting anything.
+	pub fn inspect_archive(
 		&self,
-		mod_archive_path: P,
-		mod_name: &Mod,
+		mod_archive_path: impl AsRef<Path>,
 		install_target: InstallTarget,
-	) -> Result<bool> {
-		let mod_name = self.install_index.join(mod_name.to_file_name());
-		if !mod_name.is_file() {
-			return Ok(false);
+	) -> Result<Vec<String>> {
+		let mod_archive_path = mod_archive_path.as_ref();
+		let descriptor = InstallDescriptor::read(mod_archive_path)?;
+		let entries = compress_tools::list_archive_files_with_encoding(
+			File::open(mod_archive_path)?,
+			decode_entry_name,
+		)?;
+		let folders = listed_folders(&entries);
+		Ok(entries
+			.iter()
+			.filter(|entry| !entry.ends_with('/') && !folders.contains(entry.as_str()))
+			.filter(|entry| !is_invalid_entry_name(entry))
+			.filter_map(|entry| {
+				let mapped_path = descriptor.map_path(entry);
+				let zip_data = ZipData::new(&[], &mapped_path);
+				let is_installed = zip_data.should_install(&install_target)
+					&& !self.install_options.is_never_installed(zip_data.get_path());
+				is_installed.then(|| zip_data.get_path().to_string())
+			})
+			.collect())
+	}
+
+	/// The other target when the archive installs nothing for `install_target` but does for the
+	/// other one, counted by [`Self::inspect_archive`].
+	pub fn detect_other_target(
+		&self,
+		mod_archive_path: impl AsRef<Path>,
+		install_target: InstallTarget,
+	) -> Result<Option<InstallTarget>> {
+		let mod_archive_path = mod_archive_path.as_ref();
+		if !self.inspect_archive(mod_archive_path, install_target)?.is_empty() {
+			return Ok(None);
 		}
-		let map: HashMap<String, String> =
-			serde_json::from_reader(BufReader::new(File::open(mod_name)?))?;
-		
-		let archive_iter = new_file_archive_iter(BufReader::new(File::open(mod_archive_path)?))?;
-
-		let mut buffer = Vec::default();
-		let mut zip_path = String::default();
-		for content in archive_iter {
-			match content {
-				ArchiveContents::StartOfEntry(name, _) => {
-					zip_path = name;
-				}
-				ArchiveContents::DataChunk(mut data) => buffer.append(&mut data),
-				ArchiveContents::EndOfEntry => {
-					let zip_data = ZipData::new(&buffer, &zip_path);
-					if !zip_data.should_install(&install_target) {
-						continue;
-					}
-					if !map
-						.get(zip_data.get_path())
-						.is_some_and(|str| str == zip_data.get_hash())
-					{
-						return Ok(false);
-					}
-					buffer = Vec::default();
-					zip_path = String::default();
+		let other_target = install_target.other();
+		let other_paths = self.inspect_archive(mod_archive_path, other_target)?;
+		Ok((!other_paths.is_empty()).then_some(other_target))
+	}
+
+	/// Finds the paths that an installed mod already installed, such as those from [`Self::inspect_archive`].
+	pub fn find_conflicts(&self, paths: &[String]) -> Result<Vec<ModConflict>> {
+		Ok(conflicts::find_conflicts(paths, &self.list_installed()?))
+	}
+
+	/// Lists every mod recorded in the install index, sorted by name.
+	pub fn list_installed(&self) -> Result<Vec<InstalledMod>> {
+		let mut installed_mods = Vec::new();
+		for path in self.files.list_files(&self.layout.install_index())? {
+			installed_mods.push(InstalledMod::read_from(&self.files, &path)?);
+		}
+		installed_mods.sort_by_key(|installed_mod| installed_mod.name.to_lowercase());
+		Ok(installed_mods)
+	}
+
+	/// Compares the versions and file hashes of the install index to a lockfile, such as one
+	/// distributed with a modpack.
+	pub fn audit(&self, lockfile: &Lockfile) -> Result<Vec<ModDrift>> {
+		Ok(audit::audit(&lockfile.mods, &self.list_installed()?))
+	}
+
+	/// Re-hashes the files of every installed mod on up to `threads` threads and compares them to
+	/// the install index, calling `progress` with the number of mods verified so far.
+	pub fn verify_installed(
+		&self,
+		threads: usize,
+		progress: impl Fn(usize) + Sync,
+	) -> Result<Vec<ModVerification>> {
+		let installed_mods = self.list_installed()?;
+		Ok(verify::verify_mods(self.layout.root(), &installed_mods, threads, progress))
+	}
+
+	/// Removes the files the install index recorded for the mod, the folders left empty by that and
+	/// finally its index entry, returning the removed paths. Files that another installed mod also
+	/// recorded are kept, and files that are already gone are skipped.
+	pub fn uninstall_mod<Mod: ModName>(
+		&self,
+		spt_mod: &Mod,
+		install_target: InstallTarget,
+	) -> Result<Vec<OsString>> {
+		let _operation = operation_lock::lock_root(self.layout.root());
+		let index_path = self.layout.install_index().join(spt_mod.to_file_name());
+		if !self.files.is_file(&index_path) {
+			return Err(anyhow!("'{}' isn't installed", spt_mod.get_name()));
+		}
+		let installed_mod = InstalledMod::read_from(&self.files, &index_path)?;
+		if installed_mod.install_target.is_some_and(|target| target != install_target) {
+			return Err(anyhow!(
+				"'{}' was installed for the {} and not the {install_target}",
+				spt_mod.get_name(),
+				installed_mod.install_target.unwrap_or(install_target)
+			));
+		}
+		let mut removed = self.remove_installed_files(&installed_mod, |_| false)?;
+		self.files.remove_file(&index_path)?;
+		removed.sort();
+		Ok(removed)
+	}
+
+	/// Removes the files of an installed mod that other mods don't claim and `keep` doesn't match,
+	/// along with the folders that are left empty.
+	fn remove_installed_files(
+		&self,
+		installed_mod: &InstalledMod,
+		keep: impl Fn(&str) -> bool,
+	) -> Result<Vec<OsString>> {
+		let claimed_by_others: HashSet<_> = self
+			.list_installed()?
+			.into_iter()
+			.filter(|other| other.name != installed_mod.name)
+			.flat_map(|other| other.files.into_keys())
+			.collect();
+
+		let mut removed = Vec::new();
+		let mut folders = HashSet::new();
+		for file in installed_mod.files.keys() {
+			if claimed_by_others.contains(file) || keep(file) {
+				continue;
+			}
+			let path = self.layout.root().join(file);
+			match self.files.remove_file(&path) {
+				Ok(()) => {}
+				Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+				Err(err) => return Err(err).with_context(|| format!("Failed to remove: {file}")),
+			}
+			folders.extend(path.parent().map(Path::to_path_buf));
+			removed.push(path.into_os_string());
+		}
+		if !self.install_options.keep_empty_dirs {
+			removed.extend(self.remove_empty_folders(folders));
+		}
+		Ok(removed)
+	}
+
+	/// Removes each folder and then its parents for as long as they're empty, stopping at the
+	/// folders SPT itself provides.
+	fn remove_empty_folders(&self, folders: HashSet<PathBuf>) -> Vec<OsString> {
+		let root = self.layout.root();
+		let kept = [
+			root.to_path_buf(),
+			root.join("user"),
+			root.join("BepInEx"),
+			self.layout.server_mods(),
+			self.layout.client_mods(),
+			self.layout.bepinex_config(),
+		];
+		// The deepest folders go first, so their parents are empty by the time they're reached
+		let mut folders: Vec<_> = folders.into_iter().collect();
+		folders.sort_by_key(|folder| std::cmp::Reverse(folder.components().count()));
+		let mut removed = Vec::new();
+		for folder in folders {
+			for folder in folder.ancestors() {
+				if !folder.starts_with(root) || kept.iter().any(|kept| kept == folder) {
+					break;
 				}
-				ArchiveContents::Err(err) => {
-					return Err(err.into());
+				if self.files.remove_dir(folder).is_err() {
+					break;
 				}
+				removed.push(folder.as_os_str().to_os_string());
+			}
+		}
+		removed
+	}
+
+	/// The version recorded in the install index for the mod, if it's installed.
+	pub fn installed_version<Mod: ModName>(&self, mod_name: &Mod) -> Result<Option<Versioning>> {
+		let index_path = self.layout.install_index().join(mod_name.to_file_name());
+		if !self.files.is_file(&index_path) {
+			return Ok(None);
+		}
+		Ok(InstalledMod::read_from(&self.files, &index_path)?.get_version())
+	}
+
+	/// Reads the `package.json` of every server mod currently in the mods folder.
+	pub fn installed_packages(&self) -> Result<Vec<ModPackage>> {
+		let mut packages = Vec::new();
+		let server_mods_path = self.layout.server_mods();
+		if !server_mods_path.is_dir() {
+			return Ok(packages);
+		}
+		for entry in std::fs::read_dir(server_mods_path)? {
+			let package_path = entry?.path().join("package.json");
+			if !package_path.is_file() {
+				continue;
 			}
+			packages.extend(ModPackage::parse(&std::fs::read(package_path)?));
 		}
-		Ok(true)
+		Ok(packages)
+	}
+
+	/// Checks which installed server mods declare support for an SPT version, such as one to upgrade
+	/// to, ordered by mod name.
+	pub fn check_spt_upgrade(&self, spt_version: &Versioning) -> Result<Vec<SptCompatibility>> {
+		let mut compatibilities: Vec<_> = self
+			.installed_packages()?
+			.into_iter()
+			.map(|package| SptCompatibility {
+				support: package.supports_spt(spt_version),
+				mod_name: package.name,
+			})
+			.collect();
+		compatibilities.sort_by(|a, b| a.mod_name.cmp(&b.mod_name));
+		Ok(compatibilities)
+	}
+
+	/// Checks the declared SPT version and mod dependencies of freshly installed packages against
+	/// the configured SPT version and the server mods that are installed, including loops between
+	/// the installed mods that the fresh packages are part of.
+	pub fn check_dependencies(
+		&self,
+		packages: &[ModPackage],
+		spt_version: &Versioning,
+	) -> Result<Vec<DependencyWarning>> {
+		let installed = self.installed_packages()?;
+		let mut warnings: Vec<_> = packages
+			.iter()
+			.flat_map(|package| package.check(spt_version, &installed))
+			.collect();
+		if let Some(cycle) = mod_package::find_dependency_cycle(&installed) {
+			if cycle.iter().any(|name| packages.iter().any(|package| &package.name == name)) {
+				warnings.push(DependencyWarning::CircularDependency { cycle });
+			}
+		}
+		Ok(warnings)
+	}
+
+	pub fn is_same_installed_version<P: AsRef<Path>, Mod: ModName>(
+		&self,
+		mod_archive_path: P,
+		mod_name: &Mod,
+		install_target: InstallTarget,
+	) -> Result<bool> {
+		is_same_installed(
+			&self.layout,
+			&self.install_options,
+			&self.files,
+			mod_archive_path.as_ref(),
+			mod_name,
+			install_target,
+		)
+	}
+
+	/// Checks whether each mod is already installed on up to `threads` threads. The checks only read
+	/// the SPT folder, so installs should wait until they are done. The results keep the order of `mods`.
+	pub fn find_installed_versions<P: AsRef<Path> + Sync, Mod: ModName + Sync>(
+		&self,
+		mods: &[(P, &Mod, InstallTarget)],
+		threads: usize,
+	) -> Result<Vec<bool>>
+	where
+		Files: Sync,
+	{
+		let (layout, install_options, files) = (&self.layout, &self.install_options, &self.files);
+		worker_pool::map_in_parallel(
+			mods,
+			threads,
+			|(mod_archive_path, mod_name, install_target)| {
+				is_same_installed(
+					layout,
+					install_options,
+					files,
+					mod_archive_path.as_ref(),
+					*mod_name,
+					*install_target,
+				)
+			},
+			|_| {},
+		)
+		.into_iter()
+		.collect()
 	}
 
 	pub fn install_mod_to_path(
@@ -165,14 +832,86 @@ impl<Time: TimeProvider> SptAccess<Time> {
 		mod_archive_path: impl AsRef<Path>,
 		install_path: impl AsRef<Path>,
 	) -> Result<()> {
+		let _operation = operation_lock::lock_root(self.layout.root());
+		let mod_archive_path = mod_archive_path.as_ref();
+		let install_path = install_path.as_ref();
 		let reader = BufReader::new(File::open(mod_archive_path)?);
-		compress_tools::uncompress_archive(reader, install_path.as_ref(), Ownership::Ignore)?;
+		compress_tools::uncompress_archive(reader, install_path, Ownership::Ignore)?;
+
+		let archive_hash = sha256::try_digest(mod_archive_path)?;
+		std::fs::write(install_path.join(INSTALLED_MARKER_FILE_NAME), archive_hash)?;
+		Ok(())
+	}
+
+	/// Copies a downloaded file to `target_path` as is, for mods published as a single raw file.
+	pub fn install_file_to_path(
+		&self,
+		file_path: impl AsRef<Path>,
+		target_path: impl AsRef<Path>,
+	) -> Result<()> {
+		let _operation = operation_lock::lock_root(self.layout.root());
+		let target_path = target_path.as_ref();
+		if let Some(parent) = target_path.parent() {
+			std::fs::create_dir_all(parent)?;
+		}
+		std::fs::copy(file_path, target_path)?;
 		Ok(())
 	}
+
+	/// Whether `target_path` already holds the content of the downloaded file.
+	pub fn is_same_installed_file(
+		&self,
+		file_path: impl AsRef<Path>,
+		target_path: impl AsRef<Path>,
+	) -> Result<bool> {
+		let target_path = target_path.as_ref();
+		if !target_path.is_file() {
+			return Ok(false);
+		}
+		Ok(sha256::try_digest(target_path)? == sha256::try_digest(file_path.as_ref())?)
+	}
+
+	/// Extracts the archive to `out_dir` for inspection without recording it in the install index.
+	/// Folders inside the SPT folder are refused, so a preview can't change the installation.
+	pub fn preview_install(
+		&self,
+		mod_archive_path: impl AsRef<Path>,
+		out_dir: impl AsRef<Path>,
+	) -> Result<()> {
+		let out_dir = out_dir.as_ref();
+		if path_access::resolve(out_dir).starts_with(path_access::resolve(self.layout.root())) {
+			return Err(anyhow!(
+				"The preview folder '{}' must be outside the SPT folder",
+				out_dir.display()
+			));
+		}
+		std::fs::create_dir_all(out_dir)?;
+		self.install_mod_to_path(mod_archive_path, out_dir)
+	}
+
+	/// Whether any archive was extracted to `install_path` by [`Self::install_mod_to_path`]. Such
+	/// mods aren't in the install index, so their files aren't tracked.
+	pub fn is_installed_to_path(&self, install_path: impl AsRef<Path>) -> bool {
+		install_path.as_ref().join(INSTALLED_MARKER_FILE_NAME).is_file()
+	}
+
+	pub fn is_same_installed_path_version(
+		&self,
+		mod_archive_path: impl AsRef<Path>,
+		install_path: impl AsRef<Path>,
+	) -> Result<bool> {
+		let marker_path = install_path.as_ref().join(INSTALLED_MARKER_FILE_NAME);
+		if !marker_path.is_file() {
+			return Ok(false);
+		}
+		let installed_hash = std::fs::read_to_string(marker_path)?;
+		let archive_hash = sha256::try_digest(mod_archive_path.as_ref())?;
+		Ok(installed_hash.trim() == archive_hash)
+	}
 	
 	pub async fn clear_mm_cache(&self) -> Result<Vec<OsString>>{
 		let mut vec = Vec::new();
-		let mut entries = fs::read_dir(&self.install_index).await?;
+		let mut entries = fs::read_dir(self.layout.install_index()).await?;
 		while let Some(entry) = entries.next_entry().await? {
 			let path = entry.path();
 			fs::remove_file(&path).await?;
@@ -183,41 +922,112 @@ impl<Time: TimeProvider> SptAccess<Time> {
 
 	pub async fn clear_spt_cache(&self) -> Result<Vec<OsString>>{
 		let mut vec = Vec::new();
-		let bepinex_path = &self.root_path.join(BEPINEX_CACHE_PATH);
-		vec.append(&mut remove_all_files_in_dir(bepinex_path).await?);
-		let user_path = &self.root_path.join(USER_CACHE_PATH);
-		vec.append(&mut remove_all_files_in_dir(user_path).await?);
+		vec.append(&mut remove_all_files_in_dir(self.layout.bepinex_cache()).await?);
+		vec.append(&mut remove_all_files_in_dir(self.layout.user_cache()).await?);
 		Ok(vec)
 	}
 
 	pub async fn clear_spt_config(&self) -> Result<Vec<OsString>>{
-		let path = &self.root_path.join(BEPINEX_CONFIG_PATH);
-		remove_all_files_in_dir(path).await
+		remove_all_files_in_dir(self.layout.bepinex_config()).await
 	}
 
-	pub fn backup_to<P: AsRef<Path>>(&self, archive_path: P) -> Result<()> {
-		let current_date = self.time.get_current_time();
-		let backup_name = format!("backup_{}.zip", current_date.format("%Y-%m-%dT%H-%m-%SZ"));
-		let zip_path = archive_path.as_ref().join(backup_name);
-		let writer = BufWriter::new(File::create_new(zip_path)?);
-		let mut zip_writer = ZipWriter::new(writer);
-
-		backup_folder_content(&mut zip_writer, &self.server_mods_path)?;
-		backup_folder_content(&mut zip_writer, &self.client_mods_path)?;
-		zip_writer.finish()?;
-		Ok(())
+	pub fn backup_to<P: AsRef<Path>>(&self, archive_path: P, options: &BackupOptions) -> Result<()> {
+		let zip_path = self.create_backup_path(archive_path, options)?;
+		let _operation = operation_lock::lock_root(self.layout.root());
+		backup::write_backup(
+			&zip_path,
+			self.layout.root(),
+			&self.backup_folders(options),
+			options,
+			&BackupCancellation::new(),
+			|_| {},
+		)
+	}
+
+	/// Runs the backup on the blocking thread pool, sending the number of files processed so far
+	/// through `progress`. A cancelled backup removes its partial archive before returning an error.
+	pub async fn backup_to_async<P: AsRef<Path>>(
+		&self,
+		archive_path: P,
+		options: BackupOptions,
+		cancellation: BackupCancellation,
+		progress: UnboundedSender<usize>,
+	) -> Result<PathBuf> {
+		let zip_path = self.create_backup_path(archive_path, &options)?;
+		let task_zip_path = zip_path.clone();
+		let root_path = self.layout.root().to_path_buf();
+		let folders = self.backup_folders(&options);
+		tokio::task::spawn_blocking(move || {
+			let _operation = operation_lock::lock_root(&root_path);
+			backup::write_backup(
+				&task_zip_path,
+				&root_path,
+				&folders,
+				&options,
+				&cancellation,
+				|count| {
+					let _ = progress.send(count);
+				},
+			)
+		})
+		.await??;
+		Ok(zip_path)
 	}
 
-	pub fn restore_from<P: AsRef<Path>>(&self, archive_path: P) -> Result<()> {
-		let mut zip_archive = ZipArchive::new(File::open(archive_path)?)?;
+	/// Finds the newest `backup_<timestamp>.zip` in the folder.
+	pub fn find_latest_backup(backup_folder: impl AsRef<Path>) -> Result<PathBuf> {
+		backup::find_latest_backup(backup_folder.as_ref())
+	}
 
-		zip_archive.extract(&self.root_path)?;
-		Ok(())
+	/// The sha256 of every file in a backup by its path, read from the manifest written next to it.
+	/// Backups made before manifests were written have none.
+	pub fn read_backup_manifest(archive_path: impl AsRef<Path>) -> Result<BTreeMap<String, String>> {
+		backup::read_backup_manifest(archive_path.as_ref())
+	}
+
+	/// Lists the files added, removed and changed from one backup to the other.
+	pub fn diff_backups(
+		from_path: impl AsRef<Path>,
+		to_path: impl AsRef<Path>,
+	) -> Result<Vec<BackupDifference>> {
+		backup_diff::diff_archives(from_path.as_ref(), to_path.as_ref())
+	}
+
+	/// Lists the files of a backup with their sizes and targets, without extracting anything.
+	pub fn inspect_backup<P: AsRef<Path>>(&self, archive_path: P) -> Result<Vec<BackupEntry>> {
+		backup_contents::list_archive(archive_path.as_ref())
+	}
+
+	pub fn restore_from<P: AsRef<Path>>(
+		&self,
+		archive_path: P,
+		policy: OverwritePolicy,
+		scope: RestoreScope,
+	) -> Result<()> {
+		let _operation = operation_lock::lock_root(self.layout.root());
+		restore::restore_archive(archive_path.as_ref(), self.layout.root(), policy, scope)
 	}
 	
+	/// Restores only the files of one mod from a backup, those in `user/mods/<mod_folder>` and
+	/// `BepInEx/plugins/<mod_folder>`, overwriting what is there and leaving everything else alone.
+	pub fn restore_mod_from<P: AsRef<Path>>(&self, archive_path: P, mod_folder: &str) -> Result<()> {
+		let _operation = operation_lock::lock_root(self.layout.root());
+		restore::restore_mod_archive(archive_path.as_ref(), self.layout.root(), mod_folder)
+	}
+
+	/// Lists what restoring the backup would do to each file, without extracting anything.
+	pub fn preview_restore<P: AsRef<Path>>(
+		&self,
+		archive_path: P,
+		scope: RestoreScope,
+	) -> Result<Vec<RestorePreview>> {
+		restore::preview_archive(archive_path.as_ref(), self.layout.root(), scope)
+	}
+
 	pub async fn remove_all_mods(&self) -> Result<Vec<OsString>>{
+		let _operation = operation_lock::lock_root_async(self.layout.root()).await?;
 		let mut vec = Vec::new();
-		let mut entries = fs::read_dir(&self.server_mods_path).await?;
+		let mut entries = fs::read_dir(self.layout.server_mods()).await?;
 		while let Some(entry) = entries.next_entry().await? {
 			let path = entry.path();
 			if path.is_file() {
@@ -226,10 +1036,10 @@ impl<Time: TimeProvider> SptAccess<Time> {
 			fs::remove_dir_all(&path).await?;
 			vec.push(path.into_os_string());
 		}
-		let mut entries = fs::read_dir(&self.client_mods_path).await?;
+		let mut entries = fs::read_dir(self.layout.client_mods()).await?;
 		while let Some(entry) = entries.next_entry().await? {
 			let path = entry.path();
-			if path.file_name() == Some(OsStr::new("spt")) {
+			if self.is_protected_plugin(&path) {
 				continue
 			}
 			if path.is_file() {
@@ -247,18 +1057,38 @@ impl<Time: TimeProvider> SptAccess<Time> {
 		Ok(vec)
 	}
 
-	fn write_file_to_tarkov(&self, zip_data: ZipData) -> Result<()> {
-		let path = self.root_path.join(zip_data.get_path());
-		if let Some(dir_path) = dir_parser(path.to_str().context("Failed to parse install path")?)
-			.map_err(|_| anyhow!("Failed to parse install path"))?
-		{
-			std::fs::create_dir_all(dir_path)?;
+	fn create_backup_path(
+		&self,
+		archive_path: impl AsRef<Path>,
+		options: &BackupOptions,
+	) -> Result<PathBuf> {
+		let current_date = self.time.get_current_time();
+		let backup_name = backup::backup_file_name(current_date, options.label.as_deref())?;
+		Ok(backup::unused_backup_path(archive_path.as_ref(), &backup_name))
+	}
+
+	fn backup_folders(&self, options: &BackupOptions) -> Vec<PathBuf> {
+		let mut folders = vec![self.layout.server_mods(), self.layout.client_mods()];
+		if options.include_config {
+			folders.push(self.layout.bepinex_config());
 		}
+		if options.include_profiles {
+			folders.push(self.layout.user_profiles());
+		}
+		folders
+	}
 
-		let mut writer = BufWriter::new(File::create(path)?);
-		writer.write_all(zip_data.get_data())?;
-		Ok(())
+}
+
+fn write_file(files: &impl FileSystem, path: &Path, data: &[u8]) -> Result<()> {
+	if let Some(dir_path) = dir_parser(path.to_str().context("Failed to parse install path")?)
+		.map_err(|_| anyhow!("Failed to parse install path"))?
+	{
+		files.create_dir_all(Path::new(dir_path))?;
 	}
+
+	files.write(path, data)?;
+	Ok(())
 }
 
 async fn remove_all_files_in_dir(path: impl AsRef<Path>) -> Result<Vec<OsString>> {
@@ -279,36 +1109,119 @@ async fn remove_all_files_in_dir(path: impl AsRef<Path>) -> Result<Vec<OsString>
 	Ok(vec)
 }
 
-fn backup_folder_content(
-	zip_writer: &mut ZipWriter<BufWriter<File>>,
-	path_buf: &PathBuf,
-) -> Result<()> {
-	if !path_buf.is_dir() {
-		return Ok(());
+/// Opens an archive from the cache, rejecting an empty file up front as it would otherwise read as
+/// an archive without any entries.
+fn open_cached_archive(
+	mod_archive_path: &Path,
+	skipped_entries: &HashSet<String>,
+) -> Result<ArchiveIterator<BufReader<File>>> {
+	let file = File::open(mod_archive_path)?;
+	if file.metadata()?.len() == 0 {
+		return Err(corrupt_archive_error(mod_archive_path));
 	}
+	let skipped_entries = skipped_entries.clone();
+	ArchiveIteratorBuilder::new(BufReader::new(file))
+		.decoder(decode_entry_name)
+		.filter(move |name, stat| {
+			!is_folder_entry(name, stat.st_mode) && !skipped_entries.contains(name)
+		})
+		.build()
+		.map_err(|_| match detect_archive_type(mod_archive_path) {
+			Some(_) => corrupt_archive_error(mod_archive_path),
+			None => unsupported_archive_error(mod_archive_path),
+		})
+}
 
-	let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
-	let filter = WalkDir::new(path_buf)
-		.into_iter()
-		.filter(|x| x.as_ref().is_ok_and(|e| e.path().is_file()));
-	for file_entry in filter {
-		let file_entry = file_entry?;
-		let file_path = file_entry.path();
-		let mut buffer = Vec::new();
-		let mut file = File::open(file_path)?;
-		file.read_to_end(&mut buffer)?;
-		zip_writer.start_file_from_path(file_path, options)?;
-		zip_writer.write_all(&buffer)?;
-	}
+fn corrupt_archive_error(mod_archive_path: &Path) -> anyhow::Error {
+	let archive_type = detect_archive_type(mod_archive_path)
+		.map_or(String::new(), |archive_type| format!("{archive_type} "));
+	anyhow!(
+		"Cached {archive_type}archive '{}' is empty or corrupt, delete it from the cache to download it again",
+		mod_archive_path.display()
+	)
+}
 
-	Ok(())
+fn unsupported_archive_error(mod_archive_path: &Path) -> anyhow::Error {
+	let extension = mod_archive_path
+		.extension()
+		.map_or("unknown".into(), |extension| extension.to_string_lossy());
+	anyhow!(
+		"Cached archive '{}' isn't a supported archive type ({extension}), mods can be installed from zip, 7z, rar and tar archives",
+		mod_archive_path.display()
+	)
+}
+
+fn wrong_target_error(install_target: InstallTarget, other_target: InstallTarget) -> anyhow::Error {
+	let command = match other_target {
+		InstallTarget::Server => "Update Server",
+		InstallTarget::Client => "Update Client",
+	};
+	anyhow!(
+		"Found no files for the {install_target} target, this looks like a {other_target} mod; run {command} or set its install_target to {other_target}"
+	)
+}
+
+/// The archive types that can be installed, by the bytes their files start with.
+const ARCHIVE_SIGNATURES: &[(&str, &[u8])] = &[
+	("zip", b"PK\x03\x04"),
+	("zip", b"PK\x05\x06"),
+	("7z", b"7z\xBC\xAF\x27\x1C"),
+	("rar", b"Rar!\x1A\x07"),
+	("gzip", b"\x1F\x8B"),
+	("xz", b"\xFD7zXZ\x00"),
+	("bzip2", b"BZh"),
+];
+
+fn detect_archive_type(mod_archive_path: &Path) -> Option<&'static str> {
+	let mut signature = [0; 8];
+	let read = File::open(mod_archive_path)
+		.and_then(|mut file| file.read(&mut signature))
+		.ok()?;
+	let is_tar = mod_archive_path.extension().is_some_and(|extension| extension == "tar");
+	ARCHIVE_SIGNATURES
+		.iter()
+		.find(|(_, magic)| signature[..read].starts_with(magic))
+		.map(|(archive_type, _)| *archive_type)
+		.or(is_tar.then_some("tar"))
+}
+
+/// Folders are told apart by their mode, as archives such as rar name them without a trailing slash.
+fn is_folder_entry(name: &str, mode: impl Into<u32>) -> bool {
+	const FILE_TYPE_MASK: u32 = 0o170000;
+	const FOLDER_TYPE: u32 = 0o040000;
+	name.ends_with('/') || mode.into() & FILE_TYPE_MASK == FOLDER_TYPE
+}
+
+/// The folders that hold other entries, for listings that name folders without a trailing slash.
+fn listed_folders(entries: &[String]) -> HashSet<&str> {
+	entries
+		.iter()
+		.flat_map(|entry| entry.match_indices('/').map(|(index, _)| &entry[..index]))
+		.collect()
+}
+
+/// Decodes entry names lossily, as archives made with a legacy codepage would otherwise fail to
+/// read at all. Names that didn't decode are found with [`is_invalid_entry_name`].
+fn decode_entry_name(bytes: &[u8]) -> compress_tools::Result<String> {
+	Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+fn is_invalid_entry_name(zip_path: &str) -> bool {
+	zip_path.contains(char::REPLACEMENT_CHARACTER)
 }
+
 fn new_file_archive_iter(reader: BufReader<File>) -> Result<ArchiveIterator<BufReader<File>>> {
 	Ok(ArchiveIteratorBuilder::new(reader)
-		.filter(|name, _| !name.ends_with('/'))
+		.decoder(decode_entry_name)
+		.filter(|name, stat| !is_folder_entry(name, stat.st_mode))
 		.build()?)
 }
 
+fn is_server_package(zip_path: &str) -> bool {
+	let segments: Vec<_> = zip_path.split('/').collect();
+	matches!(segments.as_slice(), ["user", "mods", _, "package.json"])
+}
+
 fn dir_parser(file_path: &str) -> PResult<Option<&str>> {
 	let (_, parsed): (&str, Option<Vec<_>>) =
 		opt(separated(1.., take_until(0.., "/"), "/")).parse_peek(file_path)?;
@@ -322,23 +1235,105 @@ fn dir_parser(file_path: &str) -> PResult<Option<&str>> {
 	Ok(Some(&file_path[..length - 1]))
 }
 
+/// Classifies an archive path by the first `user` or `BepInEx` folder in it, so archives wrapped in
+/// a folder such as `<ModName>/user/mods/...` still install. `file_name` is left starting at that
+/// folder, which is the path relative to the SPT folder.
 fn file_parser(file_name: &mut &str) -> FileType {
-	let result: PResult<FileType> = dispatch! { take_until(0.., "/");
-		"user" => empty.value(FileType::Server),
-		"BepInEx" => empty.value(FileType::Client),
-		_ => empty.value(FileType::Unknown),
+	let mut rest = *file_name;
+	loop {
+		let result: PResult<FileType> = dispatch! { take_until(0.., "/");
+			"user" => empty.value(FileType::Server),
+			"BepInEx" => empty.value(FileType::Client),
+			_ => empty.value(FileType::Unknown),
+		}
+		.parse_peek(rest)
+		.map(|(_, file_type)| file_type);
+		match result {
+			Ok(FileType::Unknown) => {}
+			Ok(file_type) => {
+				*file_name = rest;
+				return file_type;
+			}
+			Err(_) => return FileType::Unknown,
+		}
+		let Some((_, next)) = rest.split_once('/') else {
+			return FileType::Unknown;
+		};
+		rest = next;
 	}
-	.parse_next(file_name);
-	result.unwrap_or(FileType::Unknown)
 }
 
-#[cfg(test)]
-mod tests {
+fn is_same_installed<Mod: ModName>(
+	layout: &SptLayout,
+	install_options: &InstallOptions,
+	files: &impl FileSystem,
+	mod_archive_path: &Path,
+	mod_name: &Mod,
+	install_target: InstallTarget,
+) -> Result<bool> {
+	let mod_name = layout.install_index().join(mod_name.to_file_name());
+	if !files.is_file(&mod_name) {
+		return Ok(false);
+	}
+	let map = InstalledMod::read_from(files, &mod_name)?.files;
+	if install_options.strict_hash && !map.keys().all(|file| files.is_file(&layout.root().join(file))) {
+		return Ok(false);
+	}
+	let descriptor = InstallDescriptor::read(mod_archive_path)?;
+
+	let archive_iter = new_file_archive_iter(BufReader::new(File::open(mod_archive_path)?))?;
+
+	let mut buffer = Vec::default();
+	let mut zip_path = String::default();
+	for content in archive_iter {
+		match content {
+			ArchiveContents::StartOfEntry(name, _) => {
+				zip_path = name;
+				buffer.clear();
+			}
+			ArchiveContents::DataChunk(mut data) => buffer.append(&mut data),
+			ArchiveContents::EndOfEntry => {
+				if is_invalid_entry_name(&zip_path) {
+					continue;
+				}
+				let mapped_path = descriptor.map_path(&zip_path);
+				let zip_data = ZipData::new(&buffer, &mapped_path);
+				if !zip_data.should_install(&install_target)
+					|| install_options.is_never_installed(zip_data.get_path())
+				{
+					continue;
+				}
+				if map.get(zip_data.get_path()).map(String::as_str) != Some(zip_data.get_hash()) {
+					return Ok(false);
+				}
+			}
+			ArchiveContents::Err(err) => {
+				return Err(err.into());
+			}
+		}
+	}
+	Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Write;
+
 	use super::*;
+	use crate::configuration_access::ConfigurationAccess;
+	use crate::file_access::MemoryFileSystem;
 	use crate::shared_traits::MockTimeProvider;
+	use crate::spt_access::spt_layout::{BEPINEX_CONFIG_PATH, USER_PROFILES_PATH};
+	use crate::version_constraint::VersionConstraint;
 	use chrono::{DateTime, Utc};
 
-	struct TestModName(String);
+	struct TestModName(String, Versioning);
+
+	impl TestModName {
+		fn new(name: &str, version: &str) -> Self {
+			Self(name.to_string(), Versioning::new(version).unwrap())
+		}
+	}
 
 	impl ModName for TestModName {
 		fn get_name(&self) -> &str {
@@ -350,16 +1345,32 @@ mod tests {
 		}
 	}
 
+	impl ModVersion for TestModName {
+		fn get_version(&self) -> &Versioning {
+			&self.1
+		}
+
+		fn get_order<Version: ModVersion>(&self, mod_version: &Version) -> std::cmp::Ordering {
+			self.1.cmp(mod_version.get_version())
+		}
+	}
+
+	/// Creates the folder with a server executable, so it's detected as an SPT folder.
+	async fn create_spt_folder(path: &str) {
+		fs::create_dir_all(path).await.unwrap();
+		fs::write(format!("{path}/{SERVER_FILE_NAME}"), "").await.unwrap();
+	}
+
 	#[tokio::test]
 	async fn integration_test_restore() {
 		let provider = MockTimeProvider::new();
 		let buf = PathBuf::from("test_data/backup_2024-06-11T19-06-1718132955Z.zip");
 		let path = "./test_output/restore_test";
-		fs::create_dir_all(path).await.unwrap();
+		create_spt_folder(path).await;
 		let project = PathAccess::from(path, path).unwrap();
 		SptAccess::init(&project, provider).await
 			.unwrap()
-			.restore_from(buf)
+			.restore_from(buf, OverwritePolicy::Overwrite, RestoreScope::All)
 			.unwrap();
 
 		assert!(Path::new(&format!(
@@ -369,17 +1380,230 @@ mod tests {
 		fs::remove_dir_all(path).await.unwrap()
 	}
 
+	const RESTORED_MOD_PATH: &str = "user/mods/maxloo2-betterkeys-updated";
+
+	/// Restores the test backup over a root where `package.json` was just modified and
+	/// `config/config.json` was modified long before the backup was made.
+	async fn restore_over_existing_files(path: &str, policy: OverwritePolicy) {
+		let provider = MockTimeProvider::new();
+		let buf = PathBuf::from("test_data/backup_2024-06-11T19-06-1718132955Z.zip");
+		fs::create_dir_all(format!("{path}/{RESTORED_MOD_PATH}/config")).await.unwrap();
+		create_spt_folder(path).await;
+		fs::write(format!("{path}/{RESTORED_MOD_PATH}/package.json"), "newer").await.unwrap();
+		let old_file = format!("{path}/{RESTORED_MOD_PATH}/config/config.json");
+		std::fs::write(&old_file, "older").unwrap();
+		File::options()
+			.write(true)
+			.open(&old_file)
+			.unwrap()
+			.set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(86_400))
+			.unwrap();
+
+		let project = PathAccess::from(path, path).unwrap();
+		SptAccess::init(&project, provider).await
+			.unwrap()
+			.restore_from(buf, policy, RestoreScope::All)
+			.unwrap();
+
+		assert!(Path::new(&format!("{path}/{RESTORED_MOD_PATH}/LICENSE")).is_file());
+	}
+
+	async fn read_restored(path: &str, file: &str) -> String {
+		fs::read_to_string(format!("{path}/{RESTORED_MOD_PATH}/{file}")).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_restore_overwrite() {
+		let path = "./test_output/restore_overwrite_test";
+		restore_over_existing_files(path, OverwritePolicy::Overwrite).await;
+
+		assert_ne!(read_restored(path, "package.json").await, "newer");
+		assert_ne!(read_restored(path, "config/config.json").await, "older");
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_restore_single_mod_leaves_the_rest_alone() {
+		let path = "./test_output/restore_single_mod_test";
+		let _discard = fs::remove_dir_all(path).await;
+		fs::create_dir_all(format!("{path}/user/mods/other-mod")).await.unwrap();
+		create_spt_folder(path).await;
+		fs::write(format!("{path}/user/mods/other-mod/package.json"), "kept").await.unwrap();
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+		let backup = "test_data/backup_2024-06-11T19-06-1718132955Z.zip";
+
+		let misspelled = spt_access.restore_mod_from(backup, "maxloo2").unwrap_err();
+		let outside = spt_access.restore_mod_from(backup, "../mods").unwrap_err();
+		spt_access.restore_mod_from(backup, "maxloo2-betterkeys-updated").unwrap();
+
+		assert!(misspelled.to_string().contains("Found no files of 'maxloo2'"), "{misspelled}");
+		assert!(outside.to_string().contains("Invalid mod folder name"), "{outside}");
+		assert!(Path::new(&format!("{path}/{RESTORED_MOD_PATH}/LICENSE")).is_file());
+		let kept = fs::read_to_string(format!("{path}/user/mods/other-mod/package.json")).await;
+		assert_eq!(kept.unwrap(), "kept");
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_restore_skip_existing() {
+		let path = "./test_output/restore_skip_existing_test";
+		restore_over_existing_files(path, OverwritePolicy::SkipExisting).await;
+
+		assert_eq!(read_restored(path, "package.json").await, "newer");
+		assert_eq!(read_restored(path, "config/config.json").await, "older");
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_restore_overwrite_older_only() {
+		let path = "./test_output/restore_overwrite_older_test";
+		restore_over_existing_files(path, OverwritePolicy::OverwriteOlderOnly).await;
+
+		assert_eq!(read_restored(path, "package.json").await, "newer");
+		assert_ne!(read_restored(path, "config/config.json").await, "older");
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
 	#[tokio::test]
 	async fn integration_test_install() {
 		let provider = MockTimeProvider::new();
 		let buf = PathBuf::from("test_data/1.2.3_maxloo2-betterkeys-updated-v1.2.3.zip");
 		let path = "./test_output/install_test";
-		fs::create_dir_all(path).await.unwrap();
+		create_spt_folder(path).await;
 		let project = PathAccess::from(path, path).unwrap();
 		SptAccess::init(&project, provider).await
 			.unwrap()
-			.install_mod(buf, &TestModName("Test".to_string()), InstallTarget::Client)
+			.install_mod(buf, &TestModName::new("Test", "1.2.3"), InstallTarget::Client)
+			.unwrap();
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_install_older_version_over_newer() {
+		let buf = PathBuf::from("test_data/1.2.3_maxloo2-betterkeys-updated-v1.2.3.zip");
+		let path = "./test_output/downgrade_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+		let newer = TestModName::new("Better Keys", "2.0.0");
+		let older = TestModName::new("Better Keys", "1.2.3");
+		spt_access.install_mod(&buf, &newer, InstallTarget::Server).unwrap();
+
+		spt_access.install_mod(&buf, &older, InstallTarget::Server).unwrap();
+
+		assert_eq!(spt_access.installed_version(&older).unwrap(), Versioning::new("1.2.3"));
+		let installed = spt_access.list_installed().unwrap();
+		assert_eq!(installed.len(), 1);
+		assert_eq!(installed[0].version.as_deref(), Some("1.2.3"));
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_parallel_verification_matches_serial() {
+		let buf = PathBuf::from("test_data/1.2.3_maxloo2-betterkeys-updated-v1.2.3.zip");
+		let path = "./test_output/verify_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+		spt_access
+			.install_mod(&buf, &TestModName::new("Better Keys", "1.2.3"), InstallTarget::Server)
+			.unwrap();
+		let mut modified_files = HashMap::new();
+		modified_files
+			.insert("user/mods/modified/file.txt".to_string(), sha256::digest("original"));
+		modified_files
+			.insert("user/mods/modified/missing.txt".to_string(), sha256::digest("missing"));
+		fs::create_dir_all(format!("{path}/user/mods/modified")).await.unwrap();
+		fs::write(format!("{path}/user/mods/modified/file.txt"), "changed").await.unwrap();
+		for name in ["Modified", "Another Modified"] {
+			InstalledMod {
+				name: name.to_string(),
+				version: Some("1.0.0".to_string()),
+				install_target: Some(InstallTarget::Server),
+				files: modified_files.clone(),
+			}
+			.write_to(&DiskFileSystem {}, &spt_access.layout.install_index().join(name))
+			.unwrap();
+		}
+
+		let serial = spt_access.verify_installed(1, |_| {}).unwrap();
+		let verified_count = std::sync::atomic::AtomicUsize::new(0);
+		let parallel = spt_access
+			.verify_installed(4, |count| {
+				verified_count.fetch_max(count, std::sync::atomic::Ordering::Relaxed);
+			})
+			.unwrap();
+
+		assert_eq!(parallel, serial);
+		assert_eq!(verified_count.into_inner(), 3);
+		assert!(parallel
+			.iter()
+			.any(|verification| verification.name == "Better Keys" && verification.is_intact()));
+		let modified =
+			parallel.iter().find(|verification| verification.name == "Modified").unwrap();
+		assert_eq!(
+			modified.problems,
+			vec![
+				("user/mods/modified/file.txt".to_string(), FileProblem::Modified),
+				("user/mods/modified/missing.txt".to_string(), FileProblem::Missing),
+			]
+		);
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_list_installed() {
+		let provider = MockTimeProvider::new();
+		let buf = PathBuf::from("test_data/1.2.3_maxloo2-betterkeys-updated-v1.2.3.zip");
+		let path = "./test_output/list_installed_test";
+		create_spt_folder(path).await;
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, provider).await.unwrap();
+		spt_access
+			.install_mod(&buf, &TestModName::new("Better Keys", "1.2.3"), InstallTarget::Server)
 			.unwrap();
+		fs::write(
+			format!("{path}/install_hash/Legacy_Mod"),
+			r#"{"user/mods/legacy/package.json":"abc"}"#,
+		)
+		.await
+		.unwrap();
+
+		let installed = spt_access.list_installed().unwrap();
+
+		assert_eq!(installed.len(), 2);
+		assert_eq!(installed[0].name, "Better Keys");
+		assert_eq!(installed[0].get_version(), Versioning::new("1.2.3"));
+		assert_eq!(installed[0].install_target, Some(InstallTarget::Server));
+		assert_eq!(installed[1].name, "Legacy_Mod");
+		assert_eq!(installed[1].version, None);
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_install_to_path_twice_is_skipped() {
+		let provider = MockTimeProvider::new();
+		let buf = PathBuf::from("test_data/1.2.3_maxloo2-betterkeys-updated-v1.2.3.zip");
+		let path = "./test_output/install_to_path_test";
+		let install_path = format!("{path}/custom");
+		create_spt_folder(path).await;
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, provider).await.unwrap();
+
+		assert!(!spt_access.is_same_installed_path_version(&buf, &install_path).unwrap());
+		assert!(!spt_access.is_installed_to_path(&install_path));
+		spt_access.install_mod_to_path(&buf, &install_path).unwrap();
+		assert!(Path::new(&install_path).join(INSTALLED_MARKER_FILE_NAME).is_file());
+		assert!(spt_access.is_installed_to_path(&install_path));
+		assert!(spt_access.is_same_installed_path_version(&buf, &install_path).unwrap());
+
+		fs::write(Path::new(&install_path).join(INSTALLED_MARKER_FILE_NAME), "outdated")
+			.await
+			.unwrap();
+		assert!(!spt_access.is_same_installed_path_version(&buf, &install_path).unwrap());
 		fs::remove_dir_all(path).await.unwrap()
 	}
 
@@ -397,8 +1621,264 @@ mod tests {
 
 		SptAccess::init(&project, provider).await
 			.unwrap()
-			.backup_to(&path)
+			.backup_to(&path, &BackupOptions::default())
+			.unwrap();
+		fs::remove_dir_all(&path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_deduplicated_backup_stores_shared_file_once() {
+		let mut provider = MockTimeProvider::new();
+		provider
+			.expect_get_current_time()
+			.returning(DateTime::<Utc>::default);
+		let path = "./test_output/dedup_backup_test";
+		let _discard = fs::remove_dir_all(path).await;
+		let root = format!("{path}/root");
+		for mod_name in ["first-mod", "second-mod"] {
+			fs::create_dir_all(format!("{root}/user/mods/{mod_name}")).await.unwrap();
+			fs::write(format!("{root}/user/mods/{mod_name}/shared.js"), "shared library")
+				.await
+				.unwrap();
+		}
+		fs::write(format!("{root}/user/mods/first-mod/own.js"), "first only").await.unwrap();
+		create_spt_folder(&root).await;
+		let backup_path = format!("{path}/backups");
+		fs::create_dir_all(&backup_path).await.unwrap();
+		let project = PathAccess::from(&root, &root).unwrap();
+		let options = BackupOptions {
+			deduplicate: true,
+			..BackupOptions::default()
+		};
+
+		let spt_access = SptAccess::init(&project, provider).await.unwrap();
+		spt_access.backup_to(&backup_path, &options).unwrap();
+
+		let mut entries = std::fs::read_dir(&backup_path).unwrap();
+		let archive_path = entries.next().unwrap().unwrap().path();
+		let archive = zip::ZipArchive::new(File::open(&archive_path).unwrap()).unwrap();
+		let stored_contents = archive
+			.file_names()
+			.filter(|name| name.starts_with(backup::DEDUP_CONTENT_FOLDER))
+			.count();
+		assert_eq!(stored_contents, 2);
+
+		fs::remove_dir_all(format!("{root}/user")).await.unwrap();
+		spt_access
+			.restore_from(&archive_path, OverwritePolicy::Overwrite, RestoreScope::All)
+			.unwrap();
+		for mod_name in ["first-mod", "second-mod"] {
+			let restored =
+				fs::read_to_string(format!("{root}/user/mods/{mod_name}/shared.js")).await.unwrap();
+			assert_eq!(restored, "shared library");
+		}
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_deflated_backup_is_smaller_and_restores_the_same_files() {
+		let mut provider = MockTimeProvider::new();
+		provider
+			.expect_get_current_time()
+			.returning(DateTime::<Utc>::default);
+		let path = "./test_output/compressed_backup_test";
+		let _discard = fs::remove_dir_all(path).await;
+		let data_path = "./test_data/backed_up_data";
+		let data_project = PathAccess::from(data_path, data_path).unwrap();
+		let data_access = SptAccess::init(&data_project, provider).await.unwrap();
+		let mut archives = Vec::new();
+		let kinds = [("stored", CompressionKind::Stored), ("deflated", CompressionKind::Deflated)];
+		for (name, compression) in kinds {
+			let backup_path = format!("{path}/{name}");
+			fs::create_dir_all(&backup_path).await.unwrap();
+			let options = BackupOptions {
+				compression,
+				level: Some(9),
+				label: Some(name.to_string()),
+				..BackupOptions::default()
+			};
+			data_access.backup_to(&backup_path, &options).unwrap();
+			archives.push(SptAccess::<MockTimeProvider>::find_latest_backup(&backup_path).unwrap());
+		}
+		let root = format!("{path}/root");
+		create_spt_folder(&root).await;
+		let project = PathAccess::from(&root, &root).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+
+		spt_access
+			.restore_from(&archives[1], OverwritePolicy::Overwrite, RestoreScope::All)
+			.unwrap();
+
+		let stored_size = std::fs::metadata(&archives[0]).unwrap().len();
+		let deflated_size = std::fs::metadata(&archives[1]).unwrap().len();
+		assert!(deflated_size < stored_size, "{deflated_size} >= {stored_size}");
+		let manifest = SptAccess::<MockTimeProvider>::read_backup_manifest(&archives[1]).unwrap();
+		assert!(!manifest.is_empty());
+		for (file, hash) in manifest {
+			let restored = std::fs::read(format!("{root}/{file}")).unwrap();
+			let original = std::fs::read(format!("{data_path}/{file}")).unwrap();
+			assert_eq!(restored, original, "{file}");
+			assert_eq!(sha256::digest(restored.as_slice()), hash);
+		}
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	/// Backs up the test data and returns the archive and a fresh SPT root to restore it to.
+	async fn backup_for_restore(path: &str) -> (PathBuf, SptAccess<MockTimeProvider>) {
+		let mut provider = MockTimeProvider::new();
+		provider
+			.expect_get_current_time()
+			.returning(DateTime::<Utc>::default);
+		let _discard = fs::remove_dir_all(path).await;
+		let backup_path = format!("{path}/backups");
+		let root = format!("{path}/root");
+		fs::create_dir_all(&backup_path).await.unwrap();
+		create_spt_folder(&root).await;
+		let data_path = "./test_data/backed_up_data";
+		let data_project = PathAccess::from(data_path, data_path).unwrap();
+		SptAccess::init(&data_project, provider).await
+			.unwrap()
+			.backup_to(&backup_path, &BackupOptions::default())
 			.unwrap();
+		let archive_path = SptAccess::<MockTimeProvider>::find_latest_backup(&backup_path).unwrap();
+		let project = PathAccess::from(&root, &root).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+		(archive_path, spt_access)
+	}
+
+	#[tokio::test]
+	async fn integration_test_backup_with_integrity_restores() {
+		let path = "./test_output/integrity_valid_test";
+		let (archive_path, spt_access) = backup_for_restore(path).await;
+
+		spt_access
+			.restore_from(&archive_path, OverwritePolicy::Overwrite, RestoreScope::All)
+			.unwrap();
+
+		assert!(Path::new(&format!(
+			"{path}/root/user/mods/maxloo2-betterkeys-updated/package.json"
+		))
+		.is_file());
+		assert!(!Path::new(&format!("{path}/root/.sptmm")).exists());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_tampered_backup_is_rejected() {
+		let path = "./test_output/integrity_tampered_test";
+		let (archive_path, spt_access) = backup_for_restore(path).await;
+		let tampered_path = format!("{path}/backups/tampered.zip");
+		let mut archive = zip::ZipArchive::new(File::open(&archive_path).unwrap()).unwrap();
+		let mut zip_writer = zip::ZipWriter::new(File::create(&tampered_path).unwrap());
+		for index in 0..archive.len() {
+			let mut entry = archive.by_index(index).unwrap();
+			let mut data = Vec::new();
+			std::io::Read::read_to_end(&mut entry, &mut data).unwrap();
+			if entry.name().ends_with("package.json") {
+				data.extend_from_slice(b"garbage");
+			}
+			zip_writer
+				.start_file(entry.name(), zip::write::SimpleFileOptions::default())
+				.unwrap();
+			zip_writer.write_all(&data).unwrap();
+		}
+		zip_writer.finish().unwrap();
+
+		let result =
+			spt_access.restore_from(&tampered_path, OverwritePolicy::Overwrite, RestoreScope::All);
+
+		assert!(result.is_err());
+		assert!(!Path::new(&format!("{path}/root/user")).exists());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_find_latest_backup() {
+		let path = "./test_output/latest_backup_test";
+		let _discard = fs::remove_dir_all(path).await;
+		fs::create_dir_all(path).await.unwrap();
+		for file_name in [
+			"backup_2024-06-11T19-06-55Z.zip",
+			"backup_2024-07-02T08-15-00Z.zip",
+			"backup_2024-07-01T23-59-59Z.zip",
+			"backup_not-a-time.zip",
+			"notes.txt",
+		] {
+			fs::write(format!("{path}/{file_name}"), "").await.unwrap();
+		}
+
+		let latest = SptAccess::<MockTimeProvider>::find_latest_backup(path).unwrap();
+
+		assert_eq!(latest, Path::new(path).join("backup_2024-07-02T08-15-00Z.zip"));
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_find_latest_backup_without_backups_errors() {
+		let path = "./test_output/no_backups_test";
+		fs::create_dir_all(path).await.unwrap();
+		fs::write(format!("{path}/notes.txt"), "").await.unwrap();
+
+		assert!(SptAccess::<MockTimeProvider>::find_latest_backup(path).is_err());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[test]
+	fn backup_path_uses_minutes_not_months() {
+		let time = DateTime::parse_from_rfc3339("2024-06-11T19:42:55Z").unwrap().to_utc();
+		let backup_name = format!(
+			"{}{}.zip",
+			backup::BACKUP_FILE_PREFIX,
+			time.format(backup::BACKUP_TIME_FORMAT)
+		);
+
+		assert_eq!(backup_name, "backup_2024-06-11T19-42-55Z.zip");
+		assert_eq!(backup::parse_backup_time(&backup_name), Some(time));
+	}
+
+	#[test]
+	fn labeled_backup_name_keeps_timestamp_parsable() {
+		let time = DateTime::parse_from_rfc3339("2024-06-11T19:42:55Z").unwrap().to_utc();
+
+		let backup_name = backup::backup_file_name(time, Some("before-3.9-update")).unwrap();
+
+		assert_eq!(backup_name, "backup_before-3.9-update_2024-06-11T19-42-55Z.zip");
+		assert_eq!(backup::parse_backup_time(&backup_name), Some(time));
+	}
+
+	#[test]
+	fn unsafe_backup_labels_are_rejected() {
+		let time = DateTime::parse_from_rfc3339("2024-06-11T19:42:55Z").unwrap().to_utc();
+
+		for label in ["", "../escape", "with space", "a/b", "..", "con:"] {
+			assert!(backup::backup_file_name(time, Some(label)).is_err(), "{label}");
+		}
+	}
+
+	#[tokio::test]
+	async fn integration_test_cancelled_backup_removes_partial_file() {
+		let mut provider = MockTimeProvider::new();
+		provider
+			.expect_get_current_time()
+			.returning(DateTime::<Utc>::default);
+		let path = PathBuf::from("./test_output/cancelled_backup_test");
+		let _discard = fs::remove_dir_all(&path).await;
+		fs::create_dir_all(&path).await.unwrap();
+		let path1 = "./test_data/backed_up_data";
+		let project = PathAccess::from(path1, path1).unwrap();
+		let cancellation = BackupCancellation::new();
+		cancellation.cancel();
+		let (sender, _receiver) = tokio::sync::mpsc::unbounded_channel();
+
+		let result = SptAccess::init(&project, provider)
+			.await
+			.unwrap()
+			.backup_to_async(&path, BackupOptions::default(), cancellation, sender)
+			.await;
+
+		assert!(result.is_err());
+		let mut entries = fs::read_dir(&path).await.unwrap();
+		assert!(entries.next_entry().await.unwrap().is_none());
 		fs::remove_dir_all(&path).await.unwrap()
 	}
 
@@ -408,9 +1888,1393 @@ mod tests {
 		assert_eq!(buf, Some("test_data/1.2.3_/maxloo2-betterkeys-updated"))
 	}
 
+	#[test]
+	fn only_top_level_server_package_files_are_read() {
+		assert!(is_server_package("user/mods/maxloo2-betterkeys-updated/package.json"));
+		assert!(!is_server_package(
+			"user/mods/maxloo2-betterkeys-updated/node_modules/x/package.json"
+		));
+		assert!(!is_server_package("BepInEx/plugins/package.json"));
+	}
+
 	#[test]
 	fn when_parsing_no_dirs_return_none() {
 		let buf = dir_parser("test_data").unwrap();
 		assert_eq!(buf, None)
 	}
+
+	#[tokio::test]
+	async fn integration_test_remove_all_mods_keeps_protected_plugins() {
+		let path = "./test_output/remove_mods_protected_test";
+		let _discard = fs::remove_dir_all(path).await;
+		for folder in [
+			"BepInEx/plugins/spt",
+			"BepInEx/plugins/SPT",
+			"BepInEx/plugins/UserMod",
+			"user/mods/user-server-mod",
+		] {
+			fs::create_dir_all(format!("{path}/{folder}")).await.unwrap();
+			fs::write(format!("{path}/{folder}/mod.dll"), "").await.unwrap();
+		}
+		create_spt_folder(path).await;
+		let project = PathAccess::from(path, path).unwrap();
+
+		SptAccess::init(&project, MockTimeProvider::new()).await
+			.unwrap()
+			.remove_all_mods()
+			.await
+			.unwrap();
+
+		assert!(Path::new(&format!("{path}/BepInEx/plugins/spt/mod.dll")).is_file());
+		assert!(Path::new(&format!("{path}/BepInEx/plugins/SPT/mod.dll")).is_file());
+		assert!(!Path::new(&format!("{path}/BepInEx/plugins/UserMod")).exists());
+		assert!(!Path::new(&format!("{path}/user/mods/user-server-mod")).exists());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_remove_all_mods_with_custom_protected_plugins() {
+		let path = "./test_output/remove_mods_custom_protected_test";
+		let _discard = fs::remove_dir_all(path).await;
+		for folder in ["BepInEx/plugins/spt", "BepInEx/plugins/Fika", "user/mods"] {
+			fs::create_dir_all(format!("{path}/{folder}")).await.unwrap();
+		}
+		create_spt_folder(path).await;
+		let project = PathAccess::from(path, path).unwrap();
+
+		SptAccess::init(&project, MockTimeProvider::new()).await
+			.unwrap()
+			.with_protected_plugin_folders(vec!["spt".to_string(), "fika".to_string()])
+			.remove_all_mods()
+			.await
+			.unwrap();
+
+		assert!(Path::new(&format!("{path}/BepInEx/plugins/spt")).is_dir());
+		assert!(Path::new(&format!("{path}/BepInEx/plugins/Fika")).is_dir());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_custom_server_file_name() {
+		let path = "./test_output/custom_server_file_name_test";
+		let _discard = fs::remove_dir_all(path).await;
+		fs::create_dir_all(path).await.unwrap();
+		fs::write(format!("{path}/Fork.Server.exe"), "").await.unwrap();
+		let project = PathAccess::from(path, path).unwrap();
+		let custom_names = vec!["Fork.Server.exe".to_string()];
+
+		let default_result = SptAccess::init_with_server_file_names(
+			&project,
+			MockTimeProvider::new(),
+			&[SERVER_FILE_NAME.to_string(), OLD_SERVER_FILE_NAME.to_string()],
+		)
+		.await;
+		let custom_result = SptAccess::init_with_server_file_names(
+			&project,
+			MockTimeProvider::new(),
+			&custom_names,
+		)
+		.await;
+
+		assert!(default_result.is_err());
+		assert!(custom_result.is_ok());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[test]
+	fn bepinex_subfolders_are_classified_as_client() {
+		for path in [
+			"BepInEx/plugins/x.dll",
+			"BepInEx/patchers/x.dll",
+			"BepInEx/monomod/x.dll",
+			"BepInEx/config/x.cfg",
+		] {
+			assert_eq!(file_parser(&mut &*path), FileType::Client, "{path}");
+		}
+		assert_eq!(file_parser(&mut "user/mods/x/package.json"), FileType::Server);
+		assert_eq!(file_parser(&mut "README.md"), FileType::Unknown);
+	}
+
+	#[test]
+	fn wrapped_paths_are_routed_from_the_first_spt_folder() {
+		let mut server_path = "Wrapper/user/mods/x/package.json";
+		assert_eq!(file_parser(&mut server_path), FileType::Server);
+		assert_eq!(server_path, "user/mods/x/package.json");
+
+		let mut client_path = "Wrapper/BepInEx/plugins/y.dll";
+		assert_eq!(file_parser(&mut client_path), FileType::Client);
+		assert_eq!(client_path, "BepInEx/plugins/y.dll");
+
+		let mut unrouted_path = "Wrapper/docs/README.md";
+		assert_eq!(file_parser(&mut unrouted_path), FileType::Unknown);
+		assert_eq!(unrouted_path, "Wrapper/docs/README.md");
+	}
+
+	#[tokio::test]
+	async fn integration_test_install_keeps_bepinex_subfolders() {
+		let provider = MockTimeProvider::new();
+		let path = "./test_output/install_bepinex_subfolders_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let archive_path = format!("{path}/patcher_mod.zip");
+		let entries = ["BepInEx/patchers/x.dll", "BepInEx/monomod/y.dll"];
+		write_test_zip(&archive_path, &entries.map(|entry| (entry, entry)));
+		let project = PathAccess::from(path, path).unwrap();
+
+		SptAccess::init(&project, provider).await
+			.unwrap()
+			.install_mod(
+				&archive_path,
+				&TestModName::new("Patcher", "1.0.0"),
+				InstallTarget::Client,
+			)
+			.unwrap();
+
+		assert!(Path::new(&format!("{path}/BepInEx/patchers/x.dll")).is_file());
+		assert!(Path::new(&format!("{path}/BepInEx/monomod/y.dll")).is_file());
+		assert!(!Path::new(&format!("{path}/BepInEx/plugins")).exists());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_never_install_skips_matching_files_for_every_mod() {
+		let provider = MockTimeProvider::new();
+		let path = "./test_output/never_install_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let mods = [
+			("First", ["BepInEx/plugins/first/first.dll", "BepInEx/plugins/first/readme.txt"]),
+			("Second", ["BepInEx/plugins/second/second.dll", "BepInEx/plugins/second/readme.txt"]),
+		];
+		for (name, entries) in &mods {
+			write_test_zip(&format!("{path}/{name}.zip"), &entries.map(|entry| (entry, entry)));
+		}
+		let project = PathAccess::from(path, path).unwrap();
+		let install_options = InstallOptions::default()
+			.with_never_install(&["**/readme.txt".to_string()])
+			.unwrap();
+		let spt_access = SptAccess::init(&project, provider)
+			.await
+			.unwrap()
+			.with_install_options(install_options);
+
+		for (name, entries) in &mods {
+			let archive_path = format!("{path}/{name}.zip");
+			let test_mod = TestModName::new(name, "1.0.0");
+			let report = spt_access
+				.install_mod(&archive_path, &test_mod, InstallTarget::Client)
+				.unwrap();
+
+			assert_eq!(report.installed_files, 1);
+			assert_eq!(report.skipped_files, vec![entries[1].to_string()]);
+			assert!(Path::new(&format!("{path}/{}", entries[0])).is_file());
+			assert!(!Path::new(&format!("{path}/{}", entries[1])).exists());
+			assert!(spt_access
+				.is_same_installed_version(&archive_path, &test_mod, InstallTarget::Client)
+				.unwrap());
+		}
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn init_rejects_cache_inside_spt_folder() {
+		let path = "./test_output/overlapping_roots_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		// An absolute project path is used as the cache folder as is.
+		let cache_inside = std::path::absolute(path).unwrap().join("cache");
+		let project = PathAccess::from(&cache_inside, path).unwrap();
+
+		let error = SptAccess::init(&project, MockTimeProvider::new())
+			.await
+			.expect_err("overlapping folders should be rejected");
+
+		assert!(error.to_string().contains("overlap"), "{error}");
+		assert!(crate::remote_mod_access::cache_mod_access::CacheModAccess::init(&project)
+			.await
+			.is_err());
+		assert!(!Path::new(&format!("{path}/install_hash")).exists());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_config_only_restore_keeps_plugins() {
+		let mut provider = MockTimeProvider::new();
+		provider
+			.expect_get_current_time()
+			.returning(DateTime::<Utc>::default);
+		let path = "./test_output/config_only_restore_test";
+		let _discard = fs::remove_dir_all(path).await;
+		let root = format!("{path}/root");
+		let config_file = format!("{root}/{BEPINEX_CONFIG_PATH}/mod.cfg");
+		let plugin_file = format!("{root}/BepInEx/plugins/mod/mod.dll");
+		fs::create_dir_all(format!("{root}/{BEPINEX_CONFIG_PATH}")).await.unwrap();
+		fs::create_dir_all(format!("{root}/BepInEx/plugins/mod")).await.unwrap();
+		fs::write(&config_file, "good config").await.unwrap();
+		fs::write(&plugin_file, "old plugin").await.unwrap();
+		create_spt_folder(&root).await;
+		let backup_path = format!("{path}/backups");
+		fs::create_dir_all(&backup_path).await.unwrap();
+		let project = PathAccess::from(&root, &root).unwrap();
+		let options = BackupOptions {
+			include_config: true,
+			..BackupOptions::default()
+		};
+		let spt_access = SptAccess::init(&project, provider).await.unwrap();
+		spt_access.backup_to(&backup_path, &options).unwrap();
+
+		fs::write(&config_file, "bad config").await.unwrap();
+		fs::write(&plugin_file, "new plugin").await.unwrap();
+		let archive_path = SptAccess::<MockTimeProvider>::find_latest_backup(&backup_path).unwrap();
+		spt_access
+			.restore_from(&archive_path, OverwritePolicy::Overwrite, RestoreScope::Config)
+			.unwrap();
+
+		assert_eq!(fs::read_to_string(&config_file).await.unwrap(), "good config");
+		assert_eq!(fs::read_to_string(&plugin_file).await.unwrap(), "new plugin");
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_backup_includes_profiles_only_when_asked() {
+		let mut provider = MockTimeProvider::new();
+		provider
+			.expect_get_current_time()
+			.returning(DateTime::<Utc>::default);
+		let path = "./test_output/profile_backup_test";
+		let _discard = fs::remove_dir_all(path).await;
+		let root = format!("{path}/root");
+		let profile_file = format!("{root}/{USER_PROFILES_PATH}/5c0647fdd443bc2504c2d371.json");
+		fs::create_dir_all(format!("{root}/{USER_PROFILES_PATH}")).await.unwrap();
+		fs::create_dir_all(format!("{root}/user/mods/some-mod")).await.unwrap();
+		fs::write(&profile_file, r#"{ "info": { "username": "test" } }"#).await.unwrap();
+		fs::write(format!("{root}/user/mods/some-mod/package.json"), "{}").await.unwrap();
+		create_spt_folder(&root).await;
+		let project = PathAccess::from(&root, &root).unwrap();
+		let spt_access = SptAccess::init(&project, provider).await.unwrap();
+
+		let default_path = format!("{path}/default");
+		fs::create_dir_all(&default_path).await.unwrap();
+		spt_access.backup_to(&default_path, &BackupOptions::default()).unwrap();
+		let default_archive =
+			SptAccess::<MockTimeProvider>::find_latest_backup(&default_path).unwrap();
+		let archive = zip::ZipArchive::new(File::open(&default_archive).unwrap()).unwrap();
+		assert!(!archive.file_names().any(|name| name.starts_with(USER_PROFILES_PATH)));
+
+		let profiles_path = format!("{path}/profiles");
+		fs::create_dir_all(&profiles_path).await.unwrap();
+		let options = BackupOptions {
+			include_profiles: true,
+			..BackupOptions::default()
+		};
+		spt_access.backup_to(&profiles_path, &options).unwrap();
+		let archive_path =
+			SptAccess::<MockTimeProvider>::find_latest_backup(&profiles_path).unwrap();
+
+		fs::remove_dir_all(format!("{root}/{USER_PROFILES_PATH}")).await.unwrap();
+		spt_access
+			.restore_from(&archive_path, OverwritePolicy::Overwrite, RestoreScope::All)
+			.unwrap();
+		assert_eq!(
+			fs::read_to_string(&profile_file).await.unwrap(),
+			r#"{ "info": { "username": "test" } }"#
+		);
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_preview_install_leaves_spt_folder_untouched() {
+		let provider = MockTimeProvider::new();
+		let buf = PathBuf::from("test_data/1.2.3_maxloo2-betterkeys-updated-v1.2.3.zip");
+		let path = "./test_output/preview_install_test";
+		let _discard = fs::remove_dir_all(path).await;
+		let root = format!("{path}/root");
+		let out_dir = format!("{path}/preview");
+		create_spt_folder(&root).await;
+		let project = PathAccess::from(&root, &root).unwrap();
+		let spt_access = SptAccess::init(&project, provider).await.unwrap();
+
+		spt_access.preview_install(&buf, &out_dir).unwrap();
+
+		assert!(Path::new(&format!("{out_dir}/{RESTORED_MOD_PATH}/package.json")).is_file());
+		assert!(!Path::new(&format!("{root}/user")).exists());
+		assert!(spt_access.list_installed().unwrap().is_empty());
+		assert!(spt_access.preview_install(&buf, format!("{root}/preview")).is_err());
+		assert!(!Path::new(&format!("{root}/preview")).exists());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_restore_preview_compares_with_existing_files() {
+		let mut provider = MockTimeProvider::new();
+		provider
+			.expect_get_current_time()
+			.returning(DateTime::<Utc>::default);
+		let path = "./test_output/restore_preview_test";
+		let _discard = fs::remove_dir_all(path).await;
+		let root = format!("{path}/root");
+		let mod_path = format!("{root}/user/mods/preview-mod");
+		fs::create_dir_all(&mod_path).await.unwrap();
+		for file in ["identical.js", "different.js", "newer.js", "removed.js"] {
+			fs::write(format!("{mod_path}/{file}"), file).await.unwrap();
+		}
+		create_spt_folder(&root).await;
+		let backup_path = format!("{path}/backups");
+		fs::create_dir_all(&backup_path).await.unwrap();
+		let project = PathAccess::from(&root, &root).unwrap();
+		let spt_access = SptAccess::init(&project, provider).await.unwrap();
+		spt_access.backup_to(&backup_path, &BackupOptions::default()).unwrap();
+
+		let set_modified = |file: &str, time: std::time::SystemTime| {
+			File::options()
+				.write(true)
+				.open(format!("{mod_path}/{file}"))
+				.unwrap()
+				.set_modified(time)
+				.unwrap();
+		};
+		fs::write(format!("{mod_path}/different.js"), "changed").await.unwrap();
+		set_modified(
+			"different.js",
+			std::time::UNIX_EPOCH + std::time::Duration::from_secs(86_400),
+		);
+		fs::write(format!("{mod_path}/newer.js"), "changed").await.unwrap();
+		set_modified(
+			"newer.js",
+			std::time::SystemTime::now() + std::time::Duration::from_secs(3_600),
+		);
+		fs::remove_file(format!("{mod_path}/removed.js")).await.unwrap();
+
+		let archive_path = SptAccess::<MockTimeProvider>::find_latest_backup(&backup_path).unwrap();
+		let mut previews = spt_access.preview_restore(&archive_path, RestoreScope::All).unwrap();
+		previews.sort_by(|a, b| a.path.cmp(&b.path));
+
+		let changes: Vec<_> =
+			previews.iter().map(|preview| (preview.path.as_str(), preview.change)).collect();
+		assert_eq!(
+			changes,
+			vec![
+				("user/mods/preview-mod/different.js", RestoreChange::Overwrite),
+				("user/mods/preview-mod/identical.js", RestoreChange::Identical),
+				("user/mods/preview-mod/newer.js", RestoreChange::OverwriteNewer),
+				("user/mods/preview-mod/removed.js", RestoreChange::New),
+			]
+		);
+		assert!(!Path::new(&format!("{mod_path}/removed.js")).exists());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_parallel_installed_checks_match_serial() {
+		let buf = PathBuf::from("test_data/1.2.3_maxloo2-betterkeys-updated-v1.2.3.zip");
+		let path = "./test_output/installed_checks_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let project = PathAccess::from(path, path).unwrap();
+		// Every copy installs the same files
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new())
+			.await
+			.unwrap()
+			.with_install_options(InstallOptions::default().with_force(true));
+		let test_mods: Vec<_> = (0..32)
+			.map(|index| TestModName::new(&format!("Better Keys {index}"), "1.2.3"))
+			.collect();
+		for test_mod in &test_mods {
+			spt_access.install_mod(&buf, test_mod, InstallTarget::Server).unwrap();
+		}
+		let not_installed = TestModName::new("Not Installed", "1.2.3");
+		let mut checks: Vec<_> = test_mods
+			.iter()
+			.map(|test_mod| (buf.as_path(), test_mod, InstallTarget::Server))
+			.collect();
+		checks.push((buf.as_path(), &not_installed, InstallTarget::Server));
+
+		let serial = spt_access.find_installed_versions(&checks, 1).unwrap();
+		let parallel = spt_access.find_installed_versions(&checks, 8).unwrap();
+
+		assert_eq!(parallel, serial);
+		assert!(parallel[..test_mods.len()].iter().all(|installed| *installed));
+		assert!(!parallel[test_mods.len()]);
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_conflicts_with_installed_mod() {
+		let buf = PathBuf::from("test_data/1.2.3_maxloo2-betterkeys-updated-v1.2.3.zip");
+		let path = "./test_output/conflicts_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+		spt_access
+			.install_mod(&buf, &TestModName::new("Better Keys", "1.2.3"), InstallTarget::Server)
+			.unwrap();
+		InstalledMod {
+			name: "Unrelated".to_string(),
+			version: Some("1.0.0".to_string()),
+			install_target: Some(InstallTarget::Server),
+			files: HashMap::from([(
+				"user/mods/unrelated/package.json".to_string(),
+				sha256::digest("{}"),
+			)]),
+		}
+		.write_to(&DiskFileSystem {}, &spt_access.layout.install_index().join("Unrelated"))
+		.unwrap();
+
+		let paths = spt_access.inspect_archive(&buf, InstallTarget::Server).unwrap();
+		let conflicts = spt_access.find_conflicts(&paths).unwrap();
+
+		assert!(!paths.is_empty());
+		assert_eq!(conflicts.len(), paths.len());
+		assert!(conflicts.iter().all(|conflict| conflict.installed_by == "Better Keys"));
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[test]
+	fn install_routes_and_hashes_files_in_memory() {
+		let buf = PathBuf::from("test_data/1.2.3_maxloo2-betterkeys-updated-v1.2.3.zip");
+		let layout = SptLayout::new("memory_spt");
+		let files = MemoryFileSystem::new();
+		files.create_dir_all(&layout.install_index()).unwrap();
+		let spt_access = SptAccess::new(layout, MockTimeProvider::new(), files);
+		let test_mod = TestModName::new("Better Keys", "1.2.3");
+
+		let report = spt_access.install_mod(&buf, &test_mod, InstallTarget::Server).unwrap();
+
+		let index_path = spt_access.layout.install_index().join(test_mod.to_file_name());
+		let installed_mod = InstalledMod::read_from(&spt_access.files, &index_path).unwrap();
+		assert_eq!(installed_mod.files.len(), report.installed_files);
+		for (file, hash) in &installed_mod.files {
+			assert!(file.starts_with("user/mods/"));
+			let data = spt_access.files.read(&spt_access.layout.root().join(file)).unwrap();
+			assert_eq!(&sha256::digest(data.as_slice()), hash);
+		}
+		assert_eq!(spt_access.files.file_paths().len(), report.installed_files + 1);
+		assert!(spt_access
+			.is_same_installed_version(&buf, &test_mod, InstallTarget::Server)
+			.unwrap());
+		assert_eq!(spt_access.installed_version(&test_mod).unwrap(), Versioning::new("1.2.3"));
+		assert!(!Path::new("memory_spt").exists());
+	}
+
+	#[tokio::test]
+	async fn integration_test_install_rejects_empty_archive() {
+		let path = "./test_output/empty_archive_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let archive_path = format!("{path}/empty.zip");
+		fs::write(&archive_path, "").await.unwrap();
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+
+		let err = spt_access
+			.install_mod(&archive_path, &TestModName::new("Empty", "1.0.0"), InstallTarget::Client)
+			.unwrap_err();
+
+		assert!(err.to_string().contains("is empty or corrupt"), "{err}");
+		assert!(spt_access.list_installed().unwrap().is_empty());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	/// Writes the entries to a zip, marking `bad_entry` with a compression method no reader
+	/// supports.
+	fn write_zip_with_bad_entry(archive_path: &str, entries: &[&str], bad_entry: &str) {
+		let mut zip_writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+		let options = zip::write::SimpleFileOptions::default()
+			.compression_method(zip::CompressionMethod::Stored);
+		for entry in entries {
+			zip_writer.start_file(*entry, options).unwrap();
+			zip_writer.write_all(entry.as_bytes()).unwrap();
+		}
+		let mut data = zip_writer.finish().unwrap().into_inner();
+		// Local file headers keep the method at offset 8 and the name at 30, the central directory
+		// at 10 and 46
+		for (signature, method_offset, name_offset) in
+			[(b"PK\x03\x04", 8, 30), (b"PK\x01\x02", 10, 46)]
+		{
+			let header = data
+				.windows(name_offset + bad_entry.len())
+				.position(|window| {
+					window.starts_with(signature) && window.ends_with(bad_entry.as_bytes())
+				})
+				.unwrap();
+			data[header + method_offset..header + method_offset + 2]
+				.copy_from_slice(&11u16.to_le_bytes());
+		}
+		std::fs::write(archive_path, data).unwrap();
+	}
+
+	#[tokio::test]
+	async fn integration_test_skip_bad_entries_installs_the_rest() {
+		let path = "./test_output/skip_bad_entries_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let archive_path = format!("{path}/mod.zip");
+		let entries = [
+			"BepInEx/plugins/mod/first.dll",
+			"BepInEx/plugins/mod/bad.dll",
+			"BepInEx/plugins/mod/last.dll",
+		];
+		write_zip_with_bad_entry(&archive_path, &entries, entries[1]);
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+		let test_mod = TestModName::new("Mod", "1.0.0");
+
+		assert!(spt_access.install_mod(&archive_path, &test_mod, InstallTarget::Client).is_err());
+
+		let spt_access =
+			spt_access.with_install_options(InstallOptions::default().with_skip_bad_entries(true));
+		let report =
+			spt_access.install_mod(&archive_path, &test_mod, InstallTarget::Client).unwrap();
+
+		assert_eq!(report.installed_files, 2);
+		assert_eq!(
+			report.bad_entries.iter().map(|(entry, _)| entry.as_str()).collect::<Vec<_>>(),
+			vec![entries[1]]
+		);
+		assert!(Path::new(&format!("{path}/{}", entries[0])).is_file());
+		assert!(!Path::new(&format!("{path}/{}", entries[1])).exists());
+		assert!(Path::new(&format!("{path}/{}", entries[2])).is_file());
+		let installed = spt_access.list_installed().unwrap();
+		assert!(!installed[0].files.contains_key(entries[1]));
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_audit_against_lockfile() {
+		let buf = PathBuf::from("test_data/1.2.3_maxloo2-betterkeys-updated-v1.2.3.zip");
+		let path = "./test_output/audit_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+		spt_access
+			.install_mod(&buf, &TestModName::new("Better Keys", "1.2.3"), InstallTarget::Server)
+			.unwrap();
+		let lockfile_path = format!("{path}/spt_mods.lock");
+		Lockfile::from_installed(spt_access.list_installed().unwrap())
+			.write(&lockfile_path)
+			.unwrap();
+		let lockfile = Lockfile::read(&lockfile_path).unwrap();
+
+		assert_eq!(spt_access.audit(&lockfile).unwrap(), Vec::new());
+
+		let mut changed_hash = lockfile.clone();
+		let (changed_file, hash) = changed_hash.mods[0].files.iter_mut().next().unwrap();
+		let changed_file = changed_file.clone();
+		*hash = "0".repeat(64);
+		assert_eq!(
+			spt_access.audit(&changed_hash).unwrap(),
+			vec![ModDrift::HashMismatch {
+				name: "Better Keys".to_string(),
+				files: vec![changed_file],
+			}]
+		);
+
+		let mut drifted = lockfile.clone();
+		drifted.mods[0].version = Some("1.2.4".to_string());
+		drifted.mods.push(InstalledMod {
+			name: "SAIN".to_string(),
+			version: Some("2.3.0".to_string()),
+			install_target: Some(InstallTarget::Client),
+			files: HashMap::new(),
+		});
+		fs::write(
+			format!("{path}/install_hash/Legacy_Mod"),
+			r#"{"user/mods/legacy/package.json":"abc"}"#,
+		)
+		.await
+		.unwrap();
+		assert_eq!(
+			spt_access.audit(&drifted).unwrap(),
+			vec![
+				ModDrift::Missing {
+					name: "SAIN".to_string()
+				},
+				ModDrift::Extra {
+					name: "Legacy_Mod".to_string()
+				},
+				ModDrift::WrongVersion {
+					name: "Better Keys".to_string(),
+					locked: Some("1.2.4".to_string()),
+					installed: Some("1.2.3".to_string()),
+				},
+			]
+		);
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_reinstall_restores_files_and_index() {
+		let buf = PathBuf::from("test_data/1.2.3_maxloo2-betterkeys-updated-v1.2.3.zip");
+		let path = "./test_output/reinstall_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+		let mod_name = TestModName::new("Better Keys", "1.2.3");
+		spt_access.install_mod(&buf, &mod_name, InstallTarget::Server).unwrap();
+		let installed = spt_access.list_installed().unwrap();
+		let mut files: Vec<_> = installed[0].files.keys().cloned().collect();
+		files.sort();
+		fs::write(format!("{path}/{}", files[0]), "corrupted").await.unwrap();
+		fs::remove_file(format!("{path}/{}", files[1])).await.unwrap();
+
+		let removed = spt_access.uninstall_mod(&mod_name, InstallTarget::Server).unwrap();
+		let mod_folder = Path::new(path).join("user/mods/maxloo2-betterkeys-updated");
+		assert!(removed.contains(&mod_folder.clone().into_os_string()));
+		assert!(!mod_folder.exists());
+		assert!(Path::new(path).join("user/mods").is_dir());
+		assert!(spt_access.list_installed().unwrap().is_empty());
+		assert!(files.iter().all(|file| !Path::new(path).join(file).exists()));
+
+		spt_access.install_mod(&buf, &mod_name, InstallTarget::Server).unwrap();
+		assert_eq!(spt_access.list_installed().unwrap(), installed);
+		let verifications = spt_access.verify_installed(1, |_| {}).unwrap();
+		assert!(verifications.iter().all(ModVerification::is_intact));
+		assert!(spt_access
+			.uninstall_mod(&TestModName::new("Missing", "1.0.0"), InstallTarget::Server)
+			.is_err());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_install_without_index() {
+		let buf = PathBuf::from("test_data/1.2.3_maxloo2-betterkeys-updated-v1.2.3.zip");
+		let path = "./test_output/install_no_index_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new())
+			.await
+			.unwrap()
+			.with_install_options(InstallOptions::default().with_no_index(true));
+		let mod_name = TestModName::new("Better Keys", "1.2.3");
+
+		let report = spt_access.install_mod(&buf, &mod_name, InstallTarget::Server).unwrap();
+
+		assert!(report.installed_files > 0);
+		assert!(Path::new(path)
+			.join("user/mods/maxloo2-betterkeys-updated/package.json")
+			.is_file());
+		assert!(!Path::new(path).join("install_hash").join(mod_name.to_file_name()).exists());
+		assert!(spt_access.list_installed().unwrap().is_empty());
+		assert!(!spt_access
+			.is_same_installed_version(&buf, &mod_name, InstallTarget::Server)
+			.unwrap());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_uninstall_keeps_files_other_mods_claim() {
+		let buf = PathBuf::from("test_data/1.2.3_maxloo2-betterkeys-updated-v1.2.3.zip");
+		let path = "./test_output/uninstall_shared_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new())
+			.await
+			.unwrap()
+			.with_install_options(InstallOptions::default().with_force(true));
+		let original = TestModName::new("Better Keys", "1.2.3");
+		let copy = TestModName::new("Better Keys Copy", "1.2.3");
+		spt_access.install_mod(&buf, &original, InstallTarget::Server).unwrap();
+		spt_access.install_mod(&buf, &copy, InstallTarget::Server).unwrap();
+		let package_path =
+			Path::new(path).join("user/mods/maxloo2-betterkeys-updated/package.json");
+
+		assert!(spt_access.uninstall_mod(&copy, InstallTarget::Client).is_err());
+		let removed = spt_access.uninstall_mod(&copy, InstallTarget::Server).unwrap();
+		assert!(removed.is_empty());
+		assert!(package_path.is_file());
+		assert_eq!(spt_access.list_installed().unwrap().len(), 1);
+
+		let removed = spt_access.uninstall_mod(&original, InstallTarget::Server).unwrap();
+		assert!(removed.contains(&package_path.clone().into_os_string()));
+		assert!(!package_path.exists());
+		assert!(spt_access.list_installed().unwrap().is_empty());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	/// Writes a zip with the entries and their contents.
+	fn write_test_zip(archive_path: &str, entries: &[(&str, &str)]) {
+		let mut zip_writer = zip::ZipWriter::new(File::create(archive_path).unwrap());
+		for (entry, data) in entries {
+			zip_writer
+				.start_file(*entry, zip::write::SimpleFileOptions::default())
+				.unwrap();
+			zip_writer.write_all(data.as_bytes()).unwrap();
+		}
+		zip_writer.finish().unwrap();
+	}
+
+	#[tokio::test]
+	async fn integration_test_install_descriptor_remaps_folders() {
+		let provider = MockTimeProvider::new();
+		let path = "./test_output/install_descriptor_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let archive_path = format!("{path}/descriptor_mod.zip");
+		write_test_zip(
+			&archive_path,
+			&[
+				("sptmm.json", r#"{ "mappings": { "plugins/": "BepInEx/plugins/" } }"#),
+				("plugins/MyMod.dll", "dll"),
+				("user/mods/x/package.json", "{}"),
+			],
+		);
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, provider).await.unwrap();
+
+		spt_access
+			.install_mod(
+				&archive_path,
+				&TestModName::new("Descriptor", "1.0.0"),
+				InstallTarget::Client,
+			)
+			.unwrap();
+
+		assert!(Path::new(&format!("{path}/BepInEx/plugins/MyMod.dll")).is_file());
+		assert!(!Path::new(&format!("{path}/plugins")).exists());
+		assert!(!Path::new(&format!("{path}/sptmm.json")).exists());
+		let installed = spt_access.list_installed().unwrap();
+		assert!(installed[0].files.contains_key("BepInEx/plugins/MyMod.dll"));
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_install_skips_entries_with_invalid_names() {
+		let provider = MockTimeProvider::new();
+		let path = "./test_output/install_invalid_names_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let archive_path = format!("{path}/codepage_mod.zip");
+		let entries = ["BepInEx/plugins/Mod.dll", "BepInEx/plugins/caf#.txt"];
+		write_test_zip(&archive_path, &entries.map(|entry| (entry, entry)));
+		// A legacy codepage é, which isn't valid UTF-8 on its own
+		let mut archive = std::fs::read(&archive_path).unwrap();
+		for index in 0..archive.len() - 3 {
+			if &archive[index..index + 4] == b"caf#" {
+				archive[index + 3] = 0xE9;
+			}
+		}
+		std::fs::write(&archive_path, archive).unwrap();
+		let project = PathAccess::from(path, path).unwrap();
+
+		let report = SptAccess::init(&project, provider).await
+			.unwrap()
+			.install_mod(
+				&archive_path,
+				&TestModName::new("Codepage", "1.0.0"),
+				InstallTarget::Client,
+			)
+			.unwrap();
+
+		assert_eq!(report.installed_files, 1);
+		assert_eq!(report.invalid_names, vec!["BepInEx/plugins/caf\u{FFFD}.txt"]);
+		assert!(Path::new(&format!("{path}/BepInEx/plugins/Mod.dll")).is_file());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_pin_records_installed_version_in_configuration() {
+		let provider = MockTimeProvider::new();
+		let path = "./test_output/pin_installed_version_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let url = "https://github.com/maxloo2/betterkeys-updated";
+		let cfg_path = format!("{path}/spt_mods.json");
+		fs::write(
+			&cfg_path,
+			format!(r#"{{ "spt_version": "3.9.8", "mods": [{{ "url": "{url}" }}] }}"#),
+		)
+		.await
+		.unwrap();
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, provider).await.unwrap();
+		spt_access
+			.install_mod(
+				"test_data/1.2.3_maxloo2-betterkeys-updated-v1.2.3.zip",
+				&TestModName::new("betterkeys-updated", "1.2.3"),
+				InstallTarget::Server,
+			)
+			.unwrap();
+		let cfg_access = ConfigurationAccess::from_file(&cfg_path);
+
+		let installed_version = spt_access.list_installed().unwrap()[0].get_version();
+		let mut configuration = cfg_access.read_remote_mods().await.unwrap();
+		configuration.set_mod_version(url, installed_version).unwrap();
+		cfg_access.write_remote_mods(&configuration).await.unwrap();
+
+		let pinned = cfg_access.read_remote_mods().await.unwrap();
+		let pinned_version = pinned.mods[0].version.as_ref().and_then(VersionConstraint::as_exact);
+		assert_eq!(pinned_version, Versioning::new("1.2.3").as_ref());
+
+		let mut configuration = pinned;
+		configuration.set_mod_version(url, None).unwrap();
+		cfg_access.write_remote_mods(&configuration).await.unwrap();
+		assert_eq!(cfg_access.read_remote_mods().await.unwrap().mods[0].version, None);
+		assert!(configuration.set_mod_version("https://github.com/other/mod", None).is_err());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_install_fails_on_files_of_other_mods_unless_forced() {
+		let buf = PathBuf::from("test_data/1.2.3_maxloo2-betterkeys-updated-v1.2.3.zip");
+		let path = "./test_output/install_conflict_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+		let original = TestModName::new("Better Keys", "1.2.3");
+		let copy = TestModName::new("Better Keys Copy", "1.2.3");
+		spt_access.install_mod(&buf, &original, InstallTarget::Server).unwrap();
+
+		// Updating the mod itself overwrites its own files without a conflict
+		assert!(spt_access.install_mod(&buf, &original, InstallTarget::Server).is_ok());
+		let err = spt_access.install_mod(&buf, &copy, InstallTarget::Server).unwrap_err();
+
+		let conflict = err.downcast_ref::<InstallConflict>().unwrap();
+		assert_eq!(conflict.mod_name, "Better Keys Copy");
+		assert!(conflict
+			.conflicts
+			.iter()
+			.any(|conflict| conflict.path == "user/mods/maxloo2-betterkeys-updated/package.json"));
+		assert!(conflict.conflicts.iter().all(|conflict| conflict.installed_by == "Better Keys"));
+		assert_eq!(spt_access.list_installed().unwrap().len(), 1);
+
+		let spt_access =
+			spt_access.with_install_options(InstallOptions::default().with_force(true));
+		let report = spt_access.install_mod(&buf, &copy, InstallTarget::Server).unwrap();
+		assert_eq!(report.conflicts, conflict.conflicts);
+		assert_eq!(spt_access.list_installed().unwrap().len(), 2);
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_failed_install_leaves_the_installed_version_unchanged() {
+		let path = "./test_output/staged_install_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let first = "BepInEx/plugins/mod/first.dll";
+		let last = "BepInEx/plugins/mod/last.dll";
+		let added = "BepInEx/plugins/mod/added.dll";
+		let archive_path = format!("{path}/mod.zip");
+		write_test_zip(&archive_path, &[first, last].map(|entry| (entry, entry)));
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+		spt_access
+			.install_mod(&archive_path, &TestModName::new("Mod", "1.0.0"), InstallTarget::Client)
+			.unwrap();
+
+		// The bad entry fails the install after the first file is extracted
+		let bad = "BepInEx/plugins/mod/bad.dll";
+		write_zip_with_bad_entry(&archive_path, &[added, bad, first], bad);
+		let new_version = TestModName::new("Mod", "2.0.0");
+		assert!(spt_access
+			.install_mod(&archive_path, &new_version, InstallTarget::Client)
+			.is_err());
+
+		assert!(!Path::new(&format!("{path}/{added}")).exists());
+		assert!(Path::new(&format!("{path}/{first}")).is_file());
+		assert!(Path::new(&format!("{path}/{last}")).is_file());
+		assert_eq!(spt_access.installed_version(&new_version).unwrap(), Versioning::new("1.0.0"));
+		assert!(!spt_access.layout.install_staging().exists());
+
+		// A successful update replaces the files and removes those the new version left out
+		write_test_zip(&archive_path, &[added, first].map(|entry| (entry, entry)));
+		spt_access.install_mod(&archive_path, &new_version, InstallTarget::Client).unwrap();
+		assert!(Path::new(&format!("{path}/{added}")).is_file());
+		assert!(!Path::new(&format!("{path}/{last}")).exists());
+		assert_eq!(spt_access.installed_version(&new_version).unwrap(), Versioning::new("2.0.0"));
+		assert!(!spt_access.layout.install_staging().exists());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_uninstall_removes_emptied_folders_up_to_the_spt_folders() {
+		let path = "./test_output/uninstall_empty_dirs_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let archive_path = format!("{path}/mod.zip");
+		let entries = ["BepInEx/plugins/mod/lib/mod.dll", "BepInEx/plugins/mod/readme.txt"];
+		write_test_zip(&archive_path, &entries.map(|entry| (entry, entry)));
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+		let test_mod = TestModName::new("Mod", "1.0.0");
+		let mod_folder = Path::new(path).join("BepInEx/plugins/mod");
+
+		let kept = SptAccess::init(&project, MockTimeProvider::new())
+			.await
+			.unwrap()
+			.with_install_options(InstallOptions::default().with_keep_empty_dirs(true));
+		kept.install_mod(&archive_path, &test_mod, InstallTarget::Client).unwrap();
+		kept.uninstall_mod(&test_mod, InstallTarget::Client).unwrap();
+		assert!(mod_folder.join("lib").is_dir());
+
+		spt_access.install_mod(&archive_path, &test_mod, InstallTarget::Client).unwrap();
+		let removed = spt_access.uninstall_mod(&test_mod, InstallTarget::Client).unwrap();
+
+		assert!(removed.contains(&mod_folder.join("lib").into_os_string()));
+		assert!(removed.contains(&mod_folder.clone().into_os_string()));
+		assert!(!mod_folder.exists());
+		assert!(spt_access.layout.client_mods().is_dir());
+		assert!(Path::new(path).join("BepInEx").is_dir());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_same_second_backups_get_a_counter() {
+		let time = DateTime::parse_from_rfc3339("2024-06-11T19:42:55Z").unwrap().to_utc();
+		let mut provider = MockTimeProvider::new();
+		provider.expect_get_current_time().returning(move || time);
+		let path = PathBuf::from("./test_output/same_second_backup_test");
+		let _discard = fs::remove_dir_all(&path).await;
+		fs::create_dir_all(&path).await.unwrap();
+		let path1 = "./test_data/backed_up_data";
+		let project = PathAccess::from(path1, path1).unwrap();
+		let spt_access = SptAccess::init(&project, provider).await.unwrap();
+
+		spt_access.backup_to(&path, &BackupOptions::default()).unwrap();
+		spt_access.backup_to(&path, &BackupOptions::default()).unwrap();
+
+		let first = path.join("backup_2024-06-11T19-42-55Z.zip");
+		let second = path.join("backup_2024-06-11T19-42-55Z_1.zip");
+		assert!(first.is_file());
+		assert!(second.is_file());
+		assert_eq!(backup::parse_backup_time("backup_2024-06-11T19-42-55Z_1.zip"), Some(time));
+		assert_eq!(SptAccess::<MockTimeProvider>::find_latest_backup(&path).unwrap(), second);
+		fs::remove_dir_all(&path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_install_with_several_write_threads_writes_every_file() {
+		let buf = PathBuf::from("test_data/1.2.3_maxloo2-betterkeys-updated-v1.2.3.zip");
+		let path = "./test_output/write_threads_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new())
+			.await
+			.unwrap()
+			.with_install_options(InstallOptions::default().with_write_threads(4));
+
+		let report = spt_access
+			.install_mod(&buf, &TestModName::new("Better Keys", "1.2.3"), InstallTarget::Server)
+			.unwrap();
+
+		let installed = spt_access.list_installed().unwrap();
+		assert_eq!(installed[0].files.len(), report.installed_files);
+		assert_eq!(report.installed_files, 32);
+		for (file, hash) in &installed[0].files {
+			let data = std::fs::read(Path::new(path).join(file)).unwrap();
+			assert_eq!(&sha256::digest(data.as_slice()), hash, "{file}");
+		}
+		assert!(!spt_access.layout.install_staging().exists());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_strict_hash_reinstalls_mods_with_deleted_files() {
+		let buf = PathBuf::from("test_data/1.2.3_maxloo2-betterkeys-updated-v1.2.3.zip");
+		let path = "./test_output/strict_hash_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+		let test_mod = TestModName::new("Better Keys", "1.2.3");
+		spt_access.install_mod(&buf, &test_mod, InstallTarget::Server).unwrap();
+		fs::remove_file(format!("{path}/user/mods/maxloo2-betterkeys-updated/src/mod.ts"))
+			.await
+			.unwrap();
+
+		assert!(spt_access
+			.is_same_installed_version(&buf, &test_mod, InstallTarget::Server)
+			.unwrap());
+		let strict =
+			spt_access.with_install_options(InstallOptions::default().with_strict_hash(true));
+		assert!(!strict.is_same_installed_version(&buf, &test_mod, InstallTarget::Server).unwrap());
+
+		strict.install_mod(&buf, &test_mod, InstallTarget::Server).unwrap();
+		assert!(strict.is_same_installed_version(&buf, &test_mod, InstallTarget::Server).unwrap());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_backup_writes_a_manifest_of_every_file() {
+		let mut provider = MockTimeProvider::new();
+		provider
+			.expect_get_current_time()
+			.returning(DateTime::<Utc>::default);
+		let path = "./test_output/backup_manifest_test";
+		let _discard = fs::remove_dir_all(path).await;
+		let root = format!("{path}/root");
+		let files = [
+			("user/mods/server-mod/package.json", "{}"),
+			("user/mods/server-mod/src/mod.js", "exports.mod = {};"),
+			("BepInEx/plugins/client.dll", "client"),
+		];
+		for (file, content) in files {
+			let file_path = Path::new(&root).join(file);
+			fs::create_dir_all(file_path.parent().unwrap()).await.unwrap();
+			fs::write(file_path, content).await.unwrap();
+		}
+		create_spt_folder(&root).await;
+		let backup_path = format!("{path}/backups");
+		fs::create_dir_all(&backup_path).await.unwrap();
+		let project = PathAccess::from(&root, &root).unwrap();
+		let spt_access = SptAccess::init(&project, provider).await.unwrap();
+
+		spt_access.backup_to(&backup_path, &BackupOptions::default()).unwrap();
+
+		let archive_path = SptAccess::<MockTimeProvider>::find_latest_backup(&backup_path).unwrap();
+		let manifest = SptAccess::<MockTimeProvider>::read_backup_manifest(&archive_path).unwrap();
+		let expected: BTreeMap<_, _> = files
+			.iter()
+			.map(|(file, content)| (file.to_string(), sha256::digest(*content)))
+			.collect();
+		assert_eq!(manifest, expected);
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_inspect_backup_lists_sizes_and_targets() {
+		let mut provider = MockTimeProvider::new();
+		provider
+			.expect_get_current_time()
+			.returning(DateTime::<Utc>::default);
+		let path = "./test_output/inspect_backup_test";
+		let _discard = fs::remove_dir_all(path).await;
+		let root = format!("{path}/root");
+		fs::create_dir_all(format!("{root}/user/mods/server-mod")).await.unwrap();
+		fs::create_dir_all(format!("{root}/BepInEx/plugins")).await.unwrap();
+		fs::write(format!("{root}/user/mods/server-mod/package.json"), "{}").await.unwrap();
+		fs::write(format!("{root}/BepInEx/plugins/client.dll"), "client").await.unwrap();
+		create_spt_folder(&root).await;
+		let project = PathAccess::from(&root, &root).unwrap();
+		let spt_access = SptAccess::init(&project, provider).await.unwrap();
+		let expected = vec![
+			BackupEntry {
+				path: "BepInEx/plugins/client.dll".to_string(),
+				size: 6,
+				target: Some(InstallTarget::Client),
+			},
+			BackupEntry {
+				path: "user/mods/server-mod/package.json".to_string(),
+				size: 2,
+				target: Some(InstallTarget::Server),
+			},
+		];
+
+		for deduplicate in [false, true] {
+			let backup_path = format!("{path}/backups_{deduplicate}");
+			fs::create_dir_all(&backup_path).await.unwrap();
+			let options = BackupOptions {
+				deduplicate,
+				..BackupOptions::default()
+			};
+			spt_access.backup_to(&backup_path, &options).unwrap();
+			let archive_path =
+				SptAccess::<MockTimeProvider>::find_latest_backup(&backup_path).unwrap();
+
+			assert_eq!(spt_access.inspect_backup(&archive_path).unwrap(), expected);
+		}
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_backup_diff_lists_added_removed_and_changed_files() {
+		let path = "./test_output/backup_diff_test";
+		let _discard = fs::remove_dir_all(path).await;
+		fs::create_dir_all(path).await.unwrap();
+		let from = format!("{path}/a.zip");
+		write_test_zip(
+			&from,
+			&[
+				("user/mods/kept/package.json", "same"),
+				("user/mods/changed/package.json", "old"),
+				("user/mods/removed/package.json", "gone"),
+			],
+		);
+		let to = format!("{path}/b.zip");
+		write_test_zip(
+			&to,
+			&[
+				("user/mods/kept/package.json", "same"),
+				("user/mods/changed/package.json", "new"),
+				("BepInEx/plugins/added.dll", "added"),
+			],
+		);
+
+		let differences = SptAccess::<MockTimeProvider>::diff_backups(&from, &to).unwrap();
+
+		let difference = |path: &str, change| BackupDifference {
+			path: path.to_string(),
+			change,
+		};
+		assert_eq!(
+			differences,
+			vec![
+				difference("BepInEx/plugins/added.dll", BackupChange::Added),
+				difference("user/mods/changed/package.json", BackupChange::Changed),
+				difference("user/mods/removed/package.json", BackupChange::Removed),
+			]
+		);
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_spt_upgrade_check_reports_declared_support() {
+		let path = "./test_output/spt_upgrade_check_test";
+		let _discard = fs::remove_dir_all(path).await;
+		let packages = [
+			("supported", r#"{ "name": "supported", "sptVersion": "~3.10" }"#),
+			("unsupported", r#"{ "name": "unsupported", "sptVersion": "~3.9.0" }"#),
+			("legacy", r#"{ "name": "legacy", "akiVersion": ">=3.8.0" }"#),
+			("undeclared", r#"{ "name": "undeclared" }"#),
+		];
+		for (folder, package) in packages {
+			fs::create_dir_all(format!("{path}/user/mods/{folder}")).await.unwrap();
+			fs::write(format!("{path}/user/mods/{folder}/package.json"), package).await.unwrap();
+		}
+		create_spt_folder(path).await;
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+
+		let target = Versioning::new("3.10.2").unwrap();
+		let compatibilities = spt_access.check_spt_upgrade(&target).unwrap();
+
+		let compatibility = |mod_name: &str, support| SptCompatibility {
+			mod_name: mod_name.to_string(),
+			support,
+		};
+		assert_eq!(
+			compatibilities,
+			vec![
+				compatibility("legacy", SptSupport::Supported),
+				compatibility("supported", SptSupport::Supported),
+				compatibility("undeclared", SptSupport::Undeclared),
+				compatibility(
+					"unsupported",
+					SptSupport::Unsupported {
+						requirement: "~3.9.0".to_string()
+					}
+				),
+			]
+		);
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	async fn install_fixture(path: &str, archive: &str, name: &str) -> SptAccess<MockTimeProvider> {
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+		spt_access
+			.install_mod(archive, &TestModName::new(name, "1.0.0"), InstallTarget::Server)
+			.unwrap();
+		spt_access
+	}
+
+	#[tokio::test]
+	async fn integration_test_install_from_rar_skips_folders_without_trailing_slash() {
+		let path = "./test_output/install_rar_test";
+		let spt_access = install_fixture(path, "test_data/1.0.0_rar-mod.rar", "rar-mod").await;
+
+		let mod_folder = format!("{path}/user/mods/rar-mod");
+		assert!(Path::new(&mod_folder).is_dir());
+		assert!(Path::new(&format!("{mod_folder}/package.json")).is_file());
+		let script = fs::read_to_string(format!("{mod_folder}/src/mod.js")).await.unwrap();
+		assert_eq!(script, "exports.mod = {};");
+		let mut installed_files: Vec<_> =
+			spt_access.list_installed().unwrap().remove(0).files.into_keys().collect();
+		installed_files.sort();
+		assert_eq!(
+			installed_files,
+			vec!["user/mods/rar-mod/package.json", "user/mods/rar-mod/src/mod.js"]
+		);
+		let inspected = spt_access
+			.inspect_archive("test_data/1.0.0_rar-mod.rar", InstallTarget::Server)
+			.unwrap();
+		assert_eq!(inspected, installed_files);
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_install_from_7z() {
+		let path = "./test_output/install_7z_test";
+		let spt_access =
+			install_fixture(path, "test_data/1.0.0_sevenzip-mod.7z", "sevenzip-mod").await;
+
+		let mut installed_files: Vec<_> =
+			spt_access.list_installed().unwrap().remove(0).files.into_keys().collect();
+		installed_files.sort();
+		assert_eq!(
+			installed_files,
+			vec!["user/mods/sevenzip-mod/package.json", "user/mods/sevenzip-mod/src/mod.js"]
+		);
+		assert!(Path::new(&format!("{path}/user/mods/sevenzip-mod/src/mod.js")).is_file());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_install_names_unsupported_archive_types() {
+		let path = "./test_output/unsupported_archive_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let archive_path = format!("{path}/installer.exe");
+		fs::write(&archive_path, b"MZ\x90\x00not an archive").await.unwrap();
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+
+		let err = spt_access
+			.install_mod(
+				&archive_path,
+				&TestModName::new("Installer", "1.0.0"),
+				InstallTarget::Server,
+			)
+			.unwrap_err();
+
+		assert!(err.to_string().contains("isn't a supported archive type (exe)"), "{err}");
+		assert!(spt_access.list_installed().unwrap().is_empty());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_install_suggests_the_other_target_when_nothing_installs() {
+		let path = "./test_output/other_target_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let client_archive = format!("{path}/client.zip");
+		let client_entry = "BepInEx/plugins/client-mod/client-mod.dll";
+		write_test_zip(&client_archive, &[(client_entry, client_entry)]);
+		let server_archive = format!("{path}/server.zip");
+		let server_entry = "user/mods/server-mod/package.json";
+		write_test_zip(&server_archive, &[(server_entry, server_entry)]);
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+
+		let client_mod = TestModName::new("Client", "1.0.0");
+		let err = spt_access
+			.install_mod(&client_archive, &client_mod, InstallTarget::Server)
+			.unwrap_err();
+		// The client target installs server mods too, so a server mod installs either way
+		let server_mod = TestModName::new("Server", "1.0.0");
+		let report = spt_access
+			.install_mod(&server_archive, &server_mod, InstallTarget::Client)
+			.unwrap();
+
+		assert!(err.to_string().contains("looks like a client mod; run Update Client"), "{err}");
+		assert_eq!(report.installed_files, 1);
+		assert_eq!(
+			spt_access.detect_other_target(&server_archive, InstallTarget::Client).unwrap(),
+			None
+		);
+		assert_eq!(
+			spt_access.detect_other_target(&client_archive, InstallTarget::Server).unwrap(),
+			Some(InstallTarget::Client)
+		);
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_install_refuses_to_write_into_protected_plugin_folders() {
+		let path = "./test_output/core_overwrite_test";
+		let _discard = fs::remove_dir_all(path).await;
+		create_spt_folder(path).await;
+		let archive = format!("{path}/core.zip");
+		let entries =
+			["BepInEx/plugins/spt/spt-core.dll", "BepInEx/plugins/sneaky-mod/sneaky-mod.dll"];
+		write_test_zip(&archive, &entries.map(|entry| (entry, entry)));
+		let project = PathAccess::from(path, path).unwrap();
+		let spt_access = SptAccess::init(&project, MockTimeProvider::new()).await.unwrap();
+		let spt_mod = TestModName::new("Sneaky", "1.0.0");
+
+		let err = spt_access.install_mod(&archive, &spt_mod, InstallTarget::Client).unwrap_err();
+
+		assert!(err.to_string().contains("BepInEx/plugins/spt/spt-core.dll"), "{err}");
+		assert!(!err.to_string().contains("sneaky-mod.dll"), "{err}");
+		assert!(!Path::new(&format!("{path}/BepInEx/plugins/spt/spt-core.dll")).exists());
+		assert!(!Path::new(&format!("{path}/BepInEx/plugins/sneaky-mod/sneaky-mod.dll")).exists());
+
+		let spt_access = spt_access
+			.with_install_options(InstallOptions::default().with_allow_core_overwrite(true));
+		let report = spt_access.install_mod(&archive, &spt_mod, InstallTarget::Client).unwrap();
+
+		assert_eq!(report.installed_files, 2);
+		assert!(Path::new(&format!("{path}/BepInEx/plugins/spt/spt-core.dll")).is_file());
+		fs::remove_dir_all(path).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn integration_test_backup_and_install_of_the_same_folder_run_one_at_a_time() {
+		let mut provider = MockTimeProvider::new();
+		provider
+			.expect_get_current_time()
+			.returning(DateTime::<Utc>::default);
+		let path = "./test_output/concurrent_backup_test";
+		let _discard = fs::remove_dir_all(path).await;
+		let root = format!("{path}/root");
+		fs::create_dir_all(format!("{root}/user/mods")).await.unwrap();
+		fs::create_dir_all(format!("{root}/BepInEx/plugins")).await.unwrap();
+		create_spt_folder(&root).await;
+		let archive = format!("{path}/slow-mod.zip");
+		let entries = ["user/mods/slow-mod/src/mod.js", "user/mods/slow-mod/src/config.js"];
+		write_test_zip(&archive, &entries.map(|entry| (entry, entry)));
+		let project = PathAccess::from(&root, &root).unwrap();
+		let spt_access = SptAccess::init(&project, provider).await.unwrap();
+		let spt_mod = TestModName::new("Slow Mod", "1.0.0");
+		let (waiting_backups, racing_backups) =
+			(format!("{path}/waiting"), format!("{path}/racing"));
+		fs::create_dir_all(&waiting_backups).await.unwrap();
+		fs::create_dir_all(&racing_backups).await.unwrap();
+
+		// Holding the lock stands in for an install that's still writing its files
+		let running_install = operation_lock::lock_root(Path::new(&root));
+		std::thread::scope(|scope| {
+			let backup = scope
+				.spawn(|| spt_access.backup_to(&waiting_backups, &BackupOptions::default()));
+			std::thread::sleep(std::time::Duration::from_millis(200));
+			assert!(!backup.is_finished());
+			drop(running_install);
+			backup.join().unwrap().unwrap();
+		});
+
+		// Mods with an install_path and raw files wait for a running backup just the same
+		let install_path = format!("{root}/user/mods/path-mod");
+		let raw_file_path = format!("{root}/user/mods/raw-mod/config.json");
+		let running_backup = operation_lock::lock_root(Path::new(&root));
+		std::thread::scope(|scope| {
+			let path_install =
+				scope.spawn(|| spt_access.install_mod_to_path(&archive, &install_path));
+			let file_install =
+				scope.spawn(|| spt_access.install_file_to_path(&archive, &raw_file_path));
+			std::thread::sleep(std::time::Duration::from_millis(200));
+			assert!(!path_install.is_finished());
+			assert!(!file_install.is_finished());
+			drop(running_backup);
+			path_install.join().unwrap().unwrap();
+			file_install.join().unwrap().unwrap();
+		});
+
+		std::thread::scope(|scope| {
+			let install =
+				scope.spawn(|| spt_access.install_mod(&archive, &spt_mod, InstallTarget::Server));
+			let backup = scope
+				.spawn(|| spt_access.backup_to(&racing_backups, &BackupOptions::default()));
+			install.join().unwrap().unwrap();
+			backup.join().unwrap().unwrap();
+		});
+
+		// The backup ran before or after the install, never halfway through it
+		let backup_path =
+			SptAccess::<MockTimeProvider>::find_latest_backup(&racing_backups).unwrap();
+		let zip_archive = zip::ZipArchive::new(File::open(backup_path).unwrap()).unwrap();
+		let mod_files = zip_archive
+			.file_names()
+			.filter(|name| name.starts_with("user/mods/slow-mod/src/"))
+			.count();
+		assert!(mod_files == 0 || mod_files == 2, "{mod_files}");
+		fs::remove_dir_all(path).await.unwrap()
+	}
 }
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::spt_access::InstalledMod;
+
+/// The exact mods, versions and file hashes of a distributed modpack.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Lockfile {
+	pub mods: Vec<InstalledMod>,
+}
+
+impl Lockfile {
+	pub fn from_installed(mods: Vec<InstalledMod>) -> Self {
+		Self { mods }
+	}
+
+	pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+		let path = path.as_ref();
+		let file = File::open(path)
+			.with_context(|| format!("Failed to open the lockfile: {}", path.display()))?;
+		serde_json::from_reader(BufReader::new(file))
+			.with_context(|| format!("Failed to parse the lockfile: {}", path.display()))
+	}
+
+	pub fn write(&self, path: impl AsRef<Path>) -> Result<()> {
+		let writer = BufWriter::new(File::create(path)?);
+		serde_json::to_writer_pretty(writer, self)?;
+		Ok(())
+	}
+}
+
+/// A way the installed mods differ from a [`Lockfile`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModDrift {
+	Missing {
+		name: String,
+	},
+	Extra {
+		name: String,
+	},
+	WrongVersion {
+		name: String,
+		locked: Option<String>,
+		installed: Option<String>,
+	},
+	/// The files the lockfile and the install index disagree on, missing from either or hashed differently.
+	HashMismatch {
+		name: String,
+		files: Vec<String>,
+	},
+}
+
+/// Compares the install index to the lockfile, listing missing and extra mods first.
+pub(super) fn audit(locked_mods: &[InstalledMod], installed_mods: &[InstalledMod]) -> Vec<ModDrift> {
+	let installed_by_name: HashMap<_, _> = installed_mods
+		.iter()
+		.map(|installed_mod| (installed_mod.name.as_str(), installed_mod))
+		.collect();
+	let locked_by_name: HashMap<_, _> = locked_mods
+		.iter()
+		.map(|locked_mod| (locked_mod.name.as_str(), locked_mod))
+		.collect();
+
+	let mut drift = Vec::new();
+	for locked_mod in locked_mods {
+		let Some(installed_mod) = installed_by_name.get(locked_mod.name.as_str()) else {
+			drift.push(ModDrift::Missing {
+				name: locked_mod.name.clone(),
+			});
+			continue;
+		};
+		if locked_mod.version != installed_mod.version {
+			drift.push(ModDrift::WrongVersion {
+				name: locked_mod.name.clone(),
+				locked: locked_mod.version.clone(),
+				installed: installed_mod.version.clone(),
+			});
+			continue;
+		}
+		let mut files: Vec<_> = locked_mod
+			.files
+			.iter()
+			.filter(|(file, hash)| installed_mod.files.get(*file) != Some(*hash))
+			.chain(
+				installed_mod
+					.files
+					.iter()
+					.filter(|(file, _)| !locked_mod.files.contains_key(*file)),
+			)
+			.map(|(file, _)| file.clone())
+			.collect();
+		if !files.is_empty() {
+			files.sort();
+			drift.push(ModDrift::HashMismatch {
+				name: locked_mod.name.clone(),
+				files,
+			});
+		}
+	}
+	drift.extend(
+		installed_mods
+			.iter()
+			.filter(|installed_mod| !locked_by_name.contains_key(installed_mod.name.as_str()))
+			.map(|installed_mod| ModDrift::Extra {
+				name: installed_mod.name.clone(),
+			}),
+	);
+	drift.sort_by_key(|drift| match drift {
+		ModDrift::Missing { .. } => 0,
+		ModDrift::Extra { .. } => 1,
+		ModDrift::WrongVersion { .. } => 2,
+		ModDrift::HashMismatch { .. } => 3,
+	});
+	drift
+}
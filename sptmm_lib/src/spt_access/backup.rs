@@ -0,0 +1,298 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::spt_access::integrity::{IntegrityBuilder, INTEGRITY_ENTRY_NAME};
+
+/// Maps each backed up path to the hash of its content in a deduplicated backup.
+pub(super) const DEDUP_INDEX_NAME: &str = ".sptmm/dedup_index.json";
+/// Holds each unique file content once, named by its sha256 hash.
+pub(super) const DEDUP_CONTENT_FOLDER: &str = ".sptmm/content";
+
+/// Replaces `.zip` in the name of the file next to each backup that lists the sha256 of every
+/// backed up path, for telling which files changed since the backup without opening it.
+pub(super) const BACKUP_MANIFEST_SUFFIX: &str = ".files.json";
+
+pub(super) const BACKUP_FILE_PREFIX: &str = "backup_";
+pub(super) const BACKUP_TIME_FORMAT: &str = "%Y-%m-%dT%H-%M-%SZ";
+const BACKUP_TIME_LENGTH: usize = "2024-06-11T19-06-55Z".len();
+const MAX_LABEL_LENGTH: usize = 64;
+
+#[derive(Debug, Clone, Default)]
+pub struct BackupOptions {
+	/// Stores byte-identical files once, which shrinks backups of mods sharing libraries.
+	pub deduplicate: bool,
+	/// Also backs up the BepInEx configuration, so it can be restored on its own.
+	pub include_config: bool,
+	/// Also backs up the SPT user profiles, which are left out by default to keep backups to the mods.
+	pub include_profiles: bool,
+	/// Tags the backup file name, such as `backup_before-3.9-update_<timestamp>.zip`.
+	pub label: Option<String>,
+	pub compression: CompressionKind,
+	/// The compression level, where higher is smaller but slower, or the method's default when
+	/// `None`. Stored backups ignore it.
+	pub level: Option<i64>,
+}
+
+/// How backed up files are compressed. Stored, the default, is fastest but leaves the backup as
+/// large as the backed up files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompressionKind {
+	#[default]
+	Stored,
+	Deflated,
+	Zstd,
+}
+
+impl CompressionKind {
+	fn method(self) -> CompressionMethod {
+		match self {
+			CompressionKind::Stored => CompressionMethod::Stored,
+			CompressionKind::Deflated => CompressionMethod::Deflated,
+			CompressionKind::Zstd => CompressionMethod::Zstd,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BackupCancellation {
+	cancelled: Arc<AtomicBool>,
+}
+
+impl BackupCancellation {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn cancel(&self) {
+		self.cancelled.store(true, Ordering::Relaxed);
+	}
+
+	pub fn is_cancelled(&self) -> bool {
+		self.cancelled.load(Ordering::Relaxed)
+	}
+}
+
+/// Names a backup `backup_<timestamp>.zip`, or `backup_<label>_<timestamp>.zip` when labelled.
+pub(super) fn backup_file_name(time: DateTime<Utc>, label: Option<&str>) -> Result<String> {
+	let timestamp = time.format(BACKUP_TIME_FORMAT);
+	let Some(label) = label else {
+		return Ok(format!("{BACKUP_FILE_PREFIX}{timestamp}.zip"));
+	};
+	let is_safe = label
+		.chars()
+		.all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+	if label.is_empty() || label.len() > MAX_LABEL_LENGTH || !is_safe || label.starts_with('.') {
+		return Err(anyhow!(
+			"Invalid backup label '{label}', use up to {MAX_LABEL_LENGTH} letters, digits, '-', '_' or '.'"
+		));
+	}
+	Ok(format!("{BACKUP_FILE_PREFIX}{label}_{timestamp}.zip"))
+}
+
+/// Picks a path in `folder` for the backup that no backup has yet, adding a counter such as
+/// `backup_2024-06-11T19-06-55Z_1.zip` when a backup was already made in the same second.
+pub(super) fn unused_backup_path(folder: &Path, backup_name: &str) -> PathBuf {
+	let stem = backup_name.strip_suffix(".zip").unwrap_or(backup_name);
+	let mut path = folder.join(backup_name);
+	let mut counter = 1;
+	while path.exists() {
+		path = folder.join(format!("{stem}_{counter}.zip"));
+		counter += 1;
+	}
+	path
+}
+
+/// Reads the time a backup was made from the end of its file name, such as `backup_2024-06-11T19-06-55Z.zip`.
+#[cfg(test)]
+pub(super) fn parse_backup_time(file_name: &str) -> Option<DateTime<Utc>> {
+	parse_backup_order(file_name).map(|(time, _)| time)
+}
+
+/// Reads the time and the same-second counter of a backup, which order backups from oldest to newest.
+fn parse_backup_order(file_name: &str) -> Option<(DateTime<Utc>, u32)> {
+	let stem = file_name
+		.strip_prefix(BACKUP_FILE_PREFIX)?
+		.strip_suffix(".zip")?;
+	let (stem, counter) = match stem.rsplit_once('_') {
+		Some((rest, counter)) if !counter.is_empty() && counter.chars().all(|c| c.is_ascii_digit()) => {
+			(rest, counter.parse().ok()?)
+		}
+		_ => (stem, 0),
+	};
+	let timestamp = stem.get(stem.len().checked_sub(BACKUP_TIME_LENGTH)?..)?;
+	let time = NaiveDateTime::parse_from_str(timestamp, BACKUP_TIME_FORMAT).ok()?;
+	Some((time.and_utc(), counter))
+}
+
+/// Finds the backup in `folder` with the newest timestamp in its file name.
+pub(super) fn find_latest_backup(folder: &Path) -> Result<PathBuf> {
+	let mut latest: Option<((DateTime<Utc>, u32), PathBuf)> = None;
+	for entry in std::fs::read_dir(folder)? {
+		let entry = entry?;
+		let file_name = entry.file_name();
+		let Some(order) = file_name.to_str().and_then(parse_backup_order) else {
+			continue;
+		};
+		let is_newer = match &latest {
+			Some((latest_order, _)) => order > *latest_order,
+			None => true,
+		};
+		if is_newer {
+			latest = Some((order, entry.path()));
+		}
+	}
+	latest.map(|(_, path)| path).ok_or_else(|| {
+		anyhow!(
+			"Found no backups named like {BACKUP_FILE_PREFIX}<timestamp>.zip in: {}",
+			folder.display()
+		)
+	})
+}
+
+/// The manifest next to a backup, `backup_<timestamp>.files.json` for `backup_<timestamp>.zip`.
+pub(super) fn backup_manifest_path(zip_path: &Path) -> PathBuf {
+	let file_name = zip_path.file_name().unwrap_or_default().to_string_lossy();
+	let stem = file_name.strip_suffix(".zip").unwrap_or(&file_name);
+	zip_path.with_file_name(format!("{stem}{BACKUP_MANIFEST_SUFFIX}"))
+}
+
+/// Writes every file in `folders` to a new archive at `zip_path` with its manifest next to it,
+/// removing the partial archive again if the backup fails or is cancelled.
+pub(super) fn write_backup(
+	zip_path: &Path,
+	root_path: &Path,
+	folders: &[PathBuf],
+	options: &BackupOptions,
+	cancellation: &BackupCancellation,
+	mut progress: impl FnMut(usize),
+) -> Result<()> {
+	let writer = BufWriter::new(File::create_new(zip_path)?);
+	let result = write_archive(
+		ZipWriter::new(writer),
+		root_path,
+		folders,
+		options,
+		cancellation,
+		&mut progress,
+	)
+	.and_then(|manifest| {
+		let manifest_file = File::create(backup_manifest_path(zip_path))?;
+		serde_json::to_writer_pretty(BufWriter::new(manifest_file), &manifest)?;
+		Ok(())
+	});
+	if result.is_err() {
+		let _ = std::fs::remove_file(backup_manifest_path(zip_path));
+		std::fs::remove_file(zip_path)?;
+	}
+	result
+}
+
+/// Reads the sha256 of every path backed up in `zip_path` from its manifest.
+pub(super) fn read_backup_manifest(zip_path: &Path) -> Result<BTreeMap<String, String>> {
+	let manifest_path = backup_manifest_path(zip_path);
+	let manifest_file = File::open(&manifest_path)
+		.with_context(|| format!("Found no manifest for the backup: {}", zip_path.display()))?;
+	Ok(serde_json::from_reader(std::io::BufReader::new(manifest_file))?)
+}
+
+fn write_archive(
+	mut zip_writer: ZipWriter<BufWriter<File>>,
+	root_path: &Path,
+	folders: &[PathBuf],
+	backup_options: &BackupOptions,
+	cancellation: &BackupCancellation,
+	progress: &mut impl FnMut(usize),
+) -> Result<BTreeMap<String, String>> {
+	let level = match backup_options.compression {
+		CompressionKind::Stored => None,
+		_ => backup_options.level,
+	};
+	let options = SimpleFileOptions::default()
+		.compression_method(backup_options.compression.method())
+		.compression_level(level);
+	let mut files_processed = 0;
+	let mut manifest = BTreeMap::new();
+	let mut dedup_index = HashMap::new();
+	let mut stored_hashes = HashSet::new();
+	let mut integrity = IntegrityBuilder::default();
+	for folder in folders {
+		if !folder.is_dir() {
+			continue;
+		}
+		let filter = WalkDir::new(folder)
+			.into_iter()
+			.filter(|x| x.as_ref().is_ok_and(|e| e.path().is_file()));
+		for file_entry in filter {
+			if cancellation.is_cancelled() {
+				return Err(anyhow!("The backup was cancelled"));
+			}
+			let file_entry = file_entry?;
+			let file_path = file_entry.path();
+			let mut buffer = Vec::new();
+			let mut file = File::open(file_path)?;
+			file.read_to_end(&mut buffer)?;
+			let entry_path = file_path.strip_prefix(root_path).unwrap_or(file_path);
+			let hash = sha256::digest(buffer.as_slice());
+			manifest.insert(to_entry_name(entry_path), hash.clone());
+			if backup_options.deduplicate {
+				if stored_hashes.insert(hash.clone()) {
+					let entry_name = format!("{DEDUP_CONTENT_FOLDER}/{hash}");
+					write_entry(&mut zip_writer, &mut integrity, entry_name, &buffer, options)?;
+				}
+				dedup_index.insert(to_entry_name(entry_path), hash);
+			} else {
+				let entry_name = to_entry_name(entry_path);
+				write_entry(&mut zip_writer, &mut integrity, entry_name, &buffer, options)?;
+			}
+			files_processed += 1;
+			progress(files_processed);
+		}
+	}
+
+	if cancellation.is_cancelled() {
+		return Err(anyhow!("The backup was cancelled"));
+	}
+	if backup_options.deduplicate {
+		let index = serde_json::to_vec(&dedup_index)?;
+		let entry_name = DEDUP_INDEX_NAME.to_string();
+		write_entry(&mut zip_writer, &mut integrity, entry_name, &index, options)?;
+	}
+	zip_writer.start_file(INTEGRITY_ENTRY_NAME, options)?;
+	serde_json::to_writer(&mut zip_writer, &integrity.finish())?;
+	zip_writer.finish()?;
+	Ok(manifest)
+}
+
+fn write_entry(
+	zip_writer: &mut ZipWriter<BufWriter<File>>,
+	integrity: &mut IntegrityBuilder,
+	entry_name: String,
+	data: &[u8],
+	options: SimpleFileOptions,
+) -> Result<()> {
+	integrity.add(&entry_name, data);
+	zip_writer.start_file(entry_name, options)?;
+	zip_writer.write_all(data)?;
+	Ok(())
+}
+
+/// Joins the path with forward slashes so the index reads the same on every platform.
+fn to_entry_name(path: &Path) -> String {
+	path.components()
+		.filter_map(|component| match component {
+			Component::Normal(part) => Some(part.to_string_lossy()),
+			_ => None,
+		})
+		.collect::<Vec<_>>()
+		.join("/")
+}
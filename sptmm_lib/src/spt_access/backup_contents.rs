@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::Result;
+use zip::ZipArchive;
+
+use crate::spt_access::backup::{DEDUP_CONTENT_FOLDER, DEDUP_INDEX_NAME};
+use crate::spt_access::integrity::INTEGRITY_ENTRY_NAME;
+use crate::spt_access::{file_parser, FileType, InstallTarget};
+
+/// A file in a backup, read from the archive's listing without extracting it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BackupEntry {
+	/// The path relative to the SPT folder the file is restored to.
+	pub path: String,
+	pub size: u64,
+	/// Which side of SPT the file belongs to, `None` for files outside `user` and `BepInEx`.
+	pub target: Option<InstallTarget>,
+}
+
+impl BackupEntry {
+	fn new(path: String, size: u64) -> Self {
+		let mut parsed_path = path.as_str();
+		let target = match file_parser(&mut parsed_path) {
+			FileType::Client => Some(InstallTarget::Client),
+			FileType::Server => Some(InstallTarget::Server),
+			FileType::Unknown => None,
+		};
+		Self { path, size, target }
+	}
+}
+
+/// Lists the files of a backup ordered by path. A deduplicated backup lists the paths of its
+/// index, sized by the content each points to.
+pub(super) fn list_archive(archive_path: &Path) -> Result<Vec<BackupEntry>> {
+	let mut zip_archive = ZipArchive::new(File::open(archive_path)?)?;
+	let mut entries = Vec::new();
+	if zip_archive.index_for_name(DEDUP_INDEX_NAME).is_some() {
+		let index: HashMap<String, String> =
+			serde_json::from_reader(zip_archive.by_name(DEDUP_INDEX_NAME)?)?;
+		for (path, hash) in index {
+			let size = zip_archive.by_name(&format!("{DEDUP_CONTENT_FOLDER}/{hash}"))?.size();
+			entries.push(BackupEntry::new(path, size));
+		}
+	} else {
+		for index in 0..zip_archive.len() {
+			let entry = zip_archive.by_index(index)?;
+			if entry.is_dir() || entry.name() == INTEGRITY_ENTRY_NAME {
+				continue;
+			}
+			entries.push(BackupEntry::new(entry.name().to_string(), entry.size()));
+		}
+	}
+	entries.sort_by(|a, b| a.path.cmp(&b.path));
+	Ok(entries)
+}
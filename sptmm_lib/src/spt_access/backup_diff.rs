@@ -0,0 +1,69 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::Result;
+use zip::ZipArchive;
+
+use crate::spt_access::backup::DEDUP_INDEX_NAME;
+use crate::spt_access::integrity::{self, INTEGRITY_ENTRY_NAME};
+
+/// How a file differs from the first backup to the second.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackupChange {
+	Added,
+	Removed,
+	/// The file is in both backups with different content.
+	Changed,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BackupDifference {
+	pub path: String,
+	pub change: BackupChange,
+}
+
+/// Compares the files of two backups by their hashes without extracting either, ordered by path.
+pub(super) fn diff_archives(from_path: &Path, to_path: &Path) -> Result<Vec<BackupDifference>> {
+	let from = hash_entries(from_path)?;
+	let mut to = hash_entries(to_path)?;
+	let mut differences = Vec::new();
+	for (path, from_hash) in from {
+		let change = match to.remove(&path) {
+			None => BackupChange::Removed,
+			Some(to_hash) if to_hash != from_hash => BackupChange::Changed,
+			Some(_) => continue,
+		};
+		differences.push(BackupDifference { path, change });
+	}
+	differences.extend(to.into_keys().map(|path| BackupDifference {
+		path,
+		change: BackupChange::Added,
+	}));
+	differences.sort_by(|a, b| a.path.cmp(&b.path));
+	Ok(differences)
+}
+
+/// The sha256 of every file in the backup by its path. A deduplicated backup already records
+/// them in its index, as its content is stored by the same hash.
+fn hash_entries(archive_path: &Path) -> Result<BTreeMap<String, String>> {
+	let mut zip_archive = ZipArchive::new(File::open(archive_path)?)?;
+	integrity::verify_archive(&mut zip_archive)?;
+	if zip_archive.index_for_name(DEDUP_INDEX_NAME).is_some() {
+		let index: HashMap<String, String> =
+			serde_json::from_reader(zip_archive.by_name(DEDUP_INDEX_NAME)?)?;
+		return Ok(index.into_iter().collect());
+	}
+	let mut hashes = BTreeMap::new();
+	for index in 0..zip_archive.len() {
+		let mut entry = zip_archive.by_index(index)?;
+		if entry.is_dir() || entry.name() == INTEGRITY_ENTRY_NAME {
+			continue;
+		}
+		let mut data = Vec::new();
+		entry.read_to_end(&mut data)?;
+		hashes.insert(entry.name().to_string(), sha256::digest(data.as_slice()));
+	}
+	Ok(hashes)
+}
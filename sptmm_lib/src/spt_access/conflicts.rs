@@ -0,0 +1,89 @@
+use std::fmt::{Display, Formatter};
+
+use crate::spt_access::InstalledMod;
+
+/// A file a new mod would install that an installed mod already owns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModConflict {
+	pub path: String,
+	pub installed_by: String,
+}
+
+/// The error installing a mod fails with when it would overwrite files another mod installed,
+/// found with `downcast_ref` on the install error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstallConflict {
+	pub mod_name: String,
+	pub conflicts: Vec<ModConflict>,
+}
+
+impl Display for InstallConflict {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} would overwrite files of other mods:", self.mod_name)?;
+		for conflict in &self.conflicts {
+			write!(f, "\n  {} (installed by {})", conflict.path, conflict.installed_by)?;
+		}
+		Ok(())
+	}
+}
+
+impl std::error::Error for InstallConflict {}
+
+/// Matches the paths against the files recorded for every installed mod, ordered by path.
+pub(super) fn find_conflicts(paths: &[String], installed_mods: &[InstalledMod]) -> Vec<ModConflict> {
+	let mut conflicts: Vec<_> = paths
+		.iter()
+		.flat_map(|path| {
+			installed_mods
+				.iter()
+				.filter(|installed_mod| installed_mod.files.contains_key(path))
+				.map(|installed_mod| ModConflict {
+					path: path.clone(),
+					installed_by: installed_mod.name.clone(),
+				})
+		})
+		.collect();
+	conflicts.sort_by(|a, b| a.path.cmp(&b.path).then_with(|| a.installed_by.cmp(&b.installed_by)));
+	conflicts
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use super::*;
+
+	fn installed_mod(name: &str, files: &[&str]) -> InstalledMod {
+		InstalledMod {
+			name: name.to_string(),
+			version: Some("1.0.0".to_string()),
+			install_target: None,
+			files: files
+				.iter()
+				.map(|file| (file.to_string(), sha256::digest(*file)))
+				.collect::<HashMap<_, _>>(),
+		}
+	}
+
+	#[test]
+	fn only_shared_paths_conflict() {
+		let installed_mods = [
+			installed_mod("Keys", &["user/mods/keys/package.json", "BepInEx/plugins/shared.dll"]),
+			installed_mod("Other", &["user/mods/other/package.json"]),
+		];
+		let paths = vec![
+			"user/mods/new/package.json".to_string(),
+			"BepInEx/plugins/shared.dll".to_string(),
+		];
+
+		let conflicts = find_conflicts(&paths, &installed_mods);
+
+		assert_eq!(
+			conflicts,
+			vec![ModConflict {
+				path: "BepInEx/plugins/shared.dll".to_string(),
+				installed_by: "Keys".to_string(),
+			}]
+		);
+	}
+}
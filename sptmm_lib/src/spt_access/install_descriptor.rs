@@ -0,0 +1,101 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+
+use crate::spt_access::decode_entry_name;
+
+/// The file at the root of a mod archive that tells where its files go.
+pub(super) const INSTALL_DESCRIPTOR_NAME: &str = "sptmm.json";
+
+/// Maps folders of an archive to folders of the SPT install, for mods that don't follow the
+/// `user`/`BepInEx` layout, such as `{ "mappings": { "plugins/": "BepInEx/plugins/" } }`.
+#[derive(Deserialize, Debug, Default, PartialEq)]
+pub(super) struct InstallDescriptor {
+	#[serde(default)]
+	mappings: HashMap<String, String>,
+}
+
+impl InstallDescriptor {
+	/// Reads the descriptor at the root of the archive, or an empty one when it has none.
+	pub(super) fn read(mod_archive_path: &Path) -> Result<Self> {
+		// A corrupt archive is reported by the install that reads it right after
+		let Ok(entries) = compress_tools::list_archive_files_with_encoding(
+			File::open(mod_archive_path)?,
+			decode_entry_name,
+		) else {
+			return Ok(Self::default());
+		};
+		if !entries.iter().any(|entry| entry == INSTALL_DESCRIPTOR_NAME) {
+			return Ok(Self::default());
+		}
+		let mut buffer = Vec::new();
+		compress_tools::uncompress_archive_file(
+			File::open(mod_archive_path)?,
+			&mut buffer,
+			INSTALL_DESCRIPTOR_NAME,
+		)?;
+		Self::parse(&buffer)
+	}
+
+	pub(super) fn parse(data: &[u8]) -> Result<Self> {
+		let descriptor: Self = serde_json::from_slice(data)
+			.with_context(|| format!("Failed to parse the mod's {INSTALL_DESCRIPTOR_NAME}"))?;
+		for target in descriptor.mappings.values() {
+			let is_outside = Path::new(target).is_absolute()
+				|| target.starts_with(['/', '\\'])
+				|| target.split(['/', '\\']).any(|segment| segment == "..");
+			if is_outside {
+				return Err(anyhow!(
+					"The mod's {INSTALL_DESCRIPTOR_NAME} maps files outside the SPT folder: {target}"
+				));
+			}
+		}
+		Ok(descriptor)
+	}
+
+	/// Replaces the longest mapped folder the path starts with, leaving unmapped paths to the
+	/// `user`/`BepInEx` classifier.
+	pub(super) fn map_path<'a>(&self, zip_path: &'a str) -> Cow<'a, str> {
+		let longest_match = self
+			.mappings
+			.iter()
+			.filter(|(source, _)| zip_path.starts_with(source.as_str()))
+			.max_by_key(|(source, _)| source.len());
+		match longest_match {
+			Some((source, target)) => Cow::Owned(format!("{target}{}", &zip_path[source.len()..])),
+			None => Cow::Borrowed(zip_path),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn longest_mapping_wins_and_unmapped_paths_are_kept() {
+		let descriptor = InstallDescriptor::parse(
+			br#"{ "mappings": {
+				"plugins/": "BepInEx/plugins/",
+				"plugins/patchers/": "BepInEx/patchers/"
+			} }"#,
+		)
+		.unwrap();
+
+		assert_eq!(descriptor.map_path("plugins/MyMod.dll"), "BepInEx/plugins/MyMod.dll");
+		assert_eq!(descriptor.map_path("plugins/patchers/Patch.dll"), "BepInEx/patchers/Patch.dll");
+		assert_eq!(descriptor.map_path("user/mods/x/package.json"), "user/mods/x/package.json");
+	}
+
+	#[test]
+	fn mappings_outside_the_spt_folder_are_rejected() {
+		for target in ["../outside/", "/etc/", "BepInEx/../../outside/"] {
+			let descriptor = format!(r#"{{ "mappings": {{ "plugins/": "{target}" }} }}"#);
+			assert!(InstallDescriptor::parse(descriptor.as_bytes()).is_err(), "{target}");
+		}
+	}
+}
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use versions::Versioning;
+
+use crate::shared_traits::{FileSystem, ModName};
+use crate::spt_access::InstallTarget;
+
+/// An entry of the install index, recording which files a mod installed and their hashes.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InstalledMod {
+	pub name: String,
+	pub version: Option<String>,
+	pub install_target: Option<InstallTarget>,
+	pub files: HashMap<String, String>,
+}
+
+/// Index files written before versions were recorded only contain the file hashes.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum InstallIndexFile {
+	Current(InstalledMod),
+	Legacy(HashMap<String, String>),
+}
+
+impl InstalledMod {
+	/// Reads an index file through `files`, such as one kept in memory.
+	pub(super) fn read_from(files: &impl FileSystem, path: &Path) -> Result<Self> {
+		let index_file: InstallIndexFile = serde_json::from_slice(&files.read(path)?)?;
+		Self::from_index_file(index_file, path)
+	}
+
+	fn from_index_file(index_file: InstallIndexFile, path: &Path) -> Result<Self> {
+		let installed_mod = match index_file {
+			InstallIndexFile::Current(installed_mod) => installed_mod,
+			InstallIndexFile::Legacy(files) => Self {
+				name: path
+					.file_name()
+					.and_then(|name| name.to_str())
+					.context("Found no index file name")?
+					.to_string(),
+				version: None,
+				install_target: None,
+				files,
+			},
+		};
+		Ok(installed_mod)
+	}
+
+	pub(super) fn write_to(&self, files: &impl FileSystem, path: &Path) -> Result<()> {
+		files.write(path, &serde_json::to_vec(self)?)?;
+		Ok(())
+	}
+
+	pub fn get_version(&self) -> Option<Versioning> {
+		self.version.as_deref().and_then(Versioning::new)
+	}
+}
+
+impl ModName for InstalledMod {
+	fn get_name(&self) -> &str {
+		&self.name
+	}
+
+	fn is_same_name<Name: ModName>(&self, mod_name: &Name) -> bool {
+		self.name == mod_name.get_name()
+	}
+}
@@ -0,0 +1,68 @@
+use std::fs::File;
+use std::io::Read;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use zip::ZipArchive;
+
+/// Records how many entries a backup holds and a hash over all of them.
+pub(super) const INTEGRITY_ENTRY_NAME: &str = ".sptmm/integrity.json";
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub(super) struct BackupIntegrity {
+	file_count: usize,
+	combined_hash: String,
+}
+
+/// Collects the entries written to a backup to build its [`BackupIntegrity`].
+#[derive(Default)]
+pub(super) struct IntegrityBuilder {
+	entries: Vec<(String, String)>,
+}
+
+impl IntegrityBuilder {
+	pub(super) fn add(&mut self, entry_name: &str, data: &[u8]) {
+		self.entries.push((entry_name.to_string(), sha256::digest(data)));
+	}
+
+	/// Hashes the entries in name order so the result doesn't depend on the order they were written in.
+	pub(super) fn finish(mut self) -> BackupIntegrity {
+		self.entries.sort();
+		let combined = self
+			.entries
+			.iter()
+			.map(|(name, hash)| format!("{name}\0{hash}\n"))
+			.collect::<String>();
+		BackupIntegrity {
+			file_count: self.entries.len(),
+			combined_hash: sha256::digest(combined),
+		}
+	}
+}
+
+/// Checks the archive against its integrity entry. Backups made before the entry existed are accepted.
+pub(super) fn verify_archive(zip_archive: &mut ZipArchive<File>) -> Result<()> {
+	if zip_archive.index_for_name(INTEGRITY_ENTRY_NAME).is_none() {
+		return Ok(());
+	}
+	let expected: BackupIntegrity =
+		serde_json::from_reader(zip_archive.by_name(INTEGRITY_ENTRY_NAME)?)?;
+
+	let mut builder = IntegrityBuilder::default();
+	for index in 0..zip_archive.len() {
+		let mut entry = zip_archive.by_index(index)?;
+		if entry.is_dir() || entry.name() == INTEGRITY_ENTRY_NAME {
+			continue;
+		}
+		let mut data = Vec::new();
+		entry.read_to_end(&mut data)?;
+		builder.add(entry.name(), &data);
+	}
+
+	if builder.finish() != expected {
+		return Err(anyhow!(
+			"The backup's contents don't match its integrity record, it may be truncated or corrupted"
+		));
+	}
+	Ok(())
+}
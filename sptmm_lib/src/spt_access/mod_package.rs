@@ -0,0 +1,288 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+
+use serde::Deserialize;
+use versions::Versioning;
+
+use crate::dependency_graph::{find_cycle, format_cycle};
+
+/// The parts of an SPT server mod's `package.json` that describe what it needs to run.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModPackage {
+	pub name: String,
+	pub version: Option<String>,
+	#[serde(alias = "akiVersion")]
+	pub spt_version: Option<String>,
+	#[serde(default)]
+	pub mod_dependencies: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DependencyWarning {
+	MissingDependency {
+		mod_name: String,
+		dependency: String,
+		requirement: String,
+	},
+	UnsatisfiedDependency {
+		mod_name: String,
+		dependency: String,
+		requirement: String,
+		installed_version: String,
+	},
+	UnsupportedSptVersion {
+		mod_name: String,
+		requirement: String,
+		spt_version: Versioning,
+	},
+	/// The mods depend on each other in a loop, listed with the first mod repeated at the end.
+	CircularDependency {
+		cycle: Vec<String>,
+	},
+}
+
+impl Display for DependencyWarning {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			DependencyWarning::MissingDependency {
+				mod_name,
+				dependency,
+				requirement,
+			} => write!(
+				f,
+				"{mod_name} depends on {dependency} ({requirement}) which isn't installed"
+			),
+			DependencyWarning::UnsatisfiedDependency {
+				mod_name,
+				dependency,
+				requirement,
+				installed_version,
+			} => write!(
+				f,
+				"{mod_name} depends on {dependency} ({requirement}) but version {installed_version} is installed"
+			),
+			DependencyWarning::UnsupportedSptVersion {
+				mod_name,
+				requirement,
+				spt_version,
+			} => write!(
+				f,
+				"{mod_name} requires SPT {requirement} but the configured version is {spt_version}"
+			),
+			DependencyWarning::CircularDependency { cycle } => {
+				write!(f, "Circular mod dependency: {}", format_cycle(cycle))
+			}
+		}
+	}
+}
+
+/// Whether a mod declares support for an SPT version in its `package.json`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SptSupport {
+	Supported,
+	Unsupported { requirement: String },
+	/// The mod doesn't say which SPT versions it supports.
+	Undeclared,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SptCompatibility {
+	pub mod_name: String,
+	pub support: SptSupport,
+}
+
+impl ModPackage {
+	pub fn parse(data: &[u8]) -> Option<Self> {
+		serde_json::from_slice(data).ok()
+	}
+
+	pub fn supports_spt(&self, spt_version: &Versioning) -> SptSupport {
+		match &self.spt_version {
+			None => SptSupport::Undeclared,
+			Some(requirement) if matches_requirement(requirement, spt_version) => SptSupport::Supported,
+			Some(requirement) => SptSupport::Unsupported {
+				requirement: requirement.clone(),
+			},
+		}
+	}
+
+	pub fn check(&self, spt_version: &Versioning, installed: &[ModPackage]) -> Vec<DependencyWarning> {
+		let mut warnings = Vec::new();
+		if let Some(requirement) = &self.spt_version {
+			if !matches_requirement(requirement, spt_version) {
+				warnings.push(DependencyWarning::UnsupportedSptVersion {
+					mod_name: self.name.clone(),
+					requirement: requirement.clone(),
+					spt_version: spt_version.clone(),
+				});
+			}
+		}
+
+		let mut dependencies: Vec<_> = self.mod_dependencies.iter().collect();
+		dependencies.sort();
+		for (dependency, requirement) in dependencies {
+			let Some(installed_package) = installed.iter().find(|p| &p.name == dependency) else {
+				warnings.push(DependencyWarning::MissingDependency {
+					mod_name: self.name.clone(),
+					dependency: dependency.clone(),
+					requirement: requirement.clone(),
+				});
+				continue;
+			};
+			let Some(installed_version) = &installed_package.version else {
+				continue;
+			};
+			let satisfied = match Versioning::new(installed_version) {
+				Some(version) => matches_requirement(requirement, &version),
+				None => true,
+			};
+			if !satisfied {
+				warnings.push(DependencyWarning::UnsatisfiedDependency {
+					mod_name: self.name.clone(),
+					dependency: dependency.clone(),
+					requirement: requirement.clone(),
+					installed_version: installed_version.clone(),
+				});
+			}
+		}
+		warnings
+	}
+}
+
+/// Finds a loop in the dependencies between the packages, ignoring dependencies that aren't installed.
+pub fn find_dependency_cycle(packages: &[ModPackage]) -> Option<Vec<String>> {
+	let graph: BTreeMap<_, _> = packages
+		.iter()
+		.map(|package| {
+			let mut dependencies: Vec<_> = package.mod_dependencies.keys().cloned().collect();
+			dependencies.sort();
+			(package.name.clone(), dependencies)
+		})
+		.collect();
+	find_cycle(&graph)
+}
+
+/// Matches an npm style range such as `~3.8`, `>=3.8.0 <3.10.0` or `3.8.x || 3.9.x`.
+/// Comparators that can't be understood are treated as satisfied to avoid false warnings.
+pub fn matches_requirement(requirement: &str, version: &Versioning) -> bool {
+	requirement.split("||").any(|range| {
+		range
+			.split_whitespace()
+			.all(|comparator| matches_comparator(comparator, version))
+	})
+}
+
+fn matches_comparator(comparator: &str, version: &Versioning) -> bool {
+	let operator_length = comparator
+		.find(|c: char| c.is_ascii_alphanumeric() || c == '*')
+		.unwrap_or(comparator.len());
+	let (operator, required) = comparator.split_at(operator_length);
+	let required = required.trim_start_matches('v');
+	let segments: Vec<_> = required
+		.split('.')
+		.take_while(|s| !s.is_empty() && *s != "x" && *s != "X" && *s != "*")
+		.collect();
+	if segments.is_empty() {
+		return true;
+	}
+	let is_wildcard = segments.len() < required.split('.').count();
+	let Some(required_version) = Versioning::new(segments.join(".")) else {
+		return true;
+	};
+
+	let same_prefix = |length: usize| (0..length).all(|n| version.nth(n) == required_version.nth(n));
+	match operator {
+		">=" => version >= &required_version,
+		">" => version > &required_version,
+		"<=" => version <= &required_version,
+		"<" => version < &required_version,
+		"~" => version >= &required_version && same_prefix(segments.len().min(2)),
+		"^" => {
+			let major_is_zero = required_version.nth(0) == Some(0);
+			version >= &required_version && same_prefix(if major_is_zero { 2 } else { 1 })
+		}
+		"" | "=" if is_wildcard => same_prefix(segments.len()),
+		"" | "=" => version == &required_version,
+		_ => true,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn version(version: &str) -> Versioning {
+		Versioning::new(version).unwrap()
+	}
+
+	#[test]
+	fn package_with_dependencies_reports_missing_and_spt_mismatch() {
+		let data = std::fs::read("test_data/package_with_dependencies.json").unwrap();
+		let package = ModPackage::parse(&data).unwrap();
+		let installed = vec![ModPackage {
+			name: "betterkeys-updated".to_string(),
+			version: Some("1.2.3".to_string()),
+			spt_version: Some("~3.8".to_string()),
+			mod_dependencies: HashMap::new(),
+		}];
+
+		let warnings = package.check(&version("3.8.3"), &installed);
+
+		assert_eq!(
+			warnings,
+			vec![
+				DependencyWarning::UnsupportedSptVersion {
+					mod_name: "dependent-mod".to_string(),
+					requirement: "~3.9.0".to_string(),
+					spt_version: version("3.8.3"),
+				},
+				DependencyWarning::MissingDependency {
+					mod_name: "dependent-mod".to_string(),
+					dependency: "missing-mod".to_string(),
+					requirement: "^2.0.0".to_string(),
+				},
+			]
+		);
+	}
+
+	fn package(name: &str, dependencies: &[&str]) -> ModPackage {
+		ModPackage {
+			name: name.to_string(),
+			version: Some("1.0.0".to_string()),
+			spt_version: None,
+			mod_dependencies: dependencies
+				.iter()
+				.map(|dependency| (dependency.to_string(), "*".to_string()))
+				.collect(),
+		}
+	}
+
+	#[test]
+	fn circular_dependencies_report_the_cycle() {
+		let packages = vec![
+			package("first-mod", &["second-mod"]),
+			package("second-mod", &["third-mod"]),
+			package("third-mod", &["first-mod"]),
+		];
+
+		let cycle = find_dependency_cycle(&packages).unwrap();
+
+		assert_eq!(
+			DependencyWarning::CircularDependency { cycle }.to_string(),
+			"Circular mod dependency: first-mod -> second-mod -> third-mod -> first-mod"
+		);
+	}
+
+	#[test]
+	fn requirements_match_npm_style_ranges() {
+		assert!(matches_requirement("~3.8", &version("3.8.3")));
+		assert!(!matches_requirement("~3.8", &version("3.9.0")));
+		assert!(matches_requirement("^3.8.0", &version("3.9.1")));
+		assert!(!matches_requirement("^3.8.0", &version("4.0.0")));
+		assert!(matches_requirement(">=3.8.0 <3.10.0", &version("3.9.0")));
+		assert!(matches_requirement("3.7.x || 3.8.x", &version("3.8.1")));
+		assert!(!matches_requirement("3.7.0", &version("3.8.1")));
+		assert!(matches_requirement("*", &version("3.8.1")));
+	}
+}
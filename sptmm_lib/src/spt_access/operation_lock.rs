@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+
+use once_cell::sync::Lazy;
+
+/// Whether an operation is running on each SPT folder, by its canonical path. A folder is only
+/// kept while an operation on it is running or waiting.
+static RUNNING_OPERATIONS: Lazy<Mutex<HashMap<PathBuf, Arc<OperationState>>>> =
+	Lazy::new(Default::default);
+
+#[derive(Default)]
+struct OperationState {
+	is_running: Mutex<bool>,
+	finished: Condvar,
+}
+
+/// Held while an operation changes or backs up an SPT folder, the next operation on the same
+/// folder waits until it's dropped.
+pub(super) struct OperationGuard {
+	root: PathBuf,
+}
+
+/// Waits for the running operation on the folder to finish, so a backup never archives a
+/// half-installed mod and an install never writes into a folder that's being backed up.
+pub(super) fn lock_root(root: &Path) -> OperationGuard {
+	let root = std::fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+	let state = RUNNING_OPERATIONS
+		.lock()
+		.unwrap()
+		.entry(root.clone())
+		.or_default()
+		.clone();
+	let mut is_running = state.is_running.lock().unwrap();
+	while *is_running {
+		is_running = state.finished.wait(is_running).unwrap();
+	}
+	*is_running = true;
+	drop(is_running);
+	OperationGuard { root }
+}
+
+/// [`lock_root`] for async operations, waiting on the blocking thread pool.
+pub(super) async fn lock_root_async(root: &Path) -> anyhow::Result<OperationGuard> {
+	let root = root.to_path_buf();
+	Ok(tokio::task::spawn_blocking(move || lock_root(&root)).await?)
+}
+
+impl Drop for OperationGuard {
+	fn drop(&mut self) {
+		let mut running_operations = RUNNING_OPERATIONS.lock().unwrap();
+		// Only waiting operations share the state with the map and they take their share under
+		// the same lock, so a single owner means nothing else waits for the folder
+		if Arc::strong_count(&running_operations[&self.root]) == 1 {
+			running_operations.remove(&self.root);
+			return;
+		}
+		let state = &running_operations[&self.root];
+		*state.is_running.lock().unwrap() = false;
+		state.finished.notify_one();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn folders_are_forgotten_once_their_last_operation_finishes() {
+		let root = Path::new("./test_output/operation_lock_test");
+		std::fs::create_dir_all(root).unwrap();
+		let canonical_root = std::fs::canonicalize(root).unwrap();
+		let is_known = || RUNNING_OPERATIONS.lock().unwrap().contains_key(&canonical_root);
+
+		let operation = lock_root(root);
+		std::thread::scope(|scope| {
+			let waiting = scope.spawn(|| drop(lock_root(root)));
+			std::thread::sleep(std::time::Duration::from_millis(100));
+			drop(operation);
+			waiting.join().unwrap();
+		});
+
+		assert!(!is_known());
+		std::fs::remove_dir_all(root).unwrap();
+	}
+}
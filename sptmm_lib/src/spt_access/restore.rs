@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path};
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Result};
+use chrono::{Local, NaiveDate};
+use zip::read::ZipFile;
+use zip::ZipArchive;
+
+use crate::spt_access::backup::{DEDUP_CONTENT_FOLDER, DEDUP_INDEX_NAME};
+use crate::spt_access::integrity::{self, INTEGRITY_ENTRY_NAME};
+use crate::spt_access::spt_layout::{
+	SptLayout, BEPINEX_CONFIG_PATH, CLIENT_MODS_PATH, SERVER_MODS_PATH,
+};
+
+/// Decides what happens to files in the SPT folder that also exist in the backup being restored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverwritePolicy {
+	#[default]
+	Overwrite,
+	SkipExisting,
+	/// Only replaces existing files that were modified before the file in the backup.
+	OverwriteOlderOnly,
+}
+
+/// Limits a restore to one kind of file in the backup.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RestoreScope {
+	#[default]
+	All,
+	/// Only the BepInEx configuration, which backups contain when made with `include_config`.
+	Config,
+	/// Only the server mods and client plugins.
+	Mods,
+}
+
+impl RestoreScope {
+	fn includes(self, relative_path: &Path) -> bool {
+		match self {
+			RestoreScope::All => true,
+			RestoreScope::Config => relative_path.starts_with(BEPINEX_CONFIG_PATH),
+			RestoreScope::Mods => SptLayout::mod_folders()
+				.iter()
+				.any(|folder| relative_path.starts_with(folder)),
+		}
+	}
+}
+
+/// What restoring an entry of a backup would do to the file at its path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RestoreChange {
+	/// Nothing exists at the path yet.
+	New,
+	/// The existing file has the same content as the backup.
+	Identical,
+	/// The existing file has different content and would be replaced.
+	Overwrite,
+	/// The existing file has different content and was modified after the file in the backup.
+	OverwriteNewer,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RestorePreview {
+	pub path: String,
+	pub change: RestoreChange,
+}
+
+/// Compares every entry of the backup with the SPT folder without extracting anything.
+pub(super) fn preview_archive(
+	archive_path: &Path,
+	root_path: &Path,
+	scope: RestoreScope,
+) -> Result<Vec<RestorePreview>> {
+	let mut zip_archive = ZipArchive::new(File::open(archive_path)?)?;
+	integrity::verify_archive(&mut zip_archive)?;
+	let mut previews = Vec::new();
+	if zip_archive.index_for_name(DEDUP_INDEX_NAME).is_some() {
+		let index: HashMap<String, String> =
+			serde_json::from_reader(zip_archive.by_name(DEDUP_INDEX_NAME)?)?;
+		let mut index: Vec<_> = index.into_iter().collect();
+		index.sort();
+		for (entry_name, hash) in index {
+			let relative_path = index_path(&entry_name)?;
+			if !scope.includes(relative_path) {
+				continue;
+			}
+			let entry = zip_archive.by_name(&format!("{DEDUP_CONTENT_FOLDER}/{hash}"))?;
+			let change = compare_with_existing(&root_path.join(relative_path), entry)?;
+			previews.push(RestorePreview {
+				path: entry_name,
+				change,
+			});
+		}
+		return Ok(previews);
+	}
+	for index in 0..zip_archive.len() {
+		let entry = zip_archive.by_index(index)?;
+		if entry.name() == INTEGRITY_ENTRY_NAME || entry.is_dir() {
+			continue;
+		}
+		let Some(relative_path) = entry.enclosed_name() else {
+			continue;
+		};
+		if !scope.includes(&relative_path) {
+			continue;
+		}
+		let path = entry.name().to_string();
+		let change = compare_with_existing(&root_path.join(relative_path), entry)?;
+		previews.push(RestorePreview { path, change });
+	}
+	Ok(previews)
+}
+
+fn compare_with_existing(target_path: &Path, mut entry: ZipFile) -> Result<RestoreChange> {
+	if !target_path.is_file() {
+		return Ok(RestoreChange::New);
+	}
+	let mut data = Vec::new();
+	entry.read_to_end(&mut data)?;
+	if sha256::digest(data.as_slice()) == sha256::try_digest(target_path)? {
+		return Ok(RestoreChange::Identical);
+	}
+	let is_newer = match archived_modified_time(&entry) {
+		Some(archived_time) => target_path.metadata()?.modified()? > archived_time,
+		None => false,
+	};
+	Ok(if is_newer {
+		RestoreChange::OverwriteNewer
+	} else {
+		RestoreChange::Overwrite
+	})
+}
+
+pub(super) fn restore_archive(
+	archive_path: &Path,
+	root_path: &Path,
+	policy: OverwritePolicy,
+	scope: RestoreScope,
+) -> Result<()> {
+	restore_matching(archive_path, root_path, policy, |path| scope.includes(path))?;
+	Ok(())
+}
+
+/// Restores only the server mod or client plugin in `mod_folder`, failing when the backup has
+/// neither.
+pub(super) fn restore_mod_archive(
+	archive_path: &Path,
+	root_path: &Path,
+	mod_folder: &str,
+) -> Result<()> {
+	let mod_folder_path = Path::new(mod_folder);
+	let is_single_folder = mod_folder_path.components().count() == 1
+		&& matches!(mod_folder_path.components().next(), Some(Component::Normal(_)));
+	if !is_single_folder {
+		return Err(anyhow!("Invalid mod folder name: {mod_folder}"));
+	}
+	let mod_paths =
+		[SERVER_MODS_PATH, CLIENT_MODS_PATH].map(|folder| Path::new(folder).join(mod_folder));
+	let restored = restore_matching(archive_path, root_path, OverwritePolicy::Overwrite, |path| {
+		mod_paths.iter().any(|mod_path| path.starts_with(mod_path))
+	})?;
+	if restored == 0 {
+		return Err(anyhow!(
+			"Found no files of '{mod_folder}' in {SERVER_MODS_PATH} or {CLIENT_MODS_PATH} of the backup: {}",
+			archive_path.display()
+		));
+	}
+	Ok(())
+}
+
+/// Writes the files of the backup whose relative path `includes` accepts and returns how many
+/// matched, whether or not the policy let them be written.
+fn restore_matching(
+	archive_path: &Path,
+	root_path: &Path,
+	policy: OverwritePolicy,
+	includes: impl Fn(&Path) -> bool,
+) -> Result<usize> {
+	let mut zip_archive = ZipArchive::new(File::open(archive_path)?)?;
+	integrity::verify_archive(&mut zip_archive)?;
+	if zip_archive.index_for_name(DEDUP_INDEX_NAME).is_some() {
+		return restore_deduplicated(&mut zip_archive, root_path, policy, includes);
+	}
+	let mut matched = 0;
+	for index in 0..zip_archive.len() {
+		let mut entry = zip_archive.by_index(index)?;
+		if entry.name() == INTEGRITY_ENTRY_NAME {
+			continue;
+		}
+		let Some(relative_path) = entry.enclosed_name() else {
+			continue;
+		};
+		if !includes(&relative_path) {
+			continue;
+		}
+		let target_path = root_path.join(relative_path);
+		if entry.is_dir() {
+			std::fs::create_dir_all(&target_path)?;
+			continue;
+		}
+		matched += 1;
+		if !should_restore(policy, &target_path, &entry)? {
+			continue;
+		}
+		if let Some(parent) = target_path.parent() {
+			std::fs::create_dir_all(parent)?;
+		}
+		let mut file = File::create(&target_path)?;
+		std::io::copy(&mut entry, &mut file)?;
+	}
+	Ok(matched)
+}
+
+/// Writes every path in the deduplication index from the content it points to.
+fn restore_deduplicated(
+	zip_archive: &mut ZipArchive<File>,
+	root_path: &Path,
+	policy: OverwritePolicy,
+	includes: impl Fn(&Path) -> bool,
+) -> Result<usize> {
+	let index: HashMap<String, String> =
+		serde_json::from_reader(zip_archive.by_name(DEDUP_INDEX_NAME)?)?;
+	let mut matched = 0;
+	for (entry_name, hash) in index {
+		let relative_path = index_path(&entry_name)?;
+		if !includes(relative_path) {
+			continue;
+		}
+		matched += 1;
+		let target_path = root_path.join(relative_path);
+		let mut entry = zip_archive.by_name(&format!("{DEDUP_CONTENT_FOLDER}/{hash}"))?;
+		if !should_restore(policy, &target_path, &entry)? {
+			continue;
+		}
+		if let Some(parent) = target_path.parent() {
+			std::fs::create_dir_all(parent)?;
+		}
+		let mut file = File::create(&target_path)?;
+		std::io::copy(&mut entry, &mut file)?;
+	}
+	Ok(matched)
+}
+
+/// Refuses index paths that could point outside the SPT folder.
+fn index_path(entry_name: &str) -> Result<&Path> {
+	let relative_path = Path::new(entry_name);
+	if !relative_path
+		.components()
+		.all(|component| matches!(component, Component::Normal(_)))
+	{
+		return Err(anyhow!("Invalid path in the backup index: {entry_name}"));
+	}
+	Ok(relative_path)
+}
+
+fn should_restore(policy: OverwritePolicy, target_path: &Path, entry: &ZipFile) -> Result<bool> {
+	if !target_path.exists() {
+		return Ok(true);
+	}
+	Ok(match policy {
+		OverwritePolicy::Overwrite => true,
+		OverwritePolicy::SkipExisting => false,
+		OverwritePolicy::OverwriteOlderOnly => match archived_modified_time(entry) {
+			Some(archived_time) => target_path.metadata()?.modified()? < archived_time,
+			None => false,
+		},
+	})
+}
+
+/// Zip archives store the modification time without a timezone, as the local time of the machine that wrote it.
+fn archived_modified_time(entry: &ZipFile) -> Option<SystemTime> {
+	let modified = entry.last_modified()?;
+	let date_time = NaiveDate::from_ymd_opt(
+		modified.year().into(),
+		modified.month().into(),
+		modified.day().into(),
+	)?
+	.and_hms_opt(
+		modified.hour().into(),
+		modified.minute().into(),
+		modified.second().into(),
+	)?;
+	Some(date_time.and_local_timezone(Local).earliest()?.into())
+}
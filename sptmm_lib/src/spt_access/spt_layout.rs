@@ -0,0 +1,145 @@
+use std::path::{Path, PathBuf};
+
+pub(super) const SERVER_MODS_PATH: &str = "user/mods";
+pub(super) const CLIENT_MODS_PATH: &str = "BepInEx/plugins";
+pub(super) const BEPINEX_CONFIG_PATH: &str = "BepInEx/config";
+pub(super) const BEPINEX_CACHE_PATH: &str = "BepInEx/cache";
+pub(super) const USER_CACHE_PATH: &str = "user/cache";
+pub(super) const USER_PROFILES_PATH: &str = "user/profiles";
+const INSTALL_INDEX_PATH: &str = "install_hash";
+const INSTALL_STAGING_PATH: &str = "install_staging";
+/// Stands for the SPT folder at the start of an `install_path`.
+pub const SPT_ROOT_TOKEN: &str = "${SPT_ROOT}";
+/// Shorthands for the SPT folders at the start of an `install_path`, such as `@plugins/MyMod`.
+pub const INSTALL_PATH_TOKENS: [(&str, &str); 3] = [
+	("@plugins", CLIENT_MODS_PATH),
+	("@mods", SERVER_MODS_PATH),
+	("@config", BEPINEX_CONFIG_PATH),
+];
+
+/// Where SPT and the mod manager keep their files inside the SPT folder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SptLayout {
+	root: PathBuf,
+}
+
+impl SptLayout {
+	pub fn new(root: impl Into<PathBuf>) -> Self {
+		Self { root: root.into() }
+	}
+
+	pub fn root(&self) -> &Path {
+		&self.root
+	}
+
+	pub fn server_mods(&self) -> PathBuf {
+		self.root.join(SERVER_MODS_PATH)
+	}
+
+	pub fn client_mods(&self) -> PathBuf {
+		self.root.join(CLIENT_MODS_PATH)
+	}
+
+	pub fn bepinex_config(&self) -> PathBuf {
+		self.root.join(BEPINEX_CONFIG_PATH)
+	}
+
+	pub fn bepinex_cache(&self) -> PathBuf {
+		self.root.join(BEPINEX_CACHE_PATH)
+	}
+
+	pub fn user_cache(&self) -> PathBuf {
+		self.root.join(USER_CACHE_PATH)
+	}
+
+	pub fn user_profiles(&self) -> PathBuf {
+		self.root.join(USER_PROFILES_PATH)
+	}
+
+	/// Holds one file per installed mod, recording the files it installed.
+	pub fn install_index(&self) -> PathBuf {
+		self.root.join(INSTALL_INDEX_PATH)
+	}
+
+	/// Holds a mod's files while they're extracted, until all of them can be moved into place. It's
+	/// inside the SPT folder so moving them is a rename on the same drive.
+	pub fn install_staging(&self) -> PathBuf {
+		self.root.join(INSTALL_STAGING_PATH)
+	}
+
+	/// Resolves a configured `install_path` against the SPT folder, expanding a leading
+	/// [`SPT_ROOT_TOKEN`] or one of the [`INSTALL_PATH_TOKENS`]. Absolute paths are kept as they are.
+	pub fn resolve_install_path(&self, install_path: &str) -> PathBuf {
+		if let Some(rest) = install_path.strip_prefix(SPT_ROOT_TOKEN) {
+			return self.root.join(rest.trim_start_matches(['/', '\\']));
+		}
+		for (token, folder) in INSTALL_PATH_TOKENS {
+			let Some(rest) = install_path.strip_prefix(token) else {
+				continue;
+			};
+			if rest.is_empty() || rest.starts_with(['/', '\\']) {
+				return self.root.join(folder).join(rest.trim_start_matches(['/', '\\']));
+			}
+		}
+		self.root.join(install_path)
+	}
+
+	/// The folders mods are installed to, relative to the SPT folder.
+	pub fn mod_folders() -> [&'static str; 2] {
+		[SERVER_MODS_PATH, CLIENT_MODS_PATH]
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn layout_resolves_paths_from_root() {
+		let root = Path::new("spt");
+		let layout = SptLayout::new(root);
+
+		assert_eq!(layout.root(), root);
+		assert_eq!(layout.server_mods(), root.join("user").join("mods"));
+		assert_eq!(layout.client_mods(), root.join("BepInEx").join("plugins"));
+		assert_eq!(layout.bepinex_config(), root.join("BepInEx").join("config"));
+		assert_eq!(layout.bepinex_cache(), root.join("BepInEx").join("cache"));
+		assert_eq!(layout.user_cache(), root.join("user").join("cache"));
+		assert_eq!(layout.user_profiles(), root.join("user").join("profiles"));
+		assert_eq!(layout.install_index(), root.join("install_hash"));
+	}
+
+	#[test]
+	fn install_path_tokens_expand_inside_root() {
+		let root = std::path::absolute("spt").unwrap();
+		let layout = SptLayout::new(&root);
+
+		assert_eq!(
+			layout.resolve_install_path("@plugins/MyMod"),
+			root.join("BepInEx").join("plugins").join("MyMod")
+		);
+		assert_eq!(
+			layout.resolve_install_path("@mods/my-mod/config"),
+			root.join("user").join("mods").join("my-mod/config")
+		);
+		assert_eq!(
+			layout.resolve_install_path("@config/my-mod.cfg"),
+			root.join("BepInEx").join("config").join("my-mod.cfg")
+		);
+		assert_eq!(layout.resolve_install_path("@plugins"), root.join("BepInEx").join("plugins"));
+		assert_eq!(layout.resolve_install_path("${SPT_ROOT}/tools/x"), root.join("tools/x"));
+		assert_eq!(layout.resolve_install_path("tools/x"), root.join("tools/x"));
+		assert_eq!(layout.resolve_install_path("@pluginsExtra/x"), root.join("@pluginsExtra/x"));
+		assert!(layout.resolve_install_path("@plugins/MyMod").is_absolute());
+	}
+
+	#[test]
+	fn mod_folders_are_inside_root() {
+		let layout = SptLayout::new("spt");
+
+		assert_eq!(
+			SptLayout::mod_folders().map(|folder| layout.root().join(folder)),
+			[layout.server_mods(), layout.client_mods()]
+		);
+	}
+}
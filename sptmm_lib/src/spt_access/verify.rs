@@ -0,0 +1,64 @@
+use std::path::Path;
+
+use crate::spt_access::worker_pool::map_in_parallel;
+use crate::spt_access::InstalledMod;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileProblem {
+	Missing,
+	Modified,
+	Unreadable(String),
+}
+
+/// The installed files of a mod that no longer match the install index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModVerification {
+	pub name: String,
+	pub checked_files: usize,
+	pub problems: Vec<(String, FileProblem)>,
+}
+
+impl ModVerification {
+	pub fn is_intact(&self) -> bool {
+		self.problems.is_empty()
+	}
+}
+
+pub(super) fn verify_mod(root_path: &Path, installed_mod: &InstalledMod) -> ModVerification {
+	let mut problems: Vec<_> = installed_mod
+		.files
+		.iter()
+		.filter_map(|(file, expected_hash)| {
+			let path = root_path.join(file);
+			let problem = match sha256::try_digest(path.as_path()) {
+				Ok(hash) if &hash == expected_hash => return None,
+				Ok(_) => FileProblem::Modified,
+				Err(err) if err.kind() == std::io::ErrorKind::NotFound => FileProblem::Missing,
+				Err(err) => FileProblem::Unreadable(err.to_string()),
+			};
+			Some((file.clone(), problem))
+		})
+		.collect();
+	problems.sort_by(|(a, _), (b, _)| a.cmp(b));
+	ModVerification {
+		name: installed_mod.name.clone(),
+		checked_files: installed_mod.files.len(),
+		problems,
+	}
+}
+
+/// Verifies the mods on up to `threads` threads, calling `progress` with the number of mods verified
+/// so far. The results keep the order of `installed_mods`.
+pub(super) fn verify_mods(
+	root_path: &Path,
+	installed_mods: &[InstalledMod],
+	threads: usize,
+	progress: impl Fn(usize) + Sync,
+) -> Vec<ModVerification> {
+	map_in_parallel(
+		installed_mods,
+		threads,
+		|installed_mod| verify_mod(root_path, installed_mod),
+		progress,
+	)
+}
@@ -0,0 +1,46 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Runs `work` over `items` on up to `threads` threads, calling `progress` with the number of items
+/// done so far. The results keep the order of `items`.
+pub(super) fn map_in_parallel<Item: Sync, Output: Send>(
+	items: &[Item],
+	threads: usize,
+	work: impl Fn(&Item) -> Output + Sync,
+	progress: impl Fn(usize) + Sync,
+) -> Vec<Output> {
+	let next_item = AtomicUsize::new(0);
+	let done_items = AtomicUsize::new(0);
+	let results = Mutex::new(Vec::with_capacity(items.len()));
+	std::thread::scope(|scope| {
+		for _ in 0..threads.clamp(1, items.len().max(1)) {
+			scope.spawn(|| loop {
+				let index = next_item.fetch_add(1, Ordering::Relaxed);
+				let Some(item) = items.get(index) else {
+					break;
+				};
+				let output = work(item);
+				results.lock().unwrap().push((index, output));
+				progress(done_items.fetch_add(1, Ordering::Relaxed) + 1);
+			});
+		}
+	});
+
+	let mut results = results.into_inner().unwrap();
+	results.sort_by_key(|(index, _)| *index);
+	results.into_iter().map(|(_, output)| output).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn results_keep_item_order() {
+		let items: Vec<usize> = (0..50).collect();
+
+		let doubled = map_in_parallel(&items, 4, |item| item * 2, |_| {});
+
+		assert_eq!(doubled, items.iter().map(|item| item * 2).collect::<Vec<_>>());
+	}
+}
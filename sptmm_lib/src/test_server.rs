@@ -0,0 +1,152 @@
+//! A minimal HTTP server for tests, answering each request path with a canned response.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use reqwest::Url;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+#[derive(Clone, Debug)]
+pub struct TestResponse {
+	pub status: u16,
+	pub headers: Vec<(String, String)>,
+	pub body: Vec<u8>,
+	/// How long to wait before answering, to test timeouts.
+	pub delay: Duration,
+	/// Headers the request must carry, answering with a 406 otherwise.
+	pub required_headers: Vec<(String, String)>,
+}
+
+impl TestResponse {
+	pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+		Self {
+			status: 200,
+			headers: Vec::new(),
+			body: body.into(),
+			delay: Duration::ZERO,
+			required_headers: Vec::new(),
+		}
+	}
+
+	pub fn status(status: u16) -> Self {
+		Self {
+			status,
+			headers: Vec::new(),
+			body: Vec::new(),
+			delay: Duration::ZERO,
+			required_headers: Vec::new(),
+		}
+	}
+
+	pub fn with_header(mut self, name: &str, value: &str) -> Self {
+		self.headers.push((name.to_string(), value.to_string()));
+		self
+	}
+
+	pub fn with_required_header(mut self, name: &str, value: &str) -> Self {
+		self.required_headers.push((name.to_string(), value.to_string()));
+		self
+	}
+
+	pub fn with_delay(mut self, delay: Duration) -> Self {
+		self.delay = delay;
+		self
+	}
+}
+
+pub struct TestServer {
+	url: Url,
+	requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl TestServer {
+	/// Starts serving the routes, keyed by path and query, answering anything else with a 404.
+	pub async fn start(routes: Vec<(&str, TestResponse)>) -> Self {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+		let routes: HashMap<String, TestResponse> = routes
+			.into_iter()
+			.map(|(path, response)| (path.to_string(), response))
+			.collect();
+		let requests = Arc::new(Mutex::new(Vec::new()));
+		let server_requests = requests.clone();
+		tokio::spawn(async move {
+			while let Ok((stream, _)) = listener.accept().await {
+				let routes = routes.clone();
+				let requests = server_requests.clone();
+				tokio::spawn(async move {
+					let _ = respond(stream, &routes, &requests).await;
+				});
+			}
+		});
+		Self { url, requests }
+	}
+
+	pub fn url(&self, path: &str) -> Url {
+		self.url.join(path.trim_start_matches('/')).unwrap()
+	}
+
+	/// The requests received so far, as `METHOD /path`.
+	pub fn requests(&self) -> Vec<String> {
+		self.requests.lock().unwrap().clone()
+	}
+}
+
+async fn respond(
+	mut stream: TcpStream,
+	routes: &HashMap<String, TestResponse>,
+	requests: &Mutex<Vec<String>>,
+) -> std::io::Result<()> {
+	let mut request = Vec::new();
+	let mut buffer = [0; 1024];
+	while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+		let read = stream.read(&mut buffer).await?;
+		if read == 0 {
+			return Ok(());
+		}
+		request.extend_from_slice(&buffer[..read]);
+	}
+
+	let request = String::from_utf8_lossy(&request);
+	let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+	let method = request_line.next().unwrap_or_default().to_string();
+	let path = request_line.next().unwrap_or_default().to_string();
+	requests.lock().unwrap().push(format!("{method} {path}"));
+
+	let headers: Vec<_> = request
+		.lines()
+		.skip(1)
+		.filter_map(|line| line.split_once(':'))
+		.map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+		.collect();
+	let response = match routes.get(&path) {
+		Some(response) => {
+			let is_missing_header = response.required_headers.iter().any(|(name, value)| {
+				!headers.contains(&(name.to_lowercase(), value.to_string()))
+			});
+			if is_missing_header {
+				TestResponse::status(406)
+			} else {
+				response.clone()
+			}
+		}
+		None => TestResponse::status(404),
+	};
+	tokio::time::sleep(response.delay).await;
+	let mut head = format!(
+		"HTTP/1.1 {} Test\r\nContent-Length: {}\r\nConnection: close\r\n",
+		response.status,
+		response.body.len()
+	);
+	for (name, value) in &response.headers {
+		head.push_str(&format!("{name}: {value}\r\n"));
+	}
+	head.push_str("\r\n");
+	stream.write_all(head.as_bytes()).await?;
+	if method != "HEAD" {
+		stream.write_all(&response.body).await?;
+	}
+	stream.shutdown().await
+}
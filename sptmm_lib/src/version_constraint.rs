@@ -0,0 +1,220 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+use anyhow::{anyhow, Context, Result};
+use versions::Versioning;
+
+/// The versions a configured mod may install, either an exact version such as `1.2.3` or a range
+/// such as `>=1.2.0, <2.0.0`, `^1.2` or `~1.2.3`, of which the highest available one is installed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionConstraint {
+	text: String,
+	comparators: Vec<Comparator>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Comparator {
+	operator: Operator,
+	version: Versioning,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+	Exact,
+	Greater,
+	GreaterOrEqual,
+	Less,
+	LessOrEqual,
+}
+
+impl VersionConstraint {
+	/// Reads comma separated comparisons, where `^` allows changes that keep the first non-zero
+	/// part and `~` allows changes to the last part given. A plain version matches only itself.
+	pub fn parse(text: &str) -> Result<Self> {
+		let text = text.trim();
+		let mut comparators = Vec::new();
+		for part in text.split(',').map(str::trim) {
+			let parsed = parse_comparison(part)
+				.with_context(|| format!("Invalid version constraint '{text}'"))?;
+			comparators.extend(parsed);
+		}
+		Ok(Self {
+			text: text.to_string(),
+			comparators,
+		})
+	}
+
+	/// The version a plain version string names, `None` for a range.
+	pub fn as_exact(&self) -> Option<&Versioning> {
+		match self.comparators.as_slice() {
+			[Comparator {
+				operator: Operator::Exact,
+				version,
+			}] => Some(version),
+			_ => None,
+		}
+	}
+
+	pub fn matches(&self, version: &Versioning) -> bool {
+		self.comparators.iter().all(|comparator| {
+			let ordering = version.cmp(&comparator.version);
+			match comparator.operator {
+				Operator::Exact => ordering == Ordering::Equal,
+				Operator::Greater => ordering == Ordering::Greater,
+				Operator::GreaterOrEqual => ordering != Ordering::Less,
+				Operator::Less => ordering == Ordering::Less,
+				Operator::LessOrEqual => ordering != Ordering::Greater,
+			}
+		})
+	}
+
+	/// The candidate with the highest version the constraint allows.
+	pub fn select_highest<T>(
+		&self,
+		candidates: impl IntoIterator<Item = (T, Versioning)>,
+	) -> Option<(T, Versioning)> {
+		candidates
+			.into_iter()
+			.filter(|(_, version)| self.matches(version))
+			.max_by(|(_, a), (_, b)| a.cmp(b))
+	}
+}
+
+impl From<Versioning> for VersionConstraint {
+	fn from(version: Versioning) -> Self {
+		Self {
+			text: version.to_string(),
+			comparators: vec![Comparator {
+				operator: Operator::Exact,
+				version,
+			}],
+		}
+	}
+}
+
+impl Display for VersionConstraint {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.write_str(&self.text)
+	}
+}
+
+fn parse_comparison(part: &str) -> Result<Vec<Comparator>> {
+	let operators = [
+		(">=", Operator::GreaterOrEqual),
+		("<=", Operator::LessOrEqual),
+		(">", Operator::Greater),
+		("<", Operator::Less),
+		("=", Operator::Exact),
+	];
+	if let Some(rest) = part.strip_prefix('^') {
+		return parse_bounded(rest, |parts| {
+			// ^1.2 allows anything below 2.0.0, ^0.2 anything below 0.3.0
+			match parts.iter().position(|part| *part != 0) {
+				Some(index) => index,
+				None => parts.len() - 1,
+			}
+		});
+	}
+	if let Some(rest) = part.strip_prefix('~') {
+		// ~1.2.3 and ~1.2 allow anything below 1.3.0, ~1 anything below 2.0.0
+		return parse_bounded(rest, |parts| parts.len().min(2) - 1);
+	}
+	let (operator, version) = operators
+		.iter()
+		.find_map(|(prefix, operator)| part.strip_prefix(prefix).map(|rest| (*operator, rest)))
+		.unwrap_or((Operator::Exact, part));
+	Ok(vec![Comparator {
+		operator,
+		version: parse_version(version)?,
+	}])
+}
+
+/// A range from the version up to, but not including, the version with the part at the index
+/// `bumped_part` picks increased and the parts after it zeroed.
+fn parse_bounded(version: &str, bumped_part: impl Fn(&[u64]) -> usize) -> Result<Vec<Comparator>> {
+	let lower = parse_version(version)?;
+	let parts = version
+		.trim()
+		.split('.')
+		.map(|part| part.parse::<u64>())
+		.collect::<Result<Vec<_>, _>>()
+		.map_err(|_| anyhow!("'{version}' must be numbers separated by dots to use ^ or ~"))?;
+	let bumped = bumped_part(&parts);
+	let upper = (0..parts.len().max(3))
+		.map(|index| match index.cmp(&bumped) {
+			Ordering::Less => parts[index],
+			Ordering::Equal => parts[index] + 1,
+			Ordering::Greater => 0,
+		})
+		.map(|part| part.to_string())
+		.collect::<Vec<_>>()
+		.join(".");
+	Ok(vec![
+		Comparator {
+			operator: Operator::GreaterOrEqual,
+			version: lower,
+		},
+		Comparator {
+			operator: Operator::Less,
+			version: parse_version(&upper)?,
+		},
+	])
+}
+
+fn parse_version(version: &str) -> Result<Versioning> {
+	let version = version.trim();
+	if version.is_empty() {
+		return Err(anyhow!("Found an empty version"));
+	}
+	Versioning::new(version).ok_or_else(|| anyhow!("Invalid version '{version}'"))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn version(version: &str) -> Versioning {
+		Versioning::new(version).unwrap()
+	}
+
+	#[test]
+	fn plain_version_matches_only_itself() {
+		let constraint = VersionConstraint::parse("1.2.3").unwrap();
+
+		assert_eq!(constraint.as_exact(), Some(&version("1.2.3")));
+		assert!(constraint.matches(&version("1.2.3")));
+		assert!(!constraint.matches(&version("1.2.4")));
+		assert_eq!(constraint.to_string(), "1.2.3");
+	}
+
+	#[test]
+	fn range_selects_the_highest_allowed_version() {
+		let candidates = ["1.1.0", "1.2.0", "1.9.4", "2.0.0"].map(|name| (name, version(name)));
+
+		let range = VersionConstraint::parse(">=1.2.0, <2.0.0").unwrap();
+		let caret = VersionConstraint::parse("^1.2").unwrap();
+		let tilde = VersionConstraint::parse("~1.1").unwrap();
+
+		assert_eq!(range.as_exact(), None);
+		assert_eq!(range.select_highest(candidates.clone()).unwrap().0, "1.9.4");
+		assert_eq!(caret.select_highest(candidates.clone()).unwrap().0, "1.9.4");
+		assert_eq!(tilde.select_highest(candidates.clone()).unwrap().0, "1.1.0");
+		assert!(VersionConstraint::parse(">2.0.0").unwrap().select_highest(candidates).is_none());
+	}
+
+	#[test]
+	fn caret_on_zero_major_stays_within_the_minor_version() {
+		let caret = VersionConstraint::parse("^0.2.1").unwrap();
+
+		assert!(caret.matches(&version("0.2.9")));
+		assert!(!caret.matches(&version("0.3.0")));
+		assert!(!caret.matches(&version("0.2.0")));
+	}
+
+	#[test]
+	fn invalid_constraints_are_rejected() {
+		assert!(VersionConstraint::parse(">=1.0.0,").is_err());
+		assert!(VersionConstraint::parse("^1.x").is_err());
+		assert!(VersionConstraint::parse("").is_err());
+	}
+}
@@ -0,0 +1,129 @@
+use std::cmp::Ordering;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use versions::Versioning;
+
+/// The formats tried for date versions when the configuration names none, which all order the
+/// same as plain versions do. Formats such as `%d-%m-%Y` have to be configured.
+pub const DEFAULT_DATE_FORMATS: &[&str] = &["%Y.%m.%d", "%Y-%m-%d", "%Y%m%d"];
+
+/// How the versions of a mod are ordered when picking the newest one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum VersionScheme {
+	#[default]
+	Semver,
+	/// Versions are dates, read with the chrono `format` such as `%d-%m-%Y` or one of
+	/// [`DEFAULT_DATE_FORMATS`] without one.
+	Date { format: Option<String> },
+}
+
+/// The name of a [`VersionScheme`] in the configuration, which keeps the date format apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionSchemeName {
+	Semver,
+	Date,
+}
+
+impl VersionScheme {
+	pub fn name(&self) -> VersionSchemeName {
+		match self {
+			VersionScheme::Semver => VersionSchemeName::Semver,
+			VersionScheme::Date { .. } => VersionSchemeName::Date,
+		}
+	}
+
+	pub fn get_date_format(&self) -> Option<&str> {
+		match self {
+			VersionScheme::Semver => None,
+			VersionScheme::Date { format } => format.as_deref(),
+		}
+	}
+
+	/// Compares two versions of the same mod. Under the date scheme a version that isn't a date
+	/// sorts before every date, and two such versions compare as plain versions.
+	pub fn compare(&self, a: &Versioning, b: &Versioning) -> Ordering {
+		let VersionScheme::Date { format } = self else {
+			return a.cmp(b);
+		};
+		let format = format.as_deref();
+		match (parse_date(a, format), parse_date(b, format)) {
+			(Some(a), Some(b)) => a.cmp(&b),
+			(Some(_), None) => Ordering::Greater,
+			(None, Some(_)) => Ordering::Less,
+			(None, None) => a.cmp(b),
+		}
+	}
+
+	/// The item with the newest version, the last one when several compare equal.
+	pub fn newest<T>(
+		&self,
+		items: impl IntoIterator<Item = T>,
+		version: impl Fn(&T) -> &Versioning,
+	) -> Option<T> {
+		items
+			.into_iter()
+			.max_by(|a, b| self.compare(version(a), version(b)))
+	}
+}
+
+fn parse_date(version: &Versioning, format: Option<&str>) -> Option<NaiveDate> {
+	let version = version.to_string();
+	let version = version.trim_start_matches(['v', 'V']);
+	match format {
+		Some(format) => NaiveDate::parse_from_str(version, format).ok(),
+		None => DEFAULT_DATE_FORMATS
+			.iter()
+			.find_map(|format| NaiveDate::parse_from_str(version, format).ok()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn versions(versions: &[&str]) -> Vec<Versioning> {
+		versions
+			.iter()
+			.map(|version| Versioning::new(version).unwrap())
+			.collect()
+	}
+
+	#[test]
+	fn day_first_dates_pick_the_newest_date_with_their_format() {
+		let releases = versions(&["28-02-2024", "11-06-2024", "30-12-2023"]);
+		let date = VersionScheme::Date {
+			format: Some("%d-%m-%Y".to_string()),
+		};
+
+		let newest = date.newest(&releases, |version| *version).unwrap();
+
+		assert_eq!(newest, &releases[1]);
+		// Compared as plain versions the day decides, picking the oldest release
+		let plain_newest = VersionScheme::Semver.newest(&releases, |version| *version).unwrap();
+		assert_eq!(plain_newest, &releases[2]);
+	}
+
+	#[test]
+	fn default_formats_read_dotted_and_compact_dates() {
+		let date = VersionScheme::Date { format: None };
+
+		let dotted = versions(&["2024.06.11", "2023.12.30"]);
+		let compact = versions(&["20231230", "20240611"]);
+
+		assert_eq!(date.newest(&dotted, |version| *version), Some(&dotted[0]));
+		assert_eq!(date.newest(&compact, |version| *version), Some(&compact[1]));
+	}
+
+	#[test]
+	fn versions_that_are_not_dates_sort_before_dates() {
+		let date = VersionScheme::Date {
+			format: Some("%d-%m-%Y".to_string()),
+		};
+
+		let releases = versions(&["9.9.9", "01-01-2024"]);
+
+		assert_eq!(date.newest(&releases, |version| *version), Some(&releases[1]));
+	}
+}